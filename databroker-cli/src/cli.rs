@@ -11,9 +11,11 @@
 * SPDX-License-Identifier: Apache-2.0
 ********************************************************************************/
 
+use databroker_proto::kuksa::val::v2 as proto;
 use http::Uri;
-use std::io::Write;
-use std::sync::Arc;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::{fmt, io};
 
 use ansi_term::Color;
@@ -36,13 +38,13 @@ impl fmt::Display for ParseError {
 #[derive(Debug, Parser, Clone)]
 #[clap(author, version, about, long_about = None)]
 pub struct Cli {
-    /// Server to connect to
+    /// Server to connect to, e.g. http://127.0.0.1:55555 or unix:///run/kuksa/databroker.sock
     #[clap(long, display_order = 1, default_value = "http://127.0.0.1:55555")]
     server: String,
 
     // #[clap(short, long)]
     // port: Option<u16>,
-    /// File containing access token
+    /// File containing access token, read again whenever it changes
     #[clap(long, value_name = "FILE", display_order = 2)]
     token_file: Option<String>,
 
@@ -55,6 +57,10 @@ pub struct Cli {
     #[clap(long, short = 'p', value_enum, default_value_t = Protocol::KuksaValV1)]
     protocol: Protocol,
 
+    /// Format of signal values printed to stdout
+    #[clap(long, short = 'o', value_enum, default_value_t = OutputFormat::Human)]
+    output: OutputFormat,
+
     // Sub command
     #[clap(subcommand)]
     command: Option<Commands>,
@@ -81,6 +87,10 @@ impl Cli {
     pub fn get_protocol(&mut self) -> Protocol {
         self.protocol
     }
+
+    pub fn get_output(&mut self) -> OutputFormat {
+        self.output
+    }
 }
 
 #[derive(Debug, Subcommand, Clone)]
@@ -90,6 +100,25 @@ pub enum Commands {
         #[clap(value_name = "PATH")]
         paths: Vec<String>,
     },
+    /// Subscribe to one or more datapoint(s) and print their updates
+    Subscribe {
+        #[clap(value_name = "PATH", required = true)]
+        paths: Vec<String>,
+        /// Exit after receiving N updates
+        #[clap(long, value_name = "N")]
+        count: Option<usize>,
+        /// Exit after SECONDS, failing if --count updates were not received by then
+        #[clap(long, value_name = "SECONDS")]
+        timeout: Option<f64>,
+    },
+    /// Run the commands in FILE (or stdin if "-"), one per line
+    Batch {
+        #[clap(value_name = "FILE", default_value = "-")]
+        file: String,
+        /// Continue with the next command after a failure
+        #[clap(long)]
+        keep_going: bool,
+    },
     /// Set a datapoint
     Set {
         #[clap(value_name = "PATH")]
@@ -111,6 +140,331 @@ pub enum Commands {
         #[clap(value_name = "VALUE")]
         value: String,
     },
+    /// Export the VSS tree of the server as JSON to FILE or stdout
+    Export {
+        /// Only export signals below this branch
+        #[clap(long, value_name = "PATH", default_value = "")]
+        root: String,
+        /// Include current values of the signals
+        #[clap(long)]
+        values: bool,
+        #[clap(value_name = "FILE")]
+        file: Option<String>,
+    },
+    /// Act as provider of actuators and signals until interrupted (kuksa.val.v2 only)
+    Provide {
+        /// Claim actuation of the actuator at PATH or ID
+        #[clap(long = "actuator", value_name = "PATH|ID")]
+        actuators: Vec<String>,
+        /// Claim the signal at PATH or ID
+        #[clap(long = "signal", value_name = "PATH|ID")]
+        signals: Vec<String>,
+        /// Acknowledge actuation requests by publishing the target as current value
+        #[clap(long)]
+        auto_ack: bool,
+    },
+    /// Record updates of signals matching PATTERN to a JSON Lines FILE until interrupted (kuksa.val.v2 only)
+    Record {
+        #[clap(value_name = "PATTERN")]
+        pattern: String,
+        #[clap(value_name = "FILE")]
+        file: String,
+    },
+    /// Replay a trace recorded with `record` with its original timing (kuksa.val.v2 only)
+    Replay {
+        #[clap(value_name = "FILE")]
+        file: String,
+        /// Replay faster (> 1) or slower (< 1) than recorded
+        #[clap(long, default_value_t = 1.0)]
+        speed: f64,
+        /// Start over at the end of the trace, until interrupted
+        #[clap(long = "loop")]
+        repeat: bool,
+        /// Publish signals recorded below FROM below TO instead
+        #[clap(long, value_name = "FROM=TO")]
+        remap: Vec<String>,
+        /// Publish through a provider stream, claiming the replayed signals
+        #[clap(long)]
+        provider: bool,
+    },
+}
+
+/// A single line of a batch file
+#[derive(Debug, Parser)]
+#[clap(no_binary_name = true)]
+struct BatchLine {
+    #[clap(subcommand)]
+    command: Commands,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    Human,
+    /// One JSON object per line
+    Json,
+    /// Comma separated values
+    Csv,
+}
+
+/// Exit codes of the non-interactive commands, reflecting the first failure
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    Failure = 1,
+    Usage = 2,
+    Connection = 3,
+    NotFound = 4,
+    PermissionDenied = 5,
+    InvalidArgument = 6,
+    Unavailable = 7,
+    Timeout = 8,
+}
+
+impl From<tonic::Code> for ExitCode {
+    fn from(code: tonic::Code) -> Self {
+        match code {
+            tonic::Code::Ok => ExitCode::Success,
+            tonic::Code::NotFound => ExitCode::NotFound,
+            tonic::Code::PermissionDenied | tonic::Code::Unauthenticated => {
+                ExitCode::PermissionDenied
+            }
+            tonic::Code::InvalidArgument | tonic::Code::OutOfRange => ExitCode::InvalidArgument,
+            tonic::Code::Unavailable => ExitCode::Unavailable,
+            tonic::Code::DeadlineExceeded => ExitCode::Timeout,
+            _ => ExitCode::Failure,
+        }
+    }
+}
+
+static EXIT_CODE: AtomicI32 = AtomicI32::new(ExitCode::Success as i32);
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Record a failure, unless an earlier one was recorded already
+pub fn set_exit_code(code: ExitCode) {
+    let _ = EXIT_CODE.compare_exchange(
+        ExitCode::Success as i32,
+        code as i32,
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+    );
+}
+
+pub fn exit_code() -> i32 {
+    EXIT_CODE.load(Ordering::Relaxed)
+}
+
+pub fn set_output_format(format: OutputFormat) {
+    let _ = OUTPUT_FORMAT.set(format);
+}
+
+pub fn output_format() -> OutputFormat {
+    OUTPUT_FORMAT.get().copied().unwrap_or(OutputFormat::Human)
+}
+
+/// Read the lines of a batch file, or of stdin if `file` is "-"
+pub fn read_batch_lines(file: &str) -> io::Result<Vec<String>> {
+    if file == "-" {
+        io::stdin().lock().lines().collect()
+    } else {
+        io::BufReader::new(std::fs::File::open(file)?)
+            .lines()
+            .collect()
+    }
+}
+
+/// Split a line into words, keeping quoted ("..." or '...') words together
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut word = String::new();
+        while let Some(&ch) = chars.peek() {
+            match ch {
+                '"' | '\'' => {
+                    chars.next();
+                    loop {
+                        match chars.next() {
+                            Some(c) if c == ch => break,
+                            Some(c) => word.push(c),
+                            None => return Err(format!("Unterminated {ch}")),
+                        }
+                    }
+                }
+                ch if ch.is_whitespace() => break,
+                ch => {
+                    chars.next();
+                    word.push(ch);
+                }
+            }
+        }
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Parse a line of a batch file, `None` for empty lines and comments (#)
+pub fn parse_batch_line(line: &str) -> Result<Option<Commands>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let words = split_words(line)?;
+    match BatchLine::try_parse_from(words) {
+        Ok(BatchLine {
+            command: Commands::Batch { .. },
+        }) => Err("Batches can't be nested".to_owned()),
+        Ok(batch_line) => Ok(Some(batch_line.command)),
+        Err(err) => Err(err.render().to_string().trim_end().to_owned()),
+    }
+}
+
+pub fn value_to_json(value: &proto::Value) -> serde_json::Value {
+    use proto::value::TypedValue;
+    match &value.typed_value {
+        Some(TypedValue::String(value)) => value.clone().into(),
+        Some(TypedValue::Bool(value)) => (*value).into(),
+        Some(TypedValue::Int32(value)) => (*value).into(),
+        Some(TypedValue::Int64(value)) => (*value).into(),
+        Some(TypedValue::Uint32(value)) => (*value).into(),
+        Some(TypedValue::Uint64(value)) => (*value).into(),
+        Some(TypedValue::Float(value)) => (*value).into(),
+        Some(TypedValue::Double(value)) => (*value).into(),
+        Some(TypedValue::Timestamp(value)) => value.to_string().into(),
+        Some(TypedValue::StringArray(array)) => array.values.clone().into(),
+        Some(TypedValue::BoolArray(array)) => array.values.clone().into(),
+        Some(TypedValue::Int32Array(array)) => array.values.clone().into(),
+        Some(TypedValue::Int64Array(array)) => array.values.clone().into(),
+        Some(TypedValue::Uint32Array(array)) => array.values.clone().into(),
+        Some(TypedValue::Uint64Array(array)) => array.values.clone().into(),
+        Some(TypedValue::FloatArray(array)) => array.values.clone().into(),
+        Some(TypedValue::DoubleArray(array)) => array.values.clone().into(),
+        Some(TypedValue::TimestampArray(array)) => array
+            .values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .into(),
+        Some(TypedValue::StructValue(value)) => struct_to_json(value),
+        Some(TypedValue::StructArray(array)) => array.values.iter().map(struct_to_json).collect(),
+        None => serde_json::Value::Null,
+    }
+}
+
+fn struct_to_json(value: &proto::StructValue) -> serde_json::Value {
+    value
+        .fields
+        .iter()
+        .map(|(name, value)| (name.clone(), value_to_json(value)))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Print the updates received on `subscription`, as converted to lines by
+/// `to_lines`, until `count` updates were printed or `timeout` (seconds) expired.
+pub async fn print_subscription<T: prost::Message + Default>(
+    operation: &str,
+    mut subscription: tonic::Streaming<T>,
+    count: Option<usize>,
+    timeout: Option<f64>,
+    to_lines: impl Fn(T) -> Vec<String>,
+) -> io::Result<()> {
+    let deadline = match timeout.map(std::time::Duration::try_from_secs_f64) {
+        Some(Ok(timeout)) => Some(tokio::time::Instant::now() + timeout),
+        Some(Err(_)) => {
+            set_exit_code(ExitCode::Usage);
+            return print_error(operation, "Timeout must be a positive number of seconds");
+        }
+        None => None,
+    };
+
+    let mut received = 0;
+    while !count.is_some_and(|count| received >= count) {
+        let message = match deadline {
+            Some(deadline) => {
+                match tokio::time::timeout_at(deadline, subscription.message()).await {
+                    Ok(message) => message,
+                    Err(_) => {
+                        if count.is_some() {
+                            set_exit_code(ExitCode::Timeout);
+                            print_error(
+                                operation,
+                                format!("Timeout after receiving {received} updates"),
+                            )?;
+                        }
+                        return Ok(());
+                    }
+                }
+            }
+            None => subscription.message().await,
+        };
+        match message {
+            Ok(Some(response)) => {
+                for line in to_lines(response) {
+                    if count.is_some_and(|count| received >= count) {
+                        break;
+                    }
+                    println!("{line}");
+                    received += 1;
+                }
+            }
+            Ok(None) => {
+                set_exit_code(ExitCode::Unavailable);
+                return print_error(operation, "Server gone. Subscription stopped");
+            }
+            Err(status) => return print_resp_err(operation, &status),
+        }
+    }
+    Ok(())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Format the value of a signal in the selected output format. `human` is
+/// the line used for human readable output.
+pub fn format_value(
+    path: &str,
+    datapoint: Option<&proto::Datapoint>,
+    unit: &str,
+    human: impl fmt::Display,
+) -> String {
+    let value = datapoint
+        .and_then(|datapoint| datapoint.value.as_ref())
+        .map(value_to_json)
+        .unwrap_or(serde_json::Value::Null);
+    let timestamp = datapoint
+        .and_then(|datapoint| datapoint.timestamp.as_ref())
+        .map(|timestamp| timestamp.to_string());
+    match output_format() {
+        OutputFormat::Human => human.to_string(),
+        OutputFormat::Json => serde_json::json!({
+            "path": path,
+            "value": value,
+            "unit": unit,
+            "timestamp": timestamp,
+        })
+        .to_string(),
+        OutputFormat::Csv => {
+            let value = match value {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::String(value) => value,
+                value => value.to_string(),
+            };
+            [path, &value, unit, &timestamp.unwrap_or_default()]
+                .map(csv_field)
+                .join(",")
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -119,6 +473,8 @@ pub enum Protocol {
     KuksaValV1 = 1,
     #[clap(name = "sdv.databroker.v1")]
     SdvDatabrokerV1 = 2,
+    #[clap(name = "kuksa.val.v2")]
+    KuksaValV2 = 3,
 }
 
 pub fn set_connected_prompt(interface: &Arc<Interface<DefaultTerminal>>, text: String) {
@@ -226,6 +582,7 @@ pub fn print_logo(version: impl fmt::Display) {
 }
 
 pub fn print_resp_err(operation: impl AsRef<str>, err: &tonic::Status) -> io::Result<()> {
+    set_exit_code(err.code().into());
     let mut output = io::stderr().lock();
     output.write_fmt(format_args!(
         "{} {} {}",
@@ -242,6 +599,7 @@ pub fn print_resp_err(operation: impl AsRef<str>, err: &tonic::Status) -> io::Re
 }
 
 pub fn print_resp_err_fmt(operation: impl AsRef<str>, fmt: fmt::Arguments<'_>) -> io::Result<()> {
+    set_exit_code(ExitCode::Failure);
     let mut stderr = io::stderr().lock();
     let mut stdout = io::stdout().lock();
     write_resp_ok(&mut stderr, operation)?;
@@ -287,6 +645,7 @@ pub fn print_info(info: impl AsRef<str>) -> io::Result<()> {
 }
 
 pub fn print_error(operation: impl AsRef<str>, msg: impl AsRef<str>) -> io::Result<()> {
+    set_exit_code(ExitCode::Failure);
     let mut output = io::stderr().lock();
     output.write_fmt(format_args!(
         "{} {} {}\n",
@@ -299,6 +658,11 @@ pub fn print_error(operation: impl AsRef<str>, msg: impl AsRef<str>) -> io::Resu
     output.flush()
 }
 
+pub fn print_connection_error(operation: impl AsRef<str>, msg: impl AsRef<str>) -> io::Result<()> {
+    set_exit_code(ExitCode::Connection);
+    print_error(operation, msg)
+}
+
 pub fn split_first_word(s: &str) -> (&str, &str) {
     let s = s.trim();
 
@@ -412,3 +776,42 @@ pub fn to_uri(uri: impl AsRef<str>) -> Result<Uri, String> {
     parts.path_and_query = Some("/".parse().expect("uri path should be /"));
     tonic::transport::Uri::from_parts(parts).map_err(|err| format!("{err}"))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_batch_line() {
+        assert!(parse_batch_line("").unwrap().is_none());
+        assert!(parse_batch_line("  # comment").unwrap().is_none());
+
+        match parse_batch_line(
+            "publish Vehicle.Cabin.Infotainment.Media.Played.Track 'Yellow Submarine'",
+        ) {
+            Ok(Some(Commands::Publish { path, value })) => {
+                assert_eq!(path, "Vehicle.Cabin.Infotainment.Media.Played.Track");
+                assert_eq!(value, "Yellow Submarine");
+            }
+            other => panic!("Unexpected {other:?}"),
+        }
+        match parse_batch_line("subscribe Vehicle.Speed --count 3") {
+            Ok(Some(Commands::Subscribe { paths, count, .. })) => {
+                assert_eq!(paths, vec!["Vehicle.Speed"]);
+                assert_eq!(count, Some(3));
+            }
+            other => panic!("Unexpected {other:?}"),
+        }
+
+        assert!(parse_batch_line("unknown Vehicle.Speed").is_err());
+        assert!(parse_batch_line("batch commands.txt").is_err());
+        assert!(parse_batch_line("publish Vehicle.Speed \"50").is_err());
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("Vehicle.Speed"), "Vehicle.Speed");
+        assert_eq!(csv_field("[1,2]"), "\"[1,2]\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -13,6 +13,7 @@
 
 use databroker_proto::kuksa::val as proto;
 use kuksa::*;
+use kuksa_common::conversion::ConvertToV2;
 use kuksa_common::ClientTraitV1;
 
 use prost_types::Timestamp;
@@ -50,6 +51,11 @@ const CLI_COMMANDS: &[(&str, &str, &str)] = &[
         "[PATTERN]",
         "Fetch metadata. Provide PATTERN to list metadata of signals matching pattern.",
     ),
+    (
+        "export",
+        "[--values] [FILE]",
+        "Export the VSS tree of the server as JSON, optionally with current values, to FILE or stdout",
+    ),
     ("token", "<TOKEN>", "Use TOKEN as access token"),
     (
         "token-file",
@@ -61,6 +67,7 @@ const CLI_COMMANDS: &[(&str, &str, &str)] = &[
 ];
 
 fn print_usage(command: impl AsRef<str>) {
+    cli::set_exit_code(cli::ExitCode::Usage);
     for (cmd, usage, _) in CLI_COMMANDS {
         if *cmd == command.as_ref() {
             println!("Usage: {cmd} {usage}");
@@ -87,7 +94,7 @@ async fn handle_get_metadata(
             Ok(None)
         }
         Err(kuksa_common::ClientError::Connection(msg)) => {
-            cli::print_error("get metadata", msg)?;
+            cli::print_connection_error("get metadata", msg)?;
             Ok(None)
         }
         Err(kuksa_common::ClientError::Function(msg)) => {
@@ -141,7 +148,9 @@ async fn handle_actuate_command(
                 match client.set_target_values(datapoints).await {
                     Ok(_) => cli::print_resp_ok("actuate")?,
                     Err(ClientError::Status(status)) => cli::print_resp_err("actuate", &status)?,
-                    Err(ClientError::Connection(msg)) => cli::print_error("actuate", msg)?,
+                    Err(ClientError::Connection(msg)) => {
+                        cli::print_connection_error("actuate", msg)?
+                    }
                     Err(ClientError::Function(msg)) => {
                         cli::print_resp_err_fmt("actuate", format_args!("Error {msg:?}"))?
                     }
@@ -192,7 +201,7 @@ async fn handle_publish_command(
                         cli::print_resp_err("publish", &status)?
                     }
                     Err(kuksa_common::ClientError::Connection(msg)) => {
-                        cli::print_error("publish", msg)?
+                        cli::print_connection_error("publish", msg)?
                     }
                     Err(kuksa_common::ClientError::Function(msg)) => {
                         cli::print_resp_err_fmt("publish", format_args!("Error {msg:?}"))?;
@@ -205,6 +214,70 @@ async fn handle_publish_command(
     Ok(())
 }
 
+/// Format a datapoint in the selected output format
+fn format_datapoint(path: &str, datapoint: Option<proto::v1::Datapoint>, unit: &str) -> String {
+    match datapoint {
+        Some(datapoint) => {
+            let value = proto::v2::Datapoint {
+                timestamp: datapoint.timestamp,
+                value: Some(datapoint.clone().convert_to_v2()),
+            };
+            cli::format_value(
+                path,
+                Some(&value),
+                unit,
+                format_args!("{}: {} {}", path, DisplayDatapoint(datapoint), unit),
+            )
+        }
+        None => cli::format_value(path, None, unit, format_args!("{path}: NotAvailable")),
+    }
+}
+
+async fn handle_subscribe_command(
+    paths: Vec<String>,
+    count: Option<usize>,
+    timeout: Option<f64>,
+    client: &mut KuksaClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match client.subscribe(paths).await {
+        Ok(subscription) => {
+            cli::print_subscription(
+                "subscribe",
+                subscription,
+                count,
+                timeout,
+                |response: proto::v1::SubscribeResponse| {
+                    response
+                        .updates
+                        .into_iter()
+                        .filter_map(|update| update.entry)
+                        .filter(|entry| entry.value.is_some())
+                        .map(|entry| {
+                            let unit = entry
+                                .metadata
+                                .and_then(|meta| meta.unit)
+                                .unwrap_or_default();
+                            format_datapoint(&entry.path, entry.value, &unit)
+                        })
+                        .collect()
+                },
+            )
+            .await?
+        }
+        Err(kuksa_common::ClientError::Status(status)) => {
+            cli::print_resp_err("subscribe", &status)?
+        }
+        Err(kuksa_common::ClientError::Connection(msg)) => {
+            cli::print_connection_error("subscribe", msg)?
+        }
+        Err(kuksa_common::ClientError::Function(msg)) => {
+            cli::print_resp_err_fmt("subscribe", format_args!("Error {msg:?}"))?
+        }
+    }
+
+    Ok(())
+}
+
 async fn handle_get_command(
     paths: Vec<String>,
     client: &mut KuksaClient,
@@ -213,27 +286,18 @@ async fn handle_get_command(
         Ok(data_entries) => {
             cli::print_resp_ok("get")?;
             for entry in data_entries {
-                if let Some(val) = entry.value {
-                    println!(
-                        "{}: {} {}",
-                        entry.path,
-                        DisplayDatapoint(val),
-                        entry
-                            .metadata
-                            .and_then(|meta| meta.unit)
-                            .map(|unit| unit.to_string())
-                            .unwrap_or_else(|| "".to_string())
-                    );
-                } else {
-                    println!("{}: NotAvailable", entry.path);
-                }
+                let unit = entry
+                    .metadata
+                    .and_then(|meta| meta.unit)
+                    .unwrap_or_default();
+                println!("{}", format_datapoint(&entry.path, entry.value, &unit));
             }
         }
         Err(kuksa_common::ClientError::Status(err)) => {
             cli::print_resp_err("get", &err)?;
         }
         Err(kuksa_common::ClientError::Connection(msg)) => {
-            cli::print_error("get", msg)?;
+            cli::print_connection_error("get", msg)?;
         }
         Err(kuksa_common::ClientError::Function(msg)) => {
             cli::print_resp_err_fmt("get", format_args!("Error {msg:?}"))?;
@@ -243,6 +307,96 @@ async fn handle_get_command(
     Ok(())
 }
 
+pub(crate) async fn handle_export_command(
+    root: String,
+    include_values: bool,
+    file: Option<&str>,
+    client: &mut kuksa_common::Client,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Exporting is only supported by kuksa.val.v2, on the same connection
+    let channel = match client.get_channel().await {
+        Ok(channel) => channel.clone(),
+        Err(err) => {
+            cli::print_error("export", format!("{err}"))?;
+            return Ok(());
+        }
+    };
+    let mut val_client =
+        proto::v2::val_client::ValClient::with_interceptor(channel, client.get_auth_interceptor());
+    let request = proto::v2::ExportVssRequest {
+        root,
+        include_values,
+    };
+    match val_client.export_vss(request).await {
+        Ok(response) => {
+            let vss = response.into_inner().vss;
+            match file {
+                Some(file) => match std::fs::write(file, format!("{vss}\n")) {
+                    Ok(()) => {
+                        cli::print_resp_ok("export")?;
+                        cli::print_info(format!("VSS tree written to \"{file}\""))?;
+                    }
+                    Err(err) => {
+                        cli::print_error("export", format!("Failed to write \"{file}\": {err}"))?
+                    }
+                },
+                None => {
+                    cli::print_resp_ok("export")?;
+                    println!("{vss}");
+                }
+            }
+        }
+        Err(status) => cli::print_resp_err("export", &status)?,
+    }
+
+    Ok(())
+}
+
+/// Run a non-interactive command
+async fn run_command(
+    command: cli::Commands,
+    client: &mut KuksaClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        cli::Commands::Get { paths } => handle_get_command(paths, client).await,
+        cli::Commands::Subscribe {
+            paths,
+            count,
+            timeout,
+        } => handle_subscribe_command(paths, count, timeout, client).await,
+        cli::Commands::Set { .. } => {
+            cli::set_exit_code(cli::ExitCode::Usage);
+            cli::print_error("set", "The set command is not implemented for kuksa.val.v1 protocol because it is not intended to be named like this anymore. Use publish instead.")?;
+            Ok(())
+        }
+        cli::Commands::Actuate { path, value } => {
+            handle_actuate_command(&path, &value, client).await
+        }
+        cli::Commands::Publish { path, value } => {
+            handle_publish_command(&path, &value, client).await
+        }
+        cli::Commands::Export { root, values, file } => {
+            handle_export_command(root, values, file.as_deref(), &mut client.basic_client).await
+        }
+        cli::Commands::Provide { .. } => {
+            cli::print_error("provide", "Providing is only supported by kuksa.val.v2")?;
+            Ok(())
+        }
+        cli::Commands::Record { .. } | cli::Commands::Replay { .. } => {
+            cli::print_error(
+                "trace",
+                "Recording and replaying is only supported by kuksa.val.v2",
+            )?;
+            Ok(())
+        }
+        cli::Commands::Batch { .. } => {
+            cli::set_exit_code(cli::ExitCode::Usage);
+            cli::print_error("batch", "Batches can't be nested")?;
+            Ok(())
+        }
+    }
+}
+
 pub async fn kuksa_main(_cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     println!("Using {VERSION}");
 
@@ -262,8 +416,7 @@ pub async fn kuksa_main(_cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     let mut client = KuksaClient::new(kuksa_common::to_uri(cli.get_server())?);
 
     if let Some(token_filename) = cli.get_token_file() {
-        let token = std::fs::read_to_string(token_filename)?;
-        client.basic_client.set_access_token(token)?;
+        client.basic_client.set_token_file(token_filename)?;
     }
 
     #[cfg(feature = "tls")]
@@ -302,17 +455,24 @@ pub async fn kuksa_main(_cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     });
 
     match cli.get_command() {
-        Some(cli::Commands::Get { paths }) => {
-            return handle_get_command(paths, &mut client).await;
-        }
-        Some(cli::Commands::Set { path: _, value: _ }) => {
-            unimplemented!("The set command is not implemented for kuksa.val.v1 protocol because it is not intended to be named like this anymore. Use publish instead.");
-        }
-        Some(cli::Commands::Actuate { path, value }) => {
-            return handle_actuate_command(&path, &value, &mut client).await;
+        Some(cli::Commands::Batch { file, keep_going }) => {
+            for line in cli::read_batch_lines(&file)? {
+                match cli::parse_batch_line(&line) {
+                    Ok(Some(command)) => run_command(command, &mut client).await?,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        cli::set_exit_code(cli::ExitCode::Usage);
+                        cli::print_error("batch", err)?;
+                    }
+                }
+                if !keep_going && cli::exit_code() != 0 {
+                    break;
+                }
+            }
+            return Ok(());
         }
-        Some(cli::Commands::Publish { path, value }) => {
-            return handle_publish_command(&path, &value, &mut client).await;
+        Some(command) => {
+            return run_command(command, &mut client).await;
         }
         None => {
             // No subcommand => run interactive client
@@ -405,7 +565,7 @@ pub async fn kuksa_main(_cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                                     cli::print_resp_err(cmd, &err)?;
                                 }
                                 Err(kuksa_common::ClientError::Connection(msg)) => {
-                                    cli::print_error(cmd, msg)?;
+                                    cli::print_connection_error(cmd, msg)?;
                                 }
                                 Err(kuksa_common::ClientError::Function(msg)) => {
                                     cli::print_resp_err_fmt(cmd, format_args!("Error {msg:?}"))?;
@@ -445,28 +605,21 @@ pub async fn kuksa_main(_cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                             }
 
                             let token_filename = args.trim();
-                            match std::fs::read_to_string(token_filename) {
-                                Ok(token) => match client.basic_client.set_access_token(token) {
-                                    Ok(()) => {
-                                        cli::print_info("Access token set.")?;
-                                        if let Some(entries) =
-                                            handle_get_metadata(vec![], &mut client).await.unwrap()
-                                        {
-                                            interface.set_completer(Arc::new(
-                                                CliCompleter::from_metadata(&entries),
-                                            ));
-                                        }
-                                    }
-                                    Err(err) => {
-                                        cli::print_error(cmd, format!("Malformed token: {err}"))?
+                            match client.basic_client.set_token_file(token_filename) {
+                                Ok(()) => {
+                                    cli::print_info("Access token set.")?;
+                                    if let Some(entries) =
+                                        handle_get_metadata(vec![], &mut client).await.unwrap()
+                                    {
+                                        interface.set_completer(Arc::new(
+                                            CliCompleter::from_metadata(&entries),
+                                        ));
                                     }
-                                },
-                                Err(err) => cli::print_error(
-                                    cmd,
-                                    format!(
-                                        "Failed to open token file \"{token_filename}\": {err}"
-                                    ),
-                                )?,
+                                }
+                                Err(kuksa_common::TokenError::MalformedTokenError(err)) => {
+                                    cli::print_error(cmd, format!("Malformed token: {err}"))?
+                                }
+                                Err(err) => cli::print_error(cmd, format!("{err}"))?,
                             }
                         }
                         "actuate" => {
@@ -609,7 +762,7 @@ pub async fn kuksa_main(_cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                                     cli::print_resp_err(cmd, &status)?
                                 }
                                 Err(kuksa_common::ClientError::Connection(msg)) => {
-                                    cli::print_error(cmd, msg)?
+                                    cli::print_connection_error(cmd, msg)?
                                 }
                                 Err(kuksa_common::ClientError::Function(msg)) => {
                                     cli::print_resp_err_fmt(cmd, format_args!("Error {msg:?}"))?
@@ -669,6 +822,29 @@ pub async fn kuksa_main(_cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                                 }
                             };
                         }
+                        "export" => {
+                            interface.add_history_unique(line.clone());
+
+                            let (include_values, file) =
+                                match args.split_whitespace().collect::<Vec<_>>()[..] {
+                                    [] => (false, None),
+                                    ["--values"] => (true, None),
+                                    ["--values", file] | [file, "--values"] => (true, Some(file)),
+                                    [file] => (false, Some(file)),
+                                    _ => {
+                                        print_usage(cmd);
+                                        continue;
+                                    }
+                                };
+
+                            handle_export_command(
+                                String::new(),
+                                include_values,
+                                file,
+                                &mut client.basic_client,
+                            )
+                            .await?
+                        }
                         "metadata" => {
                             interface.add_history_unique(line.clone());
 
@@ -752,7 +928,8 @@ pub async fn kuksa_main(_cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-struct CliCompleter {
+pub(crate) struct CliCompleter {
+    commands: &'static [(&'static str, &'static str, &'static str)],
     paths: PathPart,
 }
 
@@ -774,16 +951,32 @@ impl PathPart {
 }
 impl CliCompleter {
     fn new() -> CliCompleter {
+        CliCompleter::with_commands(CLI_COMMANDS)
+    }
+
+    /// Completer for the given (command, usage, help) list, without any paths
+    pub(crate) fn with_commands(
+        commands: &'static [(&'static str, &'static str, &'static str)],
+    ) -> CliCompleter {
         CliCompleter {
+            commands,
             paths: PathPart::new(),
         }
     }
 
-    fn from_metadata(entries: &Vec<proto::v1::DataEntry>) -> CliCompleter {
+    fn from_metadata(entries: &[proto::v1::DataEntry]) -> CliCompleter {
+        CliCompleter::new().with_paths(entries.iter().map(|entry| entry.path.as_str()))
+    }
+
+    /// Complete signal paths from `paths`
+    pub(crate) fn with_paths<'a>(
+        mut self,
+        paths: impl IntoIterator<Item = &'a str>,
+    ) -> CliCompleter {
         let mut root = PathPart::new();
-        for entry in entries {
+        for path in paths {
             let mut parent = &mut root;
-            let parts = entry.path.split('.');
+            let parts = path.split('.');
             for part in parts {
                 let full_path = match parent.full_path.as_str() {
                     "" => part.to_owned(),
@@ -801,7 +994,8 @@ impl CliCompleter {
                 parent = entry;
             }
         }
-        CliCompleter { paths: root }
+        self.paths = root;
+        self
     }
 
     fn complete_entry_path(&self, word: &str) -> Option<Vec<Completion>> {
@@ -889,8 +1083,9 @@ impl<Term: Terminal> Completer<Term> for CliCompleter {
             None => {
                 let mut compls = Vec::new();
 
-                for &(cmd, _, _) in CLI_COMMANDS {
-                    if cmd.starts_with(word) {
+                for &(cmd, _, _) in self.commands {
+                    // Commands may be listed once per usage variant
+                    if cmd.starts_with(word) && !compls.iter().any(|c| c.completion == cmd) {
                         compls.push(Completion {
                             completion: cmd.to_owned(),
                             display: None,
@@ -909,7 +1104,25 @@ impl<Term: Terminal> Completer<Term> for CliCompleter {
                     None
                 }
             }
-            Some("get") | Some("metadata") | Some("gettarget") => self.complete_entry_path(word),
+            Some("batch-actuate") => {
+                // Every other parameter is a path, followed by its value
+                if words.count() % 2 == 0 {
+                    self.complete_entry_path(word)
+                } else {
+                    None
+                }
+            }
+            Some("provide") => {
+                // Paths follow the subcommand
+                if words.count() >= 1 {
+                    self.complete_entry_path(word)
+                } else {
+                    None
+                }
+            }
+            Some("get") | Some("metadata") | Some("gettarget") | Some("watch") => {
+                self.complete_entry_path(word)
+            }
             Some("subscribe") => {
                 if words.count() == 0 {
                     self.complete_entry_path(word)
@@ -917,7 +1130,7 @@ impl<Term: Terminal> Completer<Term> for CliCompleter {
                     None
                 }
             }
-            Some("token-file") => {
+            Some("token-file") | Some("export") => {
                 let path_completer = linefeed::complete::PathCompleter;
                 path_completer.complete(word, prompter, start, _end)
             }
@@ -926,11 +1139,11 @@ impl<Term: Terminal> Completer<Term> for CliCompleter {
     }
 }
 
-struct DisplayDataType(Option<proto::v1::DataType>);
-struct DisplayEntryType(Option<proto::v1::EntryType>);
-struct DisplayDatapoint(proto::v1::Datapoint);
+pub(crate) struct DisplayDataType(Option<proto::v1::DataType>);
+pub(crate) struct DisplayEntryType(Option<proto::v1::EntryType>);
+pub(crate) struct DisplayDatapoint(pub(crate) proto::v1::Datapoint);
 
-fn display_array<T>(f: &mut fmt::Formatter<'_>, array: &[T]) -> fmt::Result
+pub(crate) fn display_array<T>(f: &mut fmt::Formatter<'_>, array: &[T]) -> fmt::Result
 where
     T: fmt::Display,
 {
@@ -965,6 +1178,10 @@ impl fmt::Display for DisplayDatapoint {
                 proto::v1::datapoint::Value::Uint64Array(array) => display_array(f, &array.values),
                 proto::v1::datapoint::Value::FloatArray(array) => display_array(f, &array.values),
                 proto::v1::datapoint::Value::DoubleArray(array) => display_array(f, &array.values),
+                proto::v1::datapoint::Value::TimestampValue(value) => f.pad(&format!("{value}")),
+                proto::v1::datapoint::Value::TimestampArray(array) => {
+                    display_array(f, &array.values)
+                }
             },
             None => f.pad("None"),
         }
@@ -1001,7 +1218,7 @@ impl fmt::Display for DisplayDataType {
     }
 }
 
-fn try_into_data_value(
+pub(crate) fn try_into_data_value(
     input: &str,
     data_type: proto::v1::DataType,
 ) -> Result<proto::v1::datapoint::Value, ParseError> {
@@ -1127,6 +1344,16 @@ fn try_into_data_value(
             )),
             Err(err) => Err(err),
         },
+        proto::v1::DataType::Timestamp => match input.parse::<prost_types::Timestamp>() {
+            Ok(value) => Ok(proto::v1::datapoint::Value::TimestampValue(value)),
+            Err(_) => Err(ParseError {}),
+        },
+        proto::v1::DataType::TimestampArray => match cli::get_array_from_input(input.to_owned()) {
+            Ok(value) => Ok(proto::v1::datapoint::Value::TimestampArray(
+                proto::v1::TimestampArray { values: value },
+            )),
+            Err(err) => Err(err),
+        },
         _ => Err(ParseError {}),
     }
 }
@@ -1200,6 +1427,22 @@ mod test {
         assert!(try_into_data_value("33000", proto::v1::DataType::Int16).is_err());
         assert!(try_into_data_value("-33000", proto::v1::DataType::Int16).is_err());
         assert!(try_into_data_value("-32000.1", proto::v1::DataType::Int16).is_err());
+
+        // Timestamp
+        assert!(matches!(
+            try_into_data_value("2024-05-01T12:00:00Z", proto::v1::DataType::Timestamp),
+            Ok(proto::v1::datapoint::Value::TimestampValue(value)) if value.seconds == 1714564800 && value.nanos == 0
+        ));
+        assert!(matches!(
+            try_into_data_value("2024-05-01T14:00:00.5+02:00", proto::v1::DataType::Timestamp),
+            Ok(proto::v1::datapoint::Value::TimestampValue(value)) if value.seconds == 1714564800 && value.nanos == 500_000_000
+        ));
+        assert!(try_into_data_value("yesterday", proto::v1::DataType::Timestamp).is_err());
+        // TimestampArray
+        assert!(matches!(
+            try_into_data_value("[2024-05-01T12:00:00Z, 1970-01-01T00:00:00Z]", proto::v1::DataType::TimestampArray),
+            Ok(proto::v1::datapoint::Value::TimestampArray(array)) if array.values.len() == 2 && array.values[1].seconds == 0
+        ));
     }
 
     #[test]
@@ -0,0 +1,1679 @@
+/********************************************************************************
+* Copyright (c) 2025 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+use databroker_proto::kuksa::val as proto;
+use kuksa_val_v2::provider::{Provider, ProviderBuilder};
+use kuksa_val_v2::*;
+
+use tokio_stream::StreamExt;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ansi_term::Color;
+
+use crate::cli::ParseError;
+use crate::cli::{self, Cli};
+use crate::kuksa_cli::{
+    display_array, handle_export_command, CliCompleter, DisplayDataType, DisplayEntryType,
+};
+use crate::trace;
+use crate::watch;
+use linefeed::{Command, Interface, ReadResult};
+
+const VERSION: &str = "kuksa.val.v2";
+const TIMEOUT: Duration = Duration::from_millis(500);
+const SET_UNSUPPORTED: &str =
+    "The set command is not supported by the kuksa.val.v2 protocol. Use publish or actuate instead.";
+
+const CLI_COMMANDS: &[(&str, &str, &str)] = &[
+    ("connect", "[URI]", "Connect to server"),
+    ("get", "<PATH|ID> [[PATH|ID] ...]", "Get signal value(s)"),
+    ("actuate", "<PATH|ID> <VALUE>", "Set actuator signal"),
+    (
+        "batch-actuate",
+        "<PATH|ID> <VALUE> [[PATH|ID] [VALUE] ...]",
+        "Set several actuator signals at once",
+    ),
+    (
+        "subscribe",
+        "<PATH|ID> [[PATH|ID] ...]",
+        "Subscribe to signals. Subscribes by ID if only IDs are given",
+    ),
+    (
+        "watch",
+        "<PATH|ID|PATTERN> [[PATH|ID|PATTERN] ...]",
+        "Show a live table of signals, in which the selected actuator can be actuated",
+    ),
+    ("publish", "<PATH|ID> <VALUE>", "Publish signal value"),
+    (
+        "metadata",
+        "[PATTERN]",
+        "Fetch metadata. Provide PATTERN to list metadata of signals matching pattern.",
+    ),
+    ("info", "", "Get server information"),
+    (
+        "provide",
+        "<actuation [--auto-ack] | signal> <PATH|ID> [[PATH|ID] ...]",
+        "Act as provider of actuators or signals",
+    ),
+    (
+        "provide",
+        "publish <PATH|ID> <VALUE>",
+        "Publish a value of a provided signal",
+    ),
+    (
+        "provide",
+        "<auto-ack <on|off> | close>",
+        "Toggle acknowledging actuations as current value, or stop providing",
+    ),
+    (
+        "export",
+        "[--values] [FILE]",
+        "Export the VSS tree of the server as JSON, optionally with current values, to FILE or stdout",
+    ),
+    ("token", "<TOKEN>", "Use TOKEN as access token"),
+    (
+        "token-file",
+        "<FILE>",
+        "Use content of FILE as access token",
+    ),
+    ("help", "", "You're looking at it."),
+    ("quit", "", "Quit"),
+];
+
+/// A signal as given on the command line, either by path or by numeric ID
+#[derive(Debug, PartialEq)]
+enum SignalRef<'a> {
+    Path(&'a str),
+    Id(i32),
+}
+
+impl<'a> From<&'a str> for SignalRef<'a> {
+    fn from(input: &'a str) -> Self {
+        match input.parse::<i32>() {
+            Ok(id) => SignalRef::Id(id),
+            Err(_) => SignalRef::Path(input),
+        }
+    }
+}
+
+fn print_usage(command: impl AsRef<str>) {
+    cli::set_exit_code(cli::ExitCode::Usage);
+    for (cmd, usage, _) in CLI_COMMANDS {
+        if *cmd == command.as_ref() {
+            println!("Usage: {cmd} {usage}");
+        }
+    }
+}
+
+pub(crate) fn print_client_error(
+    cmd: &str,
+    err: ClientError,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match err {
+        ClientError::Status(status) => cli::print_resp_err(cmd, &status)?,
+        ClientError::Connection(msg) => cli::print_connection_error(cmd, msg)?,
+        ClientError::Function(msg) => cli::print_resp_err_fmt(cmd, format_args!("Error {msg:?}"))?,
+    }
+    Ok(())
+}
+
+/// Split `args` into (signal, value) pairs, `None` if a value is missing
+fn parse_batch_args(args: &str) -> Option<Vec<(&str, &str)>> {
+    let words = args.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() || words.len() % 2 != 0 {
+        return None;
+    }
+    Some(words.chunks(2).map(|pair| (pair[0], pair[1])).collect())
+}
+
+async fn handle_list_metadata(
+    cmd: &str,
+    root: &str,
+    client: &mut KuksaClientV2,
+) -> Result<Option<Vec<proto::v2::Metadata>>, Box<dyn std::error::Error>> {
+    match client.list_metadata((root.to_owned(), String::new())).await {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(err) => {
+            print_client_error(cmd, err)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Look up the metadata of the signals referenced by `signals`, in order.
+/// Errors are printed and result in `None`.
+async fn resolve_signals(
+    cmd: &str,
+    signals: &[&str],
+    client: &mut KuksaClientV2,
+) -> Result<Option<Vec<proto::v2::Metadata>>, Box<dyn std::error::Error>> {
+    let mut all_signals = None;
+    let mut resolved = Vec::with_capacity(signals.len());
+    for &signal in signals {
+        let metadata = match SignalRef::from(signal) {
+            SignalRef::Id(id) => {
+                if all_signals.is_none() {
+                    all_signals = handle_list_metadata(cmd, "**", client).await?;
+                }
+                all_signals
+                    .iter()
+                    .flatten()
+                    .find(|metadata| metadata.id == id)
+                    .cloned()
+            }
+            SignalRef::Path(path) => handle_list_metadata(cmd, path, client)
+                .await?
+                .and_then(|entries| entries.into_iter().find(|metadata| metadata.path == path)),
+        };
+        match metadata {
+            Some(metadata) => resolved.push(metadata),
+            None => {
+                cli::print_error(cmd, format!("{signal} is not a known signal"))?;
+                return Ok(None);
+            }
+        }
+    }
+    Ok(Some(resolved))
+}
+
+/// Like [`resolve_signals`], but signals containing wildcards are looked up
+/// as pattern. Signals matched several times are only included once.
+async fn resolve_watched_signals(
+    cmd: &str,
+    signals: &[&str],
+    client: &mut KuksaClientV2,
+) -> Result<Option<Vec<proto::v2::Metadata>>, Box<dyn std::error::Error>> {
+    let mut resolved = Vec::new();
+    let mut ids = HashSet::new();
+    for &signal in signals {
+        let metadata = if signal.contains('*') {
+            match handle_list_metadata(cmd, signal, client).await? {
+                Some(metadata) if !metadata.is_empty() => metadata,
+                Some(_) => {
+                    cli::print_error(cmd, format!("{signal} does not match any signal"))?;
+                    return Ok(None);
+                }
+                None => return Ok(None),
+            }
+        } else {
+            match resolve_signals(cmd, &[signal], client).await? {
+                Some(metadata) => metadata,
+                None => return Ok(None),
+            }
+        };
+        resolved.extend(
+            metadata
+                .into_iter()
+                .filter(|metadata| ids.insert(metadata.id)),
+        );
+    }
+    Ok(Some(resolved))
+}
+
+fn try_into_value(
+    cmd: &str,
+    input: &str,
+    metadata: &proto::v2::Metadata,
+) -> Result<Option<proto::v2::Value>, Box<dyn std::error::Error>> {
+    let data_type = proto::v2::DataType::try_from(metadata.data_type)
+        .unwrap_or(proto::v2::DataType::Unspecified);
+    match try_into_typed_value(input, data_type) {
+        Ok(value) => Ok(Some(value)),
+        Err(_) => {
+            cli::print_error(cmd, format!("Could not parse \"{input}\" as {data_type:?}"))?;
+            Ok(None)
+        }
+    }
+}
+
+fn parse<T: FromStr>(input: &str) -> Result<T, ParseError> {
+    input.parse().map_err(|_| ParseError {})
+}
+
+/// Parses `input` as value of `data_type`. Structs are given as JSON object
+/// with the type and value of each property, e.g.
+/// `{"Latitude": {"double": 48.1}, "Name": {"string": "Home"}}`, and arrays
+/// of structs as JSON array of such objects.
+pub(crate) fn try_into_typed_value(
+    input: &str,
+    data_type: proto::v2::DataType,
+) -> Result<proto::v2::Value, ParseError> {
+    use proto::v2::value::TypedValue;
+    use proto::v2::DataType;
+
+    if input == "NotAvailable" {
+        return Ok(proto::v2::Value { typed_value: None });
+    }
+
+    let typed_value = match data_type {
+        DataType::String => TypedValue::String(input.to_owned()),
+        DataType::Boolean => TypedValue::Bool(parse(input)?),
+        DataType::Int8 => TypedValue::Int32(parse::<i8>(input)?.into()),
+        DataType::Int16 => TypedValue::Int32(parse::<i16>(input)?.into()),
+        DataType::Int32 => TypedValue::Int32(parse(input)?),
+        DataType::Int64 => TypedValue::Int64(parse(input)?),
+        DataType::Uint8 => TypedValue::Uint32(parse::<u8>(input)?.into()),
+        DataType::Uint16 => TypedValue::Uint32(parse::<u16>(input)?.into()),
+        DataType::Uint32 => TypedValue::Uint32(parse(input)?),
+        DataType::Uint64 => TypedValue::Uint64(parse(input)?),
+        DataType::Float => TypedValue::Float(parse(input)?),
+        DataType::Double => TypedValue::Double(parse(input)?),
+        DataType::Timestamp => TypedValue::Timestamp(parse(input)?),
+        DataType::StringArray => TypedValue::StringArray(proto::v2::StringArray {
+            values: cli::get_array_from_input(input.to_owned())?,
+        }),
+        DataType::BooleanArray => TypedValue::BoolArray(proto::v2::BoolArray {
+            values: cli::get_array_from_input(input.to_owned())?,
+        }),
+        DataType::Int8Array => TypedValue::Int32Array(proto::v2::Int32Array {
+            values: cli::get_array_from_input::<i8>(input.to_owned())?
+                .into_iter()
+                .map(i32::from)
+                .collect(),
+        }),
+        DataType::Int16Array => TypedValue::Int32Array(proto::v2::Int32Array {
+            values: cli::get_array_from_input::<i16>(input.to_owned())?
+                .into_iter()
+                .map(i32::from)
+                .collect(),
+        }),
+        DataType::Int32Array => TypedValue::Int32Array(proto::v2::Int32Array {
+            values: cli::get_array_from_input(input.to_owned())?,
+        }),
+        DataType::Int64Array => TypedValue::Int64Array(proto::v2::Int64Array {
+            values: cli::get_array_from_input(input.to_owned())?,
+        }),
+        DataType::Uint8Array => TypedValue::Uint32Array(proto::v2::Uint32Array {
+            values: cli::get_array_from_input::<u8>(input.to_owned())?
+                .into_iter()
+                .map(u32::from)
+                .collect(),
+        }),
+        DataType::Uint16Array => TypedValue::Uint32Array(proto::v2::Uint32Array {
+            values: cli::get_array_from_input::<u16>(input.to_owned())?
+                .into_iter()
+                .map(u32::from)
+                .collect(),
+        }),
+        DataType::Uint32Array => TypedValue::Uint32Array(proto::v2::Uint32Array {
+            values: cli::get_array_from_input(input.to_owned())?,
+        }),
+        DataType::Uint64Array => TypedValue::Uint64Array(proto::v2::Uint64Array {
+            values: cli::get_array_from_input(input.to_owned())?,
+        }),
+        DataType::FloatArray => TypedValue::FloatArray(proto::v2::FloatArray {
+            values: cli::get_array_from_input(input.to_owned())?,
+        }),
+        DataType::DoubleArray => TypedValue::DoubleArray(proto::v2::DoubleArray {
+            values: cli::get_array_from_input(input.to_owned())?,
+        }),
+        DataType::TimestampArray => TypedValue::TimestampArray(proto::v2::TimestampArray {
+            values: cli::get_array_from_input(input.to_owned())?,
+        }),
+        DataType::Struct => {
+            let json = serde_json::from_str(input).map_err(|_| ParseError {})?;
+            TypedValue::StructValue(try_into_struct_value(&json)?)
+        }
+        DataType::StructArray => {
+            let json: Vec<serde_json::Value> =
+                serde_json::from_str(input).map_err(|_| ParseError {})?;
+            TypedValue::StructArray(proto::v2::StructArray {
+                values: json
+                    .iter()
+                    .map(try_into_struct_value)
+                    .collect::<Result<_, _>>()?,
+            })
+        }
+        DataType::Unspecified => return Err(ParseError {}),
+    };
+    Ok(proto::v2::Value {
+        typed_value: Some(typed_value),
+    })
+}
+
+/// Parses a struct given as JSON object, see [`try_into_typed_value`]
+fn try_into_struct_value(json: &serde_json::Value) -> Result<proto::v2::StructValue, ParseError> {
+    let properties = json.as_object().ok_or(ParseError {})?;
+    let mut fields = HashMap::new();
+    for (name, typed) in properties {
+        let Some((data_type, value)) = typed
+            .as_object()
+            .filter(|typed| typed.len() == 1)
+            .and_then(|typed| typed.iter().next())
+        else {
+            return Err(ParseError {});
+        };
+        let data_type =
+            proto::v2::DataType::from_str_name(&format!("DATA_TYPE_{}", data_type.to_uppercase()))
+                .ok_or(ParseError {})?;
+        let input = match value {
+            serde_json::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        fields.insert(name.clone(), try_into_typed_value(&input, data_type)?);
+    }
+    Ok(proto::v2::StructValue { fields })
+}
+
+pub(crate) struct DisplayValue(pub(crate) proto::v2::Value);
+
+fn display_struct(f: &mut fmt::Formatter<'_>, value: &proto::v2::StructValue) -> fmt::Result {
+    // Sorted by name for a stable output
+    let mut fields: Vec<_> = value.fields.iter().collect();
+    fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+    f.write_str("{")?;
+    let mut delimiter = "";
+    for (name, value) in fields {
+        write!(f, "{delimiter}{name}: {}", DisplayValue(value.clone()))?;
+        delimiter = ", ";
+    }
+    f.write_str("}")
+}
+
+impl fmt::Display for DisplayValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use proto::v2::value::TypedValue;
+        match &self.0.typed_value {
+            Some(value) => match value {
+                TypedValue::Bool(value) => f.pad(&format!("{value}")),
+                TypedValue::Int32(value) => f.pad(&format!("{value}")),
+                TypedValue::Int64(value) => f.pad(&format!("{value}")),
+                TypedValue::Uint32(value) => f.pad(&format!("{value}")),
+                TypedValue::Uint64(value) => f.pad(&format!("{value}")),
+                TypedValue::Float(value) => f.pad(&format!("{value:.2}")),
+                TypedValue::Double(value) => f.pad(&format!("{value}")),
+                TypedValue::String(value) => f.pad(&format!("'{value}'")),
+                TypedValue::Timestamp(value) => f.pad(&format!("{value}")),
+                TypedValue::StringArray(array) => display_array(f, &array.values),
+                TypedValue::BoolArray(array) => display_array(f, &array.values),
+                TypedValue::Int32Array(array) => display_array(f, &array.values),
+                TypedValue::Int64Array(array) => display_array(f, &array.values),
+                TypedValue::Uint32Array(array) => display_array(f, &array.values),
+                TypedValue::Uint64Array(array) => display_array(f, &array.values),
+                TypedValue::FloatArray(array) => display_array(f, &array.values),
+                TypedValue::DoubleArray(array) => display_array(f, &array.values),
+                TypedValue::TimestampArray(array) => display_array(f, &array.values),
+                TypedValue::StructValue(value) => display_struct(f, value),
+                TypedValue::StructArray(array) => {
+                    f.write_str("[")?;
+                    let mut delimiter = "";
+                    for value in &array.values {
+                        f.write_str(delimiter)?;
+                        display_struct(f, value)?;
+                        delimiter = ", ";
+                    }
+                    f.write_str("]")
+                }
+            },
+            None => f.pad("NotAvailable"),
+        }
+    }
+}
+
+pub(crate) fn format_datapoint(
+    path: &str,
+    datapoint: Option<proto::v2::Datapoint>,
+    unit: Option<&str>,
+) -> String {
+    let unit = unit.unwrap_or_default();
+    let value = datapoint
+        .as_ref()
+        .and_then(|datapoint| datapoint.value.clone())
+        .filter(|value| value.typed_value.is_some());
+    let human = match value {
+        Some(value) => format!("{}: {} {}", path, DisplayValue(value), unit),
+        None => format!("{path}: NotAvailable"),
+    };
+    cli::format_value(path, datapoint.as_ref(), unit, human)
+}
+
+/// Format the entries of a subscription update, sorted by path
+fn format_entries(
+    mut entries: Vec<(String, proto::v2::Datapoint)>,
+    units: &HashMap<String, String>,
+) -> Vec<String> {
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries
+        .into_iter()
+        .map(|(path, datapoint)| {
+            let unit = units.get(&path).map(String::as_str);
+            format_datapoint(&path, Some(datapoint), unit)
+        })
+        .collect()
+}
+
+/// Whether all `signals` are given by ID
+fn all_ids(signals: &[&str]) -> bool {
+    signals
+        .iter()
+        .all(|&signal| matches!(SignalRef::from(signal), SignalRef::Id(_)))
+}
+
+async fn handle_subscribe_command(
+    signals: &[&str],
+    count: Option<usize>,
+    timeout: Option<f64>,
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    const CMD: &str = "subscribe";
+
+    let Some(metadata) = resolve_signals(CMD, signals, client).await? else {
+        return Ok(());
+    };
+    let units = metadata
+        .iter()
+        .map(|metadata| (metadata.path.clone(), metadata.unit.clone()))
+        .collect::<HashMap<_, _>>();
+
+    if all_ids(signals) {
+        let ids = metadata.iter().map(|metadata| metadata.id).collect();
+        let paths = metadata
+            .iter()
+            .map(|metadata| (metadata.id, metadata.path.clone()))
+            .collect::<HashMap<_, _>>();
+        match client.subscribe_by_id(ids, None).await {
+            Ok(subscription) => {
+                cli::print_subscription(
+                    CMD,
+                    subscription,
+                    count,
+                    timeout,
+                    |resp: proto::v2::SubscribeByIdResponse| {
+                        let entries = resp
+                            .entries
+                            .into_iter()
+                            .map(|(id, datapoint)| {
+                                let path =
+                                    paths.get(&id).cloned().unwrap_or_else(|| id.to_string());
+                                (path, datapoint)
+                            })
+                            .collect();
+                        format_entries(entries, &units)
+                    },
+                )
+                .await?
+            }
+            Err(err) => print_client_error(CMD, err)?,
+        }
+    } else {
+        let paths = metadata
+            .iter()
+            .map(|metadata| metadata.path.clone())
+            .collect();
+        match client.subscribe(paths, None).await {
+            Ok(subscription) => {
+                cli::print_subscription(
+                    CMD,
+                    subscription,
+                    count,
+                    timeout,
+                    |resp: proto::v2::SubscribeResponse| {
+                        format_entries(resp.entries.into_iter().collect(), &units)
+                    },
+                )
+                .await?
+            }
+            Err(err) => print_client_error(CMD, err)?,
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_get_command(
+    signals: &[&str],
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(metadata) = resolve_signals("get", signals, client).await? else {
+        return Ok(());
+    };
+    let paths = metadata
+        .iter()
+        .map(|metadata| metadata.path.clone())
+        .collect();
+    match client.get_values(paths).await {
+        Ok(datapoints) => {
+            cli::print_resp_ok("get")?;
+            for (metadata, datapoint) in metadata.iter().zip(datapoints) {
+                println!(
+                    "{}",
+                    format_datapoint(&metadata.path, Some(datapoint), Some(&metadata.unit))
+                );
+            }
+        }
+        Err(err) => print_client_error("get", err)?,
+    }
+
+    Ok(())
+}
+
+async fn handle_publish_command(
+    signal: &str,
+    value: &str,
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(metadata) = resolve_signals("publish", &[signal], client).await? else {
+        return Ok(());
+    };
+    let metadata = &metadata[0];
+    let Some(value) = try_into_value("publish", value, metadata)? else {
+        return Ok(());
+    };
+    match client.publish_value(metadata.path.clone(), value).await {
+        Ok(_) => cli::print_resp_ok("publish")?,
+        Err(err) => print_client_error("publish", err)?,
+    }
+
+    Ok(())
+}
+
+/// Parse the values of the given actuators, `None` if any of them fails
+async fn resolve_actuations(
+    cmd: &str,
+    actuations: &[(&str, &str)],
+    client: &mut KuksaClientV2,
+) -> Result<Option<HashMap<String, proto::v2::Value>>, Box<dyn std::error::Error>> {
+    let signals = actuations
+        .iter()
+        .map(|(signal, _)| *signal)
+        .collect::<Vec<_>>();
+    let Some(metadata) = resolve_signals(cmd, &signals, client).await? else {
+        return Ok(None);
+    };
+    let mut values = HashMap::new();
+    for (metadata, (_, value)) in metadata.iter().zip(actuations) {
+        if metadata.entry_type != proto::v2::EntryType::Actuator as i32 {
+            cli::print_error(cmd, format!("{} is not an actuator.", metadata.path))?;
+            return Ok(None);
+        }
+        match try_into_value(cmd, value, metadata)? {
+            Some(value) => values.insert(metadata.path.clone(), value),
+            None => return Ok(None),
+        };
+    }
+    Ok(Some(values))
+}
+
+async fn handle_actuate_command(
+    signal: &str,
+    value: &str,
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if value.is_empty() {
+        print_usage("actuate");
+        return Ok(());
+    }
+
+    let Some(values) = resolve_actuations("actuate", &[(signal, value)], client).await? else {
+        return Ok(());
+    };
+    for (path, value) in values {
+        match client.actuate(path, value).await {
+            Ok(_) => cli::print_resp_ok("actuate")?,
+            Err(err) => print_client_error("actuate", err)?,
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_batch_actuate_command(
+    actuations: &[(&str, &str)],
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(values) = resolve_actuations("batch-actuate", actuations, client).await? else {
+        return Ok(());
+    };
+    match client.batch_actuate(values).await {
+        Ok(_) => cli::print_resp_ok("batch-actuate")?,
+        Err(err) => print_client_error("batch-actuate", err)?,
+    }
+
+    Ok(())
+}
+
+async fn handle_server_info_command(
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match client.get_server_info().await {
+        Ok(info) => {
+            cli::print_resp_ok("info")?;
+            println!("Name:        {}", info.name);
+            println!("Version:     {}", info.version);
+            println!("Commit hash: {}", info.commit_hash);
+        }
+        Err(err) => print_client_error("info", err)?,
+    }
+
+    Ok(())
+}
+
+/// Output of the provider, one line per call
+pub(crate) type Printer = Arc<dyn Fn(String) + Send + Sync>;
+
+/// Open a provider stream, printing rejected values, filter updates and the
+/// stream being closed by the server
+pub(crate) async fn start_provider(
+    client: &KuksaClientV2,
+    auto_ack: bool,
+    print: Printer,
+) -> Result<Provider, ClientError> {
+    let print_publish_error = print.clone();
+    let print_filter_update = print.clone();
+    ProviderBuilder::new()
+        .auto_ack(auto_ack)
+        .on_publish_error(move |path, error| {
+            print_publish_error(format!(
+                "Publishing {path} failed: {:?} {}",
+                error.code(),
+                error.message
+            ))
+        })
+        .on_filter_update(move |path, interval| match interval {
+            Some(interval) => print_filter_update(format!(
+                "Filter updated: {path} (min sample interval {} ms)",
+                interval.as_millis()
+            )),
+            None => print_filter_update(format!("Filter updated: {path}")),
+        })
+        .on_close(move |status| match status {
+            Some(status) => print(format!("Provider stopped: {}", status.message())),
+            None => print("Server gone. Provider stopped".to_string()),
+        })
+        .start(client.clone())
+        .await
+}
+
+async fn open_provider(
+    cmd: &str,
+    client: &KuksaClientV2,
+    auto_ack: bool,
+    print: Printer,
+) -> Result<Option<Provider>, Box<dyn std::error::Error>> {
+    match start_provider(client, auto_ack, print).await {
+        Ok(provider) => Ok(Some(provider)),
+        Err(err) => {
+            print_client_error(cmd, err)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Provide actuation of `actuators`, accepting and printing all actuation requests
+async fn provide_actuators(
+    provider: &Provider,
+    actuators: &[proto::v2::Metadata],
+    print: &Printer,
+) -> Result<(), ClientError> {
+    for metadata in actuators {
+        let path = metadata.path.clone();
+        let print = print.clone();
+        provider
+            .provide_actuator(metadata.path.clone(), move |value| {
+                let datapoint = proto::v2::Datapoint {
+                    timestamp: None,
+                    value: Some(value.clone()),
+                };
+                print(format!(
+                    "Actuation requested: {}",
+                    format_datapoint(&path, Some(datapoint), None)
+                ));
+                Ok(())
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+fn paths(signals: &[proto::v2::Metadata]) -> Vec<String> {
+    signals
+        .iter()
+        .map(|metadata| metadata.path.clone())
+        .collect()
+}
+
+/// Resolve `signals`, failing if any of them is not an actuator
+async fn resolve_actuators(
+    cmd: &str,
+    signals: &[&str],
+    client: &mut KuksaClientV2,
+) -> Result<Option<Vec<proto::v2::Metadata>>, Box<dyn std::error::Error>> {
+    let Some(metadata) = resolve_signals(cmd, signals, client).await? else {
+        return Ok(None);
+    };
+    for metadata in &metadata {
+        if metadata.entry_type != proto::v2::EntryType::Actuator as i32 {
+            cli::print_error(cmd, format!("{} is not an actuator.", metadata.path))?;
+            return Ok(None);
+        }
+    }
+    Ok(Some(metadata))
+}
+
+/// Provide the given actuators and signals until interrupted or the server is gone
+async fn handle_provide_command(
+    actuators: &[&str],
+    signals: &[&str],
+    auto_ack: bool,
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if actuators.is_empty() && signals.is_empty() {
+        cli::print_error("provide", "Nothing to provide, use --actuator or --signal")?;
+        return Ok(());
+    }
+    let Some(actuators) = resolve_actuators("provide", actuators, client).await? else {
+        return Ok(());
+    };
+    let Some(signals) = resolve_signals("provide", signals, client).await? else {
+        return Ok(());
+    };
+    let print: Printer = Arc::new(|line| println!("{line}"));
+    let Some(provider) = open_provider("provide", client, auto_ack, print.clone()).await? else {
+        return Ok(());
+    };
+    if !actuators.is_empty() {
+        provide_actuators(&provider, &actuators, &print).await?;
+        print("Actuation provided".to_string());
+    }
+    if !signals.is_empty() {
+        provider.provide_signals(paths(&signals)).await?;
+        print("Signals provided".to_string());
+    }
+
+    while !provider.is_closed() {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = tokio::time::sleep(TIMEOUT) => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Lines held back at most while the console is paused, older ones are dropped
+const MAX_HELD_BACK_LINES: usize = 1000;
+
+#[derive(Default)]
+struct HeldBack {
+    lines: VecDeque<String>,
+    dropped: usize,
+}
+
+/// Output of background tasks (subscriptions and the provider) to the
+/// interactive console. It's held back while a full-screen view like
+/// `watch` owns the terminal.
+#[derive(Clone)]
+struct Console {
+    interface: Arc<Interface<linefeed::DefaultTerminal>>,
+    held_back: Arc<std::sync::Mutex<Option<HeldBack>>>,
+}
+
+impl Console {
+    fn new(interface: Arc<Interface<linefeed::DefaultTerminal>>) -> Self {
+        Console {
+            interface,
+            held_back: Default::default(),
+        }
+    }
+
+    /// Write `text`, consisting of complete lines
+    fn write(&self, text: String) {
+        let mut held_back = self.held_back.lock().unwrap();
+        match held_back.as_mut() {
+            Some(held_back) => {
+                held_back.lines.push_back(text);
+                if held_back.lines.len() > MAX_HELD_BACK_LINES {
+                    held_back.lines.pop_front();
+                    held_back.dropped += 1;
+                }
+            }
+            None => {
+                let _ = write!(self.interface, "{text}");
+            }
+        }
+    }
+
+    /// Hold back output until [`Self::resume`] is called
+    fn pause(&self) {
+        *self.held_back.lock().unwrap() = Some(HeldBack::default());
+    }
+
+    /// Write the output held back since [`Self::pause`]
+    fn resume(&self) {
+        let mut held_back = self.held_back.lock().unwrap();
+        if let Some(HeldBack { lines, dropped }) = held_back.take() {
+            if dropped > 0 {
+                let _ = writeln!(
+                    self.interface,
+                    "{}",
+                    Color::White
+                        .dimmed()
+                        .paint(format!("({dropped} lines of output dropped)"))
+                );
+            }
+            for text in lines {
+                let _ = write!(self.interface, "{text}");
+            }
+        }
+    }
+}
+
+/// Print provider output on the interactive console, prefixed by `[provider]`
+fn provider_printer(console: Console) -> Printer {
+    Arc::new(move |line| {
+        console.write(format!(
+            "{} {line}\n",
+            Color::White.dimmed().paint("[provider]")
+        ));
+    })
+}
+
+/// Handle the interactive `provide` command, opening the provider stream on first use
+async fn handle_interactive_provide_command(
+    args: &str,
+    provider: &mut Option<Provider>,
+    console: &Console,
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    const CMD: &str = "provide";
+
+    if provider.as_ref().is_some_and(Provider::is_closed) {
+        *provider = None;
+    }
+
+    let (subcommand, args) = cli::split_first_word(args);
+    let words = args.split_whitespace().collect::<Vec<_>>();
+    match (subcommand, &words[..]) {
+        ("actuation", words) => {
+            let (auto_ack, actuators) = match words {
+                ["--auto-ack", actuators @ ..] => (true, actuators),
+                actuators => (false, actuators),
+            };
+            if actuators.is_empty() {
+                print_usage(CMD);
+                return Ok(());
+            }
+            let Some(actuators) = resolve_actuators(CMD, actuators, client).await? else {
+                return Ok(());
+            };
+            let print = provider_printer(console.clone());
+            if provider.is_none() {
+                *provider = open_provider(CMD, client, auto_ack, print.clone()).await?;
+            }
+            if let Some(provider) = provider {
+                if auto_ack {
+                    provider.set_auto_ack(true);
+                }
+                match provide_actuators(provider, &actuators, &print).await {
+                    Ok(()) => cli::print_resp_ok(CMD)?,
+                    Err(err) => print_client_error(CMD, err)?,
+                }
+            }
+        }
+        ("signal", signals) if !signals.is_empty() => {
+            let Some(signals) = resolve_signals(CMD, signals, client).await? else {
+                return Ok(());
+            };
+            if provider.is_none() {
+                let print = provider_printer(console.clone());
+                *provider = open_provider(CMD, client, false, print).await?;
+            }
+            if let Some(provider) = provider {
+                match provider.provide_signals(paths(&signals)).await {
+                    Ok(()) => cli::print_resp_ok(CMD)?,
+                    Err(err) => print_client_error(CMD, err)?,
+                }
+            }
+        }
+        ("publish", [_, ..]) => {
+            let (signal, value) = cli::split_first_word(args);
+            if value.is_empty() {
+                print_usage(CMD);
+                return Ok(());
+            }
+            let Some(provider) = provider else {
+                cli::print_error(CMD, "Not providing any signals, use `provide signal` first")?;
+                return Ok(());
+            };
+            let Some(metadata) = resolve_signals(CMD, &[signal], client).await? else {
+                return Ok(());
+            };
+            let Some(value) = try_into_value(CMD, value, &metadata[0])? else {
+                return Ok(());
+            };
+            match provider.publish(&metadata[0].path, value).await {
+                Ok(()) => cli::print_resp_ok(CMD)?,
+                Err(err) => print_client_error(CMD, err)?,
+            }
+        }
+        ("auto-ack", [setting @ ("on" | "off")]) => match provider {
+            Some(provider) => {
+                provider.set_auto_ack(*setting == "on");
+                cli::print_resp_ok(CMD)?;
+            }
+            None => cli::print_error(CMD, "Not providing any actuators")?,
+        },
+        ("close", []) => {
+            if provider.take().is_some() {
+                cli::print_info("Stopped providing.")?;
+            }
+        }
+        _ => print_usage(CMD),
+    }
+
+    Ok(())
+}
+
+/// Print the updates received on `subscription` in the background, prefixed by
+/// `[subscription_nbr]`. `to_entries` maps each response to (path, datapoint)
+/// pairs.
+fn spawn_subscription_printer<T, F>(
+    console: Console,
+    subscription_nbr: i32,
+    mut subscription: tonic::Streaming<T>,
+    units: HashMap<String, String>,
+    to_entries: F,
+) where
+    T: prost::Message + Default + Send + 'static,
+    F: Fn(T) -> Vec<(String, proto::v2::Datapoint)> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let sub_disp = format!("[{subscription_nbr}]");
+        let sub_disp_pad = " ".repeat(sub_disp.len());
+        let sub_disp_color = format!("{}", Color::White.dimmed().paint(&sub_disp));
+
+        loop {
+            match subscription.message().await {
+                Ok(Some(resp)) => {
+                    // Build output before writing it
+                    // (to avoid interleaving confusion)
+                    use std::fmt::Write;
+                    let mut output = String::new();
+                    let mut entries = to_entries(resp);
+                    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    for (i, (path, datapoint)) in entries.into_iter().enumerate() {
+                        let prefix = if i == 0 {
+                            &sub_disp_color
+                        } else {
+                            &sub_disp_pad
+                        };
+                        let unit = units.get(&path).map(String::as_str);
+                        writeln!(
+                            output,
+                            "{} {}",
+                            prefix,
+                            format_datapoint(&path, Some(datapoint), unit)
+                        )
+                        .unwrap();
+                    }
+                    console.write(output);
+                }
+                Ok(None) => {
+                    console.write(format!(
+                        "{} {}\n",
+                        Color::Red.dimmed().paint(&sub_disp),
+                        Color::White
+                            .dimmed()
+                            .paint("Server gone. Subscription stopped"),
+                    ));
+                    break;
+                }
+                Err(err) => {
+                    console.write(format!(
+                        "{} {}\n",
+                        &sub_disp_color,
+                        Color::Red.dimmed().paint(format!("Channel error: {err}"))
+                    ));
+                    break;
+                }
+            }
+        }
+    });
+}
+
+async fn update_completer(
+    interface: &Interface<linefeed::DefaultTerminal>,
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(metadata) = handle_list_metadata("metadata", "**", client).await? {
+        interface.set_completer(Arc::new(
+            CliCompleter::with_commands(CLI_COMMANDS)
+                .with_paths(metadata.iter().map(|metadata| metadata.path.as_str())),
+        ));
+    }
+    Ok(())
+}
+
+/// Run a non-interactive command
+async fn run_command(
+    command: cli::Commands,
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        cli::Commands::Get { paths } => {
+            let signals = paths.iter().map(String::as_str).collect::<Vec<_>>();
+            handle_get_command(&signals, client).await
+        }
+        cli::Commands::Subscribe {
+            paths,
+            count,
+            timeout,
+        } => {
+            let signals = paths.iter().map(String::as_str).collect::<Vec<_>>();
+            handle_subscribe_command(&signals, count, timeout, client).await
+        }
+        cli::Commands::Set { .. } => {
+            cli::set_exit_code(cli::ExitCode::Usage);
+            cli::print_error("set", SET_UNSUPPORTED)?;
+            Ok(())
+        }
+        cli::Commands::Actuate { path, value } => {
+            handle_actuate_command(&path, &value, client).await
+        }
+        cli::Commands::Publish { path, value } => {
+            handle_publish_command(&path, &value, client).await
+        }
+        cli::Commands::Export { root, values, file } => {
+            handle_export_command(root, values, file.as_deref(), &mut client.basic_client).await
+        }
+        cli::Commands::Record { pattern, file } => trace::record(&pattern, &file, client).await,
+        cli::Commands::Replay {
+            file,
+            speed,
+            repeat,
+            remap,
+            provider,
+        } => trace::replay(&file, speed, repeat, &remap, provider, client).await,
+        cli::Commands::Provide {
+            actuators,
+            signals,
+            auto_ack,
+        } => {
+            let actuators = actuators.iter().map(String::as_str).collect::<Vec<_>>();
+            let signals = signals.iter().map(String::as_str).collect::<Vec<_>>();
+            handle_provide_command(&actuators, &signals, auto_ack, client).await
+        }
+        cli::Commands::Batch { .. } => {
+            cli::set_exit_code(cli::ExitCode::Usage);
+            cli::print_error("batch", "Batches can't be nested")?;
+            Ok(())
+        }
+    }
+}
+
+pub async fn kuksa_val_v2_main(_cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Using {VERSION}");
+
+    let mut subscription_nbr = 1;
+    let mut provider = None;
+
+    let completer = CliCompleter::with_commands(CLI_COMMANDS);
+    let interface = Arc::new(Interface::new("client")?);
+    interface.set_completer(Arc::new(completer));
+
+    interface.define_function("enter-function", Arc::new(cli::EnterFunction));
+    interface.bind_sequence("\r", Command::from_str("enter-function"));
+    interface.bind_sequence("\n", Command::from_str("enter-function"));
+
+    cli::set_disconnected_prompt(&interface);
+    let console = Console::new(interface.clone());
+
+    let mut cli = _cli;
+    let mut client = KuksaClientV2::new(kuksa_common::to_uri(cli.get_server())?);
+
+    if let Some(token_filename) = cli.get_token_file() {
+        client.basic_client.set_token_file(token_filename)?;
+    }
+
+    #[cfg(feature = "tls")]
+    if let Some(ca_cert_filename) = cli.get_ca_cert() {
+        let pem = std::fs::read(ca_cert_filename)?;
+        let ca_cert = tonic::transport::Certificate::from_pem(pem);
+
+        let tls_config = tonic::transport::ClientTlsConfig::new().ca_certificate(ca_cert);
+
+        client.basic_client.set_tls_config(tls_config);
+    }
+
+    let mut connection_state_subscription = client.basic_client.subscribe_to_connection_state();
+    let interface_ref = interface.clone();
+
+    tokio::spawn(async move {
+        while let Some(state) = connection_state_subscription.next().await {
+            match state {
+                Ok(state) => match state {
+                    kuksa_common::ConnectionState::Connected => {
+                        cli::set_connected_prompt(&interface_ref, VERSION.to_string());
+                    }
+                    kuksa_common::ConnectionState::Disconnected => {
+                        cli::set_disconnected_prompt(&interface_ref);
+                    }
+                },
+                Err(err) => {
+                    cli::print_error(
+                        "connection",
+                        format!("Connection state subscription failed: {err}"),
+                    )
+                    .unwrap_or_default();
+                }
+            }
+        }
+    });
+
+    match cli.get_command() {
+        Some(cli::Commands::Batch { file, keep_going }) => {
+            for line in cli::read_batch_lines(&file)? {
+                match cli::parse_batch_line(&line) {
+                    Ok(Some(command)) => run_command(command, &mut client).await?,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        cli::set_exit_code(cli::ExitCode::Usage);
+                        cli::print_error("batch", err)?;
+                    }
+                }
+                if !keep_going && cli::exit_code() != 0 {
+                    break;
+                }
+            }
+            return Ok(());
+        }
+        Some(command) => {
+            return run_command(command, &mut client).await;
+        }
+        None => {
+            // No subcommand => run interactive client
+            let version = match option_env!("CARGO_PKG_VERSION") {
+                Some(version) => format!("v{version}"),
+                None => String::new(),
+            };
+            cli::print_logo(version);
+
+            match client.basic_client.try_connect().await {
+                Ok(()) => {
+                    cli::print_info(format!(
+                        "Successfully connected to {}",
+                        client.basic_client.get_uri()
+                    ))?;
+                    update_completer(&interface, &mut client).await?;
+                }
+                Err(err) => {
+                    cli::print_error("connect", format!("{err}"))?;
+                }
+            }
+        }
+    };
+
+    loop {
+        if let Some(res) = interface.read_line_step(Some(TIMEOUT))? {
+            match res {
+                ReadResult::Input(line) => {
+                    let (cmd, args) = cli::split_first_word(&line);
+                    match cmd {
+                        "help" => {
+                            println!();
+                            for &(cmd, args, help) in CLI_COMMANDS {
+                                println!("  {:24} {}", format!("{cmd} {args}"), help);
+                            }
+                            println!();
+                        }
+                        "get" => {
+                            interface.add_history_unique(line.clone());
+
+                            if args.is_empty() {
+                                print_usage(cmd);
+                                continue;
+                            }
+                            let signals = args.split_whitespace().collect::<Vec<_>>();
+
+                            handle_get_command(&signals, &mut client).await?
+                        }
+                        "token" => {
+                            interface.add_history_unique(line.clone());
+
+                            if args.is_empty() {
+                                print_usage(cmd);
+                                continue;
+                            }
+
+                            match client.basic_client.set_access_token(args) {
+                                Ok(()) => {
+                                    cli::print_info("Access token set.")?;
+                                    update_completer(&interface, &mut client).await?;
+                                }
+                                Err(err) => {
+                                    cli::print_error(cmd, format!("Malformed token: {err}"))?
+                                }
+                            }
+                        }
+                        "token-file" => {
+                            interface.add_history_unique(line.clone());
+
+                            if args.is_empty() {
+                                print_usage(cmd);
+                                continue;
+                            }
+
+                            let token_filename = args.trim();
+                            match client.basic_client.set_token_file(token_filename) {
+                                Ok(()) => {
+                                    cli::print_info("Access token set.")?;
+                                    update_completer(&interface, &mut client).await?;
+                                }
+                                Err(kuksa_common::TokenError::MalformedTokenError(err)) => {
+                                    cli::print_error(cmd, format!("Malformed token: {err}"))?
+                                }
+                                Err(err) => cli::print_error(cmd, format!("{err}"))?,
+                            }
+                        }
+                        "actuate" => {
+                            interface.add_history_unique(line.clone());
+
+                            let (signal, value) = cli::split_first_word(args);
+
+                            if value.is_empty() {
+                                print_usage(cmd);
+                                continue;
+                            }
+
+                            handle_actuate_command(signal, value, &mut client).await?
+                        }
+                        "batch-actuate" => {
+                            interface.add_history_unique(line.clone());
+
+                            let Some(actuations) = parse_batch_args(args) else {
+                                print_usage(cmd);
+                                continue;
+                            };
+
+                            handle_batch_actuate_command(&actuations, &mut client).await?
+                        }
+                        "set" => {
+                            interface.add_history_unique(line.clone());
+                            cli::print_error(cmd, SET_UNSUPPORTED)?;
+                        }
+                        "publish" => {
+                            interface.add_history_unique(line.clone());
+
+                            let (signal, value) = cli::split_first_word(args);
+
+                            if value.is_empty() {
+                                print_usage(cmd);
+                                continue;
+                            }
+
+                            handle_publish_command(signal, value, &mut client).await?
+                        }
+                        "subscribe" => {
+                            interface.add_history_unique(line.clone());
+
+                            if args.is_empty() {
+                                print_usage(cmd);
+                                continue;
+                            }
+
+                            let signals = args.split_whitespace().collect::<Vec<_>>();
+                            let Some(metadata) =
+                                resolve_signals(cmd, &signals, &mut client).await?
+                            else {
+                                continue;
+                            };
+                            let units = metadata
+                                .iter()
+                                .map(|metadata| (metadata.path.clone(), metadata.unit.clone()))
+                                .collect::<HashMap<_, _>>();
+
+                            let result = if all_ids(&signals) {
+                                let ids = metadata.iter().map(|metadata| metadata.id).collect();
+                                let paths = metadata
+                                    .iter()
+                                    .map(|metadata| (metadata.id, metadata.path.clone()))
+                                    .collect::<HashMap<_, _>>();
+                                client.subscribe_by_id(ids, None).await.map(|subscription| {
+                                    spawn_subscription_printer(
+                                        console.clone(),
+                                        subscription_nbr,
+                                        subscription,
+                                        units,
+                                        move |resp: proto::v2::SubscribeByIdResponse| {
+                                            resp.entries
+                                                .into_iter()
+                                                .map(|(id, datapoint)| {
+                                                    let path = paths
+                                                        .get(&id)
+                                                        .cloned()
+                                                        .unwrap_or_else(|| id.to_string());
+                                                    (path, datapoint)
+                                                })
+                                                .collect()
+                                        },
+                                    )
+                                })
+                            } else {
+                                let paths = metadata
+                                    .iter()
+                                    .map(|metadata| metadata.path.clone())
+                                    .collect();
+                                client.subscribe(paths, None).await.map(|subscription| {
+                                    spawn_subscription_printer(
+                                        console.clone(),
+                                        subscription_nbr,
+                                        subscription,
+                                        units,
+                                        |resp: proto::v2::SubscribeResponse| {
+                                            resp.entries.into_iter().collect()
+                                        },
+                                    )
+                                })
+                            };
+
+                            match result {
+                                Ok(()) => {
+                                    cli::print_resp_ok(cmd)?;
+                                    cli::print_info(format!(
+                                        "Subscription is now running in the background. Received data is identified by [{subscription_nbr}]."
+                                    ))?;
+                                    subscription_nbr += 1;
+                                }
+                                Err(err) => print_client_error(cmd, err)?,
+                            }
+                        }
+                        "watch" => {
+                            interface.add_history_unique(line.clone());
+
+                            if args.is_empty() {
+                                print_usage(cmd);
+                                continue;
+                            }
+
+                            let signals = args.split_whitespace().collect::<Vec<_>>();
+                            let Some(metadata) =
+                                resolve_watched_signals(cmd, &signals, &mut client).await?
+                            else {
+                                continue;
+                            };
+                            // Background output would garble the dashboard
+                            console.pause();
+                            let result = watch::watch(metadata, &mut client).await;
+                            console.resume();
+                            result?
+                        }
+                        "connect" => {
+                            interface.add_history_unique(line.clone());
+                            if !client.basic_client.is_connected() || !args.is_empty() {
+                                if args.is_empty() {
+                                    match client.basic_client.try_connect().await {
+                                        Ok(()) => {
+                                            cli::print_info(format!(
+                                                "[{cmd}] Successfully connected to {}",
+                                                client.basic_client.get_uri()
+                                            ))?;
+                                        }
+                                        Err(err) => {
+                                            cli::print_error(cmd, format!("{err}"))?;
+                                        }
+                                    }
+                                } else {
+                                    match kuksa_common::to_uri(args) {
+                                        Ok(valid_uri) => {
+                                            match client
+                                                .basic_client
+                                                .try_connect_to(valid_uri)
+                                                .await
+                                            {
+                                                Ok(()) => {
+                                                    cli::print_info(format!(
+                                                        "[{cmd}] Successfully connected to {}",
+                                                        client.basic_client.get_uri()
+                                                    ))?;
+                                                }
+                                                Err(err) => {
+                                                    cli::print_error(cmd, format!("{err}"))?;
+                                                }
+                                            }
+                                        }
+                                        Err(err) => {
+                                            cli::print_error(
+                                                cmd,
+                                                format!("Failed to parse endpoint address: {err}"),
+                                            )?;
+                                        }
+                                    }
+                                };
+                                if client.basic_client.is_connected() {
+                                    update_completer(&interface, &mut client).await?;
+                                }
+                            };
+                        }
+                        "export" => {
+                            interface.add_history_unique(line.clone());
+
+                            let (include_values, file) =
+                                match args.split_whitespace().collect::<Vec<_>>()[..] {
+                                    [] => (false, None),
+                                    ["--values"] => (true, None),
+                                    ["--values", file] | [file, "--values"] => (true, Some(file)),
+                                    [file] => (false, Some(file)),
+                                    _ => {
+                                        print_usage(cmd);
+                                        continue;
+                                    }
+                                };
+
+                            handle_export_command(
+                                String::new(),
+                                include_values,
+                                file,
+                                &mut client.basic_client,
+                            )
+                            .await?
+                        }
+                        "info" => {
+                            interface.add_history_unique(line.clone());
+
+                            handle_server_info_command(&mut client).await?
+                        }
+                        "provide" => {
+                            interface.add_history_unique(line.clone());
+
+                            handle_interactive_provide_command(
+                                args,
+                                &mut provider,
+                                &console,
+                                &mut client,
+                            )
+                            .await?
+                        }
+                        "metadata" => {
+                            interface.add_history_unique(line.clone());
+
+                            let pattern = match args.trim() {
+                                "" => "**",
+                                pattern => pattern,
+                            };
+
+                            if let Some(entries) =
+                                handle_list_metadata(cmd, pattern, &mut client).await?
+                            {
+                                cli::print_resp_ok(cmd)?;
+                                if !entries.is_empty() {
+                                    let max_len_path = entries
+                                        .iter()
+                                        .map(|entry| entry.path.len())
+                                        .max()
+                                        .unwrap_or_default();
+
+                                    cli::print_info(format!(
+                                        "{:<6} {:<max_len_path$} {:<10} {:<9}",
+                                        "ID", "Path", "Entry type", "Data type"
+                                    ))?;
+
+                                    for entry in &entries {
+                                        println!(
+                                            "{:<6} {:<max_len_path$} {:<10} {:<9}",
+                                            entry.id,
+                                            entry.path,
+                                            DisplayEntryType::from(
+                                                proto::v1::EntryType::try_from(entry.entry_type)
+                                                    .ok()
+                                            ),
+                                            DisplayDataType::from(
+                                                proto::v1::DataType::try_from(entry.data_type).ok()
+                                            ),
+                                        );
+                                    }
+                                }
+                            }
+                        }
+                        "quit" | "exit" => {
+                            println!("Bye bye!");
+                            break;
+                        }
+                        "" => {} // Ignore empty input
+                        _ => {
+                            println!(
+                                "Unknown command. See `help` for a list of available commands."
+                            );
+                            interface.add_history_unique(line.clone());
+                        }
+                    }
+                }
+                ReadResult::Eof => {
+                    println!("Bye bye!");
+                    break;
+                }
+                ReadResult::Signal(sig) => {
+                    if sig == linefeed::Signal::Interrupt {
+                        interface.cancel_read_line()?;
+                    }
+
+                    let _ = writeln!(interface, "signal received: {sig:?}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use prost_types::Timestamp;
+
+    #[test]
+    fn test_parse_signal_ref() {
+        assert_eq!(
+            SignalRef::from("Vehicle.Speed"),
+            SignalRef::Path("Vehicle.Speed")
+        );
+        assert_eq!(SignalRef::from("42"), SignalRef::Id(42));
+        assert_eq!(SignalRef::from("-1"), SignalRef::Id(-1));
+    }
+
+    #[test]
+    fn test_parse_batch_args() {
+        assert_eq!(
+            parse_batch_args("Vehicle.A true 12 3"),
+            Some(vec![("Vehicle.A", "true"), ("12", "3")])
+        );
+        assert_eq!(parse_batch_args(""), None);
+        assert_eq!(parse_batch_args("Vehicle.A true 12"), None);
+    }
+
+    #[test]
+    fn test_parse_typed_values() {
+        use proto::v2::value::TypedValue;
+        use proto::v2::DataType;
+
+        let typed_value = |input: &str, data_type| {
+            try_into_typed_value(input, data_type)
+                .ok()
+                .and_then(|value| value.typed_value)
+        };
+        assert_eq!(
+            typed_value("-100", DataType::Int8),
+            Some(TypedValue::Int32(-100))
+        );
+        assert_eq!(typed_value("300", DataType::Int8), None);
+        assert_eq!(
+            typed_value("[1, 2]", DataType::Uint16Array),
+            Some(TypedValue::Uint32Array(proto::v2::Uint32Array {
+                values: vec![1, 2]
+            }))
+        );
+        assert_eq!(typed_value("NotAvailable", DataType::Float), None,);
+        assert_eq!(
+            typed_value("2024-05-01T12:00:00Z", DataType::Timestamp),
+            Some(TypedValue::Timestamp(Timestamp {
+                seconds: 1714564800,
+                nanos: 0
+            }))
+        );
+
+        let home = proto::v2::StructValue {
+            fields: HashMap::from([
+                (
+                    "Latitude".to_owned(),
+                    proto::v2::Value {
+                        typed_value: Some(TypedValue::Double(48.1)),
+                    },
+                ),
+                (
+                    "Name".to_owned(),
+                    proto::v2::Value {
+                        typed_value: Some(TypedValue::String("Home".to_owned())),
+                    },
+                ),
+            ]),
+        };
+        let input = r#"{"Latitude": {"double": 48.1}, "Name": {"string": "Home"}}"#;
+        assert_eq!(
+            typed_value(input, DataType::Struct),
+            Some(TypedValue::StructValue(home.clone()))
+        );
+        assert_eq!(
+            typed_value(&format!("[{input}]"), DataType::StructArray),
+            Some(TypedValue::StructArray(proto::v2::StructArray {
+                values: vec![home]
+            }))
+        );
+        // The type of each property is required
+        assert_eq!(typed_value(r#"{"Latitude": 48.1}"#, DataType::Struct), None);
+        assert_eq!(
+            typed_value(r#"{"Latitude": {"vector": 48.1}}"#, DataType::Struct),
+            None
+        );
+    }
+
+    #[test]
+    fn test_display_value() {
+        let value = try_into_typed_value(
+            r#"{"Name": {"string": "Home"}, "Latitude": {"float": 48.1}}"#,
+            proto::v2::DataType::Struct,
+        )
+        .unwrap();
+        assert_eq!(
+            DisplayValue(value).to_string(),
+            "{Latitude: 48.10, Name: 'Home'}"
+        );
+
+        let value =
+            try_into_typed_value("2024-05-01T12:00:00Z", proto::v2::DataType::Timestamp).unwrap();
+        assert_eq!(DisplayValue(value).to_string(), "2024-05-01T12:00:00Z");
+    }
+}
@@ -16,17 +16,28 @@ use cli::Protocol;
 
 pub mod cli;
 mod kuksa_cli;
+mod kuksa_val_v2_cli;
+mod trace;
+mod watch;
 
 #[tokio::main]
 async fn main() {
     let mut cli = cli::Cli::parse();
-    if cli.get_protocol() == Protocol::KuksaValV1 {
-        let err = kuksa_cli::kuksa_main(cli.clone()).await;
-        match err {
-            Ok(_) => (),
-            Err(e) => eprintln!("Error: {e}"),
+    cli::set_output_format(cli.get_output());
+    let interactive = cli.get_command().is_none();
+    let err = match cli.get_protocol() {
+        Protocol::KuksaValV1 => kuksa_cli::kuksa_main(cli.clone()).await,
+        Protocol::KuksaValV2 => kuksa_val_v2_cli::kuksa_val_v2_main(cli.clone()).await,
+        Protocol::SdvDatabrokerV1 => {
+            println!("Choose one protocol. Currently kuksa.val.v1 and kuksa.val.v2 are supported");
+            return;
         }
-    } else {
-        println!("Choose one protocol.Currently kuksa.val.v1 is supported")
+    };
+    if let Err(e) = err {
+        cli::set_exit_code(cli::ExitCode::Failure);
+        eprintln!("Error: {e}");
+    }
+    if !interactive {
+        std::process::exit(cli::exit_code());
     }
 }
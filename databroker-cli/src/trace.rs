@@ -0,0 +1,426 @@
+/********************************************************************************
+* Copyright (c) 2025 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+//! Recording of signal updates to JSON Lines traces and replaying them.
+//!
+//! Each line of a trace holds one update, e.g.
+//! `{"t":1.25,"path":"Vehicle.Speed","value":50.0}`, where `t` is the time in
+//! seconds since the recording started.
+
+use databroker_proto::kuksa::val::v2 as proto;
+use proto::value::TypedValue;
+use proto::DataType;
+
+use kuksa_val_v2::{ClientTraitV2, KuksaClientV2};
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cli::{self, value_to_json};
+use crate::kuksa_val_v2_cli::{print_client_error, start_provider, Printer};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct TraceEntry {
+    t: f64,
+    path: String,
+    value: serde_json::Value,
+}
+
+fn json_array<T>(
+    json: &serde_json::Value,
+    convert: impl Fn(&serde_json::Value) -> Option<T>,
+) -> Option<Vec<T>> {
+    json.as_array()?.iter().map(convert).collect()
+}
+
+fn as_i32(json: &serde_json::Value) -> Option<i32> {
+    i32::try_from(json.as_i64()?).ok()
+}
+
+fn as_u32(json: &serde_json::Value) -> Option<u32> {
+    u32::try_from(json.as_u64()?).ok()
+}
+
+fn as_timestamp(json: &serde_json::Value) -> Option<prost_types::Timestamp> {
+    json.as_str()?.parse().ok()
+}
+
+/// Convert a recorded value to a value of `data_type`, `None` if it doesn't fit
+fn json_to_value(json: &serde_json::Value, data_type: DataType) -> Option<proto::Value> {
+    let typed_value = match data_type {
+        DataType::String => TypedValue::String(json.as_str()?.to_owned()),
+        DataType::Boolean => TypedValue::Bool(json.as_bool()?),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 => TypedValue::Int32(as_i32(json)?),
+        DataType::Int64 => TypedValue::Int64(json.as_i64()?),
+        DataType::Uint8 | DataType::Uint16 | DataType::Uint32 => TypedValue::Uint32(as_u32(json)?),
+        DataType::Uint64 => TypedValue::Uint64(json.as_u64()?),
+        DataType::Float => TypedValue::Float(json.as_f64()? as f32),
+        DataType::Double => TypedValue::Double(json.as_f64()?),
+        DataType::Timestamp => TypedValue::Timestamp(as_timestamp(json)?),
+        DataType::StringArray => TypedValue::StringArray(proto::StringArray {
+            values: json_array(json, |json| json.as_str().map(str::to_owned))?,
+        }),
+        DataType::BooleanArray => TypedValue::BoolArray(proto::BoolArray {
+            values: json_array(json, serde_json::Value::as_bool)?,
+        }),
+        DataType::Int8Array | DataType::Int16Array | DataType::Int32Array => {
+            TypedValue::Int32Array(proto::Int32Array {
+                values: json_array(json, as_i32)?,
+            })
+        }
+        DataType::Int64Array => TypedValue::Int64Array(proto::Int64Array {
+            values: json_array(json, serde_json::Value::as_i64)?,
+        }),
+        DataType::Uint8Array | DataType::Uint16Array | DataType::Uint32Array => {
+            TypedValue::Uint32Array(proto::Uint32Array {
+                values: json_array(json, as_u32)?,
+            })
+        }
+        DataType::Uint64Array => TypedValue::Uint64Array(proto::Uint64Array {
+            values: json_array(json, serde_json::Value::as_u64)?,
+        }),
+        DataType::FloatArray => TypedValue::FloatArray(proto::FloatArray {
+            values: json_array(json, |json| json.as_f64().map(|value| value as f32))?,
+        }),
+        DataType::DoubleArray => TypedValue::DoubleArray(proto::DoubleArray {
+            values: json_array(json, serde_json::Value::as_f64)?,
+        }),
+        DataType::TimestampArray => TypedValue::TimestampArray(proto::TimestampArray {
+            values: json_array(json, as_timestamp)?,
+        }),
+        DataType::Unspecified | DataType::Struct | DataType::StructArray => return None,
+    };
+    Some(proto::Value {
+        typed_value: Some(typed_value),
+    })
+}
+
+fn read_trace(contents: &str) -> Result<Vec<TraceEntry>, String> {
+    let trace = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|err| format!("Line {}: {err}", index + 1))
+        })
+        .collect::<Result<Vec<TraceEntry>, String>>()?;
+    // Nothing to replay, and looping over it would never wait
+    if trace.is_empty() {
+        return Err("No values recorded".to_owned());
+    }
+    Ok(trace)
+}
+
+/// Parse a path remapping rule of the form "FROM=TO"
+fn parse_remap_rule(rule: &str) -> Result<(String, String), String> {
+    match rule.split_once('=') {
+        Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+            Ok((from.to_owned(), to.to_owned()))
+        }
+        _ => Err(format!("Invalid remapping \"{rule}\", expected FROM=TO")),
+    }
+}
+
+/// Replace the prefix of `path` according to the first matching rule
+fn remap_path(path: &str, rules: &[(String, String)]) -> String {
+    for (from, to) in rules {
+        if let Some(rest) = path.strip_prefix(from.as_str()) {
+            if rest.is_empty() || rest.starts_with('.') {
+                return format!("{to}{rest}");
+            }
+        }
+    }
+    path.to_owned()
+}
+
+/// Record updates of the signals matching `pattern` to `file` until interrupted
+pub(crate) async fn record(
+    pattern: &str,
+    file: &str,
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    const CMD: &str = "record";
+
+    let paths = match client
+        .list_metadata((pattern.to_owned(), String::new()))
+        .await
+    {
+        Ok(metadata) => metadata
+            .into_iter()
+            .map(|metadata| metadata.path)
+            .collect::<Vec<_>>(),
+        Err(err) => return print_client_error(CMD, err),
+    };
+    let signal_count = paths.len();
+    let mut subscription = match client.subscribe(paths, None).await {
+        Ok(subscription) => subscription,
+        Err(err) => return print_client_error(CMD, err),
+    };
+
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(file)?);
+    cli::print_info(format!(
+        "Recording {signal_count} signals to \"{file}\", press Ctrl+C to stop"
+    ))?;
+
+    let start = tokio::time::Instant::now();
+    let mut update_count = 0;
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => break,
+            message = subscription.message() => match message {
+                Ok(Some(response)) => {
+                    let t = start.elapsed().as_secs_f64();
+                    let mut entries = response.entries.into_iter().collect::<Vec<_>>();
+                    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    for (path, datapoint) in entries {
+                        if let Some(value) = datapoint.value {
+                            let entry = TraceEntry {
+                                t,
+                                path,
+                                value: value_to_json(&value),
+                            };
+                            serde_json::to_writer(&mut writer, &entry)?;
+                            writer.write_all(b"\n")?;
+                            update_count += 1;
+                        }
+                    }
+                    writer.flush()?;
+                }
+                Ok(None) => {
+                    cli::print_error(CMD, "Server gone. Recording stopped")?;
+                    break;
+                }
+                Err(status) => {
+                    cli::print_resp_err(CMD, &status)?;
+                    break;
+                }
+            }
+        }
+    }
+    writer.flush()?;
+
+    cli::print_info(format!("Recorded {update_count} updates"))?;
+    Ok(())
+}
+
+struct ReplayEntry {
+    t: f64,
+    metadata: proto::Metadata,
+    value: proto::Value,
+}
+
+/// Replay the trace in `file`, `speed` times faster than recorded
+pub(crate) async fn replay(
+    file: &str,
+    speed: f64,
+    repeat: bool,
+    remap: &[String],
+    use_provider: bool,
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    const CMD: &str = "replay";
+
+    if !(speed.is_finite() && speed > 0.0) {
+        cli::print_error(CMD, "Speed must be a positive number")?;
+        return Ok(());
+    }
+    let rules = match remap
+        .iter()
+        .map(|rule| parse_remap_rule(rule))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(rules) => rules,
+        Err(err) => {
+            cli::print_error(CMD, err)?;
+            return Ok(());
+        }
+    };
+    let trace = match read_trace(&std::fs::read_to_string(file)?) {
+        Ok(trace) => trace,
+        Err(err) => {
+            cli::print_error(CMD, format!("\"{file}\": {err}"))?;
+            return Ok(());
+        }
+    };
+
+    let signals = match client.list_metadata(("**".to_owned(), String::new())).await {
+        Ok(metadata) => metadata
+            .into_iter()
+            .map(|metadata| (metadata.path.clone(), metadata))
+            .collect::<HashMap<_, _>>(),
+        Err(err) => return print_client_error(CMD, err),
+    };
+    let mut entries = Vec::with_capacity(trace.len());
+    for entry in trace {
+        let path = remap_path(&entry.path, &rules);
+        let Some(metadata) = signals.get(&path) else {
+            cli::print_error(CMD, format!("{path} is not a known signal"))?;
+            return Ok(());
+        };
+        let data_type = DataType::try_from(metadata.data_type).unwrap_or(DataType::Unspecified);
+        let Some(value) = json_to_value(&entry.value, data_type) else {
+            cli::print_error(
+                CMD,
+                format!(
+                    "Recorded value {} of {path} is not {data_type:?}",
+                    entry.value
+                ),
+            )?;
+            return Ok(());
+        };
+        entries.push(ReplayEntry {
+            t: entry.t,
+            metadata: metadata.clone(),
+            value,
+        });
+    }
+
+    let provider = if use_provider {
+        let print: Printer = Arc::new(|line| println!("{line}"));
+        let provider = match start_provider(client, false, print).await {
+            Ok(provider) => provider,
+            Err(err) => return print_client_error(CMD, err),
+        };
+        let mut provided = entries
+            .iter()
+            .map(|entry| entry.metadata.path.clone())
+            .collect::<Vec<_>>();
+        provided.sort();
+        provided.dedup();
+        provider.provide_signals(provided).await?;
+        Some(provider)
+    } else {
+        None
+    };
+
+    cli::print_info(format!(
+        "Replaying {} updates from \"{file}\", press Ctrl+C to stop",
+        entries.len()
+    ))?;
+
+    let replay = async {
+        loop {
+            let start = tokio::time::Instant::now();
+            for entry in &entries {
+                tokio::time::sleep_until(start + Duration::from_secs_f64(entry.t / speed)).await;
+                let result = match &provider {
+                    Some(provider) => {
+                        provider
+                            .publish(&entry.metadata.path, entry.value.clone())
+                            .await
+                    }
+                    None => {
+                        client
+                            .publish_value(entry.metadata.path.clone(), entry.value.clone())
+                            .await
+                    }
+                };
+                if let Err(err) = result {
+                    print_client_error(CMD, err)?;
+                }
+            }
+            if !repeat {
+                break;
+            }
+        }
+        Ok::<(), Box<dyn std::error::Error>>(())
+    };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        result = replay => result?,
+    }
+
+    cli::print_resp_ok(CMD)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_value_json_round_trip() {
+        let values = [
+            (TypedValue::String("ON".to_owned()), DataType::String),
+            (TypedValue::Bool(true), DataType::Boolean),
+            (TypedValue::Int32(-12), DataType::Int8),
+            (TypedValue::Uint64(u64::MAX), DataType::Uint64),
+            (TypedValue::Float(0.5), DataType::Float),
+            (
+                TypedValue::Timestamp("2024-05-01T12:00:00Z".parse().unwrap()),
+                DataType::Timestamp,
+            ),
+            (
+                TypedValue::Uint32Array(proto::Uint32Array { values: vec![1, 2] }),
+                DataType::Uint8Array,
+            ),
+        ];
+        for (typed_value, data_type) in values {
+            let value = proto::Value {
+                typed_value: Some(typed_value),
+            };
+            assert_eq!(
+                json_to_value(&value_to_json(&value), data_type),
+                Some(value)
+            );
+        }
+
+        // Values not fitting the data type are rejected
+        assert_eq!(json_to_value(&300.into(), DataType::Uint8), None);
+        assert_eq!(json_to_value(&"1".into(), DataType::Int32), None);
+    }
+
+    #[test]
+    fn test_read_trace() {
+        let trace = read_trace(
+            "{\"t\":0.0,\"path\":\"Vehicle.Speed\",\"value\":10}\n\n\
+             {\"t\":0.5,\"path\":\"Vehicle.Speed\",\"value\":12.5}\n",
+        )
+        .unwrap();
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace[1].t, 0.5);
+        assert_eq!(trace[1].value, serde_json::json!(12.5));
+
+        let err =
+            read_trace("{\"t\":0.0,\"path\":\"Vehicle.Speed\",\"value\":10}\n{\n").unwrap_err();
+        assert!(err.starts_with("Line 2:"));
+
+        assert!(read_trace("\n\n").is_err());
+    }
+
+    #[test]
+    fn test_remap_path() {
+        let rules = vec![
+            parse_remap_rule("Vehicle.OBD=Vehicle.Powertrain").unwrap(),
+            parse_remap_rule("Vehicle.Speed=Vehicle.OBD.Speed").unwrap(),
+        ];
+        assert_eq!(
+            remap_path("Vehicle.OBD.EngineSpeed", &rules),
+            "Vehicle.Powertrain.EngineSpeed"
+        );
+        assert_eq!(remap_path("Vehicle.Speed", &rules), "Vehicle.OBD.Speed");
+        assert_eq!(
+            remap_path("Vehicle.SpeedLimit", &rules),
+            "Vehicle.SpeedLimit"
+        );
+        assert!(parse_remap_rule("Vehicle.Speed").is_err());
+        assert!(parse_remap_rule("=Vehicle.Speed").is_err());
+    }
+}
@@ -0,0 +1,616 @@
+/********************************************************************************
+* Copyright (c) 2025 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+//! Full-screen table of subscribed signals, updated live, which allows to
+//! actuate the selected signal.
+
+use databroker_proto::kuksa::val::v2 as proto;
+
+use kuksa_val_v2::{ClientError, ClientTraitV2, KuksaClientV2};
+
+use mortal::{Color, CursorMode, Event, Key, PrepareConfig, Screen, Style};
+use tokio::sync::mpsc;
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::kuksa_val_v2_cli::{print_client_error, try_into_typed_value, DisplayValue};
+
+/// Updates within this window make up the displayed update rate
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+/// How long changed values stay highlighted
+const HIGHLIGHT_DURATION: Duration = Duration::from_secs(1);
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Title, header and status line
+const FRAME_LINES: usize = 3;
+
+const UNIT_WIDTH: usize = 8;
+const TIME_WIDTH: usize = 12;
+const RATE_WIDTH: usize = 8;
+
+struct Row {
+    metadata: proto::Metadata,
+    datapoint: Option<proto::Datapoint>,
+    // Times of the updates within RATE_WINDOW
+    updates: VecDeque<Instant>,
+    changed_at: Option<Instant>,
+}
+
+impl Row {
+    fn new(metadata: proto::Metadata) -> Self {
+        Row {
+            metadata,
+            datapoint: None,
+            updates: VecDeque::new(),
+            changed_at: None,
+        }
+    }
+
+    fn update(&mut self, datapoint: proto::Datapoint, now: Instant) {
+        // The initial value of the subscription is not a change
+        if let Some(previous) = &self.datapoint {
+            if previous.value != datapoint.value {
+                self.changed_at = Some(now);
+            }
+        }
+        self.datapoint = Some(datapoint);
+        self.updates.push_back(now);
+        while let Some(&first) = self.updates.front() {
+            if now.duration_since(first) <= RATE_WINDOW {
+                break;
+            }
+            self.updates.pop_front();
+        }
+    }
+
+    /// Updates per second within the last RATE_WINDOW
+    fn rate(&self, now: Instant) -> f64 {
+        let count = self
+            .updates
+            .iter()
+            .filter(|&&at| now.duration_since(at) <= RATE_WINDOW)
+            .count();
+        count as f64 / RATE_WINDOW.as_secs_f64()
+    }
+
+    fn is_highlighted(&self, now: Instant) -> bool {
+        self.changed_at
+            .is_some_and(|at| now.duration_since(at) < HIGHLIGHT_DURATION)
+    }
+
+    fn is_actuator(&self) -> bool {
+        self.metadata.entry_type == proto::EntryType::Actuator as i32
+    }
+
+    fn value(&self) -> String {
+        match self
+            .datapoint
+            .as_ref()
+            .and_then(|datapoint| datapoint.value.clone())
+        {
+            Some(value) => DisplayValue(value).to_string(),
+            None => "NotAvailable".to_string(),
+        }
+    }
+
+    fn time(&self) -> String {
+        self.datapoint
+            .as_ref()
+            .and_then(|datapoint| datapoint.timestamp.as_ref())
+            .map(format_time)
+            .unwrap_or_default()
+    }
+}
+
+/// Time of day in UTC with milliseconds, e.g. "12:34:56.789"
+fn format_time(timestamp: &prost_types::Timestamp) -> String {
+    let text = timestamp.to_string();
+    let time = text
+        .split_once('T')
+        .map_or(text.as_str(), |(_, time)| time)
+        .trim_end_matches('Z');
+    time.chars().take(TIME_WIDTH).collect()
+}
+
+/// Shorten `text` to at most `width` characters
+fn fit(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else if width == 0 {
+        String::new()
+    } else {
+        let mut fitted: String = text.chars().take(width - 1).collect();
+        fitted.push('…');
+        fitted
+    }
+}
+
+/// Widths of the path and value columns, the other columns have fixed widths
+struct Columns {
+    path: usize,
+    value: usize,
+}
+
+impl Columns {
+    fn new(screen_width: usize, longest_path: usize) -> Self {
+        let flexible = screen_width.saturating_sub(UNIT_WIDTH + TIME_WIDTH + RATE_WIDTH + 4);
+        let path = longest_path.clamp(4, (flexible / 2).max(4)).min(flexible);
+        Columns {
+            path,
+            value: flexible - path,
+        }
+    }
+
+    fn format(&self, path: &str, value: &str, unit: &str, time: &str, rate: &str) -> String {
+        format!(
+            "{:<path_width$} {:<value_width$} {:<UNIT_WIDTH$} {:<TIME_WIDTH$} {:>RATE_WIDTH$}",
+            fit(path, self.path),
+            fit(value, self.value),
+            fit(unit, UNIT_WIDTH),
+            fit(time, TIME_WIDTH),
+            fit(rate, RATE_WIDTH),
+            path_width = self.path,
+            value_width = self.value,
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    None,
+    Quit,
+    Actuate(String, proto::Value),
+}
+
+struct Dashboard {
+    rows: Vec<Row>,
+    // Row index per signal ID
+    ids: HashMap<i32, usize>,
+    selected: usize,
+    // Index of the first row on screen
+    offset: usize,
+    // Value being entered to actuate the selected row
+    input: Option<String>,
+    status: String,
+}
+
+impl Dashboard {
+    fn new(metadata: Vec<proto::Metadata>) -> Self {
+        let ids = metadata
+            .iter()
+            .enumerate()
+            .map(|(index, metadata)| (metadata.id, index))
+            .collect();
+        Dashboard {
+            rows: metadata.into_iter().map(Row::new).collect(),
+            ids,
+            selected: 0,
+            offset: 0,
+            input: None,
+            status: String::new(),
+        }
+    }
+
+    fn update(&mut self, entries: HashMap<i32, proto::Datapoint>, now: Instant) {
+        for (id, datapoint) in entries {
+            if let Some(&index) = self.ids.get(&id) {
+                self.rows[index].update(datapoint, now);
+            }
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.rows.len().saturating_sub(1));
+    }
+
+    /// Handle a key press, `page` being the number of rows on screen
+    fn handle_key(&mut self, key: Key, page: usize) -> Action {
+        if let Some(input) = &mut self.input {
+            match key {
+                Key::Char(c) => input.push(c),
+                Key::Backspace => {
+                    input.pop();
+                }
+                Key::Escape | Key::Ctrl('c') => {
+                    self.input = None;
+                    self.status.clear();
+                }
+                Key::Enter => {
+                    let input = self.input.take().unwrap_or_default();
+                    let metadata = &self.rows[self.selected].metadata;
+                    match parse_value(&input, metadata) {
+                        Ok(value) => return Action::Actuate(metadata.path.clone(), value),
+                        Err(err) => self.status = err,
+                    }
+                }
+                _ => {}
+            }
+            return Action::None;
+        }
+
+        match key {
+            Key::Char('q') | Key::Escape | Key::Ctrl('c') => return Action::Quit,
+            Key::Up | Key::Char('k') => self.select(self.selected.saturating_sub(1)),
+            Key::Down | Key::Char('j') => self.select(self.selected + 1),
+            Key::PageUp => self.select(self.selected.saturating_sub(page)),
+            Key::PageDown => self.select(self.selected + page),
+            Key::Home => self.select(0),
+            Key::End => self.select(self.rows.len()),
+            Key::Enter | Key::Char('a') => match self.rows.get(self.selected) {
+                Some(row) if row.is_actuator() => {
+                    self.input = Some(String::new());
+                    self.status.clear();
+                }
+                Some(row) => self.status = format!("{} is not an actuator", row.metadata.path),
+                None => {}
+            },
+            _ => {}
+        }
+        Action::None
+    }
+
+    /// Scroll so that the selected row is one of the `page` rows on screen
+    fn scroll(&mut self, page: usize) {
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + page {
+            self.offset = self.selected + 1 - page;
+        }
+    }
+
+    fn render(&mut self, screen: &Screen, now: Instant) -> std::io::Result<()> {
+        let size = screen.size();
+        screen.clear_screen();
+        if size.lines <= FRAME_LINES || size.columns == 0 {
+            return screen.refresh();
+        }
+        let page = size.lines - FRAME_LINES;
+        self.scroll(page);
+
+        let title = format!(
+            "Watching {} signals | Up/Down: select | Enter: actuate | q: quit",
+            self.rows.len()
+        );
+        screen.write_styled_at(
+            (0, 0),
+            None,
+            None,
+            Style::REVERSE,
+            &format!(
+                "{:<width$}",
+                fit(&title, size.columns),
+                width = size.columns
+            ),
+        );
+
+        let longest_path = self
+            .rows
+            .iter()
+            .map(|row| row.metadata.path.chars().count())
+            .max()
+            .unwrap_or_default();
+        let columns = Columns::new(size.columns, longest_path);
+        screen.write_styled_at(
+            (1, 0),
+            None,
+            None,
+            Style::BOLD,
+            &columns.format("PATH", "VALUE", "UNIT", "TIME (UTC)", "RATE"),
+        );
+
+        for (line, (index, row)) in self
+            .rows
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(page)
+            .enumerate()
+        {
+            let text = columns.format(
+                &row.metadata.path,
+                &row.value(),
+                &row.metadata.unit,
+                &row.time(),
+                &format!("{:.1}/s", row.rate(now)),
+            );
+            let fg = row.is_highlighted(now).then_some(Color::Yellow);
+            let style = (index == self.selected).then_some(Style::REVERSE);
+            screen.write_styled_at((line + 2, 0), fg, None, style, &text);
+        }
+
+        // Avoid the last column of the last line, which scrolls some terminals
+        let status_width = size.columns - 1;
+        match &self.input {
+            Some(input) => {
+                let prompt = format!("{} = {input}", self.rows[self.selected].metadata.path);
+                let prompt = fit(&prompt, status_width);
+                screen.write_styled_at((size.lines - 1, 0), None, None, Style::BOLD, &prompt);
+                screen.set_cursor((size.lines - 1, prompt.chars().count()));
+                screen.set_cursor_mode(CursorMode::Normal)?;
+            }
+            None => {
+                screen.write_at((size.lines - 1, 0), &fit(&self.status, status_width));
+                screen.set_cursor_mode(CursorMode::Invisible)?;
+            }
+        }
+        screen.refresh()
+    }
+}
+
+fn parse_value(input: &str, metadata: &proto::Metadata) -> Result<proto::Value, String> {
+    let data_type =
+        proto::DataType::try_from(metadata.data_type).unwrap_or(proto::DataType::Unspecified);
+    try_into_typed_value(input, data_type)
+        .map_err(|_| format!("Could not parse \"{input}\" as {data_type:?}"))
+}
+
+fn describe_error(err: ClientError) -> String {
+    match err {
+        ClientError::Status(status) => format!("{:?}: {}", status.code(), status.message()),
+        err => err.to_string(),
+    }
+}
+
+/// Forward terminal events until `events` is closed
+fn read_events(screen: &Screen, events: mpsc::Sender<Event>) {
+    while !events.is_closed() {
+        match screen.read_event(Some(EVENT_POLL_INTERVAL)) {
+            Ok(Some(event)) => {
+                if events.blocking_send(event).is_err() {
+                    break;
+                }
+            }
+            Ok(None) => {}
+            Err(_) => break,
+        }
+    }
+}
+
+/// Show the signals of `metadata` until the user quits
+pub(crate) async fn watch(
+    metadata: Vec<proto::Metadata>,
+    client: &mut KuksaClientV2,
+) -> Result<(), Box<dyn std::error::Error>> {
+    const CMD: &str = "watch";
+
+    let ids = metadata.iter().map(|metadata| metadata.id).collect();
+    let mut subscription = match client.subscribe_by_id(ids, None).await {
+        Ok(subscription) => subscription,
+        Err(err) => return print_client_error(CMD, err),
+    };
+
+    let screen = Arc::new(Screen::new(PrepareConfig::default())?);
+    let (event_sender, mut events) = mpsc::channel(16);
+    let reader = {
+        let screen = screen.clone();
+        std::thread::spawn(move || read_events(&screen, event_sender))
+    };
+
+    // Actuations are sent next to the UI, which keeps updating meanwhile,
+    // and their results are reported back through a channel
+    let (actuation_sender, mut actuation_requests) =
+        mpsc::unbounded_channel::<(String, proto::Value)>();
+    let (result_sender, mut actuation_results) = mpsc::unbounded_channel();
+    let actuator = async {
+        while let Some((path, value)) = actuation_requests.recv().await {
+            let result = client.actuate(path.clone(), value).await;
+            let _ = result_sender.send((path, result.map_err(describe_error)));
+        }
+    };
+
+    let ui = async {
+        let mut dashboard = Dashboard::new(metadata);
+        let mut redraw = tokio::time::interval(REDRAW_INTERVAL);
+        let mut subscribed = true;
+        loop {
+            tokio::select! {
+                message = subscription.message(), if subscribed => match message {
+                    Ok(Some(response)) => dashboard.update(response.entries, Instant::now()),
+                    Ok(None) => {
+                        subscribed = false;
+                        dashboard.status = "Subscription ended by the server".to_string();
+                    }
+                    Err(status) => {
+                        subscribed = false;
+                        dashboard.status = format!("Subscription failed: {}", status.message());
+                    }
+                },
+                event = events.recv() => match event {
+                    Some(Event::Key(key)) => {
+                        let page = screen.size().lines.saturating_sub(FRAME_LINES).max(1);
+                        match dashboard.handle_key(key, page) {
+                            Action::Quit => break Ok(()),
+                            Action::Actuate(path, value) => {
+                                dashboard.status = format!("Actuating {path}...");
+                                let _ = actuation_sender.send((path, value));
+                            }
+                            Action::None => {}
+                        }
+                    }
+                    // Redrawn below, e.g. after resizing
+                    Some(_) => {}
+                    None => break Ok(()),
+                },
+                Some((path, result)) = actuation_results.recv() => {
+                    dashboard.status = match result {
+                        Ok(()) => format!("Actuation of {path} requested"),
+                        Err(err) => format!("Actuation of {path} failed: {err}"),
+                    };
+                }
+                _ = redraw.tick() => {}
+            }
+            if let Err(err) = dashboard.render(&screen, Instant::now()) {
+                break Err(err);
+            }
+        }
+    };
+
+    let result = tokio::select! {
+        result = ui => result,
+        // Waits for requests for as long as the UI runs
+        () = actuator => Ok(()),
+    };
+
+    // The terminal is restored when the last reference to the screen is dropped
+    drop(events);
+    let _ = reader.join();
+    drop(screen);
+    result?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proto::value::TypedValue;
+
+    fn metadata(id: i32, path: &str, entry_type: proto::EntryType) -> proto::Metadata {
+        proto::Metadata {
+            id,
+            path: path.to_string(),
+            entry_type: entry_type as i32,
+            data_type: proto::DataType::Float as i32,
+            ..Default::default()
+        }
+    }
+
+    fn datapoint(value: f32) -> proto::Datapoint {
+        proto::Datapoint {
+            timestamp: None,
+            value: Some(proto::Value {
+                typed_value: Some(TypedValue::Float(value)),
+            }),
+        }
+    }
+
+    fn dashboard() -> Dashboard {
+        Dashboard::new(vec![
+            metadata(1, "Vehicle.Speed", proto::EntryType::Sensor),
+            metadata(
+                2,
+                "Vehicle.Cabin.Light.Intensity",
+                proto::EntryType::Actuator,
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_row_rate_and_highlight() {
+        let start = Instant::now();
+        let mut row = Row::new(metadata(1, "Vehicle.Speed", proto::EntryType::Sensor));
+        row.update(datapoint(1.0), start);
+        assert!(!row.is_highlighted(start));
+        row.update(datapoint(1.0), start + Duration::from_secs(1));
+        assert!(!row.is_highlighted(start + Duration::from_secs(1)));
+        row.update(datapoint(2.0), start + Duration::from_secs(2));
+        assert!(row.is_highlighted(start + Duration::from_secs(2)));
+        assert!(!row.is_highlighted(start + Duration::from_secs(4)));
+
+        assert_eq!(row.rate(start + Duration::from_secs(2)), 3.0 / 5.0);
+        assert_eq!(row.rate(start + Duration::from_secs(6)), 2.0 / 5.0);
+        row.update(datapoint(3.0), start + Duration::from_secs(10));
+        assert_eq!(row.updates.len(), 1);
+    }
+
+    #[test]
+    fn test_dashboard_update_by_id() {
+        let mut dashboard = dashboard();
+        dashboard.update(
+            HashMap::from([(2, datapoint(50.0)), (3, datapoint(1.0))]),
+            Instant::now(),
+        );
+        assert!(dashboard.rows[0].datapoint.is_none());
+        assert_eq!(dashboard.rows[1].value(), "50.00");
+    }
+
+    #[test]
+    fn test_dashboard_actuate_selected() {
+        let mut dashboard = dashboard();
+        assert_eq!(dashboard.handle_key(Key::Enter, 10), Action::None);
+        assert_eq!(dashboard.status, "Vehicle.Speed is not an actuator");
+        assert!(dashboard.input.is_none());
+
+        dashboard.handle_key(Key::Down, 10);
+        dashboard.handle_key(Key::Down, 10);
+        assert_eq!(dashboard.selected, 1);
+        dashboard.handle_key(Key::Enter, 10);
+        for c in "7x".chars() {
+            dashboard.handle_key(Key::Char(c), 10);
+        }
+        dashboard.handle_key(Key::Backspace, 10);
+        dashboard.handle_key(Key::Char('5'), 10);
+        assert_eq!(
+            dashboard.handle_key(Key::Enter, 10),
+            Action::Actuate(
+                "Vehicle.Cabin.Light.Intensity".to_string(),
+                proto::Value {
+                    typed_value: Some(TypedValue::Float(75.0)),
+                }
+            )
+        );
+        assert!(dashboard.input.is_none());
+
+        dashboard.handle_key(Key::Char('a'), 10);
+        dashboard.handle_key(Key::Char('x'), 10);
+        assert_eq!(dashboard.handle_key(Key::Enter, 10), Action::None);
+        assert_eq!(dashboard.status, "Could not parse \"x\" as Float");
+
+        dashboard.handle_key(Key::Char('a'), 10);
+        assert_eq!(dashboard.handle_key(Key::Escape, 10), Action::None);
+        assert!(dashboard.input.is_none());
+        assert_eq!(dashboard.handle_key(Key::Char('q'), 10), Action::Quit);
+    }
+
+    #[test]
+    fn test_dashboard_scroll() {
+        let mut dashboard = Dashboard::new(
+            (0..10)
+                .map(|id| metadata(id, &format!("Vehicle.S{id}"), proto::EntryType::Sensor))
+                .collect(),
+        );
+        dashboard.handle_key(Key::PageDown, 4);
+        dashboard.handle_key(Key::Down, 4);
+        assert_eq!(dashboard.selected, 5);
+        dashboard.scroll(4);
+        assert_eq!(dashboard.offset, 2);
+        dashboard.handle_key(Key::Home, 4);
+        dashboard.scroll(4);
+        assert_eq!(dashboard.offset, 0);
+        dashboard.handle_key(Key::End, 4);
+        assert_eq!(dashboard.selected, 9);
+    }
+
+    #[test]
+    fn test_format_time() {
+        let timestamp = prost_types::Timestamp {
+            seconds: 1700000000,
+            nanos: 123456789,
+        };
+        assert_eq!(format_time(&timestamp), "22:13:20.123");
+        let timestamp = prost_types::Timestamp {
+            seconds: 1700000000,
+            nanos: 0,
+        };
+        assert_eq!(format_time(&timestamp), "22:13:20");
+    }
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit("Vehicle.Speed", 20), "Vehicle.Speed");
+        assert_eq!(fit("Vehicle.Speed", 8), "Vehicle…");
+        assert_eq!(fit("Vehicle.Speed", 0), "");
+    }
+}
@@ -0,0 +1,376 @@
+/********************************************************************************
+* Copyright (c) 2024 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tracing::warn;
+
+use crate::glob;
+use crate::permissions::Permissions;
+use crate::types::DataValue;
+use crate::vss;
+
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+pub const DEFAULT_MAX_FILES: usize = 5;
+
+/// The protocol a request arrived through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Transport {
+    #[serde(rename = "internal")]
+    Internal,
+    #[serde(rename = "kuksa.val.v1")]
+    KuksaValV1,
+    #[serde(rename = "kuksa.val.v2")]
+    KuksaValV2,
+    #[serde(rename = "viss")]
+    Viss,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    Actuate,
+    RegisterActuationProvider,
+    RegisterSignalProvider,
+    PermissionDenied,
+}
+
+/// Result of an audited operation, `error` is `None` on success.
+#[derive(Debug, Clone, Default)]
+pub struct Outcome {
+    error: Option<String>,
+    message: Option<String>,
+}
+
+impl Outcome {
+    pub fn ok() -> Self {
+        Self::default()
+    }
+
+    pub fn err(error: impl Debug, message: impl Into<String>) -> Self {
+        Self {
+            error: Some(format!("{error:?}")),
+            message: Some(message.into()),
+        }
+    }
+
+    pub fn failed(error: impl Debug) -> Self {
+        Self {
+            error: Some(format!("{error:?}")),
+            message: None,
+        }
+    }
+}
+
+impl<T, E: Debug> From<&Result<T, (E, String)>> for Outcome {
+    fn from(result: &Result<T, (E, String)>) -> Self {
+        match result {
+            Ok(_) => Outcome::ok(),
+            Err((error, message)) => Outcome::err(error, message.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    /// File to write records to, rotated files get a numeric suffix
+    pub file: PathBuf,
+    /// Only paths matching one of these globs are audited
+    pub paths: Vec<String>,
+    /// Rotate when the file would grow beyond this size (in bytes)
+    pub max_file_size: u64,
+    /// Number of rotated files to keep in addition to the current one
+    pub max_files: usize,
+}
+
+impl AuditConfig {
+    pub fn new(file: impl Into<PathBuf>) -> Self {
+        Self {
+            file: file.into(),
+            paths: vec!["**".to_owned()],
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AuditError {
+    InvalidPattern(String),
+    Io(io::Error),
+}
+
+impl std::fmt::Display for AuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditError::InvalidPattern(pattern) => {
+                write!(f, "invalid audit path pattern: {pattern}")
+            }
+            AuditError::Io(err) => write!(f, "failed to open audit log: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<&'a str>,
+    transport: Transport,
+    event: AuditEvent,
+    path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<serde_json::Value>,
+    outcome: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<&'a str>,
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_owned(),
+            file,
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.file.write_all(b"\n")?;
+        self.file.flush()?;
+        self.size += len;
+        Ok(())
+    }
+}
+
+/// Writes audit records as JSON Lines to a size rotated file.
+#[derive(Default)]
+pub struct AuditLog {
+    paths: Vec<glob::Matcher>,
+    file: Option<Mutex<RotatingFile>>,
+}
+
+impl AuditLog {
+    pub fn new(config: &AuditConfig) -> Result<Self, AuditError> {
+        let paths = config
+            .paths
+            .iter()
+            .map(|pattern| {
+                glob::Matcher::new(pattern).map_err(|_| AuditError::InvalidPattern(pattern.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let file = RotatingFile::open(&config.file, config.max_file_size, config.max_files)
+            .map_err(AuditError::Io)?;
+        Ok(Self {
+            paths,
+            file: Some(Mutex::new(file)),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Returns true if operations on `path` should be recorded.
+    pub fn is_audited(&self, path: &str) -> bool {
+        if self.file.is_none() {
+            return false;
+        }
+        let glob_path = path.replace('.', "/");
+        self.paths
+            .iter()
+            .any(|matcher| matcher.is_match(&glob_path))
+    }
+
+    pub fn record(
+        &self,
+        permissions: &Permissions,
+        transport: Transport,
+        event: AuditEvent,
+        path: &str,
+        value: Option<&DataValue>,
+        outcome: &Outcome,
+    ) {
+        if !self.is_audited(path) {
+            return;
+        }
+        let Some(file) = &self.file else {
+            return;
+        };
+
+        let record = Record {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            sub: permissions.subject(),
+            transport,
+            event,
+            path,
+            value: value.map(vss::value_to_json),
+            outcome: outcome.error.as_deref().unwrap_or("Ok"),
+            message: outcome.message.as_deref(),
+        };
+        let line = match serde_json::to_vec(&record) {
+            Ok(line) => line,
+            Err(err) => {
+                warn!("Failed to serialize audit record: {}", err);
+                return;
+            }
+        };
+        if let Err(err) = file.lock().unwrap().write_line(&line) {
+            warn!("Failed to write audit record: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broker::ActuationError;
+    use crate::permissions::PermissionBuilder;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("audit-{}-{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("audit.log")
+    }
+
+    #[test]
+    fn test_record_written_as_json_line() {
+        let file = temp_file("record");
+        let mut config = AuditConfig::new(&file);
+        config.paths = vec!["Vehicle.Body.**".to_owned()];
+        let audit = AuditLog::new(&config).unwrap();
+        let permissions = PermissionBuilder::new().subject("alice").build().unwrap();
+
+        audit.record(
+            &permissions,
+            Transport::KuksaValV2,
+            AuditEvent::Actuate,
+            "Vehicle.Body.Trunk.Rear.IsOpen",
+            Some(&DataValue::Bool(true)),
+            &Outcome::ok(),
+        );
+        // Not matching the configured paths
+        audit.record(
+            &permissions,
+            Transport::KuksaValV2,
+            AuditEvent::Actuate,
+            "Vehicle.Speed",
+            Some(&DataValue::Float(10.0)),
+            &Outcome::ok(),
+        );
+        audit.record(
+            &permissions,
+            Transport::Viss,
+            AuditEvent::PermissionDenied,
+            "Vehicle.Body.Horn.IsActive",
+            None,
+            &Outcome::err(ActuationError::PermissionDenied, "Permission denied"),
+        );
+
+        let content = fs::read_to_string(&file).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["sub"], "alice");
+        assert_eq!(lines[0]["transport"], "kuksa.val.v2");
+        assert_eq!(lines[0]["event"], "actuate");
+        assert_eq!(lines[0]["path"], "Vehicle.Body.Trunk.Rear.IsOpen");
+        assert_eq!(lines[0]["value"], true);
+        assert_eq!(lines[0]["outcome"], "Ok");
+        assert_eq!(lines[1]["event"], "permission_denied");
+        assert_eq!(lines[1]["outcome"], "PermissionDenied");
+        assert!(lines[1].get("value").is_none());
+    }
+
+    #[test]
+    fn test_rotation() {
+        let file = temp_file("rotation");
+        let mut config = AuditConfig::new(&file);
+        config.max_file_size = 200;
+        config.max_files = 2;
+        let audit = AuditLog::new(&config).unwrap();
+
+        for _ in 0..20 {
+            audit.record(
+                &Permissions::builder().build().unwrap(),
+                Transport::KuksaValV1,
+                AuditEvent::Actuate,
+                "Vehicle.Cabin.Door.Row1.DriverSide.IsOpen",
+                Some(&DataValue::Bool(false)),
+                &Outcome::ok(),
+            );
+        }
+
+        let rotated = |index: usize| {
+            let mut name = file.clone().into_os_string();
+            name.push(format!(".{index}"));
+            PathBuf::from(name)
+        };
+        assert!(fs::metadata(&file).unwrap().len() <= 200);
+        assert!(rotated(1).exists());
+        assert!(rotated(2).exists());
+        assert!(!rotated(3).exists());
+    }
+}
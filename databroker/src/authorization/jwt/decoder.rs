@@ -62,6 +62,8 @@ pub struct Claims {
     pub exp: u64, // Expiration time (as UTC timestamp)
     #[allow(dead_code)]
     pub scope: String,
+    #[serde(default)]
+    pub jti: Option<String>, // Optional. Unique token id, used for revocation
 }
 
 impl Decoder {
@@ -141,40 +143,28 @@ impl TryFrom<Claims> for Permissions {
 
         let mut permissions = Permissions::builder();
         for scope in scopes {
-            match scope.path {
-                Some(path) => {
-                    permissions = match scope.action {
-                        scope::Action::Read => {
-                            permissions.add_read_permission(Permission::Glob(path))
-                        }
-                        scope::Action::Actuate => {
-                            permissions.add_actuate_permission(Permission::Glob(path))
-                        }
-                        scope::Action::Provide => {
-                            permissions.add_provide_permission(Permission::Glob(path))
-                        }
-                        scope::Action::Create => {
-                            permissions.add_create_permission(Permission::Glob(path))
-                        }
-                    }
-                }
-                None => {
-                    // Empty path => all paths
-                    permissions = match scope.action {
-                        scope::Action::Read => permissions.add_read_permission(Permission::All),
-                        scope::Action::Actuate => {
-                            permissions.add_actuate_permission(Permission::All)
-                        }
-                        scope::Action::Provide => {
-                            permissions.add_provide_permission(Permission::All)
-                        }
-                        scope::Action::Create => permissions.add_create_permission(Permission::All),
-                    };
-                }
-            }
+            // Empty path => all paths
+            let permission = match scope.path {
+                Some(path) => Permission::Glob(path),
+                None => Permission::All,
+            };
+            permissions = match (scope.action, scope.deny) {
+                (scope::Action::Read, false) => permissions.add_read_permission(permission),
+                (scope::Action::Actuate, false) => permissions.add_actuate_permission(permission),
+                (scope::Action::Provide, false) => permissions.add_provide_permission(permission),
+                (scope::Action::Create, false) => permissions.add_create_permission(permission),
+                (scope::Action::Read, true) => permissions.add_read_denial(permission),
+                (scope::Action::Actuate, true) => permissions.add_actuate_denial(permission),
+                (scope::Action::Provide, true) => permissions.add_provide_denial(permission),
+                (scope::Action::Create, true) => permissions.add_create_denial(permission),
+            };
         }
 
+        if let Some(jti) = claims.jti {
+            permissions = permissions.token_id(jti);
+        }
         permissions = permissions
+            .subject(claims.sub)
             .expires_at(std::time::UNIX_EPOCH + std::time::Duration::from_secs(claims.exp));
 
         permissions.build().map_err(|err| match err {
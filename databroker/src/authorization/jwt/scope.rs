@@ -15,6 +15,8 @@
 pub struct Scope {
     pub action: Action,
     pub path: Option<String>,
+    // Negated scope (prefixed with `!`), takes precedence over granted scopes
+    pub deny: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +36,7 @@ pub fn parse_whitespace_separated(scope: &str) -> Result<Vec<Scope>, Error> {
     let regex = regex::Regex::new(
         r"(?x)
         ^
+        (?P<deny>!)?        # match optional negation
         (?P<action>([^:]*)) # match action
 
         (?::
@@ -80,8 +83,9 @@ pub fn parse_whitespace_separated(scope: &str) -> Result<Vec<Scope>, Error> {
                     }
                 };
                 let path = captures.name("path").map(|path| path.as_str().to_owned());
+                let deny = captures.name("deny").is_some();
 
-                Scope { action, path }
+                Scope { action, path, deny }
             }
             None => {
                 // Capture groups couldn't be produced
@@ -279,4 +283,30 @@ mod test {
             Err(_) => todo!(),
         }
     }
+
+    #[test]
+    fn test_scope_deny_read_vehicle_wildcard_test() {
+        match parse_whitespace_separated("read:Vehicle !read:Vehicle.*.Test") {
+            Ok(scopes) => {
+                assert_eq!(scopes.len(), 2);
+                assert!(matches!(scopes[0].action, Action::Read));
+                assert!(!scopes[0].deny);
+                let scope = &scopes[1];
+                assert!(matches!(scope.action, Action::Read));
+                assert!(scope.deny);
+                assert_eq!(scope.path.as_deref().unwrap(), "Vehicle.*.Test");
+            }
+            Err(_) => panic!("should not error"),
+        }
+    }
+
+    #[test]
+    fn test_scope_deny_no_action() {
+        match parse_whitespace_separated("!:Vehicle.Test") {
+            Ok(_) => {
+                panic!("negation without action should result in error")
+            }
+            Err(Error::ParseError) => {}
+        }
+    }
 }
@@ -11,27 +11,74 @@
 * SPDX-License-Identifier: Apache-2.0
 ********************************************************************************/
 
+use std::sync::Arc;
+
 use thiserror::Error;
 
+use crate::permissions::{self, Permissions};
+
 pub mod jwt;
+pub mod revocation;
+
+use revocation::RevocationList;
 
 #[derive(Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum Authorization {
     Disabled,
-    Enabled { token_decoder: jwt::Decoder },
+    Enabled {
+        token_decoder: jwt::Decoder,
+        revocation_list: Arc<RevocationList>,
+    },
 }
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Invalid public key")]
     InvalidPublicKey,
+    #[error("Invalid auth token: {0}")]
+    InvalidToken(String),
+    #[error("Invalid auth token: token has been revoked")]
+    TokenRevoked,
 }
 
 impl Authorization {
     pub fn new(public_key: String) -> Result<Authorization, Error> {
         Ok(Authorization::Enabled {
             token_decoder: jwt::Decoder::new(public_key).map_err(|_| Error::InvalidPublicKey)?,
+            revocation_list: Default::default(),
         })
     }
+
+    /// Decode and verify `token`, returning the permissions it grants.
+    pub fn permissions_from_token(&self, token: &str) -> Result<Permissions, Error> {
+        match self {
+            Authorization::Disabled => Ok(permissions::ALLOW_ALL.clone()),
+            Authorization::Enabled {
+                token_decoder,
+                revocation_list,
+            } => {
+                let claims = token_decoder
+                    .decode(token)
+                    .map_err(|err| Error::InvalidToken(err.to_string()))?;
+                let permissions = Permissions::try_from(claims)
+                    .map_err(|err| Error::InvalidToken(err.to_string()))?;
+                if revocation_list.is_revoked(&permissions) {
+                    return Err(Error::TokenRevoked);
+                }
+                Ok(permissions)
+            }
+        }
+    }
+
+    /// Reject tokens found in `revocation_list`.
+    pub fn with_revocation_list(self, revocation_list: Arc<RevocationList>) -> Self {
+        match self {
+            Authorization::Disabled => Authorization::Disabled,
+            Authorization::Enabled { token_decoder, .. } => Authorization::Enabled {
+                token_decoder,
+                revocation_list,
+            },
+        }
+    }
 }
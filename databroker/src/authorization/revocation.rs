@@ -0,0 +1,193 @@
+/********************************************************************************
+* Copyright (c) 2024 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::permissions::Permissions;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to read revocation list: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid revocation list: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+/// Content of a revocation list file, e.g.
+/// `{"jti": ["4e3f..."], "sub": ["compromised-client"]}`
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct Revoked {
+    #[serde(default)]
+    jti: HashSet<String>,
+    #[serde(default)]
+    sub: HashSet<String>,
+}
+
+/// Tokens that must no longer be accepted, identified either by their
+/// `jti` or by their `sub` (revoking every token issued to that subject).
+pub struct RevocationList {
+    revoked: RwLock<Revoked>,
+    changed: watch::Sender<()>,
+}
+
+impl RevocationList {
+    pub fn new() -> Self {
+        Self {
+            revoked: RwLock::new(Revoked::default()),
+            changed: watch::Sender::new(()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let list = Self::new();
+        list.reload(path)?;
+        Ok(list)
+    }
+
+    /// Replace the current content with the content of `path`. Returns true
+    /// if anything changed.
+    pub fn reload(&self, path: &Path) -> Result<bool, Error> {
+        let content = std::fs::read_to_string(path)?;
+        let revoked: Revoked = serde_json::from_str(&content)?;
+        let changed = {
+            let mut current = self.revoked.write().unwrap();
+            if *current != revoked {
+                *current = revoked;
+                true
+            } else {
+                false
+            }
+        };
+        if changed {
+            self.changed.send_replace(());
+        }
+        Ok(changed)
+    }
+
+    pub fn revoke_token_id(&self, token_id: impl Into<String>) {
+        self.revoked.write().unwrap().jti.insert(token_id.into());
+        self.changed.send_replace(());
+    }
+
+    pub fn revoke_subject(&self, subject: impl Into<String>) {
+        self.revoked.write().unwrap().sub.insert(subject.into());
+        self.changed.send_replace(());
+    }
+
+    pub fn is_revoked(&self, permissions: &Permissions) -> bool {
+        let revoked = self.revoked.read().unwrap();
+        if revoked.jti.is_empty() && revoked.sub.is_empty() {
+            return false;
+        }
+        permissions
+            .token_id()
+            .map(|jti| revoked.jti.contains(jti))
+            .unwrap_or(false)
+            || permissions
+                .subject()
+                .map(|sub| revoked.sub.contains(sub))
+                .unwrap_or(false)
+    }
+
+    /// Notified whenever the list changes, used to terminate
+    /// long-lived streams of tokens revoked after they were opened.
+    pub fn changed(&self) -> watch::Receiver<()> {
+        self.changed.subscribe()
+    }
+
+    /// Periodically reload `path` if it has been modified.
+    pub fn watch_file(self: Arc<Self>, path: PathBuf, interval: Duration) {
+        tokio::spawn(async move {
+            let modified = |path: &Path| -> Option<SystemTime> {
+                std::fs::metadata(path).and_then(|m| m.modified()).ok()
+            };
+            let mut last_modified = modified(&path);
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                let current = modified(&path);
+                if current == last_modified {
+                    continue;
+                }
+                last_modified = current;
+                match self.reload(&path) {
+                    Ok(true) => info!("Reloaded token revocation list {:?}", path),
+                    Ok(false) => {}
+                    Err(err) => warn!("Keeping previous token revocation list: {}", err),
+                }
+            }
+        });
+    }
+}
+
+impl Default for RevocationList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revoke_by_token_id_and_subject() {
+        let list = RevocationList::new();
+        let permissions = Permissions::builder()
+            .subject("client-a")
+            .token_id("token-1")
+            .build()
+            .unwrap();
+        let other = Permissions::builder()
+            .subject("client-b")
+            .token_id("token-2")
+            .build()
+            .unwrap();
+
+        let changed = list.changed();
+        assert!(!list.is_revoked(&permissions));
+        list.revoke_token_id("token-1");
+        assert!(changed.has_changed().unwrap());
+        assert!(list.is_revoked(&permissions));
+        assert!(!list.is_revoked(&other));
+        list.revoke_subject("client-b");
+        assert!(list.is_revoked(&other));
+    }
+
+    #[test]
+    fn test_reload_from_file() {
+        let path = std::env::temp_dir().join(format!("revoked-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"{"sub": ["client-a"]}"#).unwrap();
+        let list = RevocationList::from_file(&path).unwrap();
+        let permissions = Permissions::builder().subject("client-a").build().unwrap();
+        assert!(list.is_revoked(&permissions));
+
+        std::fs::write(&path, r#"{"jti": ["token-1"]}"#).unwrap();
+        assert!(list.reload(&path).unwrap());
+        assert!(!list.is_revoked(&permissions));
+        assert!(!list.reload(&path).unwrap());
+
+        std::fs::write(&path, r#"{"unknown": []}"#).unwrap();
+        assert!(matches!(list.reload(&path), Err(Error::Parse(_))));
+        let _ = std::fs::remove_file(&path);
+    }
+}
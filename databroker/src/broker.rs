@@ -11,11 +11,17 @@
 * SPDX-License-Identifier: Apache-2.0
 ********************************************************************************/
 
+use crate::audit::{AuditEvent, AuditLog, Outcome, Transport};
+use crate::authorization::revocation::RevocationList;
 use crate::filter::filter_manager::FilterManager;
 use crate::permissions::{PermissionError, Permissions};
+use crate::rate_limit::{RateLimiter, RateLimits};
 pub use crate::types;
+use crate::vss;
 
-pub use crate::types::{ChangeType, DataType, DataValue, EntryType, SignalId, TimeInterval};
+pub use crate::types::{
+    ChangeType, DataType, DataValue, EntryType, RangePolicy, SignalId, TimeInterval,
+};
 
 use indexmap::IndexMap;
 use tokio::sync::{broadcast, RwLock};
@@ -26,6 +32,7 @@ use tokio_stream::{Stream, StreamExt};
 
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::convert::TryFrom;
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
@@ -83,6 +90,8 @@ pub enum RegistrationError {
     ValidationError,
     PermissionDenied,
     PermissionExpired,
+    AlreadyExists,
+    NotFound,
 }
 
 #[derive(Debug, Clone)]
@@ -99,6 +108,31 @@ pub struct Metadata {
     pub max: Option<types::DataValue>,
     pub allowed: Option<types::DataValue>,
     pub unit: Option<String>,
+    pub comment: Option<String>,
+    // Set if the entry is deprecated, usually says what to use instead
+    pub deprecation: Option<String>,
+    pub default: Option<types::DataValue>,
+    // What to do with values outside of min/max or allowed
+    pub range_policy: RangePolicy,
+}
+
+impl From<&Metadata> for vss::DataEntry {
+    fn from(metadata: &Metadata) -> Self {
+        vss::DataEntry {
+            data_type: metadata.data_type.clone(),
+            entry_type: metadata.entry_type.clone(),
+            change_type: metadata.change_type.clone(),
+            description: metadata.description.clone(),
+            comment: metadata.comment.clone(),
+            deprecation: metadata.deprecation.clone(),
+            unit: metadata.unit.clone(),
+            min: metadata.min.clone(),
+            max: metadata.max.clone(),
+            allowed: metadata.allowed.clone(),
+            default: metadata.default.clone(),
+            range_policy: metadata.range_policy,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -120,14 +154,28 @@ pub struct Entry {
 pub enum Field {
     Datapoint,
     ActuatorTarget,
+    // Any of description, unit, min, max, allowed or range policy
+    Metadata,
     MetadataUnit,
 }
 
+/// Notification about an entry added to, removed from or changed in the
+/// database at runtime
+#[derive(Debug, Clone)]
+pub enum MetadataChange {
+    Added(Metadata),
+    Removed(Metadata),
+    Changed(Metadata),
+}
+
 #[derive(Default)]
 pub struct Database {
     next_id: AtomicI32,
     path_to_id: HashMap<String, i32>,
     entries: HashMap<i32, Entry>,
+    // Entries registered by clients at runtime, which may also be removed
+    // again, with the subject of the client which registered them (if known)
+    registered_entries: HashMap<i32, Option<String>>,
 }
 
 #[derive(Default)]
@@ -170,6 +218,7 @@ pub enum QueryError {
 pub enum SubscriptionError {
     NotFound,
     InvalidInput,
+    PermissionDenied,
     InvalidBufferSize,
     InternalError,
 }
@@ -182,6 +231,12 @@ pub struct DataBroker {
     commit_sha: String,
     shutdown_trigger: broadcast::Sender<()>,
     filter_manager: Arc<RwLock<FilterManager>>,
+    rate_limiter: Arc<RateLimiter>,
+    audit_log: Arc<AuditLog>,
+    revocation_list: Arc<RevocationList>,
+    metadata_changes: broadcast::Sender<MetadataChange>,
+    // Deprecated paths which have already been warned about
+    warned_deprecated_paths: Arc<std::sync::Mutex<HashSet<String>>>,
 }
 
 #[async_trait::async_trait]
@@ -216,6 +271,7 @@ pub struct ActuationSubscription {
     vss_ids: Vec<i32>,
     actuation_provider: Box<dyn ActuationProvider + Send + Sync + 'static>,
     permissions: Permissions,
+    session: Option<Uuid>,
 }
 
 pub struct GetValuesProviderResponse {
@@ -227,12 +283,16 @@ pub struct SignalProviderSubscription {
     signals_intervals: HashMap<SignalId, TimeInterval>,
     signal_provider: Box<dyn SignalProvider>,
     permissions: Permissions,
+    session: Option<Uuid>,
 }
 
 pub struct ChangeSubscription {
     entries: HashMap<i32, HashSet<Field>>,
     sender: broadcast::Sender<Option<EntryUpdates>>,
     permissions: Permissions,
+    session: Option<Uuid>,
+    // Remote address of the client connection the subscription was made on
+    connection: Option<SocketAddr>,
     interval_duration: Option<Duration>,
     last_emitted: Arc<RwLock<Instant>>,
 }
@@ -262,6 +322,7 @@ pub struct EntryUpdate {
     pub min: Option<Option<types::DataValue>>,
     pub max: Option<Option<types::DataValue>>,
     pub unit: Option<String>,
+    pub range_policy: Option<RangePolicy>,
 }
 
 impl Entry {
@@ -285,27 +346,210 @@ impl Entry {
         update
     }
 
-    pub fn validate_actuator_value(&self, data_value: &DataValue) -> Result<(), UpdateError> {
-        self.validate_value(data_value)?;
-        self.validate_allowed(data_value)?;
-        Ok(())
+    /// Validates a value to be sent to an actuator, returning the value to
+    /// actually send (which may be clamped according to the range policy)
+    pub fn validate_actuator_value(
+        &self,
+        data_value: &DataValue,
+    ) -> Result<DataValue, UpdateError> {
+        let previous = self.actuator_target.as_ref().map(|target| &target.value);
+        self.enforce_range_policy(data_value.clone(), previous)
     }
 
+    /// Validates an update, returning the update to actually apply (values
+    /// may be clamped according to the range policy)
     #[cfg_attr(feature="otel", tracing::instrument(name="entry_validate", skip(self, update), fields(timestamp=chrono::Utc::now().to_string())))]
-    pub fn validate(&self, update: &EntryUpdate) -> Result<(), UpdateError> {
-        if let Some(datapoint) = &update.datapoint {
-            self.validate_value(&datapoint.value)?;
-            self.validate_allowed(&datapoint.value)?;
+    pub fn validate(&self, mut update: EntryUpdate) -> Result<EntryUpdate, UpdateError> {
+        if let Some(datapoint) = &mut update.datapoint {
+            datapoint.value =
+                self.enforce_range_policy(datapoint.value.clone(), Some(&self.datapoint.value))?;
         }
-        if let Some(Some(actuatortarget)) = &update.actuator_target {
-            self.validate_value(&actuatortarget.value)?;
-            self.validate_allowed(&actuatortarget.value)?;
+        if let Some(Some(actuatortarget)) = &mut update.actuator_target {
+            let previous = self.actuator_target.as_ref().map(|target| &target.value);
+            actuatortarget.value =
+                self.enforce_range_policy(actuatortarget.value.clone(), previous)?;
         }
         if let Some(Some(updated_allowed)) = update.allowed.clone() {
             if Some(updated_allowed.clone()) != self.metadata.allowed {
                 self.validate_allowed_type(&Some(updated_allowed))?;
             }
         }
+        Ok(update)
+    }
+
+    /// Validates a value, handling values outside of min/max or allowed
+    /// according to the range policy of the entry. `previous` is the value
+    /// the new one replaces, if any.
+    fn enforce_range_policy(
+        &self,
+        value: DataValue,
+        previous: Option<&DataValue>,
+    ) -> Result<DataValue, UpdateError> {
+        let err = match self.validate_range(&value) {
+            Ok(_) => return Ok(value),
+            Err(err @ (UpdateError::OutOfBoundsMinMax | UpdateError::OutOfBoundsAllowed)) => err,
+            Err(err) => return Err(err),
+        };
+        match (self.metadata.range_policy, &err) {
+            (RangePolicy::Clamp, UpdateError::OutOfBoundsMinMax) => {
+                // Min/max is checked before the type, so check it now.
+                // Elements of arrays are checked against min/max along with
+                // their type, that is repeated for the clamped value below.
+                match self.validate_value_type(&value) {
+                    Ok(()) | Err(UpdateError::OutOfBoundsMinMax) => {}
+                    Err(err) => return Err(err),
+                }
+                let clamped = self.clamp_value_min_max(value);
+                self.validate_value(&clamped)?;
+                self.validate_allowed(&clamped)?;
+                debug!("Clamped value for {} to {}", self.metadata.path, clamped);
+                Ok(clamped)
+            }
+            (RangePolicy::Warn, _) => {
+                self.validate_value_type(&value)?;
+                // Only warn when the value leaves the range, not for every
+                // sample while it stays outside of it
+                if !previous.is_some_and(|previous| self.is_out_of_range(previous)) {
+                    warn!(
+                        "Accepting value {} for {} although it is out of bounds ({:?})",
+                        value, self.metadata.path, err
+                    );
+                }
+                Ok(value)
+            }
+            _ => Err(err),
+        }
+    }
+
+    fn validate_range(&self, value: &DataValue) -> Result<(), UpdateError> {
+        self.validate_value(value)
+            .and_then(|_| self.validate_allowed(value))
+    }
+
+    fn is_out_of_range(&self, value: &DataValue) -> bool {
+        matches!(
+            self.validate_range(value),
+            Err(UpdateError::OutOfBoundsMinMax | UpdateError::OutOfBoundsAllowed)
+        )
+    }
+
+    /// Clamps a value to min/max. Min and max of array types apply to each
+    /// element.
+    fn clamp_value_min_max(&self, value: DataValue) -> DataValue {
+        match value {
+            DataValue::Int32Array(values) => DataValue::Int32Array(
+                values
+                    .into_iter()
+                    .map(
+                        |value| match self.clamp_scalar_min_max(DataValue::Int32(value)) {
+                            DataValue::Int32(clamped) => clamped,
+                            _ => value,
+                        },
+                    )
+                    .collect(),
+            ),
+            DataValue::Int64Array(values) => DataValue::Int64Array(
+                values
+                    .into_iter()
+                    .map(
+                        |value| match self.clamp_scalar_min_max(DataValue::Int64(value)) {
+                            DataValue::Int64(clamped) => clamped,
+                            _ => value,
+                        },
+                    )
+                    .collect(),
+            ),
+            DataValue::Uint32Array(values) => DataValue::Uint32Array(
+                values
+                    .into_iter()
+                    .map(
+                        |value| match self.clamp_scalar_min_max(DataValue::Uint32(value)) {
+                            DataValue::Uint32(clamped) => clamped,
+                            _ => value,
+                        },
+                    )
+                    .collect(),
+            ),
+            DataValue::Uint64Array(values) => DataValue::Uint64Array(
+                values
+                    .into_iter()
+                    .map(
+                        |value| match self.clamp_scalar_min_max(DataValue::Uint64(value)) {
+                            DataValue::Uint64(clamped) => clamped,
+                            _ => value,
+                        },
+                    )
+                    .collect(),
+            ),
+            DataValue::FloatArray(values) => DataValue::FloatArray(
+                values
+                    .into_iter()
+                    .map(
+                        |value| match self.clamp_scalar_min_max(DataValue::Float(value)) {
+                            DataValue::Float(clamped) => clamped,
+                            _ => value,
+                        },
+                    )
+                    .collect(),
+            ),
+            DataValue::DoubleArray(values) => DataValue::DoubleArray(
+                values
+                    .into_iter()
+                    .map(
+                        |value| match self.clamp_scalar_min_max(DataValue::Double(value)) {
+                            DataValue::Double(clamped) => clamped,
+                            _ => value,
+                        },
+                    )
+                    .collect(),
+            ),
+            value => self.clamp_scalar_min_max(value),
+        }
+    }
+
+    fn clamp_scalar_min_max(&self, value: DataValue) -> DataValue {
+        if let Some(min) = &self.metadata.min {
+            if let Ok(true) = value.less_than(min) {
+                return min.clone();
+            }
+        }
+        if let Some(max) = &self.metadata.max {
+            if let Ok(true) = value.greater_than(max) {
+                return max.clone();
+            }
+        }
+        value
+    }
+
+    /// Checks that min, max and the default value fit the data type of the
+    /// entry. Min and max of array types apply to each element.
+    pub fn validate_min_max_default_type(&self) -> Result<(), UpdateError> {
+        let element_type = match &self.metadata.data_type {
+            DataType::StringArray => DataType::String,
+            DataType::BoolArray => DataType::Bool,
+            DataType::Int8Array => DataType::Int8,
+            DataType::Int16Array => DataType::Int16,
+            DataType::Int32Array => DataType::Int32,
+            DataType::Int64Array => DataType::Int64,
+            DataType::Uint8Array => DataType::Uint8,
+            DataType::Uint16Array => DataType::Uint16,
+            DataType::Uint32Array => DataType::Uint32,
+            DataType::Uint64Array => DataType::Uint64,
+            DataType::FloatArray => DataType::Float,
+            DataType::DoubleArray => DataType::Double,
+            DataType::TimestampArray => DataType::Timestamp,
+            DataType::StructArray(struct_type) => DataType::Struct(struct_type.clone()),
+            data_type => data_type.clone(),
+        };
+        for bound in [&self.metadata.min, &self.metadata.max]
+            .into_iter()
+            .flatten()
+        {
+            validate_value_of_type(&element_type, bound)?;
+        }
+        if let Some(default) = &self.metadata.default {
+            validate_value_of_type(&self.metadata.data_type, default)?;
+        }
         Ok(())
     }
 
@@ -532,8 +776,11 @@ impl Entry {
             _ => {}
         }
 
-        // Validate value
-        match self.metadata.data_type {
+        self.validate_value_type(value)
+    }
+
+    fn validate_value_type(&self, value: &DataValue) -> Result<(), UpdateError> {
+        match &self.metadata.data_type {
             DataType::Bool => match value {
                 DataValue::Bool(_) => Ok(()),
                 _ => Err(UpdateError::WrongType),
@@ -595,6 +842,10 @@ impl Entry {
                 DataValue::Double(_) => Ok(()),
                 _ => Err(UpdateError::WrongType),
             },
+            DataType::Timestamp => match value {
+                DataValue::Timestamp(_) => Ok(()),
+                _ => Err(UpdateError::WrongType),
+            },
             DataType::BoolArray => match value {
                 DataValue::BoolArray(_) => Ok(()),
                 _ => Err(UpdateError::WrongType),
@@ -739,6 +990,20 @@ impl Entry {
                 }
                 _ => Err(UpdateError::WrongType),
             },
+            DataType::TimestampArray => match value {
+                DataValue::TimestampArray(_) => Ok(()),
+                _ => Err(UpdateError::WrongType),
+            },
+            DataType::Struct(struct_type) => match value {
+                DataValue::Struct(fields) => validate_struct(struct_type, fields),
+                _ => Err(UpdateError::WrongType),
+            },
+            DataType::StructArray(struct_type) => match value {
+                DataValue::StructArray(values) => values
+                    .iter()
+                    .try_for_each(|fields| validate_struct(struct_type, fields)),
+                _ => Err(UpdateError::WrongType),
+            },
         }
     }
 
@@ -763,15 +1028,122 @@ impl Entry {
         if let Some(updated_allowed) = update.allowed {
             if updated_allowed != self.metadata.allowed {
                 self.metadata.allowed = updated_allowed;
+                changed.insert(Field::Metadata);
+            }
+        }
+        if let Some(updated_min) = update.min {
+            if updated_min != self.metadata.min {
+                self.metadata.min = updated_min;
+                changed.insert(Field::Metadata);
+            }
+        }
+        if let Some(updated_max) = update.max {
+            if updated_max != self.metadata.max {
+                self.metadata.max = updated_max;
+                changed.insert(Field::Metadata);
+            }
+        }
+        if let Some(updated_description) = update.description {
+            if updated_description != self.metadata.description {
+                self.metadata.description = updated_description;
+                changed.insert(Field::Metadata);
+            }
+        }
+        if let Some(updated_unit) = update.unit {
+            if Some(&updated_unit) != self.metadata.unit.as_ref() {
+                self.metadata.unit = Some(updated_unit);
+                changed.insert(Field::Metadata);
+                changed.insert(Field::MetadataUnit);
+            }
+        }
+        if let Some(updated_range_policy) = update.range_policy {
+            if updated_range_policy != self.metadata.range_policy {
+                self.metadata.range_policy = updated_range_policy;
+                changed.insert(Field::Metadata);
             }
         }
-
-        // TODO: Apply the other fields as well
 
         changed
     }
 }
 
+/// Checks that `fields` contains exactly the fields declared by `struct_type`,
+/// each holding a value of the declared type.
+fn validate_struct(
+    struct_type: &types::StructType,
+    fields: &types::StructValue,
+) -> Result<(), UpdateError> {
+    if let Some(name) = fields.keys().find(|name| struct_type.field(name).is_none()) {
+        debug!("Field {} is not part of struct {}", name, struct_type.name);
+        return Err(UpdateError::WrongType);
+    }
+    for field in &struct_type.fields {
+        match fields.get(&field.name) {
+            Some(value) => validate_value_of_type(&field.data_type, value)?,
+            None => {
+                debug!(
+                    "Field {} of struct {} is missing",
+                    field.name, struct_type.name
+                );
+                return Err(UpdateError::WrongType);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `value` is a value of `data_type`, without looking at any
+/// min/max or allowed values.
+fn validate_value_of_type(data_type: &DataType, value: &DataValue) -> Result<(), UpdateError> {
+    let in_bounds = |fits: bool| match fits {
+        true => Ok(()),
+        false => Err(UpdateError::OutOfBoundsType),
+    };
+    match (data_type, value) {
+        (DataType::Bool, DataValue::Bool(_))
+        | (DataType::String, DataValue::String(_))
+        | (DataType::Int32, DataValue::Int32(_))
+        | (DataType::Int64, DataValue::Int64(_))
+        | (DataType::Uint32, DataValue::Uint32(_))
+        | (DataType::Uint64, DataValue::Uint64(_))
+        | (DataType::Float, DataValue::Float(_))
+        | (DataType::Double, DataValue::Double(_))
+        | (DataType::Timestamp, DataValue::Timestamp(_))
+        | (DataType::BoolArray, DataValue::BoolArray(_))
+        | (DataType::StringArray, DataValue::StringArray(_))
+        | (DataType::Int32Array, DataValue::Int32Array(_))
+        | (DataType::Int64Array, DataValue::Int64Array(_))
+        | (DataType::Uint32Array, DataValue::Uint32Array(_))
+        | (DataType::Uint64Array, DataValue::Uint64Array(_))
+        | (DataType::FloatArray, DataValue::FloatArray(_))
+        | (DataType::DoubleArray, DataValue::DoubleArray(_))
+        | (DataType::TimestampArray, DataValue::TimestampArray(_)) => Ok(()),
+        (DataType::Int8, DataValue::Int32(value)) => in_bounds(i8::try_from(*value).is_ok()),
+        (DataType::Int16, DataValue::Int32(value)) => in_bounds(i16::try_from(*value).is_ok()),
+        (DataType::Uint8, DataValue::Uint32(value)) => in_bounds(u8::try_from(*value).is_ok()),
+        (DataType::Uint16, DataValue::Uint32(value)) => in_bounds(u16::try_from(*value).is_ok()),
+        (DataType::Int8Array, DataValue::Int32Array(values)) => {
+            in_bounds(values.iter().all(|value| i8::try_from(*value).is_ok()))
+        }
+        (DataType::Int16Array, DataValue::Int32Array(values)) => {
+            in_bounds(values.iter().all(|value| i16::try_from(*value).is_ok()))
+        }
+        (DataType::Uint8Array, DataValue::Uint32Array(values)) => {
+            in_bounds(values.iter().all(|value| u8::try_from(*value).is_ok()))
+        }
+        (DataType::Uint16Array, DataValue::Uint32Array(values)) => {
+            in_bounds(values.iter().all(|value| u16::try_from(*value).is_ok()))
+        }
+        (DataType::Struct(struct_type), DataValue::Struct(fields)) => {
+            validate_struct(struct_type, fields)
+        }
+        (DataType::StructArray(struct_type), DataValue::StructArray(values)) => values
+            .iter()
+            .try_for_each(|fields| validate_struct(struct_type, fields)),
+        _ => Err(UpdateError::WrongType),
+    }
+}
+
 #[derive(Debug)]
 pub enum SuccessfulUpdate {
     NoChange,
@@ -800,6 +1172,29 @@ impl Subscriptions {
         uuid
     }
 
+    /// Forget about an entry removed from the database. Claims of actuation
+    /// and signal providers on it are released, so it can be provided
+    /// again once it's registered anew. Subscriptions left without any
+    /// entries are closed.
+    pub fn remove_entry(&mut self, id: i32) {
+        self.change_subscriptions.retain(|_, subscription| {
+            subscription.entries.remove(&id);
+            !subscription.entries.is_empty()
+        });
+        self.actuation_subscriptions.retain_mut(|subscription| {
+            subscription.vss_ids.retain(|vss_id| *vss_id != id);
+            !subscription.vss_ids.is_empty()
+        });
+        for subscription in self.signal_provider_subscriptions.values_mut() {
+            subscription
+                .vss_ids
+                .retain(|signal_id| signal_id.id() != id);
+            subscription
+                .signals_intervals
+                .retain(|signal_id, _| signal_id.id() != id);
+        }
+    }
+
     pub fn extend_signals(
         &mut self,
         porvider_uuid: Uuid,
@@ -847,7 +1242,7 @@ impl Subscriptions {
     }
 
     #[cfg_attr(feature="otel", tracing::instrument(name="subscriptions_cleanup", skip(self), fields(timestamp=chrono::Utc::now().to_string())))]
-    pub fn cleanup(&mut self) {
+    pub fn cleanup(&mut self, revocation_list: &RevocationList) {
         self.actuation_subscriptions.retain(|sub| {
             if !sub.actuation_provider.is_available() {
                 info!("Actuation Provider gone: removing provided actuation");
@@ -855,6 +1250,9 @@ impl Subscriptions {
             } else if sub.permissions.is_expired() {
                 info!("Permissions of Provider expired: removing provided actuation");
                 false
+            } else if revocation_list.is_revoked(&sub.permissions) {
+                info!("Token of Provider revoked: removing provided actuation");
+                false
             } else {
                 true
             }
@@ -862,7 +1260,10 @@ impl Subscriptions {
     }
 
     #[cfg_attr(feature="otel", tracing::instrument(name="signal_provider_subscriptions_cleanup", skip(self), fields(timestamp=chrono::Utc::now().to_string())))]
-    pub fn cleanup_signal_providers_subscriptions(&mut self) -> HashMap<Uuid, HashSet<SignalId>> {
+    pub fn cleanup_signal_providers_subscriptions(
+        &mut self,
+        revocation_list: &RevocationList,
+    ) -> HashMap<Uuid, HashSet<SignalId>> {
         let mut closed_signal_providers: HashMap<Uuid, HashSet<SignalId>> = HashMap::new();
         self.signal_provider_subscriptions
             .retain(|provider_uuid, signal_provider| {
@@ -874,6 +1275,10 @@ impl Subscriptions {
                     closed_signal_providers.insert(*provider_uuid, signal_provider.vss_ids.clone());
                     info!("Permissions of Provider expired: removing provided");
                     false
+                } else if revocation_list.is_revoked(&signal_provider.permissions) {
+                    closed_signal_providers.insert(*provider_uuid, signal_provider.vss_ids.clone());
+                    info!("Token of Provider revoked: removing provided");
+                    false
                 } else {
                     true
                 }
@@ -882,7 +1287,7 @@ impl Subscriptions {
     }
 
     #[cfg_attr(feature="otel", tracing::instrument(name="change_subscriptions_cleanup", skip(self), fields(timestamp=chrono::Utc::now().to_string())))]
-    pub fn cleanup_change_subscriptions(&mut self) -> Vec<Uuid> {
+    pub fn cleanup_change_subscriptions(&mut self, revocation_list: &RevocationList) -> Vec<Uuid> {
         let mut closed_subscriptions_uuids: Vec<Uuid> = Vec::new();
         self.change_subscriptions.retain(|uuid, sub| {
             if sub.sender.receiver_count() == 0 {
@@ -893,6 +1298,10 @@ impl Subscriptions {
                 closed_subscriptions_uuids.push(*uuid);
                 info!("Permissions of Subscriber expired: removing subscription");
                 false
+            } else if revocation_list.is_revoked(&sub.permissions) {
+                closed_subscriptions_uuids.push(*uuid);
+                info!("Token of Subscriber revoked: removing subscription");
+                false
             } else {
                 true
             }
@@ -1114,14 +1523,17 @@ impl<'a> Iterator for EntryReadIterator<'a, '_> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        // Entries explicitly denied for reading are not exposed at all,
+        // not even their metadata.
+        let permissions = self.permissions;
         self.inner
-            .next()
-            .map(|entry| EntryReadAccess::new(entry, self.permissions))
+            .find(|entry| !permissions.is_read_denied(&entry.metadata.path))
+            .map(|entry| EntryReadAccess::new(entry, permissions))
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.inner.size_hint()
+        (0, self.inner.size_hint().1)
     }
 }
 
@@ -1147,7 +1559,13 @@ impl DatabaseReadAccess<'_, '_> {
 
     #[cfg_attr(feature="otel", tracing::instrument(name="database_read_access_get_metadata_by_id", skip(self, id), fields(timestamp=chrono::Utc::now().to_string())))]
     pub fn get_metadata_by_id(&self, id: i32) -> Option<&Metadata> {
-        self.db.entries.get(&id).map(|entry| &entry.metadata)
+        // Like when iterating, entries explicitly denied for reading are
+        // not exposed at all.
+        self.db
+            .entries
+            .get(&id)
+            .map(|entry| &entry.metadata)
+            .filter(|metadata| !self.permissions.is_read_denied(&metadata.path))
     }
 
     #[cfg_attr(feature="otel", tracing::instrument(name="database_read_access_get_metadata_by_path", skip(self, path), fields(timestamp=chrono::Utc::now().to_string())))]
@@ -1198,7 +1616,6 @@ impl DatabaseWriteAccess<'_, '_> {
                 if update.path.is_some()
                     || update.entry_type.is_some()
                     || update.data_type.is_some()
-                    || update.description.is_some()
                 {
                     return Err(UpdateError::PermissionDenied);
                 }
@@ -1231,11 +1648,11 @@ impl DatabaseWriteAccess<'_, '_> {
                     (_, _) => {}
                 }
 
-                // Reduce update to only include changes
-                let update = entry.diff(update);
-                // Validate update
-                match entry.validate(&update) {
-                    Ok(_) => {
+                // Validate update, before reducing it to only include
+                // changes as values may be clamped to the current one
+                match entry.validate(update) {
+                    Ok(update) => {
+                        let update = entry.diff(update);
                         let changed_fields = entry.apply(update);
                         Ok(changed_fields)
                     }
@@ -1246,19 +1663,11 @@ impl DatabaseWriteAccess<'_, '_> {
         }
     }
 
-    #[allow(clippy::too_many_arguments)]
     pub fn add(
         &mut self,
         name: String,
-        data_type: DataType,
-        change_type: ChangeType,
-        entry_type: EntryType,
-        description: String,
-        min: Option<types::DataValue>,
-        max: Option<types::DataValue>,
-        allowed: Option<types::DataValue>,
+        entry: vss::DataEntry,
         datapoint: Option<Datapoint>,
-        unit: Option<String>,
     ) -> Result<i32, RegistrationError> {
         if !glob::is_valid_path(name.as_str()) {
             return Err(RegistrationError::ValidationError);
@@ -1283,14 +1692,18 @@ impl DatabaseWriteAccess<'_, '_> {
                 id: temp_id,
                 path: name.clone(),
                 glob_path: name.replace('.', "/"),
-                data_type,
-                change_type,
-                entry_type,
-                description,
-                allowed,
-                min,
-                max,
-                unit,
+                data_type: entry.data_type,
+                change_type: entry.change_type,
+                entry_type: entry.entry_type,
+                description: entry.description,
+                allowed: entry.allowed,
+                min: entry.min,
+                max: entry.max,
+                unit: entry.unit,
+                comment: entry.comment,
+                deprecation: entry.deprecation,
+                default: entry.default,
+                range_policy: entry.range_policy,
             },
             datapoint: match datapoint.clone() {
                 Some(datapoint) => datapoint,
@@ -1314,6 +1727,9 @@ impl DatabaseWriteAccess<'_, '_> {
         new_entry
             .validate_allowed_type(&new_entry.metadata.allowed)
             .map_err(|_err| RegistrationError::ValidationError)?;
+        new_entry
+            .validate_min_max_default_type()
+            .map_err(|_err| RegistrationError::ValidationError)?;
 
         // Get next id (and bump it)
         let id = self.db.next_id.fetch_add(1, Ordering::SeqCst);
@@ -1329,6 +1745,38 @@ impl DatabaseWriteAccess<'_, '_> {
         // Return the id
         Ok(id)
     }
+
+    /// Remove an entry registered at runtime. Entries loaded at startup
+    /// are part of the vehicle's catalog and can't be removed, entries
+    /// registered by another subject neither.
+    pub fn remove(&mut self, id: i32) -> Result<Metadata, RegistrationError> {
+        let Some(entry) = self.db.entries.get(&id) else {
+            return Err(RegistrationError::NotFound);
+        };
+        match self.db.registered_entries.get(&id) {
+            None => return Err(RegistrationError::PermissionDenied),
+            Some(Some(owner)) if Some(owner.as_str()) != self.permissions.subject() => {
+                return Err(RegistrationError::PermissionDenied)
+            }
+            Some(_) => {}
+        }
+
+        self.permissions
+            .can_create(&entry.metadata.path)
+            .map_err(|err| match err {
+                PermissionError::Denied => RegistrationError::PermissionDenied,
+                PermissionError::Expired => RegistrationError::PermissionExpired,
+            })?;
+
+        let entry = self
+            .db
+            .entries
+            .remove(&id)
+            .ok_or(RegistrationError::NotFound)?;
+        self.db.path_to_id.remove(&entry.metadata.path);
+        self.db.registered_entries.remove(&id);
+        Ok(entry.metadata)
+    }
 }
 
 impl Database {
@@ -1337,6 +1785,7 @@ impl Database {
             next_id: Default::default(),
             path_to_id: Default::default(),
             entries: Default::default(),
+            registered_entries: Default::default(),
         }
     }
 
@@ -1366,39 +1815,151 @@ impl Database {
 pub struct AuthorizedAccess<'a, 'b> {
     broker: &'a DataBroker,
     permissions: &'b Permissions,
+    transport: Transport,
+    session: Option<Uuid>,
+    connection: Option<SocketAddr>,
 }
 
 impl AuthorizedAccess<'_, '_> {
-    #[allow(clippy::too_many_arguments)]
+    /// Set the transport the access originates from, used for auditing.
+    pub fn via(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Tie the subscriptions and providers created by this access to
+    /// `session`, e.g. one stream, so that only those are affected by
+    /// [`Self::refresh_permissions`].
+    pub fn in_session(mut self, session: Uuid) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Set the remote address of the client connection the access
+    /// originates from. Subscriptions created on the same connection with
+    /// the same token follow [`Self::refresh_permissions`] as well.
+    pub fn on_connection(mut self, connection: Option<SocketAddr>) -> Self {
+        self.connection = connection;
+        self
+    }
+
+    async fn audit(
+        &self,
+        event: AuditEvent,
+        records: impl IntoIterator<Item = (i32, Option<&DataValue>, Outcome)>,
+    ) {
+        if !self.broker.audit_log.is_enabled() {
+            return;
+        }
+        let db = self.broker.database.read().await;
+        for (id, value, outcome) in records {
+            if let Some(entry) = db.entries.get(&id) {
+                self.broker.audit_log.record(
+                    self.permissions,
+                    self.transport,
+                    event,
+                    &entry.metadata.path,
+                    value,
+                    &outcome,
+                );
+            }
+        }
+    }
+
+    /// Log a warning the first time a client refers to this (deprecated)
+    /// entry by path
+    pub fn warn_if_deprecated(&self, metadata: &Metadata) {
+        if let Some(deprecation) = &metadata.deprecation {
+            if self.first_deprecated_use(metadata) {
+                warn!("Deprecated path {} used: {}", metadata.path, deprecation);
+            }
+        }
+    }
+
+    fn first_deprecated_use(&self, metadata: &Metadata) -> bool {
+        self.broker
+            .warned_deprecated_paths
+            .lock()
+            .unwrap()
+            .insert(metadata.path.clone())
+    }
+
     pub async fn add_entry(
         &self,
         name: String,
-        data_type: DataType,
-        change_type: ChangeType,
-        entry_type: EntryType,
-        description: String,
-        min: Option<types::DataValue>,
-        max: Option<types::DataValue>,
-        allowed: Option<types::DataValue>,
-        unit: Option<String>,
+        entry: vss::DataEntry,
     ) -> Result<i32, RegistrationError> {
         self.broker
             .database
             .write()
             .await
             .authorized_write_access(self.permissions)
-            .add(
-                name,
-                data_type,
-                change_type,
-                entry_type,
-                description,
-                min,
-                max,
-                allowed,
-                None,
-                unit,
-            )
+            .add(name, entry, None)
+    }
+
+    /// Register a new entry at runtime, e.g. a signal derived by an application.
+    ///
+    /// Unlike [`Self::add_entry`], which keeps existing entries as they are,
+    /// registering a path which already exists fails. So does registering a
+    /// path below an existing entry or a branch of existing entries, as
+    /// entries are always leaves of the tree. Metadata subscribers are
+    /// notified about the new entry.
+    pub async fn register_entry(
+        &self,
+        path: String,
+        entry: vss::DataEntry,
+    ) -> Result<Metadata, RegistrationError> {
+        let metadata = {
+            let mut db = self.broker.database.write().await;
+            if db.path_to_id.contains_key(&path) {
+                return Err(RegistrationError::AlreadyExists);
+            }
+            let branch = format!("{path}.");
+            let conflicts = db.path_to_id.keys().any(|existing| {
+                existing.starts_with(&branch)
+                    || path
+                        .strip_prefix(existing.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            });
+            if conflicts {
+                debug!("Can't register {path}, it would conflict with an existing entry");
+                return Err(RegistrationError::ValidationError);
+            }
+            let id = db
+                .authorized_write_access(self.permissions)
+                .add(path, entry, None)?;
+            db.registered_entries
+                .insert(id, self.permissions.subject().map(str::to_owned));
+            match db.entries.get(&id) {
+                Some(registered) => registered.metadata.clone(),
+                None => return Err(RegistrationError::NotFound),
+            }
+        };
+        // Nobody listening is fine
+        let _ = self
+            .broker
+            .metadata_changes
+            .send(MetadataChange::Added(metadata.clone()));
+        Ok(metadata)
+    }
+
+    /// Remove an entry registered with [`Self::register_entry`], which
+    /// requires the permission to create it. Providers claiming the entry
+    /// are released and metadata subscribers are notified about the removal.
+    pub async fn remove_entry(&self, id: i32) -> Result<Metadata, RegistrationError> {
+        let metadata = self
+            .broker
+            .database
+            .write()
+            .await
+            .authorized_write_access(self.permissions)
+            .remove(id)?;
+        self.broker.subscriptions.write().await.remove_entry(id);
+        let _ = self
+            .broker
+            .metadata_changes
+            .send(MetadataChange::Removed(metadata.clone()));
+        Ok(metadata)
     }
 
     pub async fn with_read_lock<T>(&self, f: impl FnOnce(&DatabaseReadAccess) -> T) -> T {
@@ -1418,27 +1979,54 @@ impl AuthorizedAccess<'_, '_> {
             .await
             .authorized_read_access(self.permissions)
             .get_metadata_by_path(name)
-            .map(|metadata| metadata.id)
+            .map(|metadata| {
+                self.warn_if_deprecated(metadata);
+                metadata.id
+            })
     }
 
     pub async fn get_datapoint(&self, id: i32) -> Result<Datapoint, ReadError> {
-        self.broker
+        let result = self
+            .broker
             .database
             .read()
             .await
             .authorized_read_access(self.permissions)
             .get_entry_by_id(id)
-            .map(|entry| entry.datapoint.clone())
+            .map(|entry| entry.datapoint.clone());
+        if let Err(ReadError::PermissionDenied) = result {
+            self.audit(
+                AuditEvent::PermissionDenied,
+                [(id, None, Outcome::failed(ReadError::PermissionDenied))],
+            )
+            .await;
+        }
+        result
     }
 
     pub async fn get_datapoint_by_path(&self, name: &str) -> Result<Datapoint, ReadError> {
-        self.broker
+        let result = self
+            .broker
             .database
             .read()
             .await
             .authorized_read_access(self.permissions)
             .get_entry_by_path(name)
-            .map(|entry| entry.datapoint.clone())
+            .map(|entry| {
+                self.warn_if_deprecated(&entry.metadata);
+                entry.datapoint.clone()
+            });
+        if let Err(ReadError::PermissionDenied) = result {
+            self.broker.audit_log.record(
+                self.permissions,
+                self.transport,
+                AuditEvent::PermissionDenied,
+                name,
+                None,
+                &Outcome::failed(ReadError::PermissionDenied),
+            );
+        }
+        result
     }
 
     #[cfg_attr(feature="otel", tracing::instrument(name="authorized_access_get_metadata", skip(self, id), fields(timestamp=chrono::Utc::now().to_string())))]
@@ -1459,6 +2047,7 @@ impl AuthorizedAccess<'_, '_> {
             .await
             .authorized_read_access(self.permissions)
             .get_metadata_by_path(path)
+            .inspect(|metadata| self.warn_if_deprecated(metadata))
             .cloned()
     }
 
@@ -1469,6 +2058,7 @@ impl AuthorizedAccess<'_, '_> {
             .await
             .authorized_read_access(self.permissions)
             .get_entry_by_path(path)
+            .inspect(|entry| self.warn_if_deprecated(&entry.metadata))
             .cloned()
     }
 
@@ -1524,6 +2114,9 @@ impl AuthorizedAccess<'_, '_> {
         updates: impl IntoIterator<Item = (i32, EntryUpdate)>,
     ) -> Result<(), Vec<(i32, UpdateError)>> {
         let mut errors = Vec::new();
+        let audit_enabled = self.broker.audit_log.is_enabled();
+        // (id, is actuation, requested value) of updates to be audited
+        let mut audited: Vec<(i32, bool, Option<DataValue>)> = Vec::new();
         let mut db = self.broker.database.write().await;
         let mut db_write = db.authorized_write_access(self.permissions);
         let mut lag_updates: HashMap<String, ()> = HashMap::new();
@@ -1533,6 +2126,13 @@ impl AuthorizedAccess<'_, '_> {
                 let mut changed = HashMap::<i32, HashSet<Field>>::new();
                 for (id, update) in updates {
                     debug!("setting id {} to {:?}", id, update);
+                    if audit_enabled {
+                        if let Some(target) = &update.actuator_target {
+                            audited.push((id, true, target.as_ref().map(|dp| dp.value.clone())));
+                        } else if let Some(datapoint) = &update.datapoint {
+                            audited.push((id, false, Some(datapoint.value.clone())));
+                        }
+                    }
                     match db_write.update(id, update) {
                         Ok(changed_fields) => {
                             if !changed_fields.is_empty() {
@@ -1551,6 +2151,18 @@ impl AuthorizedAccess<'_, '_> {
             // notifying subscribers (no writes in between)
             let db = db.downgrade();
 
+            for (id, fields) in &changed {
+                if fields.contains(&Field::Metadata) {
+                    if let Some(entry) = db.entries.get(id) {
+                        // Nobody listening is fine
+                        let _ = self
+                            .broker
+                            .metadata_changes
+                            .send(MetadataChange::Changed(entry.metadata.clone()));
+                    }
+                }
+            }
+
             // Notify
             match self
                 .broker
@@ -1579,7 +2191,49 @@ impl AuthorizedAccess<'_, '_> {
 
         // Cleanup closed subscriptions
         if cleanup_needed {
-            self.broker.subscriptions.write().await.cleanup();
+            self.broker
+                .subscriptions
+                .write()
+                .await
+                .cleanup(&self.broker.revocation_list);
+        }
+
+        if !audited.is_empty() {
+            let error_of = |id: i32| {
+                errors
+                    .iter()
+                    .find(|(error_id, _)| *error_id == id)
+                    .map(|(_, error)| error)
+            };
+            self.audit(
+                AuditEvent::Actuate,
+                audited
+                    .iter()
+                    .filter(|(_, is_actuation, _)| *is_actuation)
+                    .map(|(id, _, value)| {
+                        let outcome = match error_of(*id) {
+                            Some(error) => Outcome::failed(error),
+                            None => Outcome::ok(),
+                        };
+                        (*id, value.as_ref(), outcome)
+                    }),
+            )
+            .await;
+            self.audit(
+                AuditEvent::PermissionDenied,
+                audited
+                    .iter()
+                    .filter(|(_, is_actuation, _)| !*is_actuation)
+                    .filter(|(id, _, _)| error_of(*id) == Some(&UpdateError::PermissionDenied))
+                    .map(|(id, _, value)| {
+                        (
+                            *id,
+                            value.as_ref(),
+                            Outcome::failed(UpdateError::PermissionDenied),
+                        )
+                    }),
+            )
+            .await;
         }
 
         // Return errors if any
@@ -1601,6 +2255,34 @@ impl AuthorizedAccess<'_, '_> {
             return Err(SubscriptionError::InvalidInput);
         }
 
+        let denied: Vec<i32> = {
+            let db = self.broker.database.read().await;
+            valid_entries
+                .keys()
+                .filter(|id| {
+                    db.entries
+                        .get(*id)
+                        .map(|entry| self.permissions.is_read_denied(&entry.metadata.path))
+                        .unwrap_or(false)
+                })
+                .copied()
+                .collect()
+        };
+        if !denied.is_empty() {
+            self.audit(
+                AuditEvent::PermissionDenied,
+                denied.into_iter().map(|id| {
+                    (
+                        id,
+                        None,
+                        Outcome::failed(SubscriptionError::PermissionDenied),
+                    )
+                }),
+            )
+            .await;
+            return Err(SubscriptionError::PermissionDenied);
+        }
+
         let channel_capacity = if let Some(cap) = buffer_size {
             if cap > MAX_SUBSCRIBE_BUFFER_SIZE {
                 return Err(SubscriptionError::InvalidBufferSize);
@@ -1625,6 +2307,8 @@ impl AuthorizedAccess<'_, '_> {
             entries: valid_entries.clone(),
             sender,
             permissions: self.permissions.clone(),
+            session: self.session,
+            connection: self.connection,
             interval_duration,
             last_emitted: Arc::new(RwLock::new(Instant::now())),
         };
@@ -1676,6 +2360,24 @@ impl AuthorizedAccess<'_, '_> {
         &self,
         vss_ids: Vec<i32>,
         actuation_provider: Box<dyn ActuationProvider + Send + Sync + 'static>,
+    ) -> Result<(), (ActuationError, String)> {
+        let result = self
+            .register_actuation_provider(vss_ids.clone(), actuation_provider)
+            .await;
+        self.audit(
+            AuditEvent::RegisterActuationProvider,
+            vss_ids
+                .into_iter()
+                .map(|vss_id| (vss_id, None, Outcome::from(&result))),
+        )
+        .await;
+        result
+    }
+
+    async fn register_actuation_provider(
+        &self,
+        vss_ids: Vec<i32>,
+        actuation_provider: Box<dyn ActuationProvider + Send + Sync + 'static>,
     ) -> Result<(), (ActuationError, String)> {
         for vss_id in vss_ids.clone() {
             self.can_write_actuator_target(&vss_id).await?;
@@ -1704,6 +2406,7 @@ impl AuthorizedAccess<'_, '_> {
             vss_ids,
             actuation_provider,
             permissions: self.permissions.clone(),
+            session: self.session,
         };
         self.broker
             .subscriptions
@@ -1714,40 +2417,189 @@ impl AuthorizedAccess<'_, '_> {
         Ok(())
     }
 
-    async fn map_actuation_changes_by_vss_id(
+    /// Replace the permissions of the subscriptions and providers created
+    /// with the current permissions in the session of this access (see
+    /// [`Self::in_session`]) by `permissions`, e.g. after the client refreshed
+    /// its access token. The new token must be issued to the same subject.
+    /// Subscriptions created with the current permissions on the same client
+    /// connection (see [`Self::on_connection`]) are included as well.
+    ///
+    /// Subscribed and provided signals are authorized again. Signals the new
+    /// permissions don't allow anymore are dropped from the subscriptions and
+    /// providers, and subscriptions left without any signal are closed.
+    pub async fn refresh_permissions(
         &self,
-        actuation_changes: Vec<ActuationChange>,
-    ) -> HashMap<i32, Vec<ActuationChange>> {
-        let mut actuation_changes_per_vss_id: HashMap<i32, Vec<ActuationChange>> =
-            HashMap::with_capacity(actuation_changes.len());
-        for actuation_change in actuation_changes {
-            let vss_id = actuation_change.id;
-
-            let opt_vss_ids = actuation_changes_per_vss_id.get_mut(&vss_id);
-            match opt_vss_ids {
-                Some(vss_ids) => {
-                    vss_ids.push(actuation_change.clone());
-                }
-                None => {
-                    let vec = vec![actuation_change.clone()];
-                    actuation_changes_per_vss_id.insert(vss_id, vec);
-                }
-            }
+        permissions: &Permissions,
+    ) -> Result<(), PermissionError> {
+        if permissions.subject() != self.permissions.subject() {
+            return Err(PermissionError::Denied);
         }
-        actuation_changes_per_vss_id
-    }
+        if permissions.is_expired() {
+            return Err(PermissionError::Expired);
+        }
+        if self.session.is_none() && self.connection.is_none() {
+            // Nothing can have been created in the session or on the connection
+            return Ok(());
+        }
+        let in_session = |subscription_session: Option<Uuid>, old: &Permissions| {
+            self.session.is_some()
+                && subscription_session == self.session
+                && old.is_same_token(self.permissions)
+        };
+        let is_affected = |subscription: &ChangeSubscription| {
+            in_session(subscription.session, &subscription.permissions)
+                || (self.connection.is_some()
+                    && subscription.connection == self.connection
+                    && subscription.permissions.is_same_token(self.permissions))
+        };
 
-    pub async fn batch_actuate(
-        &self,
-        actuation_changes: Vec<ActuationChange>,
+        // Signals used in the session
+        let (read_ids, actuate_ids, provide_ids) = {
+            let subscriptions = self.broker.subscriptions.read().await;
+            let read_ids: HashSet<i32> = subscriptions
+                .change_subscriptions
+                .values()
+                .filter(|sub| is_affected(sub))
+                .flat_map(|sub| sub.entries.keys().copied())
+                .collect();
+            let actuate_ids: HashSet<i32> = subscriptions
+                .actuation_subscriptions
+                .iter()
+                .filter(|sub| in_session(sub.session, &sub.permissions))
+                .flat_map(|sub| sub.vss_ids.iter().copied())
+                .collect();
+            let provide_ids: HashSet<i32> = subscriptions
+                .signal_provider_subscriptions
+                .values()
+                .filter(|sub| in_session(sub.session, &sub.permissions))
+                .flat_map(|sub| sub.vss_ids.iter().map(SignalId::id))
+                .collect();
+            (read_ids, actuate_ids, provide_ids)
+        };
+
+        // Signals the new permissions don't allow anymore
+        let (read_denied, actuate_denied, provide_denied) = {
+            let db = self.broker.database.read().await;
+            let denied =
+                |ids: HashSet<i32>,
+                 check: fn(&Permissions, &str) -> Result<(), PermissionError>| {
+                    ids.into_iter()
+                        .filter(|id| match db.entries.get(id) {
+                            Some(entry) => check(permissions, &entry.metadata.path).is_err(),
+                            // Removed meanwhile, nothing to authorize
+                            None => false,
+                        })
+                        .collect::<HashSet<i32>>()
+                };
+            (
+                denied(read_ids, Permissions::can_read),
+                denied(actuate_ids, Permissions::can_write_actuator_target),
+                denied(provide_ids, Permissions::can_write_datapoint),
+            )
+        };
+
+        let mut subscriptions = self.broker.subscriptions.write().await;
+        subscriptions
+            .change_subscriptions
+            .retain(|_, subscription| {
+                if !is_affected(subscription) {
+                    return true;
+                }
+                subscription
+                    .entries
+                    .retain(|id, _| !read_denied.contains(id));
+                subscription.permissions = permissions.clone();
+                // Closes the subscription stream
+                !subscription.entries.is_empty()
+            });
+        subscriptions
+            .actuation_subscriptions
+            .retain_mut(|subscription| {
+                if !in_session(subscription.session, &subscription.permissions) {
+                    return true;
+                }
+                subscription
+                    .vss_ids
+                    .retain(|id| !actuate_denied.contains(id));
+                subscription.permissions = permissions.clone();
+                !subscription.vss_ids.is_empty()
+            });
+        for subscription in subscriptions.signal_provider_subscriptions.values_mut() {
+            if in_session(subscription.session, &subscription.permissions) {
+                subscription
+                    .vss_ids
+                    .retain(|signal_id| !provide_denied.contains(&signal_id.id()));
+                subscription
+                    .signals_intervals
+                    .retain(|signal_id, _| !provide_denied.contains(&signal_id.id()));
+                subscription.permissions = permissions.clone();
+            }
+        }
+        let dropped = read_denied.len() + actuate_denied.len() + provide_denied.len();
+        if dropped > 0 {
+            info!(
+                "Refreshed token doesn't permit {} subscribed or provided signals anymore: dropped them",
+                dropped
+            );
+        }
+        Ok(())
+    }
+
+    async fn map_actuation_changes_by_vss_id(
+        &self,
+        actuation_changes: Vec<ActuationChange>,
+    ) -> HashMap<i32, Vec<ActuationChange>> {
+        let mut actuation_changes_per_vss_id: HashMap<i32, Vec<ActuationChange>> =
+            HashMap::with_capacity(actuation_changes.len());
+        for actuation_change in actuation_changes {
+            let vss_id = actuation_change.id;
+
+            let opt_vss_ids = actuation_changes_per_vss_id.get_mut(&vss_id);
+            match opt_vss_ids {
+                Some(vss_ids) => {
+                    vss_ids.push(actuation_change.clone());
+                }
+                None => {
+                    let vec = vec![actuation_change.clone()];
+                    actuation_changes_per_vss_id.insert(vss_id, vec);
+                }
+            }
+        }
+        actuation_changes_per_vss_id
+    }
+
+    pub async fn batch_actuate(
+        &self,
+        actuation_changes: Vec<ActuationChange>,
+    ) -> Result<(), (ActuationError, String)> {
+        let audited = if self.broker.audit_log.is_enabled() {
+            actuation_changes.clone()
+        } else {
+            Vec::new()
+        };
+        let result = self.batch_actuate_signals(actuation_changes).await;
+        self.audit(
+            AuditEvent::Actuate,
+            audited
+                .iter()
+                .map(|change| (change.id, Some(&change.data_value), Outcome::from(&result))),
+        )
+        .await;
+        result
+    }
+
+    async fn batch_actuate_signals(
+        &self,
+        mut actuation_changes: Vec<ActuationChange>,
     ) -> Result<(), (ActuationError, String)> {
         let read_subscription_guard = self.broker.subscriptions.read().await;
         let actuation_subscriptions = &read_subscription_guard.actuation_subscriptions;
 
-        for actuation_change in &actuation_changes {
+        for actuation_change in &mut actuation_changes {
             let vss_id = actuation_change.id;
             self.can_write_actuator_target(&vss_id).await?;
-            self.validate_actuator_update(&vss_id, &actuation_change.data_value)
+            actuation_change.data_value = self
+                .validate_actuator_update(&vss_id, &actuation_change.data_value)
                 .await?;
         }
 
@@ -1796,11 +2648,25 @@ impl AuthorizedAccess<'_, '_> {
         &self,
         vss_id: &i32,
         data_value: &DataValue,
+    ) -> Result<(), (ActuationError, String)> {
+        let result = self.actuate_signal(vss_id, data_value).await;
+        self.audit(
+            AuditEvent::Actuate,
+            [(*vss_id, Some(data_value), Outcome::from(&result))],
+        )
+        .await;
+        result
+    }
+
+    async fn actuate_signal(
+        &self,
+        vss_id: &i32,
+        data_value: &DataValue,
     ) -> Result<(), (ActuationError, String)> {
         let vss_id = *vss_id;
 
         self.can_write_actuator_target(&vss_id).await?;
-        self.validate_actuator_update(&vss_id, data_value).await?;
+        let data_value = self.validate_actuator_update(&vss_id, data_value).await?;
 
         let read_subscription_guard = self.broker.subscriptions.read().await;
         let opt_actuation_subscription = &read_subscription_guard
@@ -1827,7 +2693,7 @@ impl AuthorizedAccess<'_, '_> {
                     .actuation_provider
                     .actuate(vec![ActuationChange {
                         id: vss_id,
-                        data_value: data_value.clone(),
+                        data_value,
                     }])
                     .await
             }
@@ -1909,11 +2775,13 @@ impl AuthorizedAccess<'_, '_> {
         }
     }
 
+    /// Returns the value to send to the provider, which may differ from
+    /// `data_value` if the entry's range policy is to clamp
     async fn validate_actuator_update(
         &self,
         vss_id: &i32,
         data_value: &DataValue,
-    ) -> Result<(), (ActuationError, String)> {
+    ) -> Result<DataValue, (ActuationError, String)> {
         let result_entry = self.get_entry_by_id(*vss_id).await;
         match result_entry {
             Ok(entry) => {
@@ -1925,7 +2793,7 @@ impl AuthorizedAccess<'_, '_> {
                 }
                 let validation = entry.validate_actuator_value(data_value);
                 match validation {
-                    Ok(_) => Ok(()),
+                    Ok(data_value) => Ok(data_value),
                     Err(UpdateError::OutOfBoundsMinMax) => {
                         let message = format!(
                             "Out of bounds min/max value provided for {}: {} | Expected range [min: {}, max: {}]",
@@ -2064,6 +2932,25 @@ impl AuthorizedAccess<'_, '_> {
         &self,
         vss_ids_intervals: HashMap<SignalId, TimeInterval>,
         signal_provider: Box<dyn SignalProvider + Send + Sync + 'static>,
+    ) -> Result<Uuid, (RegisterSignalError, String)> {
+        let signal_ids: Vec<SignalId> = vss_ids_intervals.keys().copied().collect();
+        let result = self
+            .register_signal_provider(vss_ids_intervals, signal_provider)
+            .await;
+        self.audit(
+            AuditEvent::RegisterSignalProvider,
+            signal_ids
+                .into_iter()
+                .map(|signal_id| (signal_id.id(), None, Outcome::from(&result))),
+        )
+        .await;
+        result
+    }
+
+    async fn register_signal_provider(
+        &self,
+        vss_ids_intervals: HashMap<SignalId, TimeInterval>,
+        signal_provider: Box<dyn SignalProvider + Send + Sync + 'static>,
     ) -> Result<Uuid, (RegisterSignalError, String)> {
         for signal_id in vss_ids_intervals.keys() {
             self.can_write_datapoint(signal_id).await?;
@@ -2095,6 +2982,7 @@ impl AuthorizedAccess<'_, '_> {
             signals_intervals: vss_ids_intervals,
             signal_provider,
             permissions: self.permissions.clone(),
+            session: self.session,
         };
         let provider_uuid = self
             .broker
@@ -2171,6 +3059,7 @@ impl AuthorizedAccess<'_, '_> {
                         min: None,
                         max: None,
                         unit: None,
+                        range_policy: None,
                     },
                 )
             })
@@ -2269,6 +3158,7 @@ impl AuthorizedAccess<'_, '_> {
 impl DataBroker {
     pub fn new(version: impl Into<String>, commit_sha: impl Into<String>) -> Self {
         let (shutdown_trigger, _) = broadcast::channel::<()>(1);
+        let (metadata_changes, _) = broadcast::channel(MAX_SUBSCRIBE_BUFFER_SIZE);
 
         DataBroker {
             database: Default::default(),
@@ -2277,9 +3167,39 @@ impl DataBroker {
             commit_sha: commit_sha.into(),
             shutdown_trigger,
             filter_manager: Default::default(),
+            rate_limiter: Default::default(),
+            audit_log: Default::default(),
+            revocation_list: Default::default(),
+            metadata_changes,
+            warned_deprecated_paths: Default::default(),
         }
     }
 
+    pub fn with_rate_limits(mut self, limits: RateLimits) -> Self {
+        self.rate_limiter = Arc::new(RateLimiter::new(limits));
+        self
+    }
+
+    pub fn rate_limiter(&self) -> &Arc<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Arc::new(audit_log);
+        self
+    }
+
+    /// Subscriptions and providers authenticated with a token in
+    /// `revocation_list` are closed.
+    pub fn with_revocation_list(mut self, revocation_list: Arc<RevocationList>) -> Self {
+        self.revocation_list = revocation_list;
+        self
+    }
+
+    pub fn revocation_list(&self) -> &Arc<RevocationList> {
+        &self.revocation_list
+    }
+
     #[cfg_attr(feature="otel", tracing::instrument(name="data_broker_authorized_access",skip(self, permissions), fields(timestamp=chrono::Utc::now().to_string())))]
     pub fn authorized_access<'a, 'b>(
         &'a self,
@@ -2288,6 +3208,9 @@ impl DataBroker {
         AuthorizedAccess {
             broker: self,
             permissions,
+            transport: Transport::Internal,
+            session: None,
+            connection: None,
         }
     }
 
@@ -2295,6 +3218,8 @@ impl DataBroker {
         info!("Starting housekeeping task");
         let subscriptions = self.subscriptions.clone();
         let filter_manager = self.filter_manager.clone();
+        let rate_limiter = self.rate_limiter.clone();
+        let revocation_list = self.revocation_list.clone();
 
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
@@ -2304,13 +3229,14 @@ impl DataBroker {
             loop {
                 interval.tick().await;
 
-                subscriptions.write().await.cleanup(); // Cleanup dropped subscriptions
+                subscriptions.write().await.cleanup(&revocation_list); // Cleanup dropped subscriptions
+                rate_limiter.cleanup(); // Forget idle clients
 
                 // clean up disconnected providers
                 let closed_signal_providers = subscriptions
                     .write()
                     .await
-                    .cleanup_signal_providers_subscriptions();
+                    .cleanup_signal_providers_subscriptions(&revocation_list);
 
                 if !closed_signal_providers.is_empty() {
                     // Inform remaining subscriptions about not available providers
@@ -2342,8 +3268,10 @@ impl DataBroker {
                 }
 
                 // clean up disconnected subscriptions
-                let closed_change_subscriptions =
-                    subscriptions.write().await.cleanup_change_subscriptions();
+                let closed_change_subscriptions = subscriptions
+                    .write()
+                    .await
+                    .cleanup_change_subscriptions(&revocation_list);
 
                 let new_connected_providers_count = subscriptions
                     .read()
@@ -2418,6 +3346,11 @@ impl DataBroker {
         self.shutdown_trigger.subscribe()
     }
 
+    /// Receive notifications about entries registered, removed or changed at runtime
+    pub fn subscribe_metadata_changes(&self) -> broadcast::Receiver<MetadataChange> {
+        self.metadata_changes.subscribe()
+    }
+
     pub fn get_version(&self) -> &str {
         &self.version
     }
@@ -2458,14 +3391,16 @@ pub mod tests {
         let id1 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Bool,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                Some(DataValue::BoolArray(Vec::from([true]))),
-                Some("kg".to_string()),
+                vss::DataEntry {
+                    allowed: Some(DataValue::BoolArray(Vec::from([true]))),
+                    unit: Some("kg".to_string()),
+                    ..vss::DataEntry::new(
+                        DataType::Bool,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Test datapoint 1".to_owned(),
+                    )
+                },
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2492,14 +3427,15 @@ pub mod tests {
         let id2 = broker
             .add_entry(
                 "test.datapoint2".to_owned(),
-                DataType::String,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 2".to_owned(),
-                None, // min
-                None, // max
-                None,
-                Some("km".to_string()),
+                vss::DataEntry {
+                    unit: Some("km".to_string()),
+                    ..vss::DataEntry::new(
+                        DataType::String,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Test datapoint 2".to_owned(),
+                    )
+                },
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2523,14 +3459,12 @@ pub mod tests {
         let id3 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Bool,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 1 (modified)".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Bool,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Test datapoint 1 (modified)".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2538,6 +3472,201 @@ pub mod tests {
         assert_eq!(id3, id1);
     }
 
+    #[tokio::test]
+    async fn test_register_and_remove_entry() {
+        let broker = DataBroker::default();
+        let mut metadata_changes = broker.subscribe_metadata_changes();
+        let permissions = permissions::PermissionBuilder::new()
+            .add_create_permission(permissions::Permission::Glob(
+                "Vehicle.Derived.*".to_owned(),
+            ))
+            .build()
+            .expect("Oops!");
+        let authorized_access = broker.authorized_access(&permissions);
+
+        let new_entry = || vss::DataEntry {
+            data_type: DataType::Float,
+            entry_type: EntryType::Sensor,
+            change_type: ChangeType::Continuous,
+            description: "Average speed".to_owned(),
+            comment: None,
+            deprecation: None,
+            unit: Some("km/h".to_owned()),
+            min: None,
+            max: None,
+            allowed: None,
+            default: None,
+            range_policy: RangePolicy::default(),
+        };
+
+        let metadata = authorized_access
+            .register_entry("Vehicle.Derived.AverageSpeed".to_owned(), new_entry())
+            .await
+            .expect("Register entry should succeed");
+        assert_eq!(metadata.unit, Some("km/h".to_owned()));
+        match metadata_changes.recv().await {
+            Ok(MetadataChange::Added(added)) => assert_eq!(added.id, metadata.id),
+            other => panic!("expected added entry, got {other:?}"),
+        }
+
+        assert_eq!(
+            authorized_access
+                .register_entry("Vehicle.Derived.AverageSpeed".to_owned(), new_entry())
+                .await
+                .unwrap_err(),
+            RegistrationError::AlreadyExists
+        );
+        assert_eq!(
+            authorized_access
+                .register_entry("Vehicle.Speed".to_owned(), new_entry())
+                .await
+                .unwrap_err(),
+            RegistrationError::PermissionDenied
+        );
+        assert_eq!(
+            authorized_access
+                .register_entry("Vehicle.Derived.".to_owned(), new_entry())
+                .await
+                .unwrap_err(),
+            RegistrationError::ValidationError
+        );
+        // Entries are leaves, so nothing can be registered below one
+        assert_eq!(
+            authorized_access
+                .register_entry("Vehicle.Derived.AverageSpeed.Max".to_owned(), new_entry())
+                .await
+                .unwrap_err(),
+            RegistrationError::ValidationError
+        );
+        // nor can a branch of existing entries become an entry itself
+        let branch_access = broker.authorized_access(&permissions::ALLOW_ALL);
+        assert_eq!(
+            branch_access
+                .register_entry("Vehicle.Derived".to_owned(), new_entry())
+                .await
+                .unwrap_err(),
+            RegistrationError::ValidationError
+        );
+        // Sharing a prefix is fine, as long as it's not a branch
+        let sibling = authorized_access
+            .register_entry("Vehicle.Derived.AverageSpeedMax".to_owned(), new_entry())
+            .await
+            .expect("Register entry should succeed");
+        match metadata_changes.recv().await {
+            Ok(MetadataChange::Added(added)) => assert_eq!(added.id, sibling.id),
+            other => panic!("expected added entry, got {other:?}"),
+        }
+
+        // Entries which were not registered at runtime can't be removed
+        let catalog_id = broker
+            .authorized_access(&permissions::ALLOW_ALL)
+            .add_entry(
+                "Vehicle.Derived.Speed".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Float,
+                    ChangeType::Continuous,
+                    EntryType::Sensor,
+                    "Speed".to_owned(),
+                ),
+            )
+            .await
+            .expect("Register datapoint should succeed");
+        assert_eq!(
+            authorized_access
+                .remove_entry(catalog_id)
+                .await
+                .unwrap_err(),
+            RegistrationError::PermissionDenied
+        );
+
+        let removed = authorized_access
+            .remove_entry(metadata.id)
+            .await
+            .expect("Remove entry should succeed");
+        assert_eq!(removed.path, "Vehicle.Derived.AverageSpeed");
+        match metadata_changes.recv().await {
+            Ok(MetadataChange::Removed(removed)) => assert_eq!(removed.id, metadata.id),
+            other => panic!("expected removed entry, got {other:?}"),
+        }
+        assert!(authorized_access
+            .get_id_by_path("Vehicle.Derived.AverageSpeed")
+            .await
+            .is_none());
+        assert_eq!(
+            authorized_access
+                .remove_entry(metadata.id)
+                .await
+                .unwrap_err(),
+            RegistrationError::NotFound
+        );
+
+        // Only the subject which registered an entry may remove it
+        let with_subject = |subject: &str| {
+            permissions::PermissionBuilder::new()
+                .subject(subject)
+                .add_create_permission(permissions::Permission::Glob(
+                    "Vehicle.Derived.*".to_owned(),
+                ))
+                .build()
+                .expect("Oops!")
+        };
+        let (owner, other) = (with_subject("app-a"), with_subject("app-b"));
+        let metadata = broker
+            .authorized_access(&owner)
+            .register_entry("Vehicle.Derived.MaxSpeed".to_owned(), new_entry())
+            .await
+            .expect("Register entry should succeed");
+        assert_eq!(
+            broker
+                .authorized_access(&other)
+                .remove_entry(metadata.id)
+                .await
+                .unwrap_err(),
+            RegistrationError::PermissionDenied
+        );
+        broker
+            .authorized_access(&owner)
+            .remove_entry(metadata.id)
+            .await
+            .expect("Remove entry should succeed");
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_path_warned_once() {
+        let broker = DataBroker::default();
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
+
+        let id = authorized_access
+            .add_entry(
+                "Vehicle.Deprecated.Speed".to_owned(),
+                vss::DataEntry {
+                    deprecation: Some("Use Vehicle.Speed".to_owned()),
+                    ..vss::DataEntry::new(
+                        DataType::Float,
+                        ChangeType::Continuous,
+                        EntryType::Sensor,
+                        "Speed",
+                    )
+                },
+            )
+            .await
+            .expect("Register datapoint should succeed");
+        let metadata = authorized_access
+            .get_metadata(id)
+            .await
+            .expect("Metadata should exist");
+
+        assert!(authorized_access.first_deprecated_use(&metadata));
+        assert!(!authorized_access.first_deprecated_use(&metadata));
+        authorized_access.warn_if_deprecated(&metadata);
+        assert!(!authorized_access.first_deprecated_use(&metadata));
+
+        // Other broker instances keep track of their own warnings
+        let other_broker = DataBroker::default();
+        let other_access = other_broker.authorized_access(&permissions::ALLOW_ALL);
+        assert!(other_access.first_deprecated_use(&metadata));
+    }
+
     #[tokio::test]
     async fn test_register_invalid_type() {
         let broker = DataBroker::default();
@@ -2546,14 +3675,15 @@ pub mod tests {
         if broker
             .add_entry(
                 "test.signal3".to_owned(),
-                DataType::String,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test signal 3".to_owned(),
-                None, // min
-                None, // max
-                Some(DataValue::Int32Array(Vec::from([1, 2, 3, 4]))),
-                None,
+                vss::DataEntry {
+                    allowed: Some(DataValue::Int32Array(Vec::from([1, 2, 3, 4]))),
+                    ..vss::DataEntry::new(
+                        DataType::String,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Test signal 3".to_owned(),
+                    )
+                },
             )
             .await
             .is_ok()
@@ -2564,6 +3694,58 @@ pub mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_register_invalid_min_max_default_type() {
+        let broker = DataBroker::default();
+        let broker = broker.authorized_access(&permissions::ALLOW_ALL);
+
+        let entry = |data_type| {
+            vss::DataEntry::new(
+                data_type,
+                ChangeType::OnChange,
+                EntryType::Attribute,
+                "Test signal",
+            )
+        };
+        let invalid = [
+            vss::DataEntry {
+                min: Some(DataValue::Float(0.0)),
+                ..entry(DataType::Int32)
+            },
+            vss::DataEntry {
+                max: Some(DataValue::Int32(1000)),
+                ..entry(DataType::Int8)
+            },
+            vss::DataEntry {
+                max: Some(DataValue::Int32Array(vec![1])),
+                ..entry(DataType::Int32Array)
+            },
+            vss::DataEntry {
+                default: Some(DataValue::String("fast".to_owned())),
+                ..entry(DataType::Float)
+            },
+        ];
+        for (i, entry) in invalid.into_iter().enumerate() {
+            assert_eq!(
+                broker.add_entry(format!("test.invalid{i}"), entry).await,
+                Err(RegistrationError::ValidationError)
+            );
+        }
+
+        broker
+            .add_entry(
+                "test.valid".to_owned(),
+                vss::DataEntry {
+                    min: Some(DataValue::Int32(-1)),
+                    max: Some(DataValue::Int32(1)),
+                    default: Some(DataValue::Int32Array(vec![0, 1])),
+                    ..entry(DataType::Int8Array)
+                },
+            )
+            .await
+            .expect("Register datapoint should succeed");
+    }
+
     #[tokio::test]
     async fn test_get_set_datapoint() {
         let broker = DataBroker::default();
@@ -2572,14 +3754,12 @@ pub mod tests {
         let id1 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Int32,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2587,14 +3767,12 @@ pub mod tests {
         let id2 = broker
             .add_entry(
                 "test.datapoint2".to_owned(),
-                DataType::Bool,
-                ChangeType::OnChange,
-                EntryType::Actuator,
-                "Test datapoint 2".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Bool,
+                    ChangeType::OnChange,
+                    EntryType::Actuator,
+                    "Test datapoint 2".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2639,6 +3817,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -2670,6 +3849,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -2694,6 +3874,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -2740,14 +3921,15 @@ pub mod tests {
         let id1 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                Some(DataValue::Int32Array(vec![100])),
-                None,
+                vss::DataEntry {
+                    allowed: Some(DataValue::Int32Array(vec![100])),
+                    ..vss::DataEntry::new(
+                        DataType::Int32,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Test datapoint 1".to_owned(),
+                    )
+                },
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2770,6 +3952,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -2794,6 +3977,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -2818,6 +4002,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -2843,6 +4028,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -2873,14 +4059,16 @@ pub mod tests {
         let entry_id = authorized_access
             .add_entry(
                 name.to_owned(),
-                DataType::Int8,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Some Description That Does Not Matter".to_owned(),
-                Some(types::DataValue::Int32(-5)), // min
-                Some(types::DataValue::Int32(10)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(types::DataValue::Int32(-5)),
+                    max: Some(types::DataValue::Int32(10)),
+                    ..vss::DataEntry::new(
+                        DataType::Int8,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Some Description That Does Not Matter".to_owned(),
+                    )
+                },
             )
             .await
             .unwrap();
@@ -2903,6 +4091,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -2949,74 +4138,336 @@ pub mod tests {
         }
     }
 
-    // Helper for adding an int8 signal and adding value
-    async fn helper_add_int16(
-        broker: &DataBroker,
-        name: &str,
-        value: i32,
-        timestamp: std::time::SystemTime,
-    ) -> Result<i32, Vec<(i32, UpdateError)>> {
+    #[tokio::test]
+    async fn test_update_entries_range_policy() {
+        let broker = DataBroker::default();
         let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
-        let entry_id = authorized_access
+
+        let id = authorized_access
             .add_entry(
-                name.to_owned(),
-                DataType::Int16,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Some Description That Does Not Matter".to_owned(),
-                Some(types::DataValue::Int32(-5)), // min
-                Some(types::DataValue::Int32(10)), // max
-                None,
-                None,
+                "test.datapoint1".to_owned(),
+                vss::DataEntry {
+                    min: Some(types::DataValue::Float(0.0)),
+                    max: Some(types::DataValue::Float(100.0)),
+                    ..vss::DataEntry::new(
+                        DataType::Float,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Some Description That Does Not Matter".to_owned(),
+                    )
+                },
             )
             .await
             .unwrap();
 
-        match authorized_access
-            .update_entries([(
-                entry_id,
+        let set_range_policy = |range_policy| {
+            [(
+                id,
+                EntryUpdate {
+                    range_policy: Some(range_policy),
+                    ..Default::default()
+                },
+            )]
+        };
+        let update_value = |value| {
+            [(
+                id,
                 EntryUpdate {
-                    path: None,
                     datapoint: Some(Datapoint {
-                        ts: timestamp,
+                        ts: std::time::SystemTime::now(),
                         source_ts: None,
-                        value: types::DataValue::Int32(value),
+                        value,
                     }),
-                    actuator_target: None,
-                    entry_type: None,
-                    data_type: None,
-                    description: None,
-                    allowed: None,
-                    min: None,
-                    max: None,
-                    unit: None,
+                    ..Default::default()
                 },
-            )])
+            )]
+        };
+        // Rejected by default
+        match authorized_access
+            .update_entries(update_value(types::DataValue::Float(120.0)))
             .await
         {
-            Ok(_) => Ok(entry_id),
-            Err(details) => Err(details),
-        }
-    }
-
-    #[tokio::test]
-    async fn test_update_entries_min_max_int16() {
-        let broker = DataBroker::default();
-
-        let timestamp = std::time::SystemTime::now();
-
-        match helper_add_int16(&broker, "test.datapoint1", -6, timestamp).await {
-            Err(err_vec) => {
-                assert_eq!(err_vec.len(), 1);
-                assert_eq!(err_vec.first().expect("").1, UpdateError::OutOfBoundsMinMax)
-            }
-            _ => panic!("Failure expected"),
+            Err(err_vec) => assert_eq!(err_vec[0].1, UpdateError::OutOfBoundsMinMax),
+            Ok(_) => panic!("Failure expected"),
         }
 
-        if helper_add_int16(&broker, "test.datapoint2", -5, timestamp)
+        authorized_access
+            .update_entries(set_range_policy(RangePolicy::Clamp))
             .await
-            .is_err()
-        {
+            .unwrap();
+        authorized_access
+            .update_entries(update_value(types::DataValue::Float(120.0)))
+            .await
+            .unwrap();
+        assert_eq!(
+            authorized_access
+                .get_entry_by_id(id)
+                .await
+                .unwrap()
+                .datapoint
+                .value,
+            types::DataValue::Float(100.0)
+        );
+        authorized_access
+            .update_entries(update_value(types::DataValue::Float(-1.0)))
+            .await
+            .unwrap();
+        assert_eq!(
+            authorized_access
+                .get_entry_by_id(id)
+                .await
+                .unwrap()
+                .datapoint
+                .value,
+            types::DataValue::Float(0.0)
+        );
+        // The type is still checked
+        match authorized_access
+            .update_entries(update_value(types::DataValue::Int32(120)))
+            .await
+        {
+            Err(err_vec) => assert_eq!(err_vec[0].1, UpdateError::WrongType),
+            Ok(_) => panic!("Failure expected"),
+        }
+
+        authorized_access
+            .update_entries(set_range_policy(RangePolicy::Warn))
+            .await
+            .unwrap();
+        authorized_access
+            .update_entries(update_value(types::DataValue::Float(120.0)))
+            .await
+            .unwrap();
+        assert_eq!(
+            authorized_access
+                .get_entry_by_id(id)
+                .await
+                .unwrap()
+                .datapoint
+                .value,
+            types::DataValue::Float(120.0)
+        );
+        // Staying out of bounds is accepted as well, but only warned about
+        // again once the value has been back in range
+        let entry = authorized_access.get_entry_by_id(id).await.unwrap();
+        assert!(entry.is_out_of_range(&entry.datapoint.value));
+        assert!(!entry.is_out_of_range(&types::DataValue::Float(50.0)));
+        authorized_access
+            .update_entries(update_value(types::DataValue::Float(130.0)))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_entries_range_policy_clamp_array() {
+        let broker = DataBroker::default();
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
+
+        let id = authorized_access
+            .add_entry(
+                "test.datapoint1".to_owned(),
+                vss::DataEntry {
+                    min: Some(types::DataValue::Int32(-5)),
+                    max: Some(types::DataValue::Int32(10)),
+                    range_policy: RangePolicy::Clamp,
+                    ..vss::DataEntry::new(
+                        DataType::Int8Array,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Some Description That Does Not Matter".to_owned(),
+                    )
+                },
+            )
+            .await
+            .unwrap();
+
+        let update_value = |value| {
+            [(
+                id,
+                EntryUpdate {
+                    datapoint: Some(Datapoint {
+                        ts: std::time::SystemTime::now(),
+                        source_ts: None,
+                        value,
+                    }),
+                    ..Default::default()
+                },
+            )]
+        };
+        // Each element is clamped
+        authorized_access
+            .update_entries(update_value(types::DataValue::Int32Array(vec![-6, 0, 11])))
+            .await
+            .unwrap();
+        assert_eq!(
+            authorized_access
+                .get_entry_by_id(id)
+                .await
+                .unwrap()
+                .datapoint
+                .value,
+            types::DataValue::Int32Array(vec![-5, 0, 10])
+        );
+        // The element type is still checked
+        match authorized_access
+            .update_entries(update_value(types::DataValue::Int32Array(vec![0, 200])))
+            .await
+        {
+            Err(err_vec) => assert_eq!(err_vec[0].1, UpdateError::OutOfBoundsType),
+            Ok(_) => panic!("Failure expected"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_entries_clamped_to_current_value() {
+        let broker = DataBroker::default();
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
+
+        let id = authorized_access
+            .add_entry(
+                "test.datapoint1".to_owned(),
+                vss::DataEntry {
+                    min: Some(types::DataValue::Float(0.0)),
+                    max: Some(types::DataValue::Float(100.0)),
+                    range_policy: RangePolicy::Clamp,
+                    ..vss::DataEntry::new(
+                        DataType::Float,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Some Description That Does Not Matter".to_owned(),
+                    )
+                },
+            )
+            .await
+            .unwrap();
+
+        let update_value = |value| {
+            [(
+                id,
+                EntryUpdate {
+                    datapoint: Some(Datapoint {
+                        ts: std::time::SystemTime::now(),
+                        source_ts: None,
+                        value,
+                    }),
+                    ..Default::default()
+                },
+            )]
+        };
+        let mut subscription = authorized_access
+            .subscribe(
+                HashMap::from([(id, HashSet::from([Field::Datapoint]))]),
+                None,
+                None,
+            )
+            .await
+            .expect("Subscription should succeed");
+        async fn next_value(
+            subscription: &mut (impl Stream<Item = Option<EntryUpdates>> + Unpin),
+        ) -> DataValue {
+            match subscription.next().await {
+                Some(Some(entry_updates)) => entry_updates.updates[0]
+                    .update
+                    .datapoint
+                    .as_ref()
+                    .unwrap()
+                    .value
+                    .clone(),
+                _ => panic!("did not expect stream end"),
+            }
+        }
+        // Initial notification
+        assert_eq!(next_value(&mut subscription).await, DataValue::NotAvailable);
+
+        authorized_access
+            .update_entries(update_value(types::DataValue::Float(120.0)))
+            .await
+            .unwrap();
+        assert_eq!(next_value(&mut subscription).await, DataValue::Float(100.0));
+
+        // Clamped to the current value, so nothing changed
+        authorized_access
+            .update_entries(update_value(types::DataValue::Float(130.0)))
+            .await
+            .unwrap();
+        authorized_access
+            .update_entries(update_value(types::DataValue::Float(50.0)))
+            .await
+            .unwrap();
+        assert_eq!(next_value(&mut subscription).await, DataValue::Float(50.0));
+    }
+
+    // Helper for adding an int8 signal and adding value
+    async fn helper_add_int16(
+        broker: &DataBroker,
+        name: &str,
+        value: i32,
+        timestamp: std::time::SystemTime,
+    ) -> Result<i32, Vec<(i32, UpdateError)>> {
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
+        let entry_id = authorized_access
+            .add_entry(
+                name.to_owned(),
+                vss::DataEntry {
+                    min: Some(types::DataValue::Int32(-5)),
+                    max: Some(types::DataValue::Int32(10)),
+                    ..vss::DataEntry::new(
+                        DataType::Int16,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Some Description That Does Not Matter".to_owned(),
+                    )
+                },
+            )
+            .await
+            .unwrap();
+
+        match authorized_access
+            .update_entries([(
+                entry_id,
+                EntryUpdate {
+                    path: None,
+                    datapoint: Some(Datapoint {
+                        ts: timestamp,
+                        source_ts: None,
+                        value: types::DataValue::Int32(value),
+                    }),
+                    actuator_target: None,
+                    entry_type: None,
+                    data_type: None,
+                    description: None,
+                    allowed: None,
+                    min: None,
+                    max: None,
+                    unit: None,
+                    range_policy: None,
+                },
+            )])
+            .await
+        {
+            Ok(_) => Ok(entry_id),
+            Err(details) => Err(details),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_entries_min_max_int16() {
+        let broker = DataBroker::default();
+
+        let timestamp = std::time::SystemTime::now();
+
+        match helper_add_int16(&broker, "test.datapoint1", -6, timestamp).await {
+            Err(err_vec) => {
+                assert_eq!(err_vec.len(), 1);
+                assert_eq!(err_vec.first().expect("").1, UpdateError::OutOfBoundsMinMax)
+            }
+            _ => panic!("Failure expected"),
+        }
+
+        if helper_add_int16(&broker, "test.datapoint2", -5, timestamp)
+            .await
+            .is_err()
+        {
             panic!("Success expected")
         }
 
@@ -3047,14 +4498,16 @@ pub mod tests {
         let entry_id = authorized_access
             .add_entry(
                 name.to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Some Description That Does Not Matter".to_owned(),
-                Some(types::DataValue::Int32(-500)), // min
-                Some(types::DataValue::Int32(1000)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(types::DataValue::Int32(-500)),
+                    max: Some(types::DataValue::Int32(1000)),
+                    ..vss::DataEntry::new(
+                        DataType::Int32,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Some Description That Does Not Matter".to_owned(),
+                    )
+                },
             )
             .await
             .unwrap();
@@ -3077,6 +4530,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -3155,14 +4609,16 @@ pub mod tests {
         let entry_id = authorized_access
             .add_entry(
                 name.to_owned(),
-                DataType::Int64,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Some Description That Does Not Matter".to_owned(),
-                Some(types::DataValue::Int64(-500000)),  // min
-                Some(types::DataValue::Int64(10000000)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(types::DataValue::Int64(-500000)),
+                    max: Some(types::DataValue::Int64(10000000)),
+                    ..vss::DataEntry::new(
+                        DataType::Int64,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Some Description That Does Not Matter".to_owned(),
+                    )
+                },
             )
             .await
             .unwrap();
@@ -3185,6 +4641,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -3242,14 +4699,16 @@ pub mod tests {
         let entry_id = authorized_access
             .add_entry(
                 name.to_owned(),
-                DataType::Uint8,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Some Description That Does Not Matter".to_owned(),
-                Some(types::DataValue::Uint32(3)),  // min
-                Some(types::DataValue::Uint32(26)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(types::DataValue::Uint32(3)),
+                    max: Some(types::DataValue::Uint32(26)),
+                    ..vss::DataEntry::new(
+                        DataType::Uint8,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Some Description That Does Not Matter".to_owned(),
+                    )
+                },
             )
             .await
             .unwrap();
@@ -3272,6 +4731,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -3330,14 +4790,16 @@ pub mod tests {
         let entry_id = authorized_access
             .add_entry(
                 name.to_owned(),
-                DataType::Int32Array,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Some Description That Does Not Matter".to_owned(),
-                Some(types::DataValue::Int32(-500)), // min
-                Some(types::DataValue::Int32(1000)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(types::DataValue::Int32(-500)),
+                    max: Some(types::DataValue::Int32(1000)),
+                    ..vss::DataEntry::new(
+                        DataType::Int32Array,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Some Description That Does Not Matter".to_owned(),
+                    )
+                },
             )
             .await
             .unwrap();
@@ -3360,6 +4822,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -3455,14 +4918,16 @@ pub mod tests {
         let entry_id = authorized_access
             .add_entry(
                 name.to_owned(),
-                DataType::DoubleArray,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Some Description That Does Not Matter".to_owned(),
-                Some(types::DataValue::Double(-500.2)), // min
-                Some(types::DataValue::Double(1000.2)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(types::DataValue::Double(-500.2)),
+                    max: Some(types::DataValue::Double(1000.2)),
+                    ..vss::DataEntry::new(
+                        DataType::DoubleArray,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Some Description That Does Not Matter".to_owned(),
+                    )
+                },
             )
             .await
             .unwrap();
@@ -3485,6 +4950,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -3560,14 +5026,12 @@ pub mod tests {
         let id1 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Int32,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3657,6 +5121,7 @@ pub mod tests {
                         min: None,
                         max: None,
                         unit: None,
+                        range_policy: None,
                     },
                 )])
                 .await
@@ -3706,14 +5171,12 @@ pub mod tests {
         let id1 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Int32,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3769,6 +5232,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -3807,14 +5271,12 @@ pub mod tests {
         let id2 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 1 (new description)".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Int32,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Test datapoint 1 (new description)".to_owned(),
+                ),
             )
             .await
             .expect("Registration should succeed");
@@ -3839,6 +5301,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -3883,14 +5346,12 @@ pub mod tests {
         let id1 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Int32,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3898,14 +5359,12 @@ pub mod tests {
         let id2 = broker
             .add_entry(
                 "test.datapoint2".to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 2".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Int32,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Test datapoint 2".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3959,6 +5418,7 @@ pub mod tests {
                             min: None,
                             max: None,
                             unit: None,
+                            range_policy: None,
                         },
                     ),
                     (
@@ -3978,6 +5438,7 @@ pub mod tests {
                             min: None,
                             max: None,
                             unit: None,
+                            range_policy: None,
                         },
                     ),
                 ])
@@ -4013,14 +5474,12 @@ pub mod tests {
         let id = broker
             .add_entry(
                 "Vehicle.TestArray".to_owned(),
-                DataType::BoolArray,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Run of the mill test array".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::BoolArray,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test array".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -4044,6 +5503,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4081,14 +5541,12 @@ pub mod tests {
         let id = broker
             .add_entry(
                 "Vehicle.TestArray".to_owned(),
-                DataType::StringArray,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Run of the mill test array".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::StringArray,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test array".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -4117,6 +5575,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4159,19 +5618,20 @@ pub mod tests {
         let id = broker
             .add_entry(
                 "Vehicle.TestArray".to_owned(),
-                DataType::StringArray,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Run of the mill test array".to_owned(),
-                None, // min
-                None, // max
-                Some(DataValue::StringArray(vec![
-                    String::from("yes"),
-                    String::from("no"),
-                    String::from("maybe"),
-                    String::from("nah"),
-                ])),
-                None,
+                vss::DataEntry {
+                    allowed: Some(DataValue::StringArray(vec![
+                        String::from("yes"),
+                        String::from("no"),
+                        String::from("maybe"),
+                        String::from("nah"),
+                    ])),
+                    ..vss::DataEntry::new(
+                        DataType::StringArray,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Run of the mill test array".to_owned(),
+                    )
+                },
             )
             .await
             .unwrap();
@@ -4200,6 +5660,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4258,6 +5719,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4314,6 +5776,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4333,14 +5796,12 @@ pub mod tests {
         let id = broker
             .add_entry(
                 "Vehicle.TestArray".to_owned(),
-                DataType::Int8Array,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Run of the mill test array".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Int8Array,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test array".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -4364,6 +5825,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4394,6 +5856,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4424,14 +5887,12 @@ pub mod tests {
         let id = broker
             .add_entry(
                 "Vehicle.TestArray".to_owned(),
-                DataType::Uint8Array,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Run of the mill test array".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Uint8Array,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test array".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -4455,6 +5916,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4485,6 +5947,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4518,14 +5981,12 @@ pub mod tests {
         let id = broker
             .add_entry(
                 "Vehicle.TestArray".to_owned(),
-                DataType::FloatArray,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Run of the mill test array".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::FloatArray,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test array".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -4549,6 +6010,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4585,14 +6047,12 @@ pub mod tests {
         let id1 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Int32,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -4644,6 +6104,7 @@ pub mod tests {
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )])
             .await
@@ -4699,14 +6160,12 @@ pub mod tests {
         let id1 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Int32,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -4735,28 +6194,24 @@ pub mod tests {
         let id1 = broker
             .add_entry(
                 "Vehicle.Test1".to_owned(),
-                DataType::Bool,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Run of the mill test signal".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Bool,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test signal".to_owned(),
+                ),
             )
             .await
             .unwrap();
         let id2 = broker
             .add_entry(
                 "Vehicle.Test2".to_owned(),
-                DataType::Bool,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Run of the mill test signal".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Bool,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test signal".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -4775,39 +6230,710 @@ pub mod tests {
     }
 
     #[tokio::test]
-    async fn test_register_invalid_and_valid_path() {
+    async fn test_read_denial_takes_precedence() {
+        let db = DataBroker::default();
+        let broker = db.authorized_access(&permissions::ALLOW_ALL);
+
+        let id1 = broker
+            .add_entry(
+                "Vehicle.Cabin.Seat.Row1.Occupant.Identifier".to_owned(),
+                vss::DataEntry::new(
+                    DataType::String,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test signal".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+        let id2 = broker
+            .add_entry(
+                "Vehicle.Speed".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Float,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test signal".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        let permissions = Permissions::builder()
+            .add_read_permission(permissions::Permission::Glob("Vehicle.**".to_owned()))
+            .add_provide_permission(permissions::Permission::All)
+            .add_read_denial(permissions::Permission::Glob(
+                "Vehicle.Cabin.Seat.*.Occupant.Identifier".to_owned(),
+            ))
+            .build()
+            .unwrap();
+        let broker = db.authorized_access(&permissions);
+
+        // Denied entries are hidden from listings
+        let paths = broker
+            .map_entries(|entry| entry.metadata().path.clone())
+            .await;
+        assert_eq!(paths, vec!["Vehicle.Speed".to_owned()]);
+
+        // ... and from metadata lookups
+        assert!(broker.get_metadata(id2).await.is_some());
+        assert!(broker.get_metadata(id1).await.is_none());
+        assert!(broker
+            .get_metadata_by_path("Vehicle.Cabin.Seat.Row1.Occupant.Identifier")
+            .await
+            .is_none());
+        assert!(broker
+            .get_id_by_path("Vehicle.Cabin.Seat.Row1.Occupant.Identifier")
+            .await
+            .is_none());
+
+        assert!(broker.get_datapoint(id2).await.is_ok());
+        assert!(matches!(
+            broker.get_datapoint(id1).await,
+            Err(ReadError::PermissionDenied)
+        ));
+
+        let entries = HashMap::from([(id1, HashSet::from([Field::Datapoint]))]);
+        assert!(matches!(
+            broker.subscribe(entries, None, None).await,
+            Err(SubscriptionError::PermissionDenied)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_entries_struct() {
         let broker = DataBroker::default();
-        let broker = broker.authorized_access(&permissions::ALLOW_ALL);
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
 
-        let error = broker
+        let position = Arc::new(types::StructType {
+            name: "Types.Position".to_owned(),
+            fields: vec![
+                types::StructField {
+                    name: "Latitude".to_owned(),
+                    data_type: DataType::Double,
+                },
+                types::StructField {
+                    name: "Quality".to_owned(),
+                    data_type: DataType::Uint8,
+                },
+            ],
+        });
+        let entry_id = authorized_access
             .add_entry(
-                "test. signal:3".to_owned(),
-                DataType::String,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test signal 3".to_owned(),
-                None, // min
-                None, // max
-                Some(DataValue::Int32Array(Vec::from([1, 2, 3, 4]))),
-                None,
+                "Vehicle.Position".to_owned(),
+                vss::DataEntry::new(
+                    DataType::StructArray(position),
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Some Description That Does Not Matter".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        let update = |fields: types::StructValue| {
+            (
+                entry_id,
+                EntryUpdate {
+                    datapoint: Some(Datapoint {
+                        ts: std::time::SystemTime::now(),
+                        source_ts: None,
+                        value: types::DataValue::StructArray(vec![fields]),
+                    }),
+                    ..Default::default()
+                },
             )
+        };
+        let position = |quality: types::DataValue| {
+            types::StructValue::from([
+                ("Latitude".to_owned(), types::DataValue::Double(48.1)),
+                ("Quality".to_owned(), quality),
+            ])
+        };
+
+        assert!(authorized_access
+            .update_entries([update(position(types::DataValue::Uint32(3)))])
+            .await
+            .is_ok());
+
+        let errors = authorized_access
+            .update_entries([update(position(types::DataValue::Uint32(300)))])
             .await
             .unwrap_err();
-        assert_eq!(error, RegistrationError::ValidationError);
+        assert_eq!(errors, vec![(entry_id, UpdateError::OutOfBoundsType)]);
+
+        let errors = authorized_access
+            .update_entries([update(position(types::DataValue::Bool(true)))])
+            .await
+            .unwrap_err();
+        assert_eq!(errors, vec![(entry_id, UpdateError::WrongType)]);
+
+        // Missing field
+        let mut fields = position(types::DataValue::Uint32(3));
+        fields.remove("Quality");
+        let errors = authorized_access
+            .update_entries([update(fields)])
+            .await
+            .unwrap_err();
+        assert_eq!(errors, vec![(entry_id, UpdateError::WrongType)]);
+
+        // Unknown field
+        let mut fields = position(types::DataValue::Uint32(3));
+        fields.insert("Altitude".to_owned(), types::DataValue::Double(512.0));
+        let errors = authorized_access
+            .update_entries([update(fields)])
+            .await
+            .unwrap_err();
+        assert_eq!(errors, vec![(entry_id, UpdateError::WrongType)]);
+    }
+
+    #[tokio::test]
+    async fn test_update_entries_timestamp() {
+        let broker = DataBroker::default();
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
+
+        let entry_id = authorized_access
+            .add_entry(
+                "Vehicle.LastService".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Timestamp,
+                    ChangeType::OnChange,
+                    EntryType::Attribute,
+                    "Some Description That Does Not Matter".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        let update = |value: types::DataValue| {
+            (
+                entry_id,
+                EntryUpdate {
+                    datapoint: Some(Datapoint {
+                        ts: std::time::SystemTime::now(),
+                        source_ts: None,
+                        value,
+                    }),
+                    ..Default::default()
+                },
+            )
+        };
+
+        let last_service = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1714564800);
+        assert!(authorized_access
+            .update_entries([update(types::DataValue::Timestamp(last_service))])
+            .await
+            .is_ok());
+        match authorized_access.get_datapoint(entry_id).await {
+            Ok(datapoint) => assert_eq!(datapoint.value, types::DataValue::Timestamp(last_service)),
+            Err(err) => panic!("unexpected error: {err:?}"),
+        }
+
+        let errors = authorized_access
+            .update_entries([update(types::DataValue::String(
+                "2024-05-01T12:00:00Z".to_owned(),
+            ))])
+            .await
+            .unwrap_err();
+        assert_eq!(errors, vec![(entry_id, UpdateError::WrongType)]);
+
+        let errors = authorized_access
+            .update_entries([update(types::DataValue::TimestampArray(vec![last_service]))])
+            .await
+            .unwrap_err();
+        assert_eq!(errors, vec![(entry_id, UpdateError::WrongType)]);
+    }
+
+    #[tokio::test]
+    async fn test_revoked_subscription_is_closed() {
+        let revocation_list = Arc::new(RevocationList::new());
+        let db = DataBroker::default().with_revocation_list(revocation_list.clone());
+        let broker = db.authorized_access(&permissions::ALLOW_ALL);
 
         let id = broker
             .add_entry(
-                "Vehicle._kuksa.databroker.GitVersion.Do_you_not_like_smörgåstårta.tschö_mit_ö.東京_Москва_r#true".to_owned(),
+                "Vehicle.Speed".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Float,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test signal".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        let permissions = Permissions::builder()
+            .subject("test-client")
+            .token_id("token-1")
+            .add_read_permission(permissions::Permission::All)
+            .build()
+            .unwrap();
+        let broker = db.authorized_access(&permissions);
+        let mut stream = broker
+            .subscribe(
+                HashMap::from([(id, HashSet::from([Field::Datapoint]))]),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        // Initial notification
+        assert!(stream.next().await.is_some());
+
+        revocation_list.revoke_token_id("token-1");
+        let closed = db
+            .subscriptions
+            .write()
+            .await
+            .cleanup_change_subscriptions(&revocation_list);
+        assert_eq!(closed.len(), 1);
+        assert!(stream.next().await.is_none());
+    }
+
+    struct NoopActuationProvider;
+
+    #[async_trait::async_trait]
+    impl ActuationProvider for NoopActuationProvider {
+        async fn actuate(
+            &self,
+            _actuation_changes: Vec<ActuationChange>,
+        ) -> Result<(), (ActuationError, String)> {
+            Ok(())
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_permissions() {
+        let db = DataBroker::default();
+        let broker = db.authorized_access(&permissions::ALLOW_ALL);
+
+        let id = broker
+            .add_entry(
+                "Vehicle.Speed".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Float,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test signal".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        let token = |token_id: &str, subject: &str| {
+            Permissions::builder()
+                .subject(subject)
+                .token_id(token_id)
+                .add_read_permission(permissions::Permission::All)
+                .build()
+                .unwrap()
+        };
+        let old = token("token-1", "test-client");
+        let session = Uuid::new_v4();
+        let broker = db.authorized_access(&old).in_session(session);
+        let _stream = broker
+            .subscribe(
+                HashMap::from([(id, HashSet::from([Field::Datapoint]))]),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        // Same token, but subscribed by another stream
+        let _other_stream = db
+            .authorized_access(&old)
+            .in_session(Uuid::new_v4())
+            .subscribe(
+                HashMap::from([(id, HashSet::from([Field::Datapoint]))]),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            broker
+                .refresh_permissions(&token("token-2", "other-client"))
+                .await,
+            Err(PermissionError::Denied)
+        ));
+
+        let new = token("token-2", "test-client");
+        assert!(broker.refresh_permissions(&new).await.is_ok());
+        let subscriptions = db.subscriptions.read().await;
+        assert_eq!(
+            subscriptions
+                .change_subscriptions
+                .values()
+                .filter(|subscription| subscription.permissions.is_same_token(&new))
+                .count(),
+            1
+        );
+        assert!(subscriptions
+            .change_subscriptions
+            .values()
+            .all(|subscription| subscription.permissions.is_same_token(&new)
+                == (subscription.session == Some(session))));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_permissions_narrowed_scope() {
+        let db = DataBroker::default();
+        let broker = db.authorized_access(&permissions::ALLOW_ALL);
+
+        let speed = broker
+            .add_entry(
+                "Vehicle.Speed".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Float,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test signal".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+        let trunk = broker
+            .add_entry(
+                "Vehicle.Body.Trunk.IsOpen".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Bool,
+                    ChangeType::OnChange,
+                    EntryType::Actuator,
+                    "Run of the mill test actuator".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        let old = Permissions::builder()
+            .subject("test-client")
+            .token_id("token-1")
+            .add_read_permission(permissions::Permission::All)
+            .add_actuate_permission(permissions::Permission::All)
+            .build()
+            .unwrap();
+        let broker = db.authorized_access(&old).in_session(Uuid::new_v4());
+        let mut stream = broker
+            .subscribe(
+                HashMap::from([
+                    (speed, HashSet::from([Field::Datapoint])),
+                    (trunk, HashSet::from([Field::Datapoint])),
+                ]),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(stream.next().await.is_some());
+        broker
+            .provide_actuation(vec![trunk], Box::new(NoopActuationProvider))
+            .await
+            .unwrap();
+
+        // The new token only allows reading Vehicle.Speed
+        let new = Permissions::builder()
+            .subject("test-client")
+            .token_id("token-2")
+            .add_read_permission(permissions::Permission::Glob("Vehicle.Speed".to_owned()))
+            .build()
+            .unwrap();
+        assert!(broker.refresh_permissions(&new).await.is_ok());
+
+        let subscriptions = db.subscriptions.read().await;
+        let subscription = subscriptions.change_subscriptions.values().next().unwrap();
+        assert!(subscription.permissions.is_same_token(&new));
+        assert_eq!(
+            subscription.entries.keys().copied().collect::<Vec<_>>(),
+            vec![speed]
+        );
+        // Actuating the trunk is not permitted anymore
+        assert!(subscriptions.actuation_subscriptions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_permissions_applies_to_subscriptions_of_connection() {
+        let db = DataBroker::default();
+        let broker = db.authorized_access(&permissions::ALLOW_ALL);
+
+        let speed = broker
+            .add_entry(
+                "Vehicle.Speed".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Float,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test signal".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+        let trunk = broker
+            .add_entry(
+                "Vehicle.Body.Trunk.IsOpen".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Bool,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test signal".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        let old = Permissions::builder()
+            .subject("test-client")
+            .token_id("token-1")
+            .add_read_permission(permissions::Permission::All)
+            .build()
+            .unwrap();
+        let connection: SocketAddr = "127.0.0.1:50001".parse().unwrap();
+        let other_connection: SocketAddr = "127.0.0.1:50002".parse().unwrap();
+        let entries = HashMap::from([
+            (speed, HashSet::from([Field::Datapoint])),
+            (trunk, HashSet::from([Field::Datapoint])),
+        ]);
+        // Subscribed outside of the provider stream, but on its connection
+        let mut stream = db
+            .authorized_access(&old)
+            .on_connection(Some(connection))
+            .subscribe(entries.clone(), None, None)
+            .await
+            .unwrap();
+        assert!(stream.next().await.is_some());
+        let mut other_stream = db
+            .authorized_access(&old)
+            .on_connection(Some(other_connection))
+            .subscribe(entries, None, None)
+            .await
+            .unwrap();
+        assert!(other_stream.next().await.is_some());
+
+        // The new token doesn't allow reading the trunk anymore
+        let new = Permissions::builder()
+            .subject("test-client")
+            .token_id("token-2")
+            .add_read_permission(permissions::Permission::Glob("Vehicle.Speed".to_owned()))
+            .build()
+            .unwrap();
+        assert!(db
+            .authorized_access(&old)
+            .in_session(Uuid::new_v4())
+            .on_connection(Some(connection))
+            .refresh_permissions(&new)
+            .await
+            .is_ok());
+
+        for (id, value) in [
+            (trunk, DataValue::Bool(true)),
+            (speed, DataValue::Float(50.0)),
+        ] {
+            broker
+                .update_entries([(
+                    id,
+                    EntryUpdate {
+                        datapoint: Some(Datapoint {
+                            ts: SystemTime::now(),
+                            source_ts: None,
+                            value,
+                        }),
+                        ..Default::default()
+                    },
+                )])
+                .await
+                .unwrap();
+        }
+
+        // The trunk is not delivered anymore
+        let updates = stream.next().await.unwrap().unwrap().updates;
+        assert_eq!(
+            updates.iter().map(|update| update.id).collect::<Vec<_>>(),
+            vec![speed]
+        );
+        // Subscriptions of other connections are not affected
+        let updates = other_stream.next().await.unwrap().unwrap().updates;
+        assert_eq!(
+            updates.iter().map(|update| update.id).collect::<Vec<_>>(),
+            vec![trunk]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_entry_releases_subscriptions() {
+        let db = DataBroker::default();
+        let broker = db.authorized_access(&permissions::ALLOW_ALL);
+
+        let speed = broker
+            .add_entry(
+                "Vehicle.Speed".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Float,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Run of the mill test signal",
+                ),
+            )
+            .await
+            .unwrap();
+        let new_entry = || {
+            vss::DataEntry::new(
                 DataType::Bool,
                 ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint".to_owned(),
-                None, // min
-                None, // max
-                Some(DataValue::BoolArray(Vec::from([true]))),
+                EntryType::Actuator,
+                "Registered test actuator",
+            )
+        };
+        let registered = broker
+            .register_entry("Vehicle.Derived.IsActive".to_owned(), new_entry())
+            .await
+            .unwrap();
+
+        let mut stream = broker
+            .subscribe(
+                HashMap::from([
+                    (speed, HashSet::from([Field::Datapoint])),
+                    (registered.id, HashSet::from([Field::Datapoint])),
+                ]),
+                None,
                 None,
             )
             .await
+            .unwrap();
+        assert!(stream.next().await.is_some());
+        broker
+            .provide_actuation(vec![registered.id], Box::new(NoopActuationProvider))
+            .await
+            .unwrap();
+
+        broker.remove_entry(registered.id).await.unwrap();
+        {
+            let subscriptions = db.subscriptions.read().await;
+            let subscription = subscriptions.change_subscriptions.values().next().unwrap();
+            assert_eq!(
+                subscription.entries.keys().copied().collect::<Vec<_>>(),
+                vec![speed]
+            );
+            assert!(subscriptions.actuation_subscriptions.is_empty());
+        }
+
+        // Registered again, it can be provided by someone else
+        let registered = broker
+            .register_entry("Vehicle.Derived.IsActive".to_owned(), new_entry())
+            .await
+            .unwrap();
+        broker
+            .provide_actuation(vec![registered.id], Box::new(NoopActuationProvider))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let file = std::env::temp_dir()
+            .join(format!("databroker-audit-{}", Uuid::new_v4()))
+            .with_extension("log");
+        let audit_log =
+            crate::audit::AuditLog::new(&crate::audit::AuditConfig::new(&file)).unwrap();
+        let db = DataBroker::default().with_audit_log(audit_log);
+        let broker = db.authorized_access(&permissions::ALLOW_ALL);
+
+        let id = broker
+            .add_entry(
+                "Vehicle.Body.Trunk.Rear.IsOpen".to_owned(),
+                vss::DataEntry::new(
+                    DataType::Bool,
+                    ChangeType::OnChange,
+                    EntryType::Actuator,
+                    "Run of the mill test actuator".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        let permissions = Permissions::builder()
+            .subject("test-client")
+            .add_read_permission(permissions::Permission::All)
+            .build()
+            .unwrap();
+        let broker = db
+            .authorized_access(&permissions)
+            .via(crate::audit::Transport::KuksaValV1);
+
+        let update = EntryUpdate {
+            path: None,
+            datapoint: None,
+            actuator_target: Some(Some(Datapoint {
+                ts: SystemTime::now(),
+                source_ts: None,
+                value: DataValue::Bool(true),
+            })),
+            entry_type: None,
+            data_type: None,
+            description: None,
+            allowed: None,
+            min: None,
+            max: None,
+            unit: None,
+            range_policy: None,
+        };
+        assert!(broker.update_entries([(id, update)]).await.is_err());
+
+        let broker = db
+            .authorized_access(&permissions::ALLOW_ALL)
+            .via(crate::audit::Transport::KuksaValV2);
+        assert!(matches!(
+            broker.actuate(&id, &DataValue::Bool(false)).await,
+            Err((ActuationError::ProviderNotAvailable, _))
+        ));
+
+        let records: Vec<serde_json::Value> = std::fs::read_to_string(&file)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["sub"], "test-client");
+        assert_eq!(records[0]["transport"], "kuksa.val.v1");
+        assert_eq!(records[0]["event"], "actuate");
+        assert_eq!(records[0]["path"], "Vehicle.Body.Trunk.Rear.IsOpen");
+        assert_eq!(records[0]["value"], true);
+        assert_eq!(records[0]["outcome"], "PermissionDenied");
+        assert_eq!(records[1]["transport"], "kuksa.val.v2");
+        assert_eq!(records[1]["value"], false);
+        assert_eq!(records[1]["outcome"], "ProviderNotAvailable");
+        let _ = std::fs::remove_file(&file);
+    }
+
+    #[tokio::test]
+    async fn test_register_invalid_and_valid_path() {
+        let broker = DataBroker::default();
+        let broker = broker.authorized_access(&permissions::ALLOW_ALL);
+
+        let error = broker
+            .add_entry(
+                "test. signal:3".to_owned(),
+                vss::DataEntry {
+                    allowed: Some(DataValue::Int32Array(Vec::from([1, 2, 3, 4]))),
+                    ..vss::DataEntry::new(
+                        DataType::String,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Test signal 3".to_owned(),
+                    )
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error, RegistrationError::ValidationError);
+
+        let id = broker
+            .add_entry("Vehicle._kuksa.databroker.GitVersion.Do_you_not_like_smörgåstårta.tschö_mit_ö.東京_Москва_r#true".to_owned(), vss::DataEntry { allowed: Some(DataValue::BoolArray(Vec::from([true]))), ..vss::DataEntry::new(DataType::Bool, ChangeType::OnChange, EntryType::Sensor, "Test datapoint".to_owned()) })
+            .await
             .expect("Register datapoint should succeed");
         {
             match broker.get_entry_by_id(id).await {
@@ -4846,14 +6972,12 @@ pub mod tests {
         let id1 = broker
             .add_entry(
                 "test.datapoint1".to_owned(),
-                DataType::Int32,
-                ChangeType::OnChange,
-                EntryType::Sensor,
-                "Test datapoint".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    DataType::Int32,
+                    ChangeType::OnChange,
+                    EntryType::Sensor,
+                    "Test datapoint".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
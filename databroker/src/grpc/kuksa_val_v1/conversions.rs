@@ -55,6 +55,12 @@ impl From<broker::DataType> for proto::DataType {
             broker::DataType::Uint64Array => proto::DataType::Uint64Array,
             broker::DataType::FloatArray => proto::DataType::FloatArray,
             broker::DataType::DoubleArray => proto::DataType::DoubleArray,
+            broker::DataType::Timestamp => proto::DataType::Timestamp,
+            broker::DataType::TimestampArray => proto::DataType::TimestampArray,
+            // kuksa.val.v1 has no struct types
+            broker::DataType::Struct(_) | broker::DataType::StructArray(_) => {
+                proto::DataType::Unspecified
+            }
         }
     }
 }
@@ -143,6 +149,20 @@ impl From<broker::Datapoint> for Option<proto::Datapoint> {
                 })),
                 timestamp: Some(from.ts.into()),
             }),
+            broker::DataValue::Timestamp(value) => Some(proto::Datapoint {
+                value: Some(proto::datapoint::Value::TimestampValue(value.into())),
+                timestamp: Some(from.ts.into()),
+            }),
+            broker::DataValue::TimestampArray(values) => Some(proto::Datapoint {
+                value: Some(proto::datapoint::Value::TimestampArray(
+                    proto::TimestampArray {
+                        values: values.into_iter().map(Into::into).collect(),
+                    },
+                )),
+                timestamp: Some(from.ts.into()),
+            }),
+            // Not representable in kuksa.val.v1
+            broker::DataValue::Struct(_) | broker::DataValue::StructArray(_) => None,
         }
     }
 }
@@ -231,6 +251,19 @@ impl From<broker::DataValue> for Option<proto::Datapoint> {
                 })),
                 timestamp: None,
             }),
+            broker::DataValue::Timestamp(value) => Some(proto::Datapoint {
+                value: Some(proto::datapoint::Value::TimestampValue(value.into())),
+                timestamp: None,
+            }),
+            broker::DataValue::TimestampArray(values) => Some(proto::Datapoint {
+                value: Some(proto::datapoint::Value::TimestampArray(
+                    proto::TimestampArray {
+                        values: values.into_iter().map(Into::into).collect(),
+                    },
+                )),
+                timestamp: None,
+            }),
+            broker::DataValue::Struct(_) | broker::DataValue::StructArray(_) => None,
         }
     }
 }
@@ -272,6 +305,16 @@ impl From<Option<proto::datapoint::Value>> for broker::DataValue {
                 proto::datapoint::Value::DoubleArray(array) => {
                     broker::DataValue::DoubleArray(array.values)
                 }
+                proto::datapoint::Value::TimestampValue(value) => match value.try_into() {
+                    Ok(value) => broker::DataValue::Timestamp(value),
+                    Err(_) => broker::DataValue::NotAvailable,
+                },
+                proto::datapoint::Value::TimestampArray(array) => {
+                    match array.values.into_iter().map(TryInto::try_into).collect() {
+                        Ok(values) => broker::DataValue::TimestampArray(values),
+                        Err(_) => broker::DataValue::NotAvailable,
+                    }
+                }
             },
             None => broker::DataValue::NotAvailable,
         }
@@ -283,6 +326,7 @@ impl From<&broker::Field> for proto::Field {
         match from {
             broker::Field::Datapoint => proto::Field::Value,
             broker::Field::ActuatorTarget => proto::Field::ActuatorTarget,
+            broker::Field::Metadata => proto::Field::Metadata,
             broker::Field::MetadataUnit => proto::Field::MetadataUnit,
         }
     }
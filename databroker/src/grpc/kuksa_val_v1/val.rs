@@ -28,12 +28,14 @@ use tonic::{Response, Status, Streaming};
 use tracing::debug;
 use tracing::info;
 
+use crate::audit::Transport;
 use crate::broker;
 use crate::broker::ReadError;
 use crate::broker::SubscriptionError;
 use crate::broker::{AuthorizedAccess, EntryReadAccess};
 use crate::glob::Matcher;
 use crate::permissions::Permissions;
+use crate::rate_limit::{ClientId, RateLimitError, RateLimiter};
 use crate::types::{DataType, DataValue};
 
 const MAX_REQUEST_PATH_LENGTH: usize = 1000;
@@ -52,7 +54,9 @@ impl proto::val_server::Val for broker::DataBroker {
             }
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
-        let broker = self.authorized_access(&permissions);
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV1);
 
         let requested = request.into_inner().entries;
         if requested.is_empty() {
@@ -123,12 +127,16 @@ impl proto::val_server::Val for broker::DataBroker {
                 }
             }
             if !valid_requests.is_empty() {
-                for (matcher, view_fields, _, is_match, op_error) in &mut valid_requests {
+                for (matcher, view_fields, request_path, is_match, op_error) in &mut valid_requests
+                {
                     broker
                         .for_each_entry(|entry| {
                             let mut result_fields: HashSet<proto::Field> = HashSet::new();
                             let glob_path = &entry.metadata().glob_path;
                             if matcher.is_match(glob_path) {
+                                if entry.metadata().path == *request_path {
+                                    broker.warn_if_deprecated(entry.metadata());
+                                }
                                 // Update the `is_match` to indicate a valid and used request path.
                                 *is_match = true;
                                 if view_fields.contains(&proto::Field::Metadata) {
@@ -278,8 +286,11 @@ impl proto::val_server::Val for broker::DataBroker {
             }
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
+        let client = ClientId::new(&permissions, request.remote_addr());
 
-        let broker = self.authorized_access(&permissions);
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV1);
 
         let entry_updates = request.into_inner().updates;
 
@@ -314,6 +325,8 @@ impl proto::val_server::Val for broker::DataBroker {
             }
         }
 
+        check_rate_limits(self.rate_limiter(), &client, &updates)?;
+
         match broker.update_entries(updates).await {
             Ok(()) => {}
             Err(err) => {
@@ -349,12 +362,16 @@ impl proto::val_server::Val for broker::DataBroker {
             }
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
+        let client = ClientId::new(&permissions, request.remote_addr());
         let mut stream = request.into_inner();
 
         let mut shutdown_trigger = self.get_shutdown_trigger();
+        let revocation_list = self.revocation_list().clone();
+        let mut revocations = revocation_list.changed();
 
         // Copy (to move into task below)
         let broker = self.clone();
+        let rate_limiter = self.rate_limiter().clone();
 
         // Create stream (to be returned); when changing buffer size, throughput should be measured
         let (sender, receiver) = mpsc::channel(10);
@@ -362,7 +379,9 @@ impl proto::val_server::Val for broker::DataBroker {
         tokio::spawn(async move {
             info!("Update Stream opened");
             let permissions = permissions;
-            let broker = broker.authorized_access(&permissions);
+            let broker = broker
+                .authorized_access(&permissions)
+                .via(Transport::KuksaValV1);
             loop {
                 select! {
                     message = stream.message() => {
@@ -422,6 +441,13 @@ impl proto::val_server::Val for broker::DataBroker {
                                             }
                                         }
 
+                                        if let Err(err) = check_rate_limits(&rate_limiter, &client, &updates) {
+                                            if let Err(err) = sender.send(Err(err.into())).await {
+                                                debug!("Failed to send error: {}", err);
+                                            }
+                                            continue;
+                                        }
+
                                         match broker.update_entries(updates).await {
                                             Ok(_) => {}
                                             Err(err) => {
@@ -461,6 +487,15 @@ impl proto::val_server::Val for broker::DataBroker {
                             },
                         }
                     },
+                    Ok(()) = revocations.changed() => {
+                        if revocation_list.is_revoked(&permissions) {
+                            info!("provider: token revoked, closing stream");
+                            if let Err(err) = sender.send(Err(tonic::Status::unauthenticated("Token has been revoked"))).await {
+                                debug!("Failed to send error response: {}", err);
+                            }
+                            break;
+                        }
+                    },
                     _ = shutdown_trigger.recv() => {
                         debug!("provider: shutdown received");
                         break;
@@ -495,7 +530,10 @@ impl proto::val_server::Val for broker::DataBroker {
             }
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
-        let broker = self.authorized_access(&permissions);
+        let client = ClientId::new(&permissions, request.remote_addr());
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV1);
 
         let request = request.into_inner();
 
@@ -557,6 +595,9 @@ impl proto::val_server::Val for broker::DataBroker {
                         let glob_path = &entry.metadata().glob_path;
                         if matcher.is_match(glob_path) {
                             requested_path_found = true;
+                            if entry.metadata().path == path {
+                                broker.warn_if_deprecated(entry.metadata());
+                            }
                             entries
                                 .entry(entry.metadata().id)
                                 .and_modify(|existing_fields| {
@@ -611,6 +652,10 @@ impl proto::val_server::Val for broker::DataBroker {
             }
         }
 
+        let quota = self
+            .rate_limiter()
+            .acquire_subscription(&client, entries.len())?;
+
         match broker.subscribe(entries, None, None).await {
             Ok(stream) => {
                 // Convert the internal stream (EntryUpdates) → protocol replies
@@ -620,6 +665,8 @@ impl proto::val_server::Val for broker::DataBroker {
                 let mut shutdown_rx = self.get_shutdown_trigger();
 
                 let wrapped = stream! {
+                    // The quota is released when the stream is dropped
+                    let _quota = quota;
                     let mut s = Box::pin(stream);
 
                     loop {
@@ -656,6 +703,10 @@ impl proto::val_server::Val for broker::DataBroker {
                 tonic::Code::InvalidArgument,
                 "No valid path specified",
             )),
+            Err(SubscriptionError::PermissionDenied) => Err(tonic::Status::new(
+                tonic::Code::PermissionDenied,
+                "Permission denied",
+            )),
             Err(SubscriptionError::InternalError) => {
                 Err(tonic::Status::new(tonic::Code::Internal, "Internal Error"))
             }
@@ -678,6 +729,28 @@ impl proto::val_server::Val for broker::DataBroker {
     }
 }
 
+fn check_rate_limits(
+    rate_limiter: &RateLimiter,
+    client: &ClientId,
+    updates: &[(i32, broker::EntryUpdate)],
+) -> Result<(), RateLimitError> {
+    let publish_count = updates
+        .iter()
+        .filter(|(_, update)| update.datapoint.is_some())
+        .count();
+    let actuation_count = updates
+        .iter()
+        .filter(|(_, update)| update.actuator_target.is_some())
+        .count();
+    if publish_count > 0 {
+        rate_limiter.check_publish(client, publish_count)?;
+    }
+    if actuation_count > 0 {
+        rate_limiter.check_actuation(client, actuation_count)?;
+    }
+    Ok(())
+}
+
 async fn validate_entry_update(
     broker: &AuthorizedAccess<'_, '_>,
     request: &EntryUpdate,
@@ -848,13 +921,13 @@ fn proto_entry_from_entry_and_fields(
         }
         if all || fields.contains(&proto::Field::MetadataComment) {
             metadata_is_set = true;
-            // TODO: Add to Metadata
-            metadata.comment = None;
+            metadata.comment.clone_from(&entry.metadata().comment);
         }
         if all || fields.contains(&proto::Field::MetadataDeprecation) {
             metadata_is_set = true;
-            // TODO: Add to Metadata
-            metadata.deprecation = None;
+            metadata
+                .deprecation
+                .clone_from(&entry.metadata().deprecation);
         }
         if all || fields.contains(&proto::Field::MetadataUnit) {
             metadata_is_set = true;
@@ -1148,6 +1221,7 @@ impl broker::EntryUpdate {
             min: None,
             max: None,
             unit: None,
+            range_policy: None,
         }
     }
 }
@@ -1155,7 +1229,7 @@ impl broker::EntryUpdate {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{broker::DataBroker, permissions};
+    use crate::{broker::DataBroker, permissions, vss};
     use databroker_proto::kuksa::val::v1::val_server::Val;
 
     /// Create a `tonic::Request<Streaming<T>>` from a vec of protobuf messages,
@@ -1189,14 +1263,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "test.datapoint1".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -1246,14 +1318,15 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.Speed".to_owned(),
-                broker::DataType::Float,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                Some("km/h".to_owned()),
+                vss::DataEntry {
+                    unit: Some("km/h".to_owned()),
+                    ..vss::DataEntry::new(
+                        broker::DataType::Float,
+                        broker::ChangeType::OnChange,
+                        broker::EntryType::Sensor,
+                        "Test datapoint 1".to_owned(),
+                    )
+                },
             )
             .await
             .expect("Register datapoint should succeed");
@@ -1344,14 +1417,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "test.datapoint1".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -1359,14 +1430,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "test.branch.datapoint2".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test branch datapoint 2".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test branch datapoint 2".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -1409,14 +1478,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "test.datapoint1".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
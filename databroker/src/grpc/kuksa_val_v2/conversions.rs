@@ -11,7 +11,8 @@
 // * SPDX-License-Identifier: Apache-2.0
 // ********************************************************************************/
 use crate::broker;
-use crate::types::DataValue;
+use crate::types::{DataValue, StructValue};
+use crate::vss;
 use databroker_proto::kuksa::val::v2 as proto;
 use kuksa::proto::v2::{
     BoolArray, DoubleArray, FloatArray, Int32Array, Int64Array, StringArray, Uint32Array,
@@ -164,6 +165,13 @@ impl From<broker::Datapoint> for Option<proto::Datapoint> {
                     })),
                 }),
             }),
+            value @ (broker::DataValue::Timestamp(_)
+            | broker::DataValue::TimestampArray(_)
+            | broker::DataValue::Struct(_)
+            | broker::DataValue::StructArray(_)) => Some(proto::Datapoint {
+                timestamp: Some(from.ts.into()),
+                value: Some(proto::Value::from(value)),
+            }),
         }
     }
 }
@@ -206,6 +214,18 @@ impl From<&proto::Datapoint> for broker::DataValue {
                 Some(proto::value::TypedValue::DoubleArray(array)) => {
                     broker::DataValue::DoubleArray(array.values.clone())
                 }
+                Some(proto::value::TypedValue::Timestamp(value)) => timestamp_from_proto(value),
+                Some(proto::value::TypedValue::TimestampArray(array)) => {
+                    timestamp_array_from_proto(array)
+                }
+                Some(proto::value::TypedValue::StructValue(value)) => {
+                    broker::DataValue::Struct(struct_from_proto(value))
+                }
+                Some(proto::value::TypedValue::StructArray(array)) => {
+                    broker::DataValue::StructArray(
+                        array.values.iter().map(struct_from_proto).collect(),
+                    )
+                }
                 None => broker::DataValue::NotAvailable,
             },
             None => broker::DataValue::NotAvailable,
@@ -221,13 +241,19 @@ impl From<&broker::Metadata> for proto::Metadata {
             data_type: proto::DataType::from(metadata.data_type.clone()) as i32,
             entry_type: proto::EntryType::from(metadata.entry_type.clone()) as i32,
             description: metadata.description.clone(),
-            comment: String::new(),
-            deprecation: String::new(),
+            comment: metadata.comment.clone().unwrap_or_default(),
+            deprecation: metadata.deprecation.clone().unwrap_or_default(),
             unit: metadata.unit.clone().unwrap_or_default(),
             allowed_values: transform_allowed(&metadata.allowed),
             min: transform_min_max(&metadata.min),
             max: transform_min_max(&metadata.max),
             min_sample_interval: None,
+            struct_type: match &metadata.data_type {
+                broker::DataType::Struct(struct_type)
+                | broker::DataType::StructArray(struct_type) => struct_type.name.clone(),
+                _ => String::new(),
+            },
+            default_value: metadata.default.clone().map(proto::Value::from),
         }
     }
 }
@@ -338,6 +364,118 @@ impl From<broker::DataType> for proto::DataType {
             broker::DataType::Uint64Array => proto::DataType::Uint64Array,
             broker::DataType::FloatArray => proto::DataType::FloatArray,
             broker::DataType::DoubleArray => proto::DataType::DoubleArray,
+            broker::DataType::Timestamp => proto::DataType::Timestamp,
+            broker::DataType::TimestampArray => proto::DataType::TimestampArray,
+            broker::DataType::Struct(_) => proto::DataType::Struct,
+            broker::DataType::StructArray(_) => proto::DataType::StructArray,
+        }
+    }
+}
+
+impl TryFrom<proto::DataType> for broker::DataType {
+    type Error = tonic::Status;
+
+    fn try_from(from: proto::DataType) -> Result<Self, Self::Error> {
+        match from {
+            proto::DataType::String => Ok(broker::DataType::String),
+            proto::DataType::Boolean => Ok(broker::DataType::Bool),
+            proto::DataType::Int8 => Ok(broker::DataType::Int8),
+            proto::DataType::Int16 => Ok(broker::DataType::Int16),
+            proto::DataType::Int32 => Ok(broker::DataType::Int32),
+            proto::DataType::Int64 => Ok(broker::DataType::Int64),
+            proto::DataType::Uint8 => Ok(broker::DataType::Uint8),
+            proto::DataType::Uint16 => Ok(broker::DataType::Uint16),
+            proto::DataType::Uint32 => Ok(broker::DataType::Uint32),
+            proto::DataType::Uint64 => Ok(broker::DataType::Uint64),
+            proto::DataType::Float => Ok(broker::DataType::Float),
+            proto::DataType::Double => Ok(broker::DataType::Double),
+            proto::DataType::Timestamp => Ok(broker::DataType::Timestamp),
+            proto::DataType::StringArray => Ok(broker::DataType::StringArray),
+            proto::DataType::BooleanArray => Ok(broker::DataType::BoolArray),
+            proto::DataType::Int8Array => Ok(broker::DataType::Int8Array),
+            proto::DataType::Int16Array => Ok(broker::DataType::Int16Array),
+            proto::DataType::Int32Array => Ok(broker::DataType::Int32Array),
+            proto::DataType::Int64Array => Ok(broker::DataType::Int64Array),
+            proto::DataType::Uint8Array => Ok(broker::DataType::Uint8Array),
+            proto::DataType::Uint16Array => Ok(broker::DataType::Uint16Array),
+            proto::DataType::Uint32Array => Ok(broker::DataType::Uint32Array),
+            proto::DataType::Uint64Array => Ok(broker::DataType::Uint64Array),
+            proto::DataType::FloatArray => Ok(broker::DataType::FloatArray),
+            proto::DataType::DoubleArray => Ok(broker::DataType::DoubleArray),
+            proto::DataType::TimestampArray => Ok(broker::DataType::TimestampArray),
+            proto::DataType::Struct | proto::DataType::StructArray => Err(
+                tonic::Status::invalid_argument("Struct types are not supported"),
+            ),
+            proto::DataType::Unspecified => {
+                Err(tonic::Status::invalid_argument("No data type provided"))
+            }
+        }
+    }
+}
+
+impl TryFrom<proto::EntryType> for broker::EntryType {
+    type Error = tonic::Status;
+
+    fn try_from(from: proto::EntryType) -> Result<Self, Self::Error> {
+        match from {
+            proto::EntryType::Sensor => Ok(broker::EntryType::Sensor),
+            proto::EntryType::Attribute => Ok(broker::EntryType::Attribute),
+            proto::EntryType::Actuator => Ok(broker::EntryType::Actuator),
+            proto::EntryType::Unspecified => {
+                Err(tonic::Status::invalid_argument("No entry type provided"))
+            }
+        }
+    }
+}
+
+impl TryFrom<proto::Metadata> for vss::DataEntry {
+    type Error = tonic::Status;
+
+    fn try_from(metadata: proto::Metadata) -> Result<Self, Self::Error> {
+        let data_type = proto::DataType::try_from(metadata.data_type)
+            .map_err(|_| tonic::Status::invalid_argument("Unknown data type"))
+            .and_then(broker::DataType::try_from)?;
+        let entry_type = proto::EntryType::try_from(metadata.entry_type)
+            .map_err(|_| tonic::Status::invalid_argument("Unknown entry type"))
+            .and_then(broker::EntryType::try_from)?;
+        // Same default as for signals loaded from VSS files
+        let change_type = match entry_type {
+            broker::EntryType::Attribute => broker::ChangeType::Static,
+            _ => broker::ChangeType::Continuous,
+        };
+        let non_empty = |value: String| (!value.is_empty()).then_some(value);
+        let value = |value: Option<proto::Value>| {
+            value
+                .filter(|value| value.typed_value.is_some())
+                .map(broker::DataValue::from)
+        };
+        Ok(vss::DataEntry {
+            data_type,
+            entry_type,
+            change_type,
+            description: metadata.description,
+            comment: non_empty(metadata.comment),
+            deprecation: non_empty(metadata.deprecation),
+            unit: non_empty(metadata.unit),
+            min: value(metadata.min),
+            max: value(metadata.max),
+            allowed: value(metadata.allowed_values),
+            default: value(metadata.default_value),
+            range_policy: broker::RangePolicy::default(),
+        })
+    }
+}
+
+impl broker::RegistrationError {
+    pub fn to_tonic_status(&self, message: String) -> tonic::Status {
+        match self {
+            broker::RegistrationError::ValidationError => tonic::Status::invalid_argument(message),
+            broker::RegistrationError::PermissionDenied => {
+                tonic::Status::permission_denied(message)
+            }
+            broker::RegistrationError::PermissionExpired => tonic::Status::unauthenticated(message),
+            broker::RegistrationError::AlreadyExists => tonic::Status::already_exists(message),
+            broker::RegistrationError::NotFound => tonic::Status::not_found(message),
         }
     }
 }
@@ -428,6 +566,16 @@ impl From<proto::Value> for broker::DataValue {
             Some(proto::value::TypedValue::DoubleArray(array)) => {
                 broker::DataValue::DoubleArray(array.values.clone())
             }
+            Some(proto::value::TypedValue::Timestamp(value)) => timestamp_from_proto(value),
+            Some(proto::value::TypedValue::TimestampArray(array)) => {
+                timestamp_array_from_proto(array)
+            }
+            Some(proto::value::TypedValue::StructValue(value)) => {
+                broker::DataValue::Struct(struct_from_proto(value))
+            }
+            Some(proto::value::TypedValue::StructArray(array)) => {
+                broker::DataValue::StructArray(array.values.iter().map(struct_from_proto).collect())
+            }
             None => todo!(),
         }
     }
@@ -516,11 +664,77 @@ impl From<broker::DataValue> for proto::Value {
                 })),
             },
 
+            broker::DataValue::Timestamp(value) => proto::Value {
+                typed_value: Some(proto::value::TypedValue::Timestamp((*value).into())),
+            },
+
+            broker::DataValue::TimestampArray(values) => proto::Value {
+                typed_value: Some(proto::value::TypedValue::TimestampArray(
+                    proto::TimestampArray {
+                        values: values.iter().map(|value| (*value).into()).collect(),
+                    },
+                )),
+            },
+
+            broker::DataValue::Struct(fields) => proto::Value {
+                typed_value: Some(proto::value::TypedValue::StructValue(struct_to_proto(
+                    fields,
+                ))),
+            },
+
+            broker::DataValue::StructArray(values) => proto::Value {
+                typed_value: Some(proto::value::TypedValue::StructArray(proto::StructArray {
+                    values: values.iter().map(struct_to_proto).collect(),
+                })),
+            },
+
             broker::DataValue::NotAvailable => proto::Value { typed_value: None },
         }
     }
 }
 
+fn timestamp_from_proto(value: &prost_types::Timestamp) -> broker::DataValue {
+    match SystemTime::try_from(*value) {
+        Ok(value) => broker::DataValue::Timestamp(value),
+        Err(_) => broker::DataValue::NotAvailable,
+    }
+}
+
+fn timestamp_array_from_proto(array: &proto::TimestampArray) -> broker::DataValue {
+    match array
+        .values
+        .iter()
+        .map(|value| SystemTime::try_from(*value))
+        .collect()
+    {
+        Ok(values) => broker::DataValue::TimestampArray(values),
+        Err(_) => broker::DataValue::NotAvailable,
+    }
+}
+
+fn struct_to_proto(fields: &StructValue) -> proto::StructValue {
+    proto::StructValue {
+        fields: fields
+            .iter()
+            .map(|(name, value)| (name.clone(), proto::Value::from(value.clone())))
+            .collect(),
+    }
+}
+
+fn struct_from_proto(value: &proto::StructValue) -> StructValue {
+    value
+        .fields
+        .iter()
+        .map(|(name, value)| {
+            let value = match value.typed_value {
+                Some(_) => broker::DataValue::from(value.clone()),
+                None => broker::DataValue::NotAvailable,
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
 impl broker::ActuationError {
     pub fn to_tonic_status(&self, message: String) -> tonic::Status {
         match self {
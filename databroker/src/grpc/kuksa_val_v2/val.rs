@@ -16,20 +16,25 @@ use std::{collections::HashMap, pin::Pin};
 use uuid::Uuid;
 
 use crate::{
+    audit::Transport,
+    authorization::Authorization,
     broker::{
         self, ActuationChange, ActuationProvider, AuthorizedAccess, GetValuesProviderResponse,
         ReadError, RegisterSignalError, SignalProvider, SubscriptionError,
     },
     glob::Matcher,
-    permissions::Permissions,
+    permissions::{PermissionError, Permissions},
+    rate_limit::ClientId,
     types::{DataValue, SignalId, TimeInterval},
+    vss,
 };
 
 use databroker_proto::kuksa::val::v2::{
     self as proto,
     open_provider_stream_request::Action::{
         BatchActuateStreamResponse, GetProviderValueResponse, ProvideActuationRequest,
-        ProvideSignalRequest, ProviderErrorIndication, PublishValuesRequest, UpdateFilterResponse,
+        ProvideSignalRequest, ProviderErrorIndication, PublishValuesRequest, RefreshTokenRequest,
+        UpdateFilterResponse,
     },
     open_provider_stream_response, OpenProviderStreamResponse, PublishValuesResponse,
 };
@@ -48,7 +53,7 @@ use tokio_stream::{
     wrappers::{BroadcastStream, ReceiverStream},
     Stream, StreamExt,
 };
-use tracing::debug;
+use tracing::{debug, info};
 
 const MAX_REQUEST_PATH_LENGTH: usize = 1000;
 
@@ -212,7 +217,9 @@ impl proto::val_server::Val for broker::DataBroker {
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
 
-        let broker = self.authorized_access(&permissions);
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2);
 
         let request = request.into_inner();
 
@@ -257,7 +264,9 @@ impl proto::val_server::Val for broker::DataBroker {
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
 
-        let broker = self.authorized_access(&permissions);
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2);
 
         let requested = request.into_inner().signal_ids;
         let mut response_datapoints = Vec::new();
@@ -328,10 +337,16 @@ impl proto::val_server::Val for broker::DataBroker {
             }
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
+        let client = ClientId::new(&permissions, request.remote_addr());
+        let connection = request.remote_addr();
 
         let request = request.into_inner();
 
-        let broker = self.authorized_access(&permissions);
+        // Follows token refreshes on a provider stream of the same connection
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2)
+            .on_connection(connection);
 
         let signal_paths = request.signal_paths;
         let size = signal_paths.len();
@@ -363,6 +378,10 @@ impl proto::val_server::Val for broker::DataBroker {
             None
         };
 
+        let quota = self
+            .rate_limiter()
+            .acquire_subscription(&client, valid_requests.len())?;
+
         match broker
             .subscribe(
                 valid_requests,
@@ -372,13 +391,20 @@ impl proto::val_server::Val for broker::DataBroker {
             .await
         {
             Ok(stream) => {
-                let stream = convert_to_proto_stream(stream, size);
+                // The quota is released when the stream is dropped
+                let stream = convert_to_proto_stream(stream, size).map(move |item| {
+                    let _quota = &quota;
+                    item
+                });
                 Ok(tonic::Response::new(Box::pin(stream)))
             }
             Err(SubscriptionError::NotFound) => Err(tonic::Status::not_found("Path not found")),
             Err(SubscriptionError::InvalidInput) => Err(tonic::Status::invalid_argument(
                 "No valid id or path specified",
             )),
+            Err(SubscriptionError::PermissionDenied) => {
+                Err(tonic::Status::permission_denied("Permission denied"))
+            }
             Err(SubscriptionError::InternalError) => Err(tonic::Status::internal("Internal Error")),
             Err(SubscriptionError::InvalidBufferSize) => Err(tonic::Status::new(
                 tonic::Code::InvalidArgument,
@@ -413,10 +439,16 @@ impl proto::val_server::Val for broker::DataBroker {
             }
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
+        let client = ClientId::new(&permissions, request.remote_addr());
+        let connection = request.remote_addr();
 
         let request = request.into_inner();
 
-        let broker = self.authorized_access(&permissions);
+        // Follows token refreshes on a provider stream of the same connection
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2)
+            .on_connection(connection);
 
         let signal_ids = request.signal_ids;
         let size = signal_ids.len();
@@ -448,6 +480,10 @@ impl proto::val_server::Val for broker::DataBroker {
             None
         };
 
+        let quota = self
+            .rate_limiter()
+            .acquire_subscription(&client, valid_requests.len())?;
+
         match broker
             .subscribe(
                 valid_requests,
@@ -457,7 +493,11 @@ impl proto::val_server::Val for broker::DataBroker {
             .await
         {
             Ok(stream) => {
-                let stream = convert_to_proto_stream_id(stream, size);
+                // The quota is released when the stream is dropped
+                let stream = convert_to_proto_stream_id(stream, size).map(move |item| {
+                    let _quota = &quota;
+                    item
+                });
                 Ok(tonic::Response::new(Box::pin(stream)))
             }
             Err(SubscriptionError::NotFound) => {
@@ -467,6 +507,10 @@ impl proto::val_server::Val for broker::DataBroker {
                 tonic::Code::InvalidArgument,
                 "No valid id or path specified",
             )),
+            Err(SubscriptionError::PermissionDenied) => Err(tonic::Status::new(
+                tonic::Code::PermissionDenied,
+                "Permission denied",
+            )),
             Err(SubscriptionError::InternalError) => {
                 Err(tonic::Status::new(tonic::Code::Internal, "Internal Error"))
             }
@@ -489,18 +533,24 @@ impl proto::val_server::Val for broker::DataBroker {
             }
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
+        let client = ClientId::new(&permissions, request.remote_addr());
 
         let mut stream = request.into_inner();
 
         let mut shutdown_trigger = self.get_shutdown_trigger();
+        let revocation_list = self.revocation_list().clone();
+        let mut revocations = revocation_list.changed();
 
         // Copy (to move into task below)
         let broker = self.clone();
+        let rate_limiter = self.rate_limiter().clone();
 
         // Listening on stream
         let join_handle = tokio::spawn(async move {
             let permissions = permissions;
-            let broker = broker.authorized_access(&permissions);
+            let broker = broker
+                .authorized_access(&permissions)
+                .via(Transport::KuksaValV2);
             loop {
                 select! {
                     message = stream.message() => {
@@ -508,6 +558,7 @@ impl proto::val_server::Val for broker::DataBroker {
                             Ok(request) => {
                                 match request {
                                     Some(actuator_request) => {
+                                        rate_limiter.check_actuation(&client, 1)?;
                                         let value = actuator_request.value
                                         .ok_or_else(|| tonic::Status::invalid_argument("No value provided"))?;
 
@@ -547,6 +598,12 @@ impl proto::val_server::Val for broker::DataBroker {
                             },
                         }
                     },
+                    Ok(()) = revocations.changed() => {
+                        if revocation_list.is_revoked(&permissions) {
+                            info!("client: token revoked, closing stream");
+                            return Err(tonic::Status::unauthenticated("Token has been revoked"));
+                        }
+                    },
                     _ = shutdown_trigger.recv() => {
                         debug!("client: shutdown received");
                         break;
@@ -589,7 +646,11 @@ impl proto::val_server::Val for broker::DataBroker {
             .get::<Permissions>()
             .ok_or(tonic::Status::unauthenticated("Unauthenticated"))?
             .clone();
-        let broker = self.authorized_access(&permissions);
+        let client = ClientId::new(&permissions, request.remote_addr());
+        self.rate_limiter().check_actuation(&client, 1)?;
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2);
 
         let actuator_request = request.into_inner();
         let value = actuator_request
@@ -656,8 +717,13 @@ impl proto::val_server::Val for broker::DataBroker {
             }
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
-        let broker = self.authorized_access(&permissions);
+        let client = ClientId::new(&permissions, request.remote_addr());
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2);
         let actuate_requests = request.into_inner().actuate_requests;
+        self.rate_limiter()
+            .check_actuation(&client, actuate_requests.len())?;
 
         let mut actuation_changes: Vec<ActuationChange> = vec![];
         for actuate_request in actuate_requests {
@@ -714,7 +780,9 @@ impl proto::val_server::Val for broker::DataBroker {
             }
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
-        let broker = self.authorized_access(&permissions);
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2);
 
         let metadata_request = request.into_inner();
 
@@ -743,6 +811,247 @@ impl proto::val_server::Val for broker::DataBroker {
         }
     }
 
+    type SubscribeMetadataStream = Pin<
+        Box<
+            dyn Stream<Item = Result<proto::SubscribeMetadataResponse, tonic::Status>>
+                + Send
+                + Sync
+                + 'static,
+        >,
+    >;
+
+    // Returns (GRPC error code):
+    //   UNAUTHENTICATED if no credentials provided or credentials has expired
+    //   INVALID_ARGUMENT if the provided path or wildcard is wrong.
+    //
+    async fn subscribe_metadata(
+        &self,
+        request: tonic::Request<proto::SubscribeMetadataRequest>,
+    ) -> Result<tonic::Response<Self::SubscribeMetadataStream>, tonic::Status> {
+        debug!(?request);
+        let permissions = match request.extensions().get::<Permissions>() {
+            Some(permissions) => {
+                debug!(?permissions);
+                permissions.clone()
+            }
+            None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
+        };
+
+        let Ok(matcher) = Matcher::new(&request.into_inner().root) else {
+            return Err(tonic::Status::invalid_argument("Invalid Pattern Argument"));
+        };
+
+        let mut metadata_changes = self.subscribe_metadata_changes();
+        let mut shutdown_rx = self.get_shutdown_trigger();
+        let revocation_list = self.revocation_list().clone();
+        let mut revocations = revocation_list.changed();
+
+        let stream = async_stream::stream! {
+            loop {
+                tokio::select! {
+                    change = metadata_changes.recv() => {
+                        let (change_type, metadata) = match change {
+                            Ok(broker::MetadataChange::Added(metadata)) => {
+                                (proto::MetadataChangeType::Added, metadata)
+                            }
+                            Ok(broker::MetadataChange::Removed(metadata)) => {
+                                (proto::MetadataChangeType::Removed, metadata)
+                            }
+                            Ok(broker::MetadataChange::Changed(metadata)) => {
+                                (proto::MetadataChangeType::Changed, metadata)
+                            }
+                            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                                yield Err(tonic::Status::data_loss(format!(
+                                    "Subscriber too slow, missed {missed} metadata changes"
+                                )));
+                                break;
+                            }
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        };
+                        if !matcher.is_match(&metadata.glob_path) {
+                            continue;
+                        }
+                        match permissions.can_read(&metadata.path) {
+                            Ok(()) => {
+                                yield Ok(proto::SubscribeMetadataResponse {
+                                    change: change_type as i32,
+                                    metadata: Some(proto::Metadata::from(&metadata)),
+                                });
+                            }
+                            Err(PermissionError::Denied) => {}
+                            Err(PermissionError::Expired) => {
+                                yield Err(tonic::Status::unauthenticated("Permission expired"));
+                                break;
+                            }
+                        }
+                    }
+                    Ok(()) = revocations.changed() => {
+                        if revocation_list.is_revoked(&permissions) {
+                            info!("metadata subscription: token revoked, closing stream");
+                            yield Err(tonic::Status::unauthenticated("Token has been revoked"));
+                            break;
+                        }
+                    }
+                    _ = shutdown_rx.recv() => {
+                        yield Err(tonic::Status::unavailable("Databroker shutting down"));
+                        break;
+                    }
+                }
+            }
+        };
+
+        Ok(tonic::Response::new(
+            Box::pin(stream) as Self::SubscribeMetadataStream
+        ))
+    }
+
+    // Returns (GRPC error code):
+    //   NOT_FOUND if the specified root branch does not exist.
+    //   UNAUTHENTICATED if no credentials provided or credentials has expired
+    //   INVALID_ARGUMENT if the provided path or wildcard is wrong.
+    //
+    async fn export_vss(
+        &self,
+        request: tonic::Request<proto::ExportVssRequest>,
+    ) -> Result<tonic::Response<proto::ExportVssResponse>, tonic::Status> {
+        debug!(?request);
+        let permissions = match request.extensions().get::<Permissions>() {
+            Some(permissions) => {
+                debug!(?permissions);
+                permissions.clone()
+            }
+            None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
+        };
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2);
+
+        let export_request = request.into_inner();
+
+        let Ok(matcher) = Matcher::new(&export_request.root) else {
+            return Err(tonic::Status::invalid_argument("Invalid Pattern Argument"));
+        };
+        let mut entries = Vec::new();
+        broker
+            .for_each_entry(|entry| {
+                let metadata = entry.metadata();
+                if matcher.is_match(&metadata.glob_path) {
+                    // Values of signals the caller may not read are left out
+                    let value = match export_request.include_values {
+                        true => entry
+                            .datapoint()
+                            .ok()
+                            .map(|datapoint| datapoint.value.clone()),
+                        false => None,
+                    };
+                    entries.push((metadata.path.clone(), vss::DataEntry::from(metadata), value));
+                }
+            })
+            .await;
+        if entries.is_empty() {
+            return Err(tonic::Status::not_found(
+                "Specified root branch does not exist",
+            ));
+        }
+        // Branches are created along the way, so export in tree order
+        entries.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+        let mut export = vss::VssExport::default();
+        for (path, entry, value) in &entries {
+            if let Err(err) = export.add_entry(path, entry, value.as_ref()) {
+                return Err(tonic::Status::internal(format!(
+                    "Failed to export VSS tree: {err}"
+                )));
+            }
+        }
+        match serde_json::to_string_pretty(&export.into_json()) {
+            Ok(vss) => Ok(tonic::Response::new(proto::ExportVssResponse { vss })),
+            Err(err) => Err(tonic::Status::internal(format!(
+                "Failed to serialize VSS tree: {err}"
+            ))),
+        }
+    }
+
+    // Returns (GRPC error code):
+    //   ALREADY_EXISTS if a signal with the same path already exists.
+    //   PERMISSION_DENIED if the `create` permission for the path is missing.
+    //   UNAUTHENTICATED if no credentials provided or credentials has expired
+    //   INVALID_ARGUMENT
+    //       - if the path is not a valid signal path
+    //       - if the data type or entry type is missing or not supported
+    //       - if the allowed values do not match the data type
+    //
+    async fn register_metadata(
+        &self,
+        request: tonic::Request<proto::RegisterMetadataRequest>,
+    ) -> Result<tonic::Response<proto::RegisterMetadataResponse>, tonic::Status> {
+        debug!(?request);
+        let permissions = match request.extensions().get::<Permissions>() {
+            Some(permissions) => {
+                debug!(?permissions);
+                permissions.clone()
+            }
+            None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
+        };
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2);
+
+        let Some(metadata) = request.into_inner().metadata else {
+            return Err(tonic::Status::invalid_argument("No metadata provided"));
+        };
+        if metadata.path.len() > MAX_REQUEST_PATH_LENGTH {
+            return Err(tonic::Status::invalid_argument(
+                "The provided path is too long",
+            ));
+        }
+        let path = metadata.path.clone();
+        let entry = vss::DataEntry::try_from(metadata)?;
+
+        match broker.register_entry(path.clone(), entry).await {
+            Ok(metadata) => {
+                info!("Registered signal {}", metadata.path);
+                Ok(tonic::Response::new(proto::RegisterMetadataResponse {
+                    metadata: Some(proto::Metadata::from(&metadata)),
+                }))
+            }
+            Err(err) => Err(err.to_tonic_status(format!("Failed to register {path}: {err:?}"))),
+        }
+    }
+
+    // Returns (GRPC error code):
+    //   NOT_FOUND if the signal does not exist.
+    //   PERMISSION_DENIED
+    //       - if the `create` permission for the path is missing.
+    //       - if the signal was not registered at runtime (e.g. loaded from VSS)
+    //   UNAUTHENTICATED if no credentials provided or credentials has expired
+    //
+    async fn unregister_metadata(
+        &self,
+        request: tonic::Request<proto::UnregisterMetadataRequest>,
+    ) -> Result<tonic::Response<proto::UnregisterMetadataResponse>, tonic::Status> {
+        debug!(?request);
+        let permissions = match request.extensions().get::<Permissions>() {
+            Some(permissions) => {
+                debug!(?permissions);
+                permissions.clone()
+            }
+            None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
+        };
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2);
+
+        let id = get_signal(request.into_inner().signal_id, &broker).await?;
+
+        match broker.remove_entry(id).await {
+            Ok(metadata) => {
+                info!("Unregistered signal {}", metadata.path);
+                Ok(tonic::Response::new(proto::UnregisterMetadataResponse {}))
+            }
+            Err(err) => Err(err.to_tonic_status(format!("Failed to unregister signal: {err:?}"))),
+        }
+    }
+
     // Returns (GRPC error code):
     //   NOT_FOUND if any of the signals are non-existant.
     //   PERMISSION_DENIED
@@ -768,7 +1077,12 @@ impl proto::val_server::Val for broker::DataBroker {
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
 
-        let broker = self.authorized_access(&permissions);
+        let client = ClientId::new(&permissions, request.remote_addr());
+        self.rate_limiter().check_publish(&client, 1)?;
+
+        let broker = self
+            .authorized_access(&permissions)
+            .via(Transport::KuksaValV2);
 
         let request = request.into_inner();
 
@@ -794,6 +1108,7 @@ impl proto::val_server::Val for broker::DataBroker {
                 max: None,
                 min: None,
                 unit: None,
+                range_policy: None,
             },
         );
 
@@ -854,23 +1169,38 @@ impl proto::val_server::Val for broker::DataBroker {
             }
             None => return Err(tonic::Status::unauthenticated("Unauthenticated")),
         };
+        let authorization = request.extensions().get::<Authorization>().cloned();
+        let client = ClientId::new(&permissions, request.remote_addr());
+        let connection = request.remote_addr();
 
         let mut stream = request.into_inner();
 
         let mut shutdown_trigger = self.get_shutdown_trigger();
+        let revocation_list = self.revocation_list().clone();
+        let mut revocations = revocation_list.changed();
 
         // Copy (to move into task below)
         let broker = self.clone();
+        let rate_limiter = self.rate_limiter().clone();
         // Create stream (to be returned)
         let (response_stream_sender, response_stream_receiver) = mpsc::channel(10);
         let (get_value_sender, _) = broadcast::channel(10);
 
         // Listening on stream
         tokio::spawn(async move {
-            let permissions = permissions;
-            let broker = broker.authorized_access(&permissions);
+            let mut permissions = permissions;
+            let databroker = broker;
             let mut local_provider_uuid: Option<Uuid> = None;
+            // Refreshing the token affects only what this stream provides,
+            // and the subscriptions made on the same connection
+            let session = Uuid::new_v4();
             loop {
+                // Permissions may be replaced by a refreshed token
+                let broker = databroker
+                    .authorized_access(&permissions)
+                    .via(Transport::KuksaValV2)
+                    .in_session(session)
+                    .on_connection(connection);
                 select! {
                     message = stream.message() => {
                         match message {
@@ -886,7 +1216,11 @@ impl proto::val_server::Val for broker::DataBroker {
                                                 }
                                             },
                                             Some(PublishValuesRequest(publish_values_request)) => {
-                                                if let Some(provide_uuid) = local_provider_uuid {
+                                                if let Err(err) = rate_limiter.check_publish(&client, publish_values_request.data_points.len()) {
+                                                    if let Err(err) = response_stream_sender.send(Err(err.into())).await {
+                                                        debug!("Failed to send error response: {}", err);
+                                                    }
+                                                } else if let Some(provide_uuid) = local_provider_uuid {
                                                     let response = publish_values(&broker, provide_uuid, &publish_values_request).await;
                                                     if let Some(value) = response {
                                                         if let Err(err) = response_stream_sender.send(value).await {
@@ -980,6 +1314,27 @@ impl proto::val_server::Val for broker::DataBroker {
                                                     debug!("Failed to send error response: {}", err);
                                                 }
                                             }
+                                            Some(RefreshTokenRequest(refresh_token_request)) => {
+                                                match refresh_token(&broker, authorization.as_ref(), &refresh_token_request).await {
+                                                    Ok(refreshed_permissions) => {
+                                                        permissions = refreshed_permissions;
+                                                        let response = OpenProviderStreamResponse {
+                                                            action: Some(open_provider_stream_response::Action::RefreshTokenResponse(
+                                                                proto::RefreshTokenResponse {},
+                                                            )),
+                                                        };
+                                                        if let Err(err) = response_stream_sender.send(Ok(response)).await {
+                                                            debug!("Failed to send response: {}", err)
+                                                        }
+                                                    }
+                                                    Err(status) => {
+                                                        if let Err(err) = response_stream_sender.send(Err(status)).await {
+                                                            debug!("Failed to send error response: {}", err);
+                                                        }
+                                                        break;
+                                                    }
+                                                }
+                                            }
                                             None => {
 
                                             },
@@ -997,6 +1352,15 @@ impl proto::val_server::Val for broker::DataBroker {
                             },
                         }
                     },
+                    Ok(()) = revocations.changed() => {
+                        if revocation_list.is_revoked(&permissions) {
+                            info!("provider: token revoked, closing stream");
+                            if let Err(err) = response_stream_sender.send(Err(tonic::Status::unauthenticated("Token has been revoked"))).await {
+                                debug!("Failed to send error response: {}", err);
+                            }
+                            break;
+                        }
+                    },
                     _ = shutdown_trigger.recv() => {
                         debug!("provider: shutdown received");
                         break;
@@ -1023,6 +1387,26 @@ impl proto::val_server::Val for broker::DataBroker {
     }
 }
 
+async fn refresh_token(
+    broker: &AuthorizedAccess<'_, '_>,
+    authorization: Option<&Authorization>,
+    request: &proto::RefreshTokenRequest,
+) -> Result<Permissions, tonic::Status> {
+    let Some(authorization) = authorization else {
+        return Err(tonic::Status::unauthenticated("Unauthenticated"));
+    };
+    let permissions = authorization
+        .permissions_from_token(&request.token)
+        .map_err(|err| tonic::Status::unauthenticated(err.to_string()))?;
+    match broker.refresh_permissions(&permissions).await {
+        Ok(()) => Ok(permissions),
+        Err(PermissionError::Denied) => Err(tonic::Status::unauthenticated(
+            "Token was issued to another subject",
+        )),
+        Err(PermissionError::Expired) => Err(tonic::Status::unauthenticated("Token has expired")),
+    }
+}
+
 async fn provide_actuation(
     broker: &AuthorizedAccess<'_, '_>,
     request: &databroker_proto::kuksa::val::v2::ProvideActuationRequest,
@@ -1207,6 +1591,7 @@ async fn publish_values(
                     min: None,
                     max: None,
                     unit: None,
+                    range_policy: None,
                 },
             )
         })
@@ -1329,7 +1714,7 @@ mod tests {
     use databroker_proto::kuksa::val::v2::val_server::Val;
     use proto::open_provider_stream_response::Action::{
         BatchActuateStreamRequest, GetProviderValueRequest, ProvideActuationResponse,
-        ProvideSignalResponse, PublishValuesResponse, UpdateFilterRequest,
+        ProvideSignalResponse, PublishValuesResponse, RefreshTokenResponse, UpdateFilterRequest,
     };
     use proto::{
         open_provider_stream_request, BatchActuateRequest, OpenProviderStreamRequest,
@@ -1496,14 +1881,12 @@ mod tests {
         let entry_id = authorized_access
             .add_entry(
                 "test.datapoint1".to_string(),
-                broker::DataType::Int32,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Some Description hat Does Not Matter".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Int32,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Some Description hat Does Not Matter".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -2028,14 +2411,12 @@ mod tests {
         let entry_id = authorized_access
             .add_entry(
                 "test.datapoint1".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -2077,6 +2458,52 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_publish_value_rate_limited() {
+        let broker = DataBroker::default().with_rate_limits(crate::rate_limit::RateLimits {
+            publish_rate: Some(2),
+            ..Default::default()
+        });
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
+
+        let entry_id = authorized_access
+            .add_entry(
+                "test.datapoint1".to_owned(),
+                vss::DataEntry::new(
+                    broker::DataType::Int32,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        for i in 0..3 {
+            let mut publish_value_request = tonic::Request::new(proto::PublishValueRequest {
+                signal_id: Some(proto::SignalId {
+                    signal: Some(proto::signal_id::Signal::Id(entry_id)),
+                }),
+                data_point: Some(proto::Datapoint {
+                    timestamp: None,
+                    value: Some(proto::Value {
+                        typed_value: Some(proto::value::TypedValue::Int32(i)),
+                    }),
+                }),
+            });
+            publish_value_request
+                .extensions_mut()
+                .insert(permissions::ALLOW_ALL.clone());
+
+            let result = broker.publish_value(publish_value_request).await;
+            if i < 2 {
+                assert!(result.is_ok(), "publish {i} should be accepted");
+            } else {
+                assert_eq!(result.unwrap_err().code(), tonic::Code::ResourceExhausted);
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_publish_value_signal_id_not_found() {
         let broker = DataBroker::default();
@@ -2085,14 +2512,12 @@ mod tests {
         let _entry_id = authorized_access
             .add_entry(
                 "test.datapoint1".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -2144,14 +2569,16 @@ mod tests {
         let entry_id = authorized_access
             .add_entry(
                 "test.datapoint1".to_owned(),
-                broker::DataType::Uint8,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                Some(broker::types::DataValue::Uint32(3)), // min
-                Some(broker::types::DataValue::Uint32(26)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(broker::types::DataValue::Uint32(3)),
+                    max: Some(broker::types::DataValue::Uint32(26)),
+                    ..vss::DataEntry::new(
+                        broker::DataType::Uint8,
+                        broker::ChangeType::OnChange,
+                        broker::EntryType::Sensor,
+                        "Test datapoint 1".to_owned(),
+                    )
+                },
             )
             .await
             .unwrap();
@@ -2302,14 +2729,12 @@ mod tests {
         let entry_id = authorized_access
             .add_entry(
                 "test.datapoint1".to_string(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Some Description that Does Not Matter".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Some Description that Does Not Matter".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -2450,14 +2875,12 @@ mod tests {
         let entry_id = authorized_access
             .add_entry(
                 "test.datapoint1".to_string(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Some Description that Does Not Matter".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Some Description that Does Not Matter".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -2564,14 +2987,12 @@ mod tests {
         let entry_id = authorized_access
             .add_entry(
                 "test.datapoint1".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .unwrap();
@@ -2636,6 +3057,9 @@ mod tests {
                                 Some(GetProviderValueRequest(_)) => {
                                     panic!("Should not happen")
                                 }
+                                Some(RefreshTokenResponse(_)) => {
+                                    panic!("Should not happen")
+                                }
                                 None => {
                                     panic!("Should not happen")
                                 }
@@ -2661,14 +3085,16 @@ mod tests {
         authorized_access
             .add_entry(
                 "test.datapoint1".to_owned(),
-                broker::DataType::Int32,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                Some(broker::types::DataValue::Int32(-7)), // min
-                Some(broker::types::DataValue::Int32(19)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(broker::types::DataValue::Int32(-7)),
+                    max: Some(broker::types::DataValue::Int32(19)),
+                    ..vss::DataEntry::new(
+                        broker::DataType::Int32,
+                        broker::ChangeType::OnChange,
+                        broker::EntryType::Sensor,
+                        "Test datapoint 1".to_owned(),
+                    )
+                },
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2709,6 +3135,357 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_list_metadata_comment_deprecation_default() {
+        let broker = DataBroker::default();
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
+
+        authorized_access
+            .add_entry(
+                "Vehicle.Cabin.DoorCount".to_owned(),
+                vss::DataEntry {
+                    comment: Some("Includes the trunk.".to_owned()),
+                    deprecation: Some("v5.0 - use Vehicle.Body.DoorCount".to_owned()),
+                    default: Some(broker::types::DataValue::Uint32(4)),
+                    ..vss::DataEntry::new(
+                        broker::DataType::Uint8,
+                        broker::ChangeType::Static,
+                        broker::EntryType::Attribute,
+                        "Number of doors in vehicle.".to_owned(),
+                    )
+                },
+            )
+            .await
+            .expect("Register datapoint should succeed");
+
+        let mut data_req = tonic::Request::new(proto::ListMetadataRequest {
+            root: "Vehicle.Cabin.DoorCount".to_owned(),
+            filter: "".to_owned(),
+        });
+        data_req
+            .extensions_mut()
+            .insert(permissions::ALLOW_ALL.clone());
+
+        match proto::val_server::Val::list_metadata(&broker, data_req)
+            .await
+            .map(|res| res.into_inner())
+        {
+            Ok(list_response) => {
+                let metadata = list_response.metadata.first().unwrap();
+                assert_eq!(metadata.comment, "Includes the trunk.");
+                assert_eq!(metadata.deprecation, "v5.0 - use Vehicle.Body.DoorCount");
+                assert_eq!(
+                    metadata.default_value,
+                    Some(Value {
+                        typed_value: Some(proto::value::TypedValue::Uint32(4)),
+                    })
+                );
+            }
+            Err(_status) => panic!("failed to execute list metadata request"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_vss() {
+        let broker = DataBroker::default();
+        let timestamp = std::time::SystemTime::now();
+
+        broker::tests::helper_add_int32(&broker, "Vehicle.Speed", 42, timestamp)
+            .await
+            .expect("Shall succeed");
+        broker::tests::helper_add_int32(&broker, "Vehicle.Cabin.Temperature", 21, timestamp)
+            .await
+            .expect("Shall succeed");
+
+        let permissions = permissions::PermissionBuilder::new()
+            .add_read_permission(permissions::Permission::Glob("Vehicle.Speed".to_string()))
+            .build()
+            .expect("Oops!");
+        let mut request = tonic::Request::new(proto::ExportVssRequest {
+            root: "Vehicle".to_owned(),
+            include_values: true,
+        });
+        request.extensions_mut().insert(permissions);
+
+        let response = proto::val_server::Val::export_vss(&broker, request)
+            .await
+            .expect("Export should succeed")
+            .into_inner();
+        let vss: serde_json::Value = serde_json::from_str(&response.vss).unwrap();
+        let children = &vss["Vehicle"]["children"];
+        assert_eq!(children["Speed"]["datatype"], "int32");
+        assert_eq!(children["Speed"]["min"], -500);
+        assert_eq!(children["Speed"]["x-kuksa-value"], 42);
+        // Metadata is exported, but not the value which may not be read
+        let temperature = &children["Cabin"]["children"]["Temperature"];
+        assert_eq!(temperature["type"], "sensor");
+        assert!(temperature.get("x-kuksa-value").is_none());
+
+        let entries = crate::vss::parse_vss_from_str(&response.vss).unwrap();
+        assert_eq!(
+            entries.keys().collect::<Vec<_>>(),
+            vec!["Vehicle.Cabin.Temperature", "Vehicle.Speed"]
+        );
+
+        let mut request = tonic::Request::new(proto::ExportVssRequest {
+            root: "Vehicle.Body".to_owned(),
+            include_values: false,
+        });
+        request
+            .extensions_mut()
+            .insert(permissions::ALLOW_ALL.clone());
+        match proto::val_server::Val::export_vss(&broker, request).await {
+            Ok(_) => panic!("Export of an unknown branch should fail"),
+            Err(status) => assert_eq!(status.code(), tonic::Code::NotFound),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_unregister_metadata() {
+        let broker = DataBroker::default();
+        let permissions = permissions::PermissionBuilder::new()
+            .add_read_permission(permissions::Permission::All)
+            .add_create_permission(permissions::Permission::Glob(
+                "Vehicle.Derived.*".to_owned(),
+            ))
+            .build()
+            .expect("Oops!");
+
+        let register_request = |path: &str| {
+            let mut request = tonic::Request::new(proto::RegisterMetadataRequest {
+                metadata: Some(proto::Metadata {
+                    path: path.to_owned(),
+                    data_type: proto::DataType::Uint8 as i32,
+                    entry_type: proto::EntryType::Sensor as i32,
+                    description: "Number of occupied seats".to_owned(),
+                    max: Some(Value {
+                        typed_value: Some(proto::value::TypedValue::Uint32(5)),
+                    }),
+                    ..Default::default()
+                }),
+            });
+            request.extensions_mut().insert(permissions.clone());
+            request
+        };
+
+        let metadata = proto::val_server::Val::register_metadata(
+            &broker,
+            register_request("Vehicle.Derived.OccupiedSeats"),
+        )
+        .await
+        .expect("Register should succeed")
+        .into_inner()
+        .metadata
+        .unwrap();
+        assert_eq!(metadata.path, "Vehicle.Derived.OccupiedSeats");
+        assert_eq!(metadata.data_type, proto::DataType::Uint8 as i32);
+
+        let id = broker
+            .authorized_access(&permissions::ALLOW_ALL)
+            .get_id_by_path("Vehicle.Derived.OccupiedSeats")
+            .await;
+        assert_eq!(id, Some(metadata.id));
+
+        for (path, code) in [
+            ("Vehicle.Derived.OccupiedSeats", tonic::Code::AlreadyExists),
+            ("Vehicle.OccupiedSeats", tonic::Code::PermissionDenied),
+            ("Vehicle..OccupiedSeats", tonic::Code::InvalidArgument),
+        ] {
+            match proto::val_server::Val::register_metadata(&broker, register_request(path)).await {
+                Ok(_) => panic!("Register of {path} should fail"),
+                Err(status) => assert_eq!(status.code(), code, "{path}"),
+            }
+        }
+
+        let mut request = tonic::Request::new(proto::UnregisterMetadataRequest {
+            signal_id: Some(proto::SignalId {
+                signal: Some(proto::signal_id::Signal::Id(metadata.id)),
+            }),
+        });
+        request.extensions_mut().insert(permissions.clone());
+        proto::val_server::Val::unregister_metadata(&broker, request)
+            .await
+            .expect("Unregister should succeed");
+
+        let mut request = tonic::Request::new(proto::UnregisterMetadataRequest {
+            signal_id: Some(proto::SignalId {
+                signal: Some(proto::signal_id::Signal::Path(
+                    "Vehicle.Derived.OccupiedSeats".to_owned(),
+                )),
+            }),
+        });
+        request.extensions_mut().insert(permissions.clone());
+        match proto::val_server::Val::unregister_metadata(&broker, request).await {
+            Ok(_) => panic!("Unregister of a removed signal should fail"),
+            Err(status) => assert_eq!(status.code(), tonic::Code::NotFound),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_metadata() {
+        let broker = DataBroker::default();
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
+
+        let mut request = tonic::Request::new(proto::SubscribeMetadataRequest {
+            root: "Vehicle.Derived".to_owned(),
+        });
+        request
+            .extensions_mut()
+            .insert(permissions::ALLOW_ALL.clone());
+        let mut stream = proto::val_server::Val::subscribe_metadata(&broker, request)
+            .await
+            .expect("Subscribe should succeed")
+            .into_inner();
+
+        let new_entry = |description: &str| crate::vss::DataEntry {
+            data_type: broker::DataType::Float,
+            entry_type: broker::EntryType::Sensor,
+            change_type: broker::ChangeType::Continuous,
+            description: description.to_owned(),
+            comment: None,
+            deprecation: None,
+            unit: None,
+            min: None,
+            max: None,
+            allowed: None,
+            default: None,
+            range_policy: broker::RangePolicy::default(),
+        };
+        // Not matching the subscribed root
+        authorized_access
+            .register_entry("Vehicle.Speed".to_owned(), new_entry("Speed"))
+            .await
+            .expect("Register entry should succeed");
+        let metadata = authorized_access
+            .register_entry(
+                "Vehicle.Derived.AverageSpeed".to_owned(),
+                new_entry("Average speed"),
+            )
+            .await
+            .expect("Register entry should succeed");
+        let updates = [
+            broker::EntryUpdate {
+                unit: Some("km/h".to_owned()),
+                ..Default::default()
+            },
+            broker::EntryUpdate {
+                min: Some(Some(broker::DataValue::Float(0.0))),
+                ..Default::default()
+            },
+            broker::EntryUpdate {
+                max: Some(Some(broker::DataValue::Float(250.0))),
+                ..Default::default()
+            },
+            broker::EntryUpdate {
+                allowed: Some(Some(broker::DataValue::FloatArray(vec![0.0, 250.0]))),
+                ..Default::default()
+            },
+            broker::EntryUpdate {
+                description: Some("Average vehicle speed".to_owned()),
+                ..Default::default()
+            },
+            // Not a change, so nothing is sent
+            broker::EntryUpdate {
+                unit: Some("km/h".to_owned()),
+                ..Default::default()
+            },
+        ];
+        for update in updates {
+            authorized_access
+                .update_entries([(metadata.id, update)])
+                .await
+                .expect("Update should succeed");
+        }
+        authorized_access
+            .remove_entry(metadata.id)
+            .await
+            .expect("Remove entry should succeed");
+
+        let mut changes = Vec::new();
+        for _ in 0..7 {
+            let response = stream
+                .next()
+                .await
+                .expect("Stream should not end")
+                .expect("Change should be received");
+            let metadata = response.metadata.unwrap();
+            assert_eq!(metadata.path, "Vehicle.Derived.AverageSpeed");
+            changes.push((response.change, metadata));
+        }
+        let float = |value| {
+            Some(proto::Value {
+                typed_value: Some(proto::value::TypedValue::Float(value)),
+            })
+        };
+
+        let (change, added) = &changes[0];
+        assert_eq!(*change, proto::MetadataChangeType::Added as i32);
+        assert_eq!(added.unit, "");
+        assert_eq!(added.description, "Average speed");
+
+        let (change, unit_changed) = &changes[1];
+        assert_eq!(*change, proto::MetadataChangeType::Changed as i32);
+        assert_eq!(unit_changed.unit, "km/h");
+        assert_eq!(unit_changed.min, None);
+
+        let (change, min_changed) = &changes[2];
+        assert_eq!(*change, proto::MetadataChangeType::Changed as i32);
+        assert_eq!(min_changed.min, float(0.0));
+        assert_eq!(min_changed.max, None);
+
+        let (change, max_changed) = &changes[3];
+        assert_eq!(*change, proto::MetadataChangeType::Changed as i32);
+        assert_eq!(max_changed.max, float(250.0));
+        assert_eq!(max_changed.allowed_values, None);
+
+        let (change, allowed_changed) = &changes[4];
+        assert_eq!(*change, proto::MetadataChangeType::Changed as i32);
+        assert_eq!(
+            allowed_changed.allowed_values,
+            Some(proto::Value {
+                typed_value: Some(proto::value::TypedValue::FloatArray(proto::FloatArray {
+                    values: vec![0.0, 250.0]
+                })),
+            })
+        );
+        assert_eq!(allowed_changed.description, "Average speed");
+
+        let (change, description_changed) = &changes[5];
+        assert_eq!(*change, proto::MetadataChangeType::Changed as i32);
+        assert_eq!(description_changed.description, "Average vehicle speed");
+
+        let (change, removed) = &changes[6];
+        assert_eq!(*change, proto::MetadataChangeType::Removed as i32);
+        assert_eq!(removed.unit, "km/h");
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_metadata_token_revoked() {
+        let broker = DataBroker::default();
+        let permissions = Permissions::builder()
+            .subject("test-client")
+            .token_id("token-1")
+            .add_read_permission(permissions::Permission::All)
+            .build()
+            .unwrap();
+
+        let mut request = tonic::Request::new(proto::SubscribeMetadataRequest {
+            root: "Vehicle".to_owned(),
+        });
+        request.extensions_mut().insert(permissions);
+        let mut stream = proto::val_server::Val::subscribe_metadata(&broker, request)
+            .await
+            .expect("Subscribe should succeed")
+            .into_inner();
+
+        broker.revocation_list().revoke_token_id("token-1");
+        match stream.next().await {
+            Some(Err(status)) => assert_eq!(status.code(), tonic::Code::Unauthenticated),
+            other => panic!("expected the stream to be closed with an error, got {other:?}"),
+        }
+        assert!(stream.next().await.is_none());
+    }
+
     #[tokio::test]
     async fn test_list_metadata_using_wildcard() {
         let broker = DataBroker::default();
@@ -2717,14 +3494,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "test.datapoint1".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2732,14 +3507,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "test.branch.datapoint2".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test branch datapoint 2".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test branch datapoint 2".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2854,14 +3627,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "test.datapoint1".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Sensor,
-                "Test datapoint 1".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Sensor,
+                    "Test datapoint 1".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2933,14 +3704,16 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.Cabin.Infotainment.Navigation.Volume".to_owned(),
-                broker::DataType::Uint8,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                Some(broker::types::DataValue::Uint32(0)), // min
-                Some(broker::types::DataValue::Uint32(100)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(broker::types::DataValue::Uint32(0)),
+                    max: Some(broker::types::DataValue::Uint32(100)),
+                    ..vss::DataEntry::new(
+                        broker::DataType::Uint8,
+                        broker::ChangeType::OnChange,
+                        broker::EntryType::Actuator,
+                        "Some funny description".to_owned(),
+                    )
+                },
             )
             .await
             .expect("Register datapoint should succeed");
@@ -2986,6 +3759,86 @@ mod tests {
         )
     }
 
+    #[tokio::test]
+    async fn test_actuate_out_of_range_clamped() {
+        let broker = DataBroker::default();
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
+
+        let vss_id = authorized_access
+            .add_entry(
+                "Vehicle.Cabin.Infotainment.Navigation.Volume".to_owned(),
+                vss::DataEntry {
+                    min: Some(broker::types::DataValue::Uint32(0)),
+                    max: Some(broker::types::DataValue::Uint32(100)),
+                    ..vss::DataEntry::new(
+                        broker::DataType::Uint8,
+                        broker::ChangeType::OnChange,
+                        broker::EntryType::Actuator,
+                        "Some funny description".to_owned(),
+                    )
+                },
+            )
+            .await
+            .expect("Register datapoint should succeed");
+        authorized_access
+            .update_entries([(
+                vss_id,
+                broker::EntryUpdate {
+                    range_policy: Some(broker::RangePolicy::Clamp),
+                    ..Default::default()
+                },
+            )])
+            .await
+            .expect("Setting the range policy should succeed");
+
+        let (sender, mut receiver) = mpsc::channel(10);
+        let actuation_provider = Provider {
+            sender,
+            receiver: None,
+        };
+        authorized_access
+            .provide_actuation(vec![vss_id], Box::new(actuation_provider))
+            .await
+            .expect("Registering a new Actuation Provider should succeed");
+
+        let mut request = tonic::Request::new(ActuateRequest {
+            signal_id: Some(SignalId {
+                signal: Some(proto::signal_id::Signal::Path(
+                    "Vehicle.Cabin.Infotainment.Navigation.Volume".to_string(),
+                )),
+            }),
+            value: Some(Value {
+                typed_value: Some(proto::value::TypedValue::Uint32(200)),
+            }),
+        });
+
+        request
+            .extensions_mut()
+            .insert(permissions::ALLOW_ALL.clone());
+
+        let result_response = proto::val_server::Val::actuate(&broker, request).await;
+        assert!(result_response.is_ok());
+
+        // The provider receives the value clamped to max
+        let response = receiver
+            .recv()
+            .await
+            .expect("Option should be Some")
+            .expect("Result should be Ok");
+        match response.action {
+            Some(BatchActuateStreamRequest(batch)) => {
+                assert_eq!(batch.actuate_requests.len(), 1);
+                assert_eq!(
+                    batch.actuate_requests[0].value,
+                    Some(Value {
+                        typed_value: Some(proto::value::TypedValue::Uint32(100)),
+                    })
+                );
+            }
+            _ => panic!("Expected a BatchActuateStreamRequest"),
+        }
+    }
+
     #[tokio::test]
     async fn test_actuate_signal_not_found() {
         let broker = DataBroker::default();
@@ -3018,14 +3871,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.ABS.IsEnabled".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3061,14 +3912,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.ABS.IsEnabled".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3119,14 +3968,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.ABS.IsEnabled".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint 'Vehicle.ADAS.ABS.IsEnabled' should succeed");
@@ -3134,14 +3981,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.CruiseControl.IsActive".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register 'Vehicle.ADAS.CruiseControl.IsActive' datapoint should succeed");
@@ -3149,14 +3994,16 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.Cabin.Infotainment.Navigation.Volume".to_owned(),
-                broker::DataType::Uint8,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                Some(broker::types::DataValue::Uint32(0)), // min
-                Some(broker::types::DataValue::Uint32(100)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(broker::types::DataValue::Uint32(0)),
+                    max: Some(broker::types::DataValue::Uint32(100)),
+                    ..vss::DataEntry::new(
+                        broker::DataType::Uint8,
+                        broker::ChangeType::OnChange,
+                        broker::EntryType::Actuator,
+                        "Some funny description".to_owned(),
+                    )
+                },
             )
             .await
             .expect(
@@ -3245,14 +4092,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.ABS.IsEnabled".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3299,14 +4144,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.ABS.IsEnabled".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3314,14 +4157,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.CruiseControl.IsActive".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3388,14 +4229,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.ABS.IsEnabled".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3403,14 +4242,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.CruiseControl.IsActive".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3520,14 +4357,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.ABS.IsEnabled".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3568,6 +4403,68 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_refresh_token() {
+        let broker = DataBroker::default();
+
+        let request = OpenProviderStreamRequest {
+            action: Some(open_provider_stream_request::Action::RefreshTokenRequest(
+                proto::RefreshTokenRequest {
+                    token: "not used when authorization is disabled".to_string(),
+                },
+            )),
+        };
+
+        let mut authorized_request = streaming_request(vec![request.clone()]);
+        authorized_request
+            .extensions_mut()
+            .insert(permissions::ALLOW_ALL.clone());
+        authorized_request
+            .extensions_mut()
+            .insert(Authorization::Disabled);
+
+        match proto::val_server::Val::open_provider_stream(&broker, authorized_request).await {
+            Ok(response) => {
+                let mut receiver = response.into_inner().into_inner();
+                let result_response = receiver
+                    .recv()
+                    .await
+                    .expect("result_response should be Some")
+                    .expect("Result should be Ok");
+                assert!(matches!(
+                    result_response.action,
+                    Some(RefreshTokenResponse(_))
+                ));
+            }
+            Err(_) => {
+                panic!("Should not happen")
+            }
+        }
+
+        // Without an authorization in the request extensions
+        let mut unauthorized_request = streaming_request(vec![request]);
+        unauthorized_request
+            .extensions_mut()
+            .insert(permissions::ALLOW_ALL.clone());
+
+        match proto::val_server::Val::open_provider_stream(&broker, unauthorized_request).await {
+            Ok(response) => {
+                let mut receiver = response.into_inner().into_inner();
+                let result_response = receiver
+                    .recv()
+                    .await
+                    .expect("result_response should be Some");
+                assert_eq!(
+                    result_response.unwrap_err().code(),
+                    tonic::Code::Unauthenticated
+                );
+            }
+            Err(_) => {
+                panic!("Should not happen")
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_actuate_stream_out_of_range() {
         let broker = DataBroker::default();
@@ -3576,14 +4473,16 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.Cabin.Infotainment.Navigation.Volume".to_owned(),
-                broker::DataType::Uint8,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                Some(broker::types::DataValue::Uint32(0)), // min
-                Some(broker::types::DataValue::Uint32(100)), // max
-                None,
-                None,
+                vss::DataEntry {
+                    min: Some(broker::types::DataValue::Uint32(0)),
+                    max: Some(broker::types::DataValue::Uint32(100)),
+                    ..vss::DataEntry::new(
+                        broker::DataType::Uint8,
+                        broker::ChangeType::OnChange,
+                        broker::EntryType::Actuator,
+                        "Some funny description".to_owned(),
+                    )
+                },
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3670,14 +4569,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.ABS.IsEnabled".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
@@ -3716,14 +4613,12 @@ mod tests {
         authorized_access
             .add_entry(
                 "Vehicle.ADAS.ABS.IsEnabled".to_owned(),
-                broker::DataType::Bool,
-                broker::ChangeType::OnChange,
-                broker::EntryType::Actuator,
-                "Some funny description".to_owned(),
-                None, // min
-                None, // max
-                None,
-                None,
+                vss::DataEntry::new(
+                    broker::DataType::Bool,
+                    broker::ChangeType::OnChange,
+                    broker::EntryType::Actuator,
+                    "Some funny description".to_owned(),
+                ),
             )
             .await
             .expect("Register datapoint should succeed");
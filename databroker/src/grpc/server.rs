@@ -11,7 +11,7 @@
 * SPDX-License-Identifier: Apache-2.0
 ********************************************************************************/
 
-use std::{convert::TryFrom, future::Future};
+use std::future::Future;
 
 use futures::Stream;
 use socket2::{Domain, Protocol, Socket, Type};
@@ -27,11 +27,7 @@ use tracing::{debug, info};
 
 use databroker_proto::kuksa;
 
-use crate::{
-    authorization::Authorization,
-    broker,
-    permissions::{self, Permissions},
-};
+use crate::{authorization::Authorization, broker, permissions};
 
 // https://www.linuxjournal.com/files/linuxjournal.com/linuxjournal/articles/023/2333/2333s2.html
 const MAX_ACCEPT_QUEUE_SIZE: i32 = 128;
@@ -55,39 +51,34 @@ impl tonic::service::Interceptor for Authorization {
     ) -> Result<tonic::Request<()>, tonic::Status> {
         match self {
             Authorization::Disabled => {
+                request.extensions_mut().insert(Authorization::Disabled);
                 request
                     .extensions_mut()
                     .insert(permissions::ALLOW_ALL.clone());
                 Ok(request)
             }
-            Authorization::Enabled { token_decoder } => {
-                match request.metadata().get("authorization") {
-                    Some(header) => match header.to_str() {
-                        Ok(header) if header.starts_with("Bearer ") => {
-                            let token: &str = header[7..].into();
-                            match token_decoder.decode(token) {
-                                Ok(claims) => match Permissions::try_from(claims) {
-                                    Ok(permissions) => {
-                                        request.extensions_mut().insert(permissions);
-                                        Ok(request)
-                                    }
-                                    Err(err) => Err(tonic::Status::unauthenticated(format!(
-                                        "Invalid auth token: {err}"
-                                    ))),
-                                },
-                                Err(err) => Err(tonic::Status::unauthenticated(format!(
-                                    "Invalid auth token: {err}"
-                                ))),
+            Authorization::Enabled { .. } => match request.metadata().get("authorization") {
+                Some(header) => match header.to_str() {
+                    Ok(header) if header.starts_with("Bearer ") => {
+                        let token: &str = header[7..].into();
+                        match self.permissions_from_token(token) {
+                            Ok(permissions) => {
+                                // Allows long-lived streams to refresh their token
+                                let authorization = self.clone();
+                                request.extensions_mut().insert(authorization);
+                                request.extensions_mut().insert(permissions);
+                                Ok(request)
                             }
+                            Err(err) => Err(tonic::Status::unauthenticated(err.to_string())),
                         }
-                        Ok(_) | Err(_) => Err(tonic::Status::unauthenticated("Invalid auth token")),
-                    },
-                    None => {
-                        debug!("No auth token provided");
-                        Err(tonic::Status::unauthenticated("No auth token provided"))
                     }
+                    Ok(_) | Err(_) => Err(tonic::Status::unauthenticated("Invalid auth token")),
+                },
+                None => {
+                    debug!("No auth token provided");
+                    Err(tonic::Status::unauthenticated("No auth token provided"))
                 }
-            }
+            },
         }
     }
 }
@@ -11,6 +11,7 @@
 * SPDX-License-Identifier: Apache-2.0
 ********************************************************************************/
 
+pub mod audit;
 pub mod authorization;
 pub mod broker;
 pub mod filter;
@@ -18,6 +19,7 @@ pub mod glob;
 pub mod grpc;
 pub mod open_telemetry;
 pub mod permissions;
+pub mod rate_limit;
 pub mod types;
 pub mod vss;
 
@@ -16,13 +16,20 @@
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 static DEFAULT_UNIX_SOCKET_PATH: &str = "/run/kuksa/databroker.sock";
+const REVOCATION_LIST_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
 
+use std::collections::BTreeMap;
 use std::io;
 use std::os::unix::fs::FileTypeExt;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
+use databroker::audit::{AuditConfig, AuditLog, DEFAULT_MAX_FILES, DEFAULT_MAX_FILE_SIZE};
+use databroker::authorization::revocation::RevocationList;
 use databroker::authorization::Authorization;
 use databroker::broker::RegistrationError;
+use databroker::rate_limit::RateLimits;
 
 #[cfg(feature = "tls")]
 use databroker::grpc::server::ServerTLS;
@@ -35,7 +42,7 @@ use tracing::{debug, error, info, warn};
 
 #[cfg(feature = "viss")]
 use databroker::viss;
-use databroker::{broker, grpc, permissions, vss};
+use databroker::{broker, glob, grpc, permissions, vss};
 
 async fn shutdown_handler() {
     let mut sigint =
@@ -51,6 +58,51 @@ async fn shutdown_handler() {
     };
 }
 
+async fn set_initial_value(
+    database: &broker::AuthorizedAccess<'_, '_>,
+    id: i32,
+    path: &str,
+    value: broker::types::DataValue,
+) {
+    let ids = [(
+        id,
+        broker::EntryUpdate {
+            datapoint: Some(broker::Datapoint {
+                ts: std::time::SystemTime::now(),
+                source_ts: None,
+                value,
+            }),
+            path: None,
+            actuator_target: None,
+            entry_type: None,
+            data_type: None,
+            description: None,
+            allowed: None,
+            min: None,
+            max: None,
+            unit: None,
+            range_policy: None,
+        },
+    )];
+    if let Err(errors) = database.update_entries(ids).await {
+        // There's only one error (since we're only trying to set one)
+        if let Some(error) = errors.first() {
+            info!("Failed to set value for {}: {:?}", path, error.1);
+        }
+    }
+}
+
+/// Parse a `PATTERN=POLICY` rule of the range-policy option
+fn parse_range_policy_rule(rule: &str) -> Result<(String, broker::RangePolicy), String> {
+    let Some((pattern, policy)) = rule.rsplit_once('=') else {
+        return Err(format!("expected PATTERN=POLICY, got '{rule}'"));
+    };
+    if glob::Matcher::new(pattern).is_err() {
+        return Err(format!("invalid path pattern '{pattern}'"));
+    }
+    Ok((pattern.to_owned(), policy.parse()?))
+}
+
 async fn add_kuksa_entry(
     database: &broker::AuthorizedAccess<'_, '_>,
     attribute: String,
@@ -63,44 +115,16 @@ async fn add_kuksa_entry(
     match database
         .add_entry(
             attribute.clone(),
-            data_type,
-            databroker::broker::ChangeType::OnChange,
-            databroker::broker::EntryType::Attribute,
-            description,
-            None,
-            None,
-            None,
-            None,
+            vss::DataEntry::new(
+                data_type,
+                databroker::broker::ChangeType::OnChange,
+                databroker::broker::EntryType::Attribute,
+                description,
+            ),
         )
         .await
     {
-        Ok(id) => {
-            let ids = [(
-                id,
-                broker::EntryUpdate {
-                    datapoint: Some(broker::Datapoint {
-                        ts: std::time::SystemTime::now(),
-                        source_ts: None,
-                        value,
-                    }),
-                    path: None,
-                    actuator_target: None,
-                    entry_type: None,
-                    data_type: None,
-                    description: None,
-                    allowed: None,
-                    min: None,
-                    max: None,
-                    unit: None,
-                },
-            )];
-            if let Err(errors) = database.update_entries(ids).await {
-                // There's only one error (since we're only trying to set one)
-                if let Some(error) = errors.first() {
-                    info!("Failed to set value for {}: {:?}", attribute, error.1);
-                }
-            }
-        }
+        Ok(id) => set_initial_value(database, id, &attribute, value).await,
         Err(RegistrationError::PermissionDenied) => {
             error!("Failed to add entry {attribute}: Permission denied")
         }
@@ -110,6 +134,9 @@ async fn add_kuksa_entry(
         Err(RegistrationError::ValidationError) => {
             error!("Failed to add entry {attribute}: Validation failed")
         }
+        Err(RegistrationError::AlreadyExists | RegistrationError::NotFound) => {
+            error!("Failed to add entry {attribute}: Unexpected error")
+        }
     }
 }
 
@@ -150,60 +177,39 @@ async fn add_kuksa_string_attribute(
     .await;
 }
 
-async fn read_metadata_file(
+/// Read the VSS files in order, each of them being merged as an overlay
+/// onto the ones before, and add the resulting entries. The range policy
+/// of the first matching rule in `range_policies` overrides the one given
+/// in the VSS files.
+async fn read_metadata_files(
     database: &broker::AuthorizedAccess<'_, '_>,
-    filename: &str,
+    filenames: impl IntoIterator<Item = &String>,
+    apply_defaults: bool,
+    range_policies: &[(glob::Matcher, broker::RangePolicy)],
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let path = filename.trim();
-    info!("Populating metadata from file '{}'", path);
-    let metadata_file = std::fs::OpenOptions::new().read(true).open(filename)?;
-    let buffered = std::io::BufReader::new(metadata_file);
-    let entries = vss::parse_vss_from_reader(buffered)?;
+    let mut tree = vss::VssTree::default();
+    for filename in filenames {
+        info!("Populating metadata from file '{}'", filename.trim());
+        tree.merge_from_file(filename.trim())?;
+    }
+    let entries = tree.into_entries()?;
 
-    for (path, entry) in entries {
+    for (path, mut entry) in entries {
         debug!("Adding VSS datapoint {}", path);
 
-        match database
-            .add_entry(
-                path.clone(),
-                entry.data_type,
-                entry.change_type,
-                entry.entry_type,
-                entry.description,
-                entry.min,
-                entry.max,
-                entry.allowed,
-                entry.unit,
-            )
-            .await
+        let glob_path = path.replace('.', "/");
+        if let Some((_, range_policy)) = range_policies
+            .iter()
+            .find(|(matcher, _)| matcher.is_match(&glob_path))
         {
+            entry.range_policy = *range_policy;
+        }
+        let default = entry.default.clone();
+
+        match database.add_entry(path.clone(), entry).await {
             Ok(id) => {
-                if let Some(default) = entry.default {
-                    let ids = [(
-                        id,
-                        broker::EntryUpdate {
-                            datapoint: Some(broker::Datapoint {
-                                ts: std::time::SystemTime::now(),
-                                source_ts: None,
-                                value: default,
-                            }),
-                            path: None,
-                            actuator_target: None,
-                            entry_type: None,
-                            data_type: None,
-                            description: None,
-                            allowed: None,
-                            min: None,
-                            max: None,
-                            unit: None,
-                        },
-                    )];
-                    if let Err(errors) = database.update_entries(ids).await {
-                        // There's only one error (since we're only trying to set one)
-                        if let Some(error) = errors.first() {
-                            info!("Failed to set default value for {}: {:?}", path, error.1);
-                        }
-                    }
+                if let (true, Some(default)) = (apply_defaults, default) {
+                    set_initial_value(database, id, &path, default).await;
                 }
             }
             Err(RegistrationError::PermissionDenied) => {
@@ -215,6 +221,37 @@ async fn read_metadata_file(
             Err(RegistrationError::ValidationError) => {
                 error!("Failed to add entry {path}: Validation failed")
             }
+            Err(RegistrationError::AlreadyExists | RegistrationError::NotFound) => {
+                error!("Failed to add entry {path}: Unexpected error")
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Set attribute values (e.g. VIN, model) from a JSON object mapping
+/// paths to values, overriding any VSS defaults
+async fn read_vehicle_identity_file(
+    database: &broker::AuthorizedAccess<'_, '_>,
+    filename: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Setting attribute values from file '{}'", filename.trim());
+    let file = std::fs::OpenOptions::new().read(true).open(filename)?;
+    let values: BTreeMap<String, serde_json::Value> =
+        serde_json::from_reader(std::io::BufReader::new(file))?;
+
+    for (path, value) in values {
+        let Some(metadata) = database.get_metadata_by_path(&path).await else {
+            warn!("Ignoring value for {path}: no such entry");
+            continue;
+        };
+        if metadata.entry_type != broker::EntryType::Attribute {
+            warn!("Ignoring value for {path}: not an attribute");
+            continue;
+        }
+        match vss::parse_value_from_json(value, &metadata.data_type) {
+            Ok(value) => set_initial_value(database, metadata.id, &path, value).await,
+            Err(err) => warn!("Ignoring value for {path}: {err}"),
         }
     }
     Ok(())
@@ -310,7 +347,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .display_order(5)
                 .alias("metadata")
                 .long("vss")
-                .help("Populate data broker with VSS metadata from (comma-separated) list of files")
+                .help("Populate data broker with VSS metadata from (comma-separated) list of files, later files are merged as overlays")
                 .action(ArgAction::Set)
                 .value_delimiter(',')
                 .value_name("FILE")
@@ -318,6 +355,36 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .value_parser(clap::builder::NonEmptyStringValueParser::new())
                 .required(false),
         )
+        .arg(
+            Arg::new("disable-vss-defaults")
+                .display_order(9)
+                .long("disable-vss-defaults")
+                .help("Do not initialize attributes with the default values of the VSS files")
+                .action(ArgAction::SetTrue)
+                .env("KUKSA_DATABROKER_DISABLE_VSS_DEFAULTS"),
+        )
+        .arg(
+            Arg::new("vehicle-identity")
+                .display_order(10)
+                .long("vehicle-identity")
+                .help("Set attribute values (e.g. VIN) from a JSON file mapping paths to values")
+                .action(ArgAction::Set)
+                .value_name("FILE")
+                .required(false)
+                .env("KUKSA_DATABROKER_VEHICLE_IDENTITY_FILE"),
+        )
+        .arg(
+            Arg::new("range-policy")
+                .display_order(11)
+                .long("range-policy")
+                .help("How to handle values outside of min/max or allowed values for signals matching a path pattern, given as (comma-separated) list of PATTERN=POLICY where POLICY is one of reject, clamp or warn. Overrides x-kuksa-range-policy of the VSS files")
+                .action(ArgAction::Set)
+                .value_delimiter(',')
+                .value_name("PATTERN=POLICY")
+                .env("KUKSA_DATABROKER_RANGE_POLICY")
+                .value_parser(parse_range_policy_rule)
+                .requires("vss-file"),
+        )
         .arg(
             Arg::new("jwt-public-key")
                 .display_order(6)
@@ -334,6 +401,102 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .help("Disable authorization")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("revocation-list")
+                .display_order(8)
+                .long("revocation-list")
+                .help("File listing revoked access tokens (by jti or sub), reloaded when modified")
+                .action(ArgAction::Set)
+                .value_name("FILE")
+                .required(false)
+                .env("KUKSA_DATABROKER_REVOCATION_LIST"),
+        )
+        .arg(
+            Arg::new("publish-rate-limit")
+                .display_order(40)
+                .long("publish-rate-limit")
+                .help("Maximum number of values each client may publish per second")
+                .action(ArgAction::Set)
+                .value_name("VALUES_PER_SECOND")
+                .required(false)
+                .env("KUKSA_DATABROKER_PUBLISH_RATE_LIMIT")
+                .value_parser(clap::value_parser!(u32).range(1..)),
+        )
+        .arg(
+            Arg::new("actuation-rate-limit")
+                .display_order(41)
+                .long("actuation-rate-limit")
+                .help("Maximum number of actuations each client may request per second")
+                .action(ArgAction::Set)
+                .value_name("ACTUATIONS_PER_SECOND")
+                .required(false)
+                .env("KUKSA_DATABROKER_ACTUATION_RATE_LIMIT")
+                .value_parser(clap::value_parser!(u32).range(1..)),
+        )
+        .arg(
+            Arg::new("max-subscriptions")
+                .display_order(42)
+                .long("max-subscriptions")
+                .help("Maximum number of concurrent subscriptions per client")
+                .action(ArgAction::Set)
+                .value_name("COUNT")
+                .required(false)
+                .env("KUKSA_DATABROKER_MAX_SUBSCRIPTIONS")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("max-subscribed-signals")
+                .display_order(43)
+                .long("max-subscribed-signals")
+                .help("Maximum number of signals per client across all of its subscriptions")
+                .action(ArgAction::Set)
+                .value_name("COUNT")
+                .required(false)
+                .env("KUKSA_DATABROKER_MAX_SUBSCRIBED_SIGNALS")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("audit-log")
+                .display_order(44)
+                .long("audit-log")
+                .help("Write an audit log of actuations, provider registrations and permission denials (JSON Lines) to this file")
+                .action(ArgAction::Set)
+                .value_name("FILE")
+                .required(false)
+                .env("KUKSA_DATABROKER_AUDIT_LOG"),
+        )
+        .arg(
+            Arg::new("audit-log-paths")
+                .display_order(45)
+                .long("audit-log-paths")
+                .help("Only audit signals matching this (comma-separated) list of path patterns")
+                .action(ArgAction::Set)
+                .value_delimiter(',')
+                .value_name("PATTERN")
+                .default_value("**")
+                .env("KUKSA_DATABROKER_AUDIT_LOG_PATHS")
+                .requires("audit-log"),
+        )
+        .arg(
+            Arg::new("audit-log-max-size")
+                .display_order(46)
+                .long("audit-log-max-size")
+                .help("Rotate the audit log when it grows beyond this many bytes [default: 10485760]")
+                .action(ArgAction::Set)
+                .value_name("BYTES")
+                .env("KUKSA_DATABROKER_AUDIT_LOG_MAX_SIZE")
+                .value_parser(clap::value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("audit-log-max-files")
+                .display_order(47)
+                .long("audit-log-max-files")
+                .help("Number of rotated audit log files to keep [default: 5]")
+                .action(ArgAction::Set)
+                .value_name("COUNT")
+                .env("KUKSA_DATABROKER_AUDIT_LOG_MAX_FILES")
+                .value_parser(clap::value_parser!(usize)),
+        )
         .arg(
             Arg::new("worker-threads")
                 .display_order(34)
@@ -436,7 +599,49 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .expect("port should be a number");
         let addr = std::net::SocketAddr::new(ip_addr, *port);
 
-        let broker = broker::DataBroker::new(version, commit_sha);
+        let rate_limits = RateLimits {
+            publish_rate: args.get_one::<u32>("publish-rate-limit").copied(),
+            actuation_rate: args.get_one::<u32>("actuation-rate-limit").copied(),
+            max_subscriptions: args.get_one::<usize>("max-subscriptions").copied(),
+            max_subscribed_signals: args.get_one::<usize>("max-subscribed-signals").copied(),
+        };
+        debug!("Using rate limits {:?}", rate_limits);
+
+        let mut broker = broker::DataBroker::new(version, commit_sha).with_rate_limits(rate_limits);
+
+        if let Some(audit_file) = args.get_one::<String>("audit-log") {
+            let audit_config = AuditConfig {
+                file: audit_file.into(),
+                paths: args
+                    .get_many::<String>("audit-log-paths")
+                    .map(|paths| paths.cloned().collect())
+                    .unwrap_or_default(),
+                max_file_size: args
+                    .get_one::<u64>("audit-log-max-size")
+                    .copied()
+                    .unwrap_or(DEFAULT_MAX_FILE_SIZE),
+                max_files: args
+                    .get_one::<usize>("audit-log-max-files")
+                    .copied()
+                    .unwrap_or(DEFAULT_MAX_FILES),
+            };
+            info!("Writing audit log to '{audit_file}'");
+            broker = broker.with_audit_log(AuditLog::new(&audit_config)?);
+        }
+
+        let revocation_list = match args.get_one::<String>("revocation-list") {
+            Some(filename) => {
+                let path = std::path::PathBuf::from(filename);
+                let revocation_list = Arc::new(RevocationList::from_file(&path)?);
+                info!("Using '{filename}' as token revocation list");
+                revocation_list
+                    .clone()
+                    .watch_file(path, REVOCATION_LIST_RELOAD_INTERVAL);
+                revocation_list
+            }
+            None => Arc::default(),
+        };
+        broker = broker.with_revocation_list(revocation_list.clone());
         let database = broker.authorized_access(&permissions::ALLOW_ALL);
 
         add_kuksa_string_attribute(
@@ -484,9 +689,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .await;
 
         if let Some(metadata_filenames) = args.get_many::<String>("vss-file") {
-            for filename in metadata_filenames {
-                read_metadata_file(&database, filename).await?;
-            }
+            let apply_defaults = !args.get_flag("disable-vss-defaults");
+            let range_policies = args
+                .get_many::<(String, broker::RangePolicy)>("range-policy")
+                .into_iter()
+                .flatten()
+                .filter_map(|(pattern, range_policy)| {
+                    glob::Matcher::new(pattern)
+                        .ok()
+                        .map(|matcher| (matcher, *range_policy))
+                })
+                .collect::<Vec<_>>();
+            read_metadata_files(
+                &database,
+                metadata_filenames,
+                apply_defaults,
+                &range_policies,
+            )
+            .await?;
+        }
+
+        if let Some(filename) = args.get_one::<String>("vehicle-identity") {
+            read_vehicle_identity_file(&database, filename).await?;
         }
 
         #[cfg(feature = "tls")]
@@ -541,7 +765,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }?;
 
         let authorization = match (enable_authorization, jwt_public_key) {
-            (true, Some(pub_key)) => Authorization::new(pub_key)?,
+            (true, Some(pub_key)) => {
+                Authorization::new(pub_key)?.with_revocation_list(revocation_list)
+            }
             (true, None) => {
                 warn!("Authorization is not enabled.");
                 Authorization::Disabled
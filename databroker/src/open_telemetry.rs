@@ -49,6 +49,9 @@ pub fn init_trace() -> Result<trace::Tracer, TraceError> {
 #[cfg(feature = "otel")]
 static BROADCAST_DROP_COUNTER: OnceLock<Counter<u64>> = OnceLock::new();
 
+#[cfg(feature = "otel")]
+static RATE_LIMIT_REJECTION_COUNTER: OnceLock<Counter<u64>> = OnceLock::new();
+
 // Initialises the OpenTelemetry metrics pipeline (OTLP over tonic).
 //
 // Honours the same OTEL_ENDPOINT environment variable as init_trace and
@@ -87,6 +90,12 @@ pub fn init_metrics() -> Result<BasicController, MetricsError> {
     // in lib.rs — init is expected once per process.
     let _ = BROADCAST_DROP_COUNTER.set(counter);
 
+    let counter = meter
+        .u64_counter("rate_limit_rejections_total")
+        .with_description("Count of requests rejected due to per client rate limits and quotas.")
+        .init();
+    let _ = RATE_LIMIT_REJECTION_COUNTER.set(counter);
+
     Ok(controller)
 }
 
@@ -96,3 +105,10 @@ pub fn init_metrics() -> Result<BasicController, MetricsError> {
 pub fn broadcast_drop_counter() -> Option<&'static Counter<u64>> {
     BROADCAST_DROP_COUNTER.get()
 }
+
+// Returns the rate limit rejection counter if init_metrics has run, or None
+// otherwise. Call sites should treat None as a no-op.
+#[cfg(feature = "otel")]
+pub fn rate_limit_rejection_counter() -> Option<&'static Counter<u64>> {
+    RATE_LIMIT_REJECTION_COUNTER.get()
+}
@@ -20,36 +20,61 @@ use crate::glob;
 
 lazy_static! {
     pub static ref ALLOW_ALL: Permissions = Permissions {
+        subject: None,
+        token_id: None,
         expires_at: None,
         read: PathMatcher::Everything,
         actuate: PathMatcher::Everything,
         provide: PathMatcher::Everything,
         create: PathMatcher::Everything,
+        deny_read: PathMatcher::Nothing,
+        deny_actuate: PathMatcher::Nothing,
+        deny_provide: PathMatcher::Nothing,
+        deny_create: PathMatcher::Nothing,
     };
     pub static ref ALLOW_NONE: Permissions = Permissions {
+        subject: None,
+        token_id: None,
         expires_at: None,
         read: PathMatcher::Nothing,
         actuate: PathMatcher::Nothing,
         provide: PathMatcher::Nothing,
         create: PathMatcher::Nothing,
+        deny_read: PathMatcher::Nothing,
+        deny_actuate: PathMatcher::Nothing,
+        deny_provide: PathMatcher::Nothing,
+        deny_create: PathMatcher::Nothing,
     };
 }
 
 #[derive(Debug, Clone)]
 pub struct Permissions {
+    subject: Option<String>,
+    token_id: Option<String>,
     expires_at: Option<SystemTime>,
     read: PathMatcher,
     actuate: PathMatcher,
     provide: PathMatcher,
     create: PathMatcher,
+    // Denials take precedence over any granted permission
+    deny_read: PathMatcher,
+    deny_actuate: PathMatcher,
+    deny_provide: PathMatcher,
+    deny_create: PathMatcher,
 }
 
 pub struct PermissionBuilder {
+    subject: Option<String>,
+    token_id: Option<String>,
     expiration: Option<SystemTime>,
     read: PathMatchBuilder,
     actuate: PathMatchBuilder,
     provide: PathMatchBuilder,
     create: PathMatchBuilder,
+    deny_read: PathMatchBuilder,
+    deny_actuate: PathMatchBuilder,
+    deny_provide: PathMatchBuilder,
+    deny_create: PathMatchBuilder,
 }
 
 pub enum Permission {
@@ -91,14 +116,30 @@ impl Default for PermissionBuilder {
 impl PermissionBuilder {
     pub fn new() -> Self {
         Self {
+            subject: None,
+            token_id: None,
             expiration: None,
             read: PathMatchBuilder::Nothing,
             actuate: PathMatchBuilder::Nothing,
             provide: PathMatchBuilder::Nothing,
             create: PathMatchBuilder::Nothing,
+            deny_read: PathMatchBuilder::Nothing,
+            deny_actuate: PathMatchBuilder::Nothing,
+            deny_provide: PathMatchBuilder::Nothing,
+            deny_create: PathMatchBuilder::Nothing,
         }
     }
 
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    pub fn token_id(mut self, token_id: impl Into<String>) -> Self {
+        self.token_id = Some(token_id.into());
+        self
+    }
+
     pub fn expires_at(mut self, expiration: SystemTime) -> Self {
         self.expiration = Some(expiration);
         self
@@ -148,13 +189,39 @@ impl PermissionBuilder {
         self
     }
 
+    pub fn add_read_denial(mut self, permission: Permission) -> Self {
+        self.deny_read.extend_with_permission(permission);
+        self
+    }
+
+    pub fn add_actuate_denial(mut self, permission: Permission) -> Self {
+        self.deny_actuate.extend_with_permission(permission);
+        self
+    }
+
+    pub fn add_provide_denial(mut self, permission: Permission) -> Self {
+        self.deny_provide.extend_with_permission(permission);
+        self
+    }
+
+    pub fn add_create_denial(mut self, permission: Permission) -> Self {
+        self.deny_create.extend_with_permission(permission);
+        self
+    }
+
     pub fn build(self) -> Result<Permissions, PermissionsBuildError> {
         Ok(Permissions {
+            subject: self.subject,
+            token_id: self.token_id,
             expires_at: self.expiration,
             read: self.read.build()?,
             actuate: self.actuate.build()?,
             provide: self.provide.build()?,
             create: self.create.build()?,
+            deny_read: self.deny_read.build()?,
+            deny_actuate: self.deny_actuate.build()?,
+            deny_provide: self.deny_provide.build()?,
+            deny_create: self.deny_create.build()?,
         })
     }
 }
@@ -164,11 +231,33 @@ impl Permissions {
         PermissionBuilder::new()
     }
 
+    /// The subject (`sub` claim) these permissions were issued to, if any.
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    /// The unique id (`jti` claim) of the token these permissions stem from, if any.
+    pub fn token_id(&self) -> Option<&str> {
+        self.token_id.as_deref()
+    }
+
+    /// Returns true if both stem from the same access token.
+    pub fn is_same_token(&self, other: &Permissions) -> bool {
+        self.subject == other.subject
+            && self.token_id == other.token_id
+            && self.expires_at == other.expires_at
+    }
+
     pub fn can_read(&self, path: &str) -> Result<(), PermissionError> {
         if self.is_expired() {
             return Err(PermissionError::Expired);
         }
 
+        // Explicit denials win over anything granted (or implied) below
+        if self.deny_read.is_match(path) {
+            return Err(PermissionError::Denied);
+        }
+
         if self.read.is_match(path) {
             return Ok(());
         }
@@ -194,6 +283,10 @@ impl Permissions {
             return Err(PermissionError::Expired);
         }
 
+        if self.deny_actuate.is_match(path) {
+            return Err(PermissionError::Denied);
+        }
+
         if self.actuate.is_match(path) {
             return Ok(());
         }
@@ -206,6 +299,10 @@ impl Permissions {
             return Err(PermissionError::Expired);
         }
 
+        if self.deny_provide.is_match(path) {
+            return Err(PermissionError::Denied);
+        }
+
         if self.provide.is_match(path) {
             return Ok(());
         }
@@ -217,12 +314,23 @@ impl Permissions {
             return Err(PermissionError::Expired);
         }
 
+        if self.deny_create.is_match(path) {
+            return Err(PermissionError::Denied);
+        }
+
         if self.create.is_match(path) {
             return Ok(());
         }
         Err(PermissionError::Denied)
     }
 
+    /// Returns true if reading `path` is explicitly denied, regardless of
+    /// what is granted. Such entries are hidden when iterating the database.
+    #[inline]
+    pub fn is_read_denied(&self, path: &str) -> bool {
+        self.deny_read.is_match(path)
+    }
+
     #[cfg_attr(feature="otel", tracing::instrument(name="permissions_expired", skip(self), fields(timestamp=chrono::Utc::now().to_string())))]
     #[inline]
     pub fn is_expired(&self) -> bool {
@@ -274,6 +382,16 @@ impl PathMatchBuilder {
         }
     }
 
+    pub fn extend_with_permission(&mut self, permission: Permission) {
+        match permission {
+            Permission::Nothing => {
+                // Adding nothing
+            }
+            Permission::All => self.extend_with(PathMatchBuilder::Everything),
+            Permission::Glob(path) => self.extend_with_glob(path),
+        }
+    }
+
     pub fn extend_with_glob(&mut self, glob: String) {
         match self {
             PathMatchBuilder::Nothing => *self = PathMatchBuilder::Globs(vec![glob]),
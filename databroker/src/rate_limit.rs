@@ -0,0 +1,374 @@
+/********************************************************************************
+* Copyright (c) 2024 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+
+use crate::permissions::Permissions;
+
+// Idle clients (no subscriptions, buckets refilled) are forgotten after this long
+const IDLE_CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Per client limits. `None` means unlimited.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimits {
+    /// Published values per second
+    pub publish_rate: Option<u32>,
+    /// Actuation requests (per signal) per second
+    pub actuation_rate: Option<u32>,
+    /// Number of concurrently open subscriptions
+    pub max_subscriptions: Option<usize>,
+    /// Number of signals across all open subscriptions
+    pub max_subscribed_signals: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateLimitError {
+    PublishRateExceeded,
+    ActuationRateExceeded,
+    TooManySubscriptions,
+    TooManySubscribedSignals,
+}
+
+impl std::fmt::Display for RateLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimitError::PublishRateExceeded => f.write_str("Publish rate limit exceeded"),
+            RateLimitError::ActuationRateExceeded => f.write_str("Actuation rate limit exceeded"),
+            RateLimitError::TooManySubscriptions => {
+                f.write_str("Maximum number of subscriptions reached")
+            }
+            RateLimitError::TooManySubscribedSignals => {
+                f.write_str("Maximum number of subscribed signals reached")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RateLimitError {}
+
+impl From<RateLimitError> for tonic::Status {
+    fn from(err: RateLimitError) -> Self {
+        tonic::Status::resource_exhausted(err.to_string())
+    }
+}
+
+/// Identity used for accounting, i.e. the `sub` of the access token if
+/// there is one, otherwise the address of the connection.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientId(String);
+
+impl ClientId {
+    pub fn new(permissions: &Permissions, remote_addr: Option<SocketAddr>) -> Self {
+        match (permissions.subject(), remote_addr) {
+            (Some(subject), _) => ClientId(format!("sub:{subject}")),
+            (None, Some(addr)) => ClientId(format!("addr:{addr}")),
+            (None, None) => ClientId("anonymous".to_owned()),
+        }
+    }
+}
+
+impl std::fmt::Display for ClientId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32) -> Self {
+        Self {
+            tokens: rate as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, rate: u32) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        // Allow a burst of at most one second worth of tokens
+        self.tokens = (self.tokens + elapsed * rate as f64).min(rate as f64);
+        self.last_refill = now;
+    }
+
+    fn try_take(&mut self, rate: u32, count: usize) -> bool {
+        self.refill(rate);
+        // Batches larger than the burst are accepted from a full bucket. The
+        // bucket then goes into debt, which is paid back before further
+        // requests are accepted, so the average rate still holds.
+        if self.tokens >= (count as f64).min(rate as f64) {
+            self.tokens -= count as f64;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Default)]
+struct ClientUsage {
+    publish: Option<TokenBucket>,
+    actuate: Option<TokenBucket>,
+    subscriptions: usize,
+    subscribed_signals: usize,
+    last_seen: Option<Instant>,
+}
+
+pub struct RateLimiter {
+    limits: RateLimits,
+    clients: Mutex<HashMap<ClientId, ClientUsage>>,
+}
+
+/// Held for the lifetime of a subscription, releases the quota when dropped.
+pub struct SubscriptionQuota {
+    limiter: Arc<RateLimiter>,
+    client: ClientId,
+    signals: usize,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn limits(&self) -> &RateLimits {
+        &self.limits
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.limits.publish_rate.is_none()
+            && self.limits.actuation_rate.is_none()
+            && self.limits.max_subscriptions.is_none()
+            && self.limits.max_subscribed_signals.is_none()
+    }
+
+    /// Account for `count` published values.
+    pub fn check_publish(&self, client: &ClientId, count: usize) -> Result<(), RateLimitError> {
+        let Some(rate) = self.limits.publish_rate else {
+            return Ok(());
+        };
+        let mut clients = self.clients.lock().unwrap();
+        let usage = clients.entry(client.clone()).or_default();
+        usage.last_seen = Some(Instant::now());
+        let bucket = usage.publish.get_or_insert_with(|| TokenBucket::new(rate));
+        if bucket.try_take(rate, count) {
+            Ok(())
+        } else {
+            rejected(client, RateLimitError::PublishRateExceeded)
+        }
+    }
+
+    /// Account for `count` actuated signals.
+    pub fn check_actuation(&self, client: &ClientId, count: usize) -> Result<(), RateLimitError> {
+        let Some(rate) = self.limits.actuation_rate else {
+            return Ok(());
+        };
+        let mut clients = self.clients.lock().unwrap();
+        let usage = clients.entry(client.clone()).or_default();
+        usage.last_seen = Some(Instant::now());
+        let bucket = usage.actuate.get_or_insert_with(|| TokenBucket::new(rate));
+        if bucket.try_take(rate, count) {
+            Ok(())
+        } else {
+            rejected(client, RateLimitError::ActuationRateExceeded)
+        }
+    }
+
+    /// Reserve a subscription of `signals` signals. The returned quota must be
+    /// kept alive for as long as the subscription is open.
+    pub fn acquire_subscription(
+        self: &Arc<Self>,
+        client: &ClientId,
+        signals: usize,
+    ) -> Result<SubscriptionQuota, RateLimitError> {
+        if self.is_unlimited() {
+            // Nothing to account for
+            return Ok(SubscriptionQuota {
+                limiter: self.clone(),
+                client: client.clone(),
+                signals: 0,
+            });
+        }
+
+        let mut clients = self.clients.lock().unwrap();
+        let usage = clients.entry(client.clone()).or_default();
+        usage.last_seen = Some(Instant::now());
+        if let Some(max) = self.limits.max_subscriptions {
+            if usage.subscriptions >= max {
+                return rejected(client, RateLimitError::TooManySubscriptions);
+            }
+        }
+        if let Some(max) = self.limits.max_subscribed_signals {
+            if usage.subscribed_signals + signals > max {
+                return rejected(client, RateLimitError::TooManySubscribedSignals);
+            }
+        }
+        usage.subscriptions += 1;
+        usage.subscribed_signals += signals;
+        Ok(SubscriptionQuota {
+            limiter: self.clone(),
+            client: client.clone(),
+            signals,
+        })
+    }
+
+    fn release_subscription(&self, client: &ClientId, signals: usize) {
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(usage) = clients.get_mut(client) {
+            usage.subscriptions = usage.subscriptions.saturating_sub(1);
+            usage.subscribed_signals = usage.subscribed_signals.saturating_sub(signals);
+            usage.last_seen = Some(Instant::now());
+        }
+    }
+
+    /// Forget about clients that have been idle for a while.
+    pub fn cleanup(&self) {
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|client, usage| {
+            let idle = usage.subscriptions == 0
+                && usage
+                    .last_seen
+                    .map(|last_seen| last_seen.elapsed() > IDLE_CLIENT_TIMEOUT)
+                    .unwrap_or(true);
+            if idle {
+                debug!("Removing idle client {} from rate limiter", client);
+            }
+            !idle
+        });
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimits::default())
+    }
+}
+
+impl Drop for SubscriptionQuota {
+    fn drop(&mut self) {
+        if !self.limiter.is_unlimited() {
+            self.limiter
+                .release_subscription(&self.client, self.signals);
+        }
+    }
+}
+
+fn rejected<T>(client: &ClientId, err: RateLimitError) -> Result<T, RateLimitError> {
+    warn!("Rejected request from {}: {}", client, err);
+    #[cfg(feature = "otel")]
+    if let Some(counter) = crate::open_telemetry::rate_limit_rejection_counter() {
+        counter.add(
+            &opentelemetry::Context::current(),
+            1,
+            &[opentelemetry::KeyValue::new("limit", format!("{err:?}"))],
+        );
+    }
+    Err(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permissions;
+
+    fn client() -> ClientId {
+        ClientId::new(&permissions::ALLOW_ALL, None)
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let limiter = Arc::new(RateLimiter::default());
+        for _ in 0..1000 {
+            assert!(limiter.check_publish(&client(), 10).is_ok());
+            assert!(limiter.check_actuation(&client(), 10).is_ok());
+        }
+        let _quotas: Vec<_> = (0..100)
+            .map(|_| limiter.acquire_subscription(&client(), 100).unwrap())
+            .collect();
+    }
+
+    #[test]
+    fn test_publish_rate() {
+        let limiter = RateLimiter::new(RateLimits {
+            publish_rate: Some(10),
+            ..Default::default()
+        });
+        assert!(limiter.check_publish(&client(), 6).is_ok());
+        assert!(limiter.check_publish(&client(), 4).is_ok());
+        assert_eq!(
+            limiter.check_publish(&client(), 1),
+            Err(RateLimitError::PublishRateExceeded)
+        );
+        // Actuation is not limited
+        assert!(limiter.check_actuation(&client(), 100).is_ok());
+        // Other clients have their own budget
+        let other = ClientId::new(&permissions::ALLOW_ALL, "127.0.0.1:1234".parse().ok());
+        assert!(limiter.check_publish(&other, 10).is_ok());
+    }
+
+    #[test]
+    fn test_batch_larger_than_rate() {
+        let limiter = RateLimiter::new(RateLimits {
+            publish_rate: Some(10),
+            actuation_rate: Some(10),
+            ..Default::default()
+        });
+        assert!(limiter.check_publish(&client(), 25).is_ok());
+        assert_eq!(
+            limiter.check_publish(&client(), 1),
+            Err(RateLimitError::PublishRateExceeded)
+        );
+        assert!(limiter.check_actuation(&client(), 11).is_ok());
+
+        // The debt of the batch is paid back over time
+        let mut bucket = TokenBucket::new(10);
+        assert!(bucket.try_take(10, 25));
+        bucket.last_refill -= Duration::from_secs(1);
+        assert!(!bucket.try_take(10, 1));
+        bucket.last_refill -= Duration::from_secs(1);
+        assert!(bucket.try_take(10, 1));
+    }
+
+    #[test]
+    fn test_subscription_quota_released_on_drop() {
+        let limiter = Arc::new(RateLimiter::new(RateLimits {
+            max_subscriptions: Some(2),
+            max_subscribed_signals: Some(5),
+            ..Default::default()
+        }));
+        let first = limiter.acquire_subscription(&client(), 3).unwrap();
+        assert_eq!(
+            limiter.acquire_subscription(&client(), 3).err(),
+            Some(RateLimitError::TooManySubscribedSignals)
+        );
+        let second = limiter.acquire_subscription(&client(), 2).unwrap();
+        assert_eq!(
+            limiter.acquire_subscription(&client(), 0).err(),
+            Some(RateLimitError::TooManySubscriptions)
+        );
+        drop(first);
+        drop(second);
+        assert!(limiter.acquire_subscription(&client(), 5).is_ok());
+    }
+}
@@ -11,7 +11,7 @@
 * SPDX-License-Identifier: Apache-2.0
 ********************************************************************************/
 
-use std::{convert::TryFrom, fmt};
+use std::{collections::BTreeMap, convert::TryFrom, fmt, sync::Arc, time::SystemTime};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DataType {
@@ -27,6 +27,7 @@ pub enum DataType {
     Uint64,
     Float,
     Double,
+    Timestamp,
     StringArray,
     BoolArray,
     Int8Array,
@@ -39,6 +40,29 @@ pub enum DataType {
     Uint64Array,
     FloatArray,
     DoubleArray,
+    TimestampArray,
+    Struct(Arc<StructType>),
+    StructArray(Arc<StructType>),
+}
+
+/// A user defined VSS struct type, as declared in a VSS types tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructType {
+    /// Fully qualified name, e.g. "Types.Position"
+    pub name: String,
+    pub fields: Vec<StructField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructField {
+    pub name: String,
+    pub data_type: DataType,
+}
+
+impl StructType {
+    pub fn field(&self, name: &str) -> Option<&StructField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
 }
 
 impl fmt::Display for DataType {
@@ -56,6 +80,7 @@ impl fmt::Display for DataType {
             DataType::Uint64 => write!(f, "Uint64"),
             DataType::Float => write!(f, "Float"),
             DataType::Double => write!(f, "Double"),
+            DataType::Timestamp => write!(f, "Timestamp"),
             DataType::StringArray => write!(f, "StringArray"),
             DataType::BoolArray => write!(f, "BoolArray"),
             DataType::Int8Array => write!(f, "Int8Array"),
@@ -68,6 +93,9 @@ impl fmt::Display for DataType {
             DataType::Uint64Array => write!(f, "Uint64Array"),
             DataType::FloatArray => write!(f, "FloatArray"),
             DataType::DoubleArray => write!(f, "DoubleArray"),
+            DataType::TimestampArray => write!(f, "TimestampArray"),
+            DataType::Struct(struct_type) => write!(f, "{}", struct_type.name),
+            DataType::StructArray(struct_type) => write!(f, "{}[]", struct_type.name),
         }
     }
 }
@@ -86,6 +114,43 @@ pub enum ChangeType {
     Continuous,
 }
 
+/// What to do with values outside of an entry's min/max or allowed values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RangePolicy {
+    /// Reject the value
+    #[default]
+    Reject,
+    /// Clamp the value to min/max (values which are not allowed are still rejected)
+    Clamp,
+    /// Accept the value anyway, but log a warning
+    Warn,
+}
+
+impl std::str::FromStr for RangePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(RangePolicy::Reject),
+            "clamp" => Ok(RangePolicy::Clamp),
+            "warn" => Ok(RangePolicy::Warn),
+            _ => Err(format!(
+                "unknown range policy '{s}', expected one of reject, clamp or warn"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RangePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RangePolicy::Reject => write!(f, "reject"),
+            RangePolicy::Clamp => write!(f, "clamp"),
+            RangePolicy::Warn => write!(f, "warn"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum DataValue {
     NotAvailable,
@@ -97,6 +162,7 @@ pub enum DataValue {
     Uint64(u64),
     Float(f32),
     Double(f64),
+    Timestamp(SystemTime),
     BoolArray(Vec<bool>),
     StringArray(Vec<String>),
     Int32Array(Vec<i32>),
@@ -105,8 +171,14 @@ pub enum DataValue {
     Uint64Array(Vec<u64>),
     FloatArray(Vec<f32>),
     DoubleArray(Vec<f64>),
+    TimestampArray(Vec<SystemTime>),
+    Struct(StructValue),
+    StructArray(Vec<StructValue>),
 }
 
+/// Value of a struct type, keyed by field name
+pub type StructValue = BTreeMap<String, DataValue>;
+
 #[derive(Debug)]
 pub struct CastError {}
 impl fmt::Display for DataValue {
@@ -121,6 +193,7 @@ impl fmt::Display for DataValue {
             DataValue::Uint64(value) => write!(f, "{value}"),
             DataValue::Float(value) => write!(f, "{value}"),
             DataValue::Double(value) => write!(f, "{value}"),
+            DataValue::Timestamp(value) => write!(f, "{}", to_rfc3339(value)),
             DataValue::BoolArray(values) => write!(f, "{values:?}"),
             DataValue::StringArray(values) => write!(f, "{values:?}"),
             DataValue::Int32Array(values) => write!(f, "{values:?}"),
@@ -129,8 +202,44 @@ impl fmt::Display for DataValue {
             DataValue::Uint64Array(values) => write!(f, "{values:?}"),
             DataValue::FloatArray(values) => write!(f, "{values:?}"),
             DataValue::DoubleArray(values) => write!(f, "{values:?}"),
+            DataValue::TimestampArray(values) => {
+                write!(f, "{:?}", values.iter().map(to_rfc3339).collect::<Vec<_>>())
+            }
+            DataValue::Struct(fields) => write_struct(f, fields),
+            DataValue::StructArray(values) => {
+                write!(f, "[")?;
+                for (index, fields) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_struct(f, fields)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// RFC 3339 representation of a timestamp, e.g. "2024-05-01T12:00:00.5Z"
+pub fn to_rfc3339(value: &SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(*value)
+        .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+}
+
+/// Parse an RFC 3339 timestamp, e.g. "2024-05-01T12:00:00+02:00"
+pub fn from_rfc3339(value: &str) -> Result<SystemTime, chrono::ParseError> {
+    chrono::DateTime::parse_from_rfc3339(value).map(SystemTime::from)
+}
+
+fn write_struct(f: &mut fmt::Formatter, fields: &StructValue) -> fmt::Result {
+    write!(f, "{{")?;
+    for (index, (name, value)) in fields.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
         }
+        write!(f, "{name}: {value}")?;
     }
+    write!(f, "}}")
 }
 
 impl DataValue {
@@ -283,6 +392,9 @@ impl DataValue {
                 Ok(*value > f64::from(*other_value))
             }
             (DataValue::Double(value), DataValue::Double(other_value)) => Ok(value > other_value),
+            (DataValue::Timestamp(value), DataValue::Timestamp(other_value)) => {
+                Ok(value > other_value)
+            }
             _ => Err(CastError {}),
         }
     }
@@ -308,6 +420,9 @@ impl DataValue {
     pub fn equals(&self, other: &DataValue) -> Result<bool, CastError> {
         match (&self, other) {
             (DataValue::Bool(value), DataValue::Bool(other_value)) => Ok(value == other_value),
+            (DataValue::Timestamp(value), DataValue::Timestamp(other_value)) => {
+                Ok(value == other_value)
+            }
             (DataValue::String(value), DataValue::String(other_value)) => Ok(value == other_value),
             (DataValue::Int32(value), DataValue::Int32(other_value)) => Ok(value == other_value),
             (DataValue::Int32(value), DataValue::Int64(other_value)) => {
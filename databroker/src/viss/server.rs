@@ -127,7 +127,7 @@ async fn handle_viss_v2<W, R>(
     // single consumer will write to the socket.
     let (sender, receiver) = mpsc::channel::<Message>(10);
 
-    let server = v2::server::Server::new(broker, authorization);
+    let server = v2::server::Server::new(broker, authorization, client_addr);
     let mut write_task = tokio::spawn(async move {
         let _ = receiver.map(Ok).forward(write).await;
     });
@@ -206,6 +206,12 @@ async fn handle_viss_v2<W, R>(
                                             Err(error_response) => serialize(error_response),
                                         }
                                     }
+                                    v2::Request::Authorize(request) => {
+                                        match server.authorize(request).await {
+                                            Ok(response) => serialize(response),
+                                            Err(error_response) => serialize(error_response),
+                                        }
+                                    }
                                 }
                             }
                             Err(_) => serialize(parse_v2_error(&msg)),
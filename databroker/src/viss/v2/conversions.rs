@@ -11,9 +11,10 @@
 * SPDX-License-Identifier: Apache-2.0
 ********************************************************************************/
 
-use std::{convert::TryFrom, time::SystemTime};
+use std::{collections::BTreeMap, convert::TryFrom, time::SystemTime};
 
 use crate::broker;
+use crate::types::to_rfc3339;
 
 use super::types::{
     ActuatorEntry, AttributeEntry, DataPoint, DataType, MetadataEntry, SensorEntry, Value,
@@ -37,7 +38,9 @@ impl TryFrom<Value> for String {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Array(_) | Value::None => Err(Error::ParseError),
+            Value::Array(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Scalar(value) => Ok(value),
         }
     }
@@ -48,7 +51,9 @@ impl TryFrom<Value> for bool {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Array(_) | Value::None => Err(Error::ParseError),
+            Value::Array(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Scalar(value) => value.parse::<bool>().map_err(|_| Error::ParseError),
         }
     }
@@ -59,7 +64,9 @@ impl TryFrom<Value> for i32 {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Array(_) | Value::None => Err(Error::ParseError),
+            Value::Array(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Scalar(value) => value.parse::<i32>().map_err(|_| Error::ParseError),
         }
     }
@@ -70,7 +77,9 @@ impl TryFrom<Value> for i64 {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Array(_) | Value::None => Err(Error::ParseError),
+            Value::Array(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Scalar(value) => value.parse::<i64>().map_err(|_| Error::ParseError),
         }
     }
@@ -81,7 +90,9 @@ impl TryFrom<Value> for u32 {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Array(_) | Value::None => Err(Error::ParseError),
+            Value::Array(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Scalar(value) => value.parse::<u32>().map_err(|_| Error::ParseError),
         }
     }
@@ -92,7 +103,9 @@ impl TryFrom<Value> for u64 {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Array(_) | Value::None => Err(Error::ParseError),
+            Value::Array(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Scalar(value) => value.parse::<u64>().map_err(|_| Error::ParseError),
         }
     }
@@ -103,7 +116,9 @@ impl TryFrom<Value> for f32 {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Array(_) | Value::None => Err(Error::ParseError),
+            Value::Array(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Scalar(value) => value.parse::<f32>().map_err(|_| Error::ParseError),
         }
     }
@@ -114,7 +129,9 @@ impl TryFrom<Value> for f64 {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Array(_) | Value::None => Err(Error::ParseError),
+            Value::Array(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Scalar(value) => value.parse::<f64>().map_err(|_| Error::ParseError),
         }
     }
@@ -125,7 +142,9 @@ impl TryFrom<Value> for Vec<String> {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Scalar(_) | Value::None => Err(Error::ParseError),
+            Value::Scalar(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Array(array) => Ok(array),
         }
     }
@@ -136,7 +155,9 @@ impl TryFrom<Value> for Vec<bool> {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Scalar(_) | Value::None => Err(Error::ParseError),
+            Value::Scalar(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Array(array) => array
                 .iter()
                 .map(|value| value.parse::<bool>())
@@ -151,7 +172,9 @@ impl TryFrom<Value> for Vec<i32> {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Scalar(_) | Value::None => Err(Error::ParseError),
+            Value::Scalar(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Array(array) => array
                 .iter()
                 .map(|value| value.parse::<i32>())
@@ -166,7 +189,9 @@ impl TryFrom<Value> for Vec<i64> {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Scalar(_) | Value::None => Err(Error::ParseError),
+            Value::Scalar(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Array(array) => array
                 .iter()
                 .map(|value| value.parse::<i64>())
@@ -181,7 +206,9 @@ impl TryFrom<Value> for Vec<u32> {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Scalar(_) | Value::None => Err(Error::ParseError),
+            Value::Scalar(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Array(array) => array
                 .iter()
                 .map(|value| value.parse::<u32>())
@@ -196,7 +223,9 @@ impl TryFrom<Value> for Vec<u64> {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Scalar(_) | Value::None => Err(Error::ParseError),
+            Value::Scalar(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Array(array) => array
                 .iter()
                 .map(|value| value.parse::<u64>())
@@ -211,7 +240,9 @@ impl TryFrom<Value> for Vec<f32> {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Scalar(_) | Value::None => Err(Error::ParseError),
+            Value::Scalar(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Array(array) => array
                 .iter()
                 .map(|value| value.parse::<f32>())
@@ -226,7 +257,9 @@ impl TryFrom<Value> for Vec<f64> {
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Scalar(_) | Value::None => Err(Error::ParseError),
+            Value::Scalar(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Array(array) => array
                 .iter()
                 .map(|value| value.parse::<f64>())
@@ -270,16 +303,55 @@ impl From<broker::DataValue> for Value {
             broker::DataValue::DoubleArray(array) => {
                 Value::Array(array.iter().map(|value| value.to_string()).collect())
             }
+            broker::DataValue::Timestamp(value) => Value::Scalar(to_rfc3339(&value)),
+            broker::DataValue::TimestampArray(array) => {
+                Value::Array(array.iter().map(to_rfc3339).collect())
+            }
+            broker::DataValue::Struct(fields) => Value::Struct(struct_to_value(fields)),
+            broker::DataValue::StructArray(array) => {
+                Value::StructArray(array.into_iter().map(struct_to_value).collect())
+            }
         }
     }
 }
 
+fn struct_to_value(fields: broker::types::StructValue) -> BTreeMap<String, Value> {
+    fields
+        .into_iter()
+        .map(|(name, value)| (name, value.into()))
+        .collect()
+}
+
+/// Values of all fields of `struct_type` converted to their declared type
+fn struct_from_value(
+    mut fields: BTreeMap<String, Value>,
+    struct_type: &broker::types::StructType,
+) -> Result<broker::types::StructValue, Error> {
+    let value = struct_type
+        .fields
+        .iter()
+        .map(|field| match fields.remove(&field.name) {
+            Some(value) => value
+                .try_into_type(&field.data_type)
+                .map(|value| (field.name.clone(), value)),
+            None => Err(Error::ParseError),
+        })
+        .collect::<Result<broker::types::StructValue, _>>()?;
+    match fields.is_empty() {
+        true => Ok(value),
+        // Fields not declared by the struct type
+        false => Err(Error::ParseError),
+    }
+}
+
 impl TryFrom<Value> for SystemTime {
     type Error = Error;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Array(_) | Value::None => Err(Error::ParseError),
+            Value::Array(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
             Value::Scalar(value) => chrono::DateTime::parse_from_rfc3339(&value)
                 .map(|datetime| datetime.with_timezone(&chrono::Utc))
                 .map(SystemTime::from)
@@ -288,6 +360,22 @@ impl TryFrom<Value> for SystemTime {
     }
 }
 
+impl TryFrom<Value> for Vec<SystemTime> {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Scalar(_) | Value::Struct(_) | Value::StructArray(_) | Value::None => {
+                Err(Error::ParseError)
+            }
+            Value::Array(array) => array
+                .into_iter()
+                .map(|value| SystemTime::try_from(Value::Scalar(value)))
+                .collect(),
+        }
+    }
+}
+
 impl From<broker::DataType> for DataType {
     fn from(value: broker::DataType) -> Self {
         match value {
@@ -303,6 +391,7 @@ impl From<broker::DataType> for DataType {
             broker::DataType::Uint64 => DataType::Uint64,
             broker::DataType::Float => DataType::Float,
             broker::DataType::Double => DataType::Double,
+            broker::DataType::Timestamp => DataType::Timestamp,
             broker::DataType::StringArray => DataType::StringArray,
             broker::DataType::BoolArray => DataType::BoolArray,
             broker::DataType::Int8Array => DataType::Int8Array,
@@ -315,6 +404,11 @@ impl From<broker::DataType> for DataType {
             broker::DataType::Uint64Array => DataType::Uint64Array,
             broker::DataType::FloatArray => DataType::FloatArray,
             broker::DataType::DoubleArray => DataType::DoubleArray,
+            broker::DataType::TimestampArray => DataType::TimestampArray,
+            broker::DataType::Struct(struct_type) => DataType::Struct(struct_type.name.clone()),
+            broker::DataType::StructArray(struct_type) => {
+                DataType::Struct(format!("{}[]", struct_type.name))
+            }
         }
     }
 }
@@ -324,7 +418,8 @@ impl From<&broker::Metadata> for MetadataEntry {
             broker::EntryType::Sensor => MetadataEntry::Sensor(SensorEntry {
                 datatype: metadata.data_type.clone().into(),
                 description: metadata.description.clone(),
-                comment: None,
+                comment: metadata.comment.clone(),
+                deprecation: metadata.deprecation.clone(),
                 unit: None,
                 allowed: metadata.allowed.clone().map(|allowed| allowed.into()),
                 min: None,
@@ -333,13 +428,16 @@ impl From<&broker::Metadata> for MetadataEntry {
             broker::EntryType::Attribute => MetadataEntry::Attribute(AttributeEntry {
                 datatype: metadata.data_type.clone().into(),
                 description: metadata.description.clone(),
+                comment: metadata.comment.clone(),
+                deprecation: metadata.deprecation.clone(),
                 unit: None,
                 allowed: metadata.allowed.clone().map(|allowed| allowed.into()),
-                default: None, // TODO: Add to metadata
+                default: metadata.default.clone().map(|default| default.into()),
             }),
             broker::EntryType::Actuator => MetadataEntry::Actuator(ActuatorEntry {
                 description: metadata.description.clone(),
-                comment: None,
+                comment: metadata.comment.clone(),
+                deprecation: metadata.deprecation.clone(),
                 datatype: metadata.data_type.clone().into(),
                 unit: None,
                 allowed: metadata.allowed.clone().map(|allowed| allowed.into()),
@@ -365,9 +463,9 @@ impl Value {
             broker::DataType::Uint64 => u64::try_from(self).map(broker::DataValue::Uint64),
             broker::DataType::Float => f32::try_from(self).map(broker::DataValue::Float),
             broker::DataType::Double => f64::try_from(self).map(broker::DataValue::Double),
-            // broker::DataType::Timestamp => {
-            //     SystemTime::try_from(self).map(broker::DataValue::Timestamp)
-            // }
+            broker::DataType::Timestamp => {
+                SystemTime::try_from(self).map(broker::DataValue::Timestamp)
+            }
             broker::DataType::StringArray => {
                 Vec::<String>::try_from(self).map(broker::DataValue::StringArray)
             }
@@ -396,6 +494,27 @@ impl Value {
             broker::DataType::DoubleArray => {
                 Vec::<f64>::try_from(self).map(broker::DataValue::DoubleArray)
             }
+            broker::DataType::TimestampArray => {
+                Vec::<SystemTime>::try_from(self).map(broker::DataValue::TimestampArray)
+            }
+            broker::DataType::Struct(struct_type) => match self {
+                Value::Struct(fields) => {
+                    struct_from_value(fields, struct_type).map(broker::DataValue::Struct)
+                }
+                _ => Err(Error::ParseError),
+            },
+            broker::DataType::StructArray(struct_type) => match self {
+                Value::StructArray(array) => array
+                    .into_iter()
+                    .map(|fields| struct_from_value(fields, struct_type))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(broker::DataValue::StructArray),
+                // An empty JSON array is parsed as Value::Array
+                Value::Array(array) if array.is_empty() => {
+                    Ok(broker::DataValue::StructArray(Vec::new()))
+                }
+                _ => Err(Error::ParseError),
+            },
         }
     }
 }
@@ -415,6 +534,7 @@ impl std::fmt::Display for DataType {
             DataType::Uint64 => "uint64",
             DataType::Float => "float",
             DataType::Double => "double",
+            DataType::Timestamp => "timestamp",
             DataType::StringArray => "string[]",
             DataType::BoolArray => "bool[]",
             DataType::Int8Array => "int8[]",
@@ -427,6 +547,8 @@ impl std::fmt::Display for DataType {
             DataType::Uint64Array => "uint64[]",
             DataType::FloatArray => "float[]",
             DataType::DoubleArray => "double[]",
+            DataType::TimestampArray => "timestamp[]",
+            DataType::Struct(name) => name,
         })
     }
 }
@@ -13,7 +13,7 @@
 
 use std::{
     collections::{HashMap, HashSet},
-    convert::TryFrom,
+    net::SocketAddr,
     pin::Pin,
     sync::Arc,
     time::SystemTime,
@@ -25,12 +25,15 @@ use futures::{
 };
 use tokio::sync::RwLock;
 use tracing::warn;
+use uuid::Uuid;
 
 use crate::{
+    audit::Transport,
     authorization::Authorization,
     broker::{self, AuthorizedAccess, UpdateError},
     glob::Matcher,
     permissions::{self, Permissions},
+    rate_limit::{ClientId, RateLimitError},
 };
 
 use super::{conversions, types::*};
@@ -53,15 +56,25 @@ pub(crate) trait Viss: Send + Sync + 'static {
         &self,
         request: UnsubscribeRequest,
     ) -> Result<UnsubscribeSuccessResponse, UnsubscribeErrorResponse>;
+
+    async fn authorize(
+        &self,
+        request: AuthorizeRequest,
+    ) -> Result<AuthorizeSuccessResponse, AuthorizeErrorResponse>;
 }
 
 pub struct SubscriptionHandle {
     abort_handle: AbortHandle,
+    // Permissions the subscription was created (or last refreshed) with
+    permissions: Permissions,
 }
 
-impl From<AbortHandle> for SubscriptionHandle {
-    fn from(abort_handle: AbortHandle) -> Self {
-        Self { abort_handle }
+impl SubscriptionHandle {
+    fn new(abort_handle: AbortHandle, permissions: Permissions) -> Self {
+        Self {
+            abort_handle,
+            permissions,
+        }
     }
 }
 
@@ -74,17 +87,30 @@ impl Drop for SubscriptionHandle {
 pub struct Server {
     broker: broker::DataBroker,
     authorization: Authorization,
+    client_addr: SocketAddr,
+    // Ties the subscriptions of this connection together in the broker
+    session: Uuid,
     subscriptions: Arc<RwLock<HashMap<SubscriptionId, SubscriptionHandle>>>,
 }
 
 impl Server {
-    pub fn new(broker: broker::DataBroker, authorization: Authorization) -> Self {
+    pub fn new(
+        broker: broker::DataBroker,
+        authorization: Authorization,
+        client_addr: SocketAddr,
+    ) -> Self {
         Self {
             broker,
             authorization,
+            client_addr,
+            session: Uuid::new_v4(),
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
         }
     }
+
+    fn client_id(&self, permissions: &Permissions) -> ClientId {
+        ClientId::new(permissions, Some(self.client_addr))
+    }
 }
 
 #[tonic::async_trait]
@@ -94,8 +120,11 @@ impl Viss for Server {
 
         if let Some(Filter::StaticMetadata(_)) = &request.filter {
             // Authorization not required for metadata, don't bail if an
-            // access token is missing.
-            let broker = self.broker.authorized_access(&permissions::ALLOW_NONE);
+            // access token is missing. Signals explicitly denied by a given
+            // token are still left out though.
+            let permissions = resolve_permissions(&self.authorization, &request.authorization)
+                .unwrap_or_else(|_| permissions::ALLOW_ALL.clone());
+            let broker = self.broker.authorized_access(&permissions);
             let metadata = generate_metadata(&broker, request.path.as_ref()).await;
             return Ok(GetSuccessResponse::Metadata(MetadataResponse {
                 request_id,
@@ -117,7 +146,10 @@ impl Viss for Server {
                     error,
                     ts: SystemTime::now().into(),
                 })?;
-            let broker = self.broker.authorized_access(&permissions);
+            let broker = self
+                .broker
+                .authorized_access(&permissions)
+                .via(Transport::Viss);
 
             let mut request_matcher: Vec<(Matcher, bool)> = Vec::new();
             let mut entries_data = Vec::new();
@@ -212,7 +244,10 @@ impl Viss for Server {
                     error,
                     ts: SystemTime::now().into(),
                 })?;
-            let broker = self.broker.authorized_access(&permissions);
+            let broker = self
+                .broker
+                .authorized_access(&permissions)
+                .via(Transport::Viss);
 
             // Get datapoints
             match broker.get_datapoint_by_path(request.path.as_ref()).await {
@@ -247,7 +282,18 @@ impl Viss for Server {
                 error,
                 ts: SystemTime::now().into(),
             })?;
-        let broker = self.broker.authorized_access(&permissions);
+        self.broker
+            .rate_limiter()
+            .check_actuation(&self.client_id(&permissions), 1)
+            .map_err(|err| SetErrorResponse {
+                request_id: request_id.clone(),
+                error: err.into(),
+                ts: SystemTime::now().into(),
+            })?;
+        let broker = self
+            .broker
+            .authorized_access(&permissions)
+            .via(Transport::Viss);
 
         match broker.get_metadata_by_path(request.path.as_ref()).await {
             Some(metadata) => {
@@ -278,6 +324,7 @@ impl Viss for Server {
                         max: None,
                         allowed: None,
                         unit: None,
+                        range_policy: None,
                     })
                     .map_err(|err| SetErrorResponse {
                         request_id: request_id.clone(),
@@ -363,7 +410,11 @@ impl Viss for Server {
                 error,
                 ts: SystemTime::now().into(),
             })?;
-        let broker = self.broker.authorized_access(&permissions);
+        let broker = self
+            .broker
+            .authorized_access(&permissions)
+            .via(Transport::Viss)
+            .in_session(self.session);
 
         let Some(entries) = broker
             .get_id_by_path(request.path.as_ref())
@@ -383,19 +434,32 @@ impl Viss for Server {
             None
         };
 
+        let quota = self
+            .broker
+            .rate_limiter()
+            .acquire_subscription(&self.client_id(&permissions), entries.len())
+            .map_err(|err| SubscribeErrorResponse {
+                request_id: request_id.clone(),
+                error: err.into(),
+                ts: SystemTime::now().into(),
+            })?;
+
         match broker.subscribe(entries, None, interval_ms).await {
             Ok(stream) => {
                 let subscription_id = SubscriptionId::new();
 
                 let (abort_handle, abort_registration) = AbortHandle::new_pair();
 
-                // Make the stream abortable
-                let stream = Abortable::new(stream, abort_registration);
+                // Make the stream abortable, the quota is released when it is dropped
+                let stream = Abortable::new(stream, abort_registration).map(move |item| {
+                    let _quota = &quota;
+                    item
+                });
 
                 // Register abort handle
                 self.subscriptions.write().await.insert(
                     subscription_id.clone(),
-                    SubscriptionHandle::from(abort_handle),
+                    SubscriptionHandle::new(abort_handle, permissions.clone()),
                 );
 
                 let stream = convert_to_viss_stream(subscription_id.clone(), stream);
@@ -414,6 +478,7 @@ impl Viss for Server {
                 error: match err {
                     broker::SubscriptionError::NotFound => Error::NotFoundInvalidPath,
                     broker::SubscriptionError::InvalidInput => Error::NotFoundInvalidPath,
+                    broker::SubscriptionError::PermissionDenied => Error::Forbidden { msg: None },
                     broker::SubscriptionError::InternalError => Error::InternalServerError,
                     broker::SubscriptionError::InvalidBufferSize => Error::InternalServerError,
                 },
@@ -445,6 +510,46 @@ impl Viss for Server {
             }),
         }
     }
+
+    async fn authorize(
+        &self,
+        request: AuthorizeRequest,
+    ) -> Result<AuthorizeSuccessResponse, AuthorizeErrorResponse> {
+        let request_id = request.request_id;
+        let permissions = resolve_permissions(&self.authorization, &Some(request.authorization))
+            .map_err(|error| AuthorizeErrorResponse {
+                request_id: request_id.clone(),
+                error,
+                ts: SystemTime::now().into(),
+            })?;
+
+        // Replace the permissions of every subscription of this session
+        let mut subscriptions = self.subscriptions.write().await;
+        for handle in subscriptions.values_mut() {
+            if handle.permissions.is_same_token(&permissions) {
+                continue;
+            }
+            let refreshed = self
+                .broker
+                .authorized_access(&handle.permissions)
+                .in_session(self.session)
+                .refresh_permissions(&permissions)
+                .await;
+            if refreshed.is_err() {
+                return Err(AuthorizeErrorResponse {
+                    request_id,
+                    error: Error::UnauthorizedTokenInvalid,
+                    ts: SystemTime::now().into(),
+                });
+            }
+            handle.permissions = permissions.clone();
+        }
+
+        Ok(AuthorizeSuccessResponse {
+            request_id,
+            ts: SystemTime::now().into(),
+        })
+    }
 }
 
 fn convert_to_viss_stream(
@@ -487,20 +592,22 @@ fn convert_to_viss_stream(
     })
 }
 
+impl From<RateLimitError> for Error {
+    fn from(_: RateLimitError) -> Self {
+        Error::TooManyRequests
+    }
+}
+
 fn resolve_permissions(
     authorization: &Authorization,
     token: &Option<String>,
 ) -> Result<Permissions, Error> {
     match authorization {
         Authorization::Disabled => Ok(permissions::ALLOW_ALL.clone()),
-        Authorization::Enabled { token_decoder } => match token {
-            Some(token) => match token_decoder.decode(token) {
-                Ok(claims) => match Permissions::try_from(claims) {
-                    Ok(permissions) => Ok(permissions),
-                    Err(_) => Err(Error::UnauthorizedTokenInvalid),
-                },
-                Err(_) => Err(Error::UnauthorizedTokenInvalid),
-            },
+        Authorization::Enabled { .. } => match token {
+            Some(token) => authorization
+                .permissions_from_token(token)
+                .map_err(|_| Error::UnauthorizedTokenInvalid),
             None => Err(Error::UnauthorizedTokenMissing),
         },
     }
@@ -565,3 +672,97 @@ fn insert_entry(entries: &mut HashMap<String, MetadataEntry>, path: &str, entry:
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broker::{ChangeType, DataBroker, DataType, EntryType};
+    use crate::vss;
+
+    // Signed with certificates/jwt/jwt.key, scope:
+    // "read:Vehicle !read:Vehicle.Cabin.Seat.*.Occupant.Identifier"
+    const DENY_OCCUPANT_TOKEN: &str =
+        "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.eyJzdWIiOiJsb2NhbCBkZXYiLCJpc3M\
+        iOiJjcmVhdGVUb2tlbi5weSIsImF1ZCI6WyJrdWtzYS52YWwiXSwiaWF0IjoxNzY4NTY\
+        0ODAwLCJleHAiOjE4NjE5MTk5OTksInNjb3BlIjoicmVhZDpWZWhpY2xlICFyZWFkOlZ\
+        laGljbGUuQ2FiaW4uU2VhdC4qLk9jY3VwYW50LklkZW50aWZpZXIifQ.XLzvFgnFf0Kn\
+        MMcg9rEandnUOcEa9M_2piwxrfsRaqHGdQFBP2kZP7btrpMG0sVqYt2KnfiH9MXwHAsG\
+        z_vjlyk9oi1tf0dDqOrD-aIIHK8aFQCMvS9hLcGWKOMKhdy9nIKmtijGCcQ27VaMzMXW\
+        sSkjSlRL4mIyQ9HbyjpkfC049CPLj0ZgwM0qgwPcVtokqxs_Mh3wGNK4PXfRwV8cRWpR\
+        _I_RCyip-b_IQyqr1wsvl9zRm7VBd4qK_FiNWCzpnQ3fOsq2_hfL5cCF4qjDlRquYDAy\
+        gf3jCysUoxnj-fd-CxbAGCddugxuSVMczv7dCCNDQmcYgjXYe94HV1ZSZjNv_VNDlLwn\
+        Ibl-PxRHHLRljdSSQp11yvCuUX0RvpTbMGls0FU-TWvScmn9qNX_LbwnqZg6jB3zcadg\
+        clPVZqKbCUzpxfK8fwshy2i3k8ugoqZ8whcZbqTW-Ah1Ndi57HEFuj4T-AK5kzhzETnx\
+        EUwTo5U3___ulOrXeHFZeZG-RPSpzuGdSRgRZhXZijCSfS_USYxU9H_DtJN0ZNp3ib0p\
+        -tcA_QZOUku3eztro-u6oClfuupNtfUXZZNZ3PCTs4n0pHxZ6IZecMItBunXdcrqfeTn\
+        8n396gqrn3ANmgbTUMzqkLLCPmNYKIuGdfZN4BYbVC8iYGaybnGIwkzr0LE";
+
+    async fn setup() -> Server {
+        let broker = DataBroker::default();
+        let authorized_access = broker.authorized_access(&permissions::ALLOW_ALL);
+        for (path, data_type) in [
+            ("Vehicle.Speed", DataType::Float),
+            (
+                "Vehicle.Cabin.Seat.Row1.Occupant.Identifier",
+                DataType::String,
+            ),
+        ] {
+            authorized_access
+                .add_entry(
+                    path.to_owned(),
+                    vss::DataEntry::new(
+                        data_type,
+                        ChangeType::OnChange,
+                        EntryType::Sensor,
+                        "Run of the mill test signal".to_owned(),
+                    ),
+                )
+                .await
+                .unwrap();
+        }
+
+        let public_key = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../certificates/jwt/jwt.key.pub"
+        ))
+        .unwrap();
+        let authorization = Authorization::new(public_key).unwrap();
+        Server::new(broker, authorization, "127.0.0.1:8090".parse().unwrap())
+    }
+
+    async fn get_metadata(server: &Server, token: Option<&str>) -> HashMap<String, MetadataEntry> {
+        let request: GetRequest = serde_json::from_value(serde_json::json!({
+            "action": "get",
+            "path": "Vehicle",
+            "requestId": "1",
+            "authorization": token,
+            "filter": { "type": "static-metadata" },
+        }))
+        .unwrap();
+        match server.get(request).await {
+            Ok(GetSuccessResponse::Metadata(response)) => response.metadata,
+            _ => panic!("expected a metadata response"),
+        }
+    }
+
+    fn children(metadata: &HashMap<String, MetadataEntry>) -> Vec<&str> {
+        let Some(MetadataEntry::Branch(vehicle)) = metadata.get("Vehicle") else {
+            panic!("expected the Vehicle branch");
+        };
+        let mut children: Vec<&str> = vehicle.children.keys().map(String::as_str).collect();
+        children.sort();
+        children
+    }
+
+    #[tokio::test]
+    async fn test_static_metadata_hides_read_denials() {
+        let server = setup().await;
+
+        let metadata = get_metadata(&server, Some(DENY_OCCUPANT_TOKEN)).await;
+        assert_eq!(children(&metadata), vec!["Speed"]);
+
+        // Metadata doesn't require a token, without one nothing is denied
+        let metadata = get_metadata(&server, None).await;
+        assert_eq!(children(&metadata), vec!["Cabin", "Speed"]);
+    }
+}
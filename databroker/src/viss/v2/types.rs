@@ -11,7 +11,10 @@
 * SPDX-License-Identifier: Apache-2.0
 ********************************************************************************/
 
-use std::{collections::HashMap, time::SystemTime};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::SystemTime,
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -26,6 +29,9 @@ pub enum Request {
     Subscribe(SubscribeRequest),
     #[serde(rename = "unsubscribe")]
     Unsubscribe(UnsubscribeRequest),
+    // Not part of VISS v2, replaces the token of the session's subscriptions
+    #[serde(rename = "authorize")]
+    Authorize(AuthorizeRequest),
 }
 
 // Identify responses using the `Response` trait to prevent
@@ -44,6 +50,9 @@ impl Response for SubscribeErrorResponse {}
 impl Response for UnsubscribeSuccessResponse {}
 impl Response for UnsubscribeErrorResponse {}
 
+impl Response for AuthorizeSuccessResponse {}
+impl Response for AuthorizeErrorResponse {}
+
 impl Response for SubscriptionEvent {}
 impl Response for SubscriptionErrorEvent {}
 
@@ -176,6 +185,28 @@ pub struct UnsubscribeErrorResponse {
     pub ts: Timestamp,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizeRequest {
+    pub request_id: RequestId,
+    pub authorization: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "action", rename = "authorize", rename_all = "camelCase")]
+pub struct AuthorizeSuccessResponse {
+    pub request_id: RequestId,
+    pub ts: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "action", rename = "authorize", rename_all = "camelCase")]
+pub struct AuthorizeErrorResponse {
+    pub request_id: RequestId,
+    pub error: Error,
+    pub ts: Timestamp,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GenericRequest {
@@ -292,6 +323,9 @@ pub enum Value {
     None,
     Scalar(String),
     Array(Vec<String>),
+    // Values of VSS struct types, keyed by property name
+    Struct(BTreeMap<String, Value>),
+    StructArray(Vec<BTreeMap<String, Value>>),
 }
 
 #[derive(Serialize)]
@@ -316,6 +350,8 @@ pub struct SensorEntry {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub unit: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allowed: Option<Value>,
@@ -330,6 +366,10 @@ pub struct AttributeEntry {
     pub datatype: DataType,
     pub description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub unit: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allowed: Option<Value>,
@@ -344,6 +384,8 @@ pub struct ActuatorEntry {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub unit: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allowed: Option<Value>,
@@ -368,6 +410,7 @@ pub enum DataType {
     Uint64,
     Float,
     Double,
+    Timestamp,
     #[serde(rename = "string[]")]
     StringArray,
     #[serde(rename = "boolean[]")]
@@ -392,6 +435,11 @@ pub enum DataType {
     FloatArray,
     #[serde(rename = "double[]")]
     DoubleArray,
+    #[serde(rename = "timestamp[]")]
+    TimestampArray,
+    // Name of a struct type, e.g. "Types.Position" or "Types.Position[]"
+    #[serde(untagged)]
+    Struct(String),
 }
 
 #[derive(Serialize)]
@@ -415,6 +463,7 @@ pub enum Error {
     NotFoundInvalidPath,
     NotFoundUnavailableData,
     NotFoundInvalidSubscriptionId,
+    TooManyRequests,
     InternalServerError,
     NotImplemented,
     ServiceUnavailable,
@@ -490,6 +539,11 @@ impl From<Error> for ErrorSpec {
             // NotAcceptable       406  insufficient_privileges   The privileges represented by the access token are not sufficient.
             // NotAcceptable       406  not_acceptable            The server is unable to generate content that is acceptable to the client
             // TooManyRequests     429  too_many_requests         The client has sent the server too many requests in a given amount of time.
+            Error::TooManyRequests => ErrorSpec {
+                number: 429,
+                reason: "too_many_requests".into(),
+                message: "The client has sent the server too many requests in a given amount of time.".into(),
+            },
             // InternalServerError 500  internal_server_error     The server encountered an unexpected condition which prevented it from fulfilling the request.
             Error::InternalServerError => ErrorSpec {
                 number: 500,
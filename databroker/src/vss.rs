@@ -11,8 +11,10 @@
 * SPDX-License-Identifier: Apache-2.0
 ********************************************************************************/
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use serde::Deserialize;
 
@@ -43,6 +45,7 @@ pub struct Entry {
     entry_type: EntryType,
     description: String,
     comment: Option<String>,
+    deprecation: Option<String>,
 
     // branch only
     children: Option<HashMap<String, Entry>>,
@@ -56,6 +59,8 @@ pub struct Entry {
     allowed: Option<Vec<serde_json::Value>>,
     #[serde(rename = "x-kuksa-changetype")]
     change_type: Option<ChangeType>,
+    #[serde(rename = "x-kuksa-range-policy")]
+    range_policy: Option<RangePolicy>,
 
     // attribute entry type only
     default: Option<serde_json::Value>,
@@ -67,11 +72,38 @@ pub struct DataEntry {
     pub change_type: types::ChangeType,
     pub description: String,
     pub comment: Option<String>,
+    pub deprecation: Option<String>,
     pub unit: Option<String>,
     pub min: Option<types::DataValue>,
     pub max: Option<types::DataValue>,
     pub allowed: Option<types::DataValue>,
     pub default: Option<types::DataValue>,
+    pub range_policy: types::RangePolicy,
+}
+
+impl DataEntry {
+    /// An entry without any of the optional metadata set.
+    pub fn new(
+        data_type: types::DataType,
+        change_type: types::ChangeType,
+        entry_type: types::EntryType,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            data_type,
+            entry_type,
+            change_type,
+            description: description.into(),
+            comment: None,
+            deprecation: None,
+            unit: None,
+            min: None,
+            max: None,
+            allowed: None,
+            default: None,
+            range_policy: types::RangePolicy::default(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -87,6 +119,13 @@ pub enum EntryType {
 
     #[serde(rename = "sensor")]
     Sensor,
+
+    // types tree only
+    #[serde(rename = "struct")]
+    Struct,
+
+    #[serde(rename = "property")]
+    Property,
 }
 
 #[derive(Debug, Deserialize)]
@@ -100,55 +139,85 @@ pub enum ChangeType {
 }
 
 #[derive(Debug, Deserialize)]
+pub enum RangePolicy {
+    #[serde(rename = "reject")]
+    Reject,
+    #[serde(rename = "clamp")]
+    Clamp,
+    #[serde(rename = "warn")]
+    Warn,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "String")]
 pub enum DataType {
-    #[serde(rename = "string")]
     String,
-    #[serde(rename = "boolean")]
     Boolean,
-    #[serde(rename = "int8")]
     Int8,
-    #[serde(rename = "int16")]
     Int16,
-    #[serde(rename = "int32")]
     Int32,
-    #[serde(rename = "int64")]
     Int64,
-    #[serde(rename = "uint8")]
     Uint8,
-    #[serde(rename = "uint16")]
     Uint16,
-    #[serde(rename = "uint32")]
     Uint32,
-    #[serde(rename = "uint64")]
     Uint64,
-    #[serde(rename = "float")]
     Float,
-    #[serde(rename = "double")]
     Double,
-    #[serde(rename = "string[]")]
+    Timestamp,
     StringArray,
-    #[serde(rename = "boolean[]")]
     BooleanArray,
-    #[serde(rename = "int8[]")]
     Int8Array,
-    #[serde(rename = "int16[]")]
     Int16Array,
-    #[serde(rename = "int32[]")]
     Int32Array,
-    #[serde(rename = "int64[]")]
     Int64Array,
-    #[serde(rename = "uint8[]")]
     Uint8Array,
-    #[serde(rename = "uint16[]")]
     Uint16Array,
-    #[serde(rename = "uint32[]")]
     Uint32Array,
-    #[serde(rename = "uint64[]")]
     Uint64Array,
-    #[serde(rename = "float[]")]
     FloatArray,
-    #[serde(rename = "double[]")]
     DoubleArray,
+    TimestampArray,
+    // Any other datatype refers to a struct type declared in a types tree,
+    // e.g. "Types.Position" or "Types.Position[]"
+    Struct(String),
+    StructArray(String),
+}
+
+impl From<String> for DataType {
+    fn from(from: String) -> Self {
+        match from.as_str() {
+            "string" => DataType::String,
+            "boolean" => DataType::Boolean,
+            "int8" => DataType::Int8,
+            "int16" => DataType::Int16,
+            "int32" => DataType::Int32,
+            "int64" => DataType::Int64,
+            "uint8" => DataType::Uint8,
+            "uint16" => DataType::Uint16,
+            "uint32" => DataType::Uint32,
+            "uint64" => DataType::Uint64,
+            "float" => DataType::Float,
+            "double" => DataType::Double,
+            "timestamp" => DataType::Timestamp,
+            "string[]" => DataType::StringArray,
+            "boolean[]" => DataType::BooleanArray,
+            "int8[]" => DataType::Int8Array,
+            "int16[]" => DataType::Int16Array,
+            "int32[]" => DataType::Int32Array,
+            "int64[]" => DataType::Int64Array,
+            "uint8[]" => DataType::Uint8Array,
+            "uint16[]" => DataType::Uint16Array,
+            "uint32[]" => DataType::Uint32Array,
+            "uint64[]" => DataType::Uint64Array,
+            "float[]" => DataType::FloatArray,
+            "double[]" => DataType::DoubleArray,
+            "timestamp[]" => DataType::TimestampArray,
+            _ => match from.strip_suffix("[]") {
+                Some(name) => DataType::StructArray(name.to_owned()),
+                None => DataType::Struct(from),
+            },
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -170,11 +239,56 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+impl From<serde_norway::Error> for Error {
+    fn from(from: serde_norway::Error) -> Self {
+        Error::ParseError(from.to_string())
+    }
+}
+
 impl std::error::Error for Error {}
 
-impl From<DataType> for types::DataType {
-    fn from(from: DataType) -> Self {
-        match from {
+impl Error {
+    /// Prefix the error with the VSS path of the offending node
+    fn at(self, path: &str) -> Self {
+        match self {
+            Error::ParseError(error) => Error::ParseError(format!("{path}: {error}")),
+        }
+    }
+}
+
+struct StructDeclaration {
+    // Branch the struct is declared in, used to resolve relative type names
+    scope: String,
+    properties: Vec<(String, DataType)>,
+}
+
+/// Struct types declared in VSS types trees.
+///
+/// Kept across the VSS files loaded by the databroker, so that signals can
+/// use struct types declared in a separate (earlier) file.
+#[derive(Default)]
+pub struct StructTypes {
+    declared: HashMap<String, StructDeclaration>,
+    resolved: HashMap<String, Arc<types::StructType>>,
+}
+
+impl StructTypes {
+    fn declare(&mut self, name: String, declaration: StructDeclaration) {
+        self.resolved.clear();
+        self.declared.insert(name, declaration);
+    }
+
+    fn resolve(&mut self, data_type: DataType) -> Result<types::DataType, Error> {
+        self.resolve_in_scope(data_type, None, &mut Vec::new())
+    }
+
+    fn resolve_in_scope(
+        &mut self,
+        data_type: DataType,
+        scope: Option<&str>,
+        resolving: &mut Vec<String>,
+    ) -> Result<types::DataType, Error> {
+        let data_type = match data_type {
             DataType::String => types::DataType::String,
             DataType::Boolean => types::DataType::Bool,
             DataType::Int8 => types::DataType::Int8,
@@ -187,6 +301,7 @@ impl From<DataType> for types::DataType {
             DataType::Uint64 => types::DataType::Uint64,
             DataType::Float => types::DataType::Float,
             DataType::Double => types::DataType::Double,
+            DataType::Timestamp => types::DataType::Timestamp,
             DataType::StringArray => types::DataType::StringArray,
             DataType::BooleanArray => types::DataType::BoolArray,
             DataType::Int8Array => types::DataType::Int8Array,
@@ -199,7 +314,58 @@ impl From<DataType> for types::DataType {
             DataType::Uint64Array => types::DataType::Uint64Array,
             DataType::FloatArray => types::DataType::FloatArray,
             DataType::DoubleArray => types::DataType::DoubleArray,
+            DataType::TimestampArray => types::DataType::TimestampArray,
+            DataType::Struct(name) => {
+                types::DataType::Struct(self.resolve_struct(&name, scope, resolving)?)
+            }
+            DataType::StructArray(name) => {
+                types::DataType::StructArray(self.resolve_struct(&name, scope, resolving)?)
+            }
+        };
+        Ok(data_type)
+    }
+
+    fn resolve_struct(
+        &mut self,
+        name: &str,
+        scope: Option<&str>,
+        resolving: &mut Vec<String>,
+    ) -> Result<Arc<types::StructType>, Error> {
+        // Properties may refer to structs declared in the same branch by name only
+        let name = match scope.map(|scope| format!("{scope}.{name}")) {
+            Some(relative) if self.declared.contains_key(&relative) => relative,
+            _ => name.to_owned(),
+        };
+        if let Some(struct_type) = self.resolved.get(&name) {
+            return Ok(struct_type.clone());
+        }
+        if resolving.contains(&name) {
+            return Err(Error::ParseError(format!(
+                "struct type {name} contains itself"
+            )));
+        }
+        let Some(declaration) = self.declared.get(&name) else {
+            return Err(Error::ParseError(format!("unknown datatype {name}")));
+        };
+        let scope = declaration.scope.clone();
+        let properties = declaration.properties.clone();
+
+        resolving.push(name.clone());
+        let mut fields = Vec::with_capacity(properties.len());
+        for (property, data_type) in properties {
+            fields.push(types::StructField {
+                name: property,
+                data_type: self.resolve_in_scope(data_type, Some(&scope), resolving)?,
+            });
         }
+        resolving.pop();
+
+        let struct_type = Arc::new(types::StructType {
+            name: name.clone(),
+            fields,
+        });
+        self.resolved.insert(name, struct_type.clone());
+        Ok(struct_type)
     }
 }
 
@@ -249,6 +415,12 @@ fn try_from_json_array(
                 | types::DataType::Uint64Array
                 | types::DataType::FloatArray
                 | types::DataType::DoubleArray => try_from_json_value(value, data_type),
+                types::DataType::Timestamp | types::DataType::TimestampArray => Err(
+                    Error::ParseError("allowed is not supported for timestamp types".to_owned()),
+                ),
+                types::DataType::Struct(_) | types::DataType::StructArray(_) => Err(
+                    Error::ParseError("allowed is not supported for struct types".to_owned()),
+                ),
             }
         }
         None => Ok(None),
@@ -354,11 +526,79 @@ fn try_from_json_value(
             types::DataType::DoubleArray => serde_json::from_value::<Vec<f64>>(value)
                 .map(|array| Some(types::DataValue::DoubleArray(array)))
                 .map_err(|err| err.into()),
+            types::DataType::Timestamp => serde_json::from_value::<String>(value)
+                .map_err(Error::from)
+                .and_then(|value| try_timestamp_from_str(&value))
+                .map(|value| Some(types::DataValue::Timestamp(value))),
+            types::DataType::TimestampArray => serde_json::from_value::<Vec<String>>(value)
+                .map_err(Error::from)
+                .and_then(|array| {
+                    array
+                        .iter()
+                        .map(|value| try_timestamp_from_str(value))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .map(|array| Some(types::DataValue::TimestampArray(array))),
+            types::DataType::Struct(struct_type) => try_struct_from_json(value, struct_type)
+                .map(|value| Some(types::DataValue::Struct(value))),
+            types::DataType::StructArray(struct_type) => match value {
+                serde_json::Value::Array(array) => array
+                    .into_iter()
+                    .map(|value| try_struct_from_json(value, struct_type))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|array| Some(types::DataValue::StructArray(array))),
+                _ => Err(Error::ParseError(format!(
+                    "expected an array of {}",
+                    struct_type.name
+                ))),
+            },
         },
         None => Ok(None),
     }
 }
 
+fn try_timestamp_from_str(value: &str) -> Result<std::time::SystemTime, Error> {
+    types::from_rfc3339(value)
+        .map_err(|err| Error::ParseError(format!("invalid timestamp {value}: {err}")))
+}
+
+/// Try to extract a struct value from a JSON object holding all the
+/// properties of `struct_type`
+fn try_struct_from_json(
+    value: serde_json::Value,
+    struct_type: &types::StructType,
+) -> Result<types::StructValue, Error> {
+    let serde_json::Value::Object(object) = value else {
+        return Err(Error::ParseError(format!(
+            "expected an object of type {}",
+            struct_type.name
+        )));
+    };
+    let mut fields = types::StructValue::new();
+    for (name, value) in object {
+        let Some(field) = struct_type.field(&name) else {
+            return Err(Error::ParseError(format!(
+                "{} has no property {name}",
+                struct_type.name
+            )));
+        };
+        if let Some(value) = try_from_json_value(Some(value), &field.data_type)? {
+            fields.insert(name, value);
+        }
+    }
+    if let Some(field) = struct_type
+        .fields
+        .iter()
+        .find(|field| !fields.contains_key(&field.name))
+    {
+        return Err(Error::ParseError(format!(
+            "property {} of {} is missing",
+            field.name, struct_type.name
+        )));
+    }
+    Ok(fields)
+}
+
 /// Try to extract a single value matching the given DataType,
 /// i.e. if an array type is given it will try to find a single value of the base type
 /// For example Int32 if the type is Int32 or Int32Array
@@ -394,20 +634,87 @@ fn try_from_json_single_value(
         | types::DataType::Uint64
         | types::DataType::Float
         | types::DataType::Double => try_from_json_value(value, data_type),
+        types::DataType::Timestamp | types::DataType::TimestampArray => match value {
+            Some(_) => Err(Error::ParseError(
+                "min/max is not supported for timestamp types".to_owned(),
+            )),
+            None => Ok(None),
+        },
+        types::DataType::Struct(_) | types::DataType::StructArray(_) => match value {
+            Some(_) => Err(Error::ParseError(
+                "min/max is not supported for struct types".to_owned(),
+            )),
+            None => Ok(None),
+        },
     }
 }
 
-fn flatten_vss_tree(root: RootEntry) -> Result<BTreeMap<String, DataEntry>, Error> {
+fn flatten_vss_tree(
+    root: RootEntry,
+    struct_types: &mut StructTypes,
+) -> Result<BTreeMap<String, DataEntry>, Error> {
     let mut entries = BTreeMap::new();
 
+    // Struct types need to be known before the signals using them
+    for (path, entry) in &root.0 {
+        collect_struct_types(struct_types, path, entry)?;
+    }
     for (path, entry) in root.0 {
-        add_entry(&mut entries, path, entry)?;
+        add_entry(&mut entries, struct_types, path, entry)?;
     }
     Ok(entries)
 }
 
+fn collect_struct_types(
+    struct_types: &mut StructTypes,
+    path: &str,
+    entry: &Entry,
+) -> Result<(), Error> {
+    match entry.entry_type {
+        EntryType::Branch => {
+            for (name, child) in entry.children.iter().flatten() {
+                collect_struct_types(struct_types, &format!("{path}.{name}"), child)?;
+            }
+            Ok(())
+        }
+        EntryType::Struct => {
+            let mut properties = Vec::new();
+            for (name, child) in entry.children.iter().flatten() {
+                match (&child.entry_type, &child.data_type) {
+                    (EntryType::Property, Some(data_type)) => {
+                        properties.push((name.clone(), data_type.clone()))
+                    }
+                    (EntryType::Property, None) => {
+                        return Err(Error::ParseError(format!(
+                            "datatype required for property {path}.{name}"
+                        )))
+                    }
+                    _ => {
+                        return Err(Error::ParseError(format!(
+                            "struct {path} may only contain properties"
+                        )))
+                    }
+                }
+            }
+            // JSON objects are unordered, keep the fields in a stable order
+            properties.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let scope = match path.rsplit_once('.') {
+                Some((scope, _)) => scope.to_owned(),
+                None => String::new(),
+            };
+            struct_types.declare(path.to_owned(), StructDeclaration { scope, properties });
+            Ok(())
+        }
+        EntryType::Property => Err(Error::ParseError(format!(
+            "property {path} must be declared in a struct"
+        ))),
+        EntryType::Actuator | EntryType::Attribute | EntryType::Sensor => Ok(()),
+    }
+}
+
 fn add_entry(
     entries: &mut BTreeMap<String, DataEntry>,
+    struct_types: &mut StructTypes,
     path: String,
     entry: Entry,
 ) -> Result<(), Error> {
@@ -415,101 +722,86 @@ fn add_entry(
         EntryType::Branch => match entry.children {
             Some(children) => {
                 for (name, child) in children {
-                    add_entry(entries, format!("{path}.{name}"), child)?;
+                    add_entry(entries, struct_types, format!("{path}.{name}"), child)?;
                 }
                 Ok(())
             }
-            None => Err(Error::ParseError(
-                "children required for type branch".to_owned(),
-            )),
+            None => Err(Error::ParseError(format!(
+                "{path}: children required for type branch"
+            ))),
         },
-        EntryType::Actuator => {
-            let data_type = match entry.data_type {
-                Some(data_type) => data_type.into(),
-                None => {
-                    return Err(Error::ParseError(
-                        "data_type required for actuator".to_owned(),
-                    ))
-                }
-            };
-            let _ = entries.insert(
-                path,
-                DataEntry {
-                    entry_type: types::EntryType::Actuator,
-                    change_type: determine_change_type(
-                        entry.change_type,
-                        types::EntryType::Actuator,
-                    ),
-                    description: entry.description,
-                    comment: entry.comment,
-                    unit: entry.unit,
-                    min: try_from_json_single_value(entry.min, &data_type)?,
-                    max: try_from_json_single_value(entry.max, &data_type)?,
-                    allowed: try_from_json_array(entry.allowed, &data_type)?,
-                    default: None, // isn't used by actuators
-                    data_type,
-                },
-            );
-            Ok(())
-        }
-        EntryType::Attribute => {
-            let data_type = match entry.data_type {
-                Some(data_type) => data_type.into(),
-                None => {
-                    return Err(Error::ParseError(
-                        "data_type required for actuator".to_owned(),
-                    ))
-                }
-            };
-            let _ = entries.insert(
-                path,
-                DataEntry {
-                    entry_type: types::EntryType::Attribute,
-                    description: entry.description,
-                    comment: entry.comment,
-                    unit: entry.unit,
-                    min: try_from_json_single_value(entry.min, &data_type)?,
-                    max: try_from_json_single_value(entry.max, &data_type)?,
-                    allowed: try_from_json_array(entry.allowed, &data_type)?,
-                    default: try_from_json_value(entry.default, &data_type)?,
-                    change_type: determine_change_type(
-                        entry.change_type,
-                        types::EntryType::Attribute,
-                    ),
-                    data_type,
-                },
-            );
-            Ok(())
-        }
+        // Already collected by collect_struct_types
+        EntryType::Struct | EntryType::Property => Ok(()),
+        EntryType::Actuator => add_data_entry(
+            entries,
+            struct_types,
+            path,
+            entry,
+            types::EntryType::Actuator,
+        ),
+        EntryType::Attribute => add_data_entry(
+            entries,
+            struct_types,
+            path,
+            entry,
+            types::EntryType::Attribute,
+        ),
         EntryType::Sensor => {
-            let data_type = match entry.data_type {
-                Some(data_type) => data_type.into(),
-                None => {
-                    return Err(Error::ParseError(
-                        "data_type required for actuator".to_owned(),
-                    ))
-                }
-            };
-            let _ = entries.insert(
-                path,
-                DataEntry {
-                    entry_type: types::EntryType::Sensor,
-                    description: entry.description,
-                    comment: entry.comment,
-                    unit: entry.unit,
-                    min: try_from_json_single_value(entry.min, &data_type)?,
-                    max: try_from_json_single_value(entry.max, &data_type)?,
-                    allowed: try_from_json_array(entry.allowed, &data_type)?,
-                    change_type: determine_change_type(entry.change_type, types::EntryType::Sensor),
-                    default: None, // isn't used by sensors
-                    data_type,
-                },
-            );
-            Ok(())
+            add_data_entry(entries, struct_types, path, entry, types::EntryType::Sensor)
         }
     }
 }
 
+fn add_data_entry(
+    entries: &mut BTreeMap<String, DataEntry>,
+    struct_types: &mut StructTypes,
+    path: String,
+    entry: Entry,
+    entry_type: types::EntryType,
+) -> Result<(), Error> {
+    let data_entry = to_data_entry(struct_types, entry, entry_type).map_err(|err| err.at(&path))?;
+    let _ = entries.insert(path, data_entry);
+    Ok(())
+}
+
+fn to_data_entry(
+    struct_types: &mut StructTypes,
+    entry: Entry,
+    entry_type: types::EntryType,
+) -> Result<DataEntry, Error> {
+    let data_type = match entry.data_type {
+        Some(data_type) => struct_types.resolve(data_type)?,
+        None => {
+            return Err(Error::ParseError(
+                "datatype required for actuator, attribute and sensor".to_owned(),
+            ))
+        }
+    };
+    let default = match entry_type {
+        types::EntryType::Attribute => try_from_json_value(entry.default, &data_type)?,
+        // isn't used by actuators and sensors
+        _ => None,
+    };
+    Ok(DataEntry {
+        change_type: determine_change_type(entry.change_type, entry_type.clone()),
+        entry_type,
+        description: entry.description,
+        comment: entry.comment,
+        deprecation: entry.deprecation,
+        unit: entry.unit,
+        min: try_from_json_single_value(entry.min, &data_type)?,
+        max: try_from_json_single_value(entry.max, &data_type)?,
+        allowed: try_from_json_array(entry.allowed, &data_type)?,
+        default,
+        range_policy: match entry.range_policy {
+            Some(RangePolicy::Reject) | None => types::RangePolicy::Reject,
+            Some(RangePolicy::Clamp) => types::RangePolicy::Clamp,
+            Some(RangePolicy::Warn) => types::RangePolicy::Warn,
+        },
+        data_type,
+    })
+}
+
 fn determine_change_type(
     change_type: Option<ChangeType>,
     entry_type: types::EntryType,
@@ -530,19 +822,648 @@ fn determine_change_type(
     }
 }
 
+/// A VSS tree merged from a base specification and any number of overlays.
+///
+/// Overlays are merged in order: keys given for nodes which already exist
+/// (e.g. description, min, max or allowed) replace the previous ones, new
+/// nodes are added and nodes marked with `"delete": true` are removed.
+/// Branches declaring `instances` are expanded when the tree is converted
+/// into entries, so overlays may refer to both the declaring branch and to
+/// individual instances.
+#[derive(Debug, Default)]
+pub struct VssTree {
+    root: serde_json::Map<String, serde_json::Value>,
+}
+
+impl VssTree {
+    pub fn merge_from_reader<R>(&mut self, reader: R) -> Result<(), Error>
+    where
+        R: std::io::Read,
+    {
+        let overlay = serde_json::from_reader::<R, serde_json::Map<_, _>>(reader)?;
+        self.merge(overlay)
+    }
+
+    /// Merge a VSS file, which is read as YAML (vss-tools YAML export or
+    /// vspec, including `#include` directives) if its extension is .yaml,
+    /// .yml or .vspec and as JSON otherwise
+    pub fn merge_from_file(&mut self, filename: impl AsRef<Path>) -> Result<(), Error> {
+        let filename = filename.as_ref();
+        match filename
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("yaml" | "yml" | "vspec") => {
+                self.merge_vspec_file(filename, None, &mut Vec::new())
+            }
+            _ => self
+                .merge_from_reader(std::io::BufReader::new(open_file(filename)?))
+                .map_err(|err| err.at(&filename.display().to_string())),
+        }
+    }
+
+    /// Merge YAML nodes, keyed either by their full (dot separated) path like
+    /// in vspec files or nested in `children` like in JSON
+    pub fn merge_from_yaml_reader<R>(&mut self, reader: R) -> Result<(), Error>
+    where
+        R: std::io::Read,
+    {
+        let nodes = serde_norway::from_reader::<R, Option<serde_json::Map<_, _>>>(reader)?
+            .unwrap_or_default();
+        self.merge_paths(nodes, None)
+    }
+
+    pub fn merge(
+        &mut self,
+        overlay: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), Error> {
+        merge_children(&mut self.root, overlay, None)
+    }
+
+    /// `including` are the (canonical) files currently being merged, i.e.
+    /// the chain of includes leading to this file.
+    fn merge_vspec_file(
+        &mut self,
+        filename: &Path,
+        prefix: Option<&str>,
+        including: &mut Vec<PathBuf>,
+    ) -> Result<(), Error> {
+        let canonical = std::fs::canonicalize(filename)
+            .map_err(|err| Error::ParseError(format!("{}: {err}", filename.display())))?;
+        if including.contains(&canonical) {
+            return Err(Error::ParseError(format!(
+                "{}: #include cycle",
+                filename.display()
+            )));
+        }
+        including.push(canonical);
+
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut open_file(filename)?, &mut content)
+            .map_err(|err| Error::ParseError(format!("{}: {err}", filename.display())))?;
+        let in_file = |err: Error| err.at(&filename.display().to_string());
+
+        // Nodes following an #include may refer to the included ones, so
+        // the parts in between are merged in order
+        let mut part = String::new();
+        for (index, line) in content.lines().enumerate() {
+            match line.trim().strip_prefix("#include") {
+                Some(include) => {
+                    self.merge_vspec_part(&part, prefix).map_err(in_file)?;
+                    // Keep the line numbers of YAML errors in the next part
+                    part = "\n".repeat(index + 1);
+                    let mut args = include.split_whitespace();
+                    let Some(included) = args.next() else {
+                        return Err(in_file(Error::ParseError(
+                            "#include without file".to_owned(),
+                        )));
+                    };
+                    let included_prefix = match (prefix, args.next()) {
+                        (Some(prefix), Some(included_prefix)) => {
+                            Some(format!("{prefix}.{included_prefix}"))
+                        }
+                        (None, Some(included_prefix)) => Some(included_prefix.to_owned()),
+                        (prefix, None) => prefix.map(str::to_owned),
+                    };
+                    let included = filename.parent().unwrap_or(Path::new("")).join(included);
+                    self.merge_vspec_file(&included, included_prefix.as_deref(), including)?;
+                }
+                None => {
+                    part.push_str(line);
+                    part.push('\n');
+                }
+            }
+        }
+        self.merge_vspec_part(&part, prefix).map_err(in_file)?;
+        including.pop();
+        Ok(())
+    }
+
+    fn merge_vspec_part(&mut self, part: &str, prefix: Option<&str>) -> Result<(), Error> {
+        let nodes =
+            serde_norway::from_str::<Option<serde_json::Map<_, _>>>(part)?.unwrap_or_default();
+        self.merge_paths(nodes, prefix)
+    }
+
+    fn merge_paths(
+        &mut self,
+        nodes: serde_json::Map<String, serde_json::Value>,
+        prefix: Option<&str>,
+    ) -> Result<(), Error> {
+        for (path, node) in nodes {
+            let path = match prefix {
+                Some(prefix) => format!("{prefix}.{path}"),
+                None => path,
+            };
+            let (parent, name) = match path.rsplit_once('.') {
+                Some((parent, name)) => (Some(parent), name),
+                None => (None, path.as_str()),
+            };
+            let mut children = &mut self.root;
+            for branch in parent.iter().flat_map(|parent| parent.split('.')) {
+                let Some(serde_json::Value::Object(branch)) = children.get_mut(branch) else {
+                    return Err(Error::ParseError(format!(
+                        "{path}: parent branch is not declared"
+                    )));
+                };
+                let branch_children = branch
+                    .entry("children")
+                    .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+                let serde_json::Value::Object(branch_children) = branch_children else {
+                    return Err(Error::ParseError(format!(
+                        "{path}: children of parent branch is not an object"
+                    )));
+                };
+                children = branch_children;
+            }
+            let node = serde_json::Map::from_iter([(name.to_owned(), node)]);
+            merge_children(children, node, parent)?;
+        }
+        Ok(())
+    }
+
+    pub fn into_entries(self) -> Result<BTreeMap<String, DataEntry>, Error> {
+        self.into_entries_with_struct_types(&mut StructTypes::default())
+    }
+
+    fn into_entries_with_struct_types(
+        self,
+        struct_types: &mut StructTypes,
+    ) -> Result<BTreeMap<String, DataEntry>, Error> {
+        let mut root = self.root;
+        for (path, node) in root.iter_mut() {
+            expand_instances(path, node)?;
+        }
+        let root_entry =
+            serde_path_to_error::deserialize::<_, RootEntry>(serde_json::Value::Object(root))
+                .map_err(|err| {
+                    // The path of the offending node, without the "children" in between
+                    let path = err
+                        .path()
+                        .iter()
+                        .filter_map(|segment| match segment {
+                            serde_path_to_error::Segment::Map { key } if key != "children" => {
+                                Some(key.as_str())
+                            }
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join(".");
+                    Error::ParseError(err.into_inner().to_string()).at(&path)
+                })?;
+
+        flatten_vss_tree(root_entry, struct_types)
+    }
+}
+
+fn merge_children(
+    base: &mut serde_json::Map<String, serde_json::Value>,
+    overlay: serde_json::Map<String, serde_json::Value>,
+    path: Option<&str>,
+) -> Result<(), Error> {
+    for (name, node) in overlay {
+        let node_path = match path {
+            Some(path) => format!("{path}.{name}"),
+            None => name.clone(),
+        };
+        let serde_json::Value::Object(node) = node else {
+            return Err(Error::ParseError(format!("{node_path} is not an object")));
+        };
+        if node.get("delete") == Some(&serde_json::Value::Bool(true)) {
+            base.remove(&name);
+            continue;
+        }
+        match base.get_mut(&name) {
+            Some(serde_json::Value::Object(existing)) => merge_node(existing, node, &node_path)?,
+            _ => {
+                let mut new_node = serde_json::Map::new();
+                merge_node(&mut new_node, node, &node_path)?;
+                base.insert(name, serde_json::Value::Object(new_node));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge_node(
+    base: &mut serde_json::Map<String, serde_json::Value>,
+    overlay: serde_json::Map<String, serde_json::Value>,
+    path: &str,
+) -> Result<(), Error> {
+    for (key, value) in overlay {
+        if key == "children" {
+            let serde_json::Value::Object(children) = value else {
+                return Err(Error::ParseError(format!(
+                    "children of {path} is not an object"
+                )));
+            };
+            let mut base_children = match base.remove("children") {
+                Some(serde_json::Value::Object(base_children)) => base_children,
+                _ => serde_json::Map::new(),
+            };
+            merge_children(&mut base_children, children, Some(path))?;
+            base.insert(key, serde_json::Value::Object(base_children));
+        } else {
+            base.insert(key, value);
+        }
+    }
+    Ok(())
+}
+
+fn open_file(filename: &Path) -> Result<std::fs::File, Error> {
+    std::fs::File::open(filename)
+        .map_err(|err| Error::ParseError(format!("{}: {err}", filename.display())))
+}
+
+/// Replace the children of branches declaring `instances` by one branch per
+/// instance (e.g. Row1.DriverSide, Row1.PassengerSide, ...) each holding a
+/// copy of them. Children with `"instantiate": false` stay where they are,
+/// children named like an instance (e.g. Row1) are merged into it.
+fn expand_instances(path: &str, node: &mut serde_json::Value) -> Result<(), Error> {
+    // Anything which isn't an object is reported when parsing the entries
+    let Some(node) = node.as_object_mut() else {
+        return Ok(());
+    };
+    if let Some(serde_json::Value::Object(children)) = node.get_mut("children") {
+        for (name, child) in children.iter_mut() {
+            expand_instances(&format!("{path}.{name}"), child)?;
+        }
+    }
+    let Some(instances) = node.remove("instances") else {
+        return Ok(());
+    };
+
+    let dimensions = instance_dimensions(path, &instances)?;
+    let mut template = serde_json::Map::new();
+    let mut not_instantiated = serde_json::Map::new();
+    let mut instance_overrides = serde_json::Map::new();
+    if let Some(serde_json::Value::Object(children)) = node.remove("children") {
+        for (name, child) in children {
+            if child.get("instantiate") == Some(&serde_json::Value::Bool(false)) {
+                not_instantiated.insert(name, child);
+            } else if dimensions[0].contains(&name) {
+                instance_overrides.insert(name, child);
+            } else {
+                template.insert(name, child);
+            }
+        }
+    }
+
+    let description = node
+        .get("description")
+        .cloned()
+        .unwrap_or(serde_json::Value::String(String::new()));
+    let mut children = instantiate(&dimensions, &template, &description);
+    merge_children(&mut children, instance_overrides, Some(path))?;
+    children.extend(not_instantiated);
+    node.insert("children".to_owned(), serde_json::Value::Object(children));
+    Ok(())
+}
+
+fn instantiate(
+    dimensions: &[Vec<String>],
+    template: &serde_json::Map<String, serde_json::Value>,
+    description: &serde_json::Value,
+) -> serde_json::Map<String, serde_json::Value> {
+    match dimensions.split_first() {
+        Some((names, dimensions)) => names
+            .iter()
+            .map(|name| {
+                let branch = serde_json::json!({
+                    "type": "branch",
+                    "description": description,
+                    "children": instantiate(dimensions, template, description),
+                });
+                (name.clone(), branch)
+            })
+            .collect(),
+        None => template.clone(),
+    }
+}
+
+/// Instance names of each level, e.g. [["Row1", "Row2"], ["DriverSide", "PassengerSide"]]
+/// for `["Row[1,2]", ["DriverSide", "PassengerSide"]]`. A list of strings only,
+/// e.g. `["Left", "Right"]`, declares a single level.
+fn instance_dimensions(
+    path: &str,
+    instances: &serde_json::Value,
+) -> Result<Vec<Vec<String>>, Error> {
+    let invalid = || Error::ParseError(format!("invalid instances of {path}"));
+    let names = |values: &[serde_json::Value]| {
+        values.iter().try_fold(Vec::new(), |mut names, value| {
+            names.extend(
+                expand_instance_range(value.as_str().ok_or_else(invalid)?).ok_or_else(invalid)?,
+            );
+            Ok::<_, Error>(names)
+        })
+    };
+    let dimensions = match instances {
+        serde_json::Value::String(instance) => {
+            vec![expand_instance_range(instance).ok_or_else(invalid)?]
+        }
+        serde_json::Value::Array(levels) if levels.iter().any(|level| level.is_array()) => levels
+            .iter()
+            .map(|level| match level {
+                serde_json::Value::Array(values) => names(values),
+                value => names(std::slice::from_ref(value)),
+            })
+            .collect::<Result<_, _>>()?,
+        serde_json::Value::Array(values) => vec![names(values)?],
+        _ => return Err(invalid()),
+    };
+    if dimensions.is_empty() || dimensions.iter().any(Vec::is_empty) {
+        return Err(invalid());
+    }
+    Ok(dimensions)
+}
+
+/// Expand "Row[1,4]" to Row1, Row2, Row3 and Row4, other names are kept as is
+fn expand_instance_range(instance: &str) -> Option<Vec<String>> {
+    let Some((prefix, range)) = instance
+        .strip_suffix(']')
+        .and_then(|instance| instance.split_once('['))
+    else {
+        return Some(vec![instance.to_owned()]);
+    };
+    let (first, last) = range.split_once(',')?;
+    let first = first.trim().parse::<u32>().ok()?;
+    let last = last.trim().parse::<u32>().ok()?;
+    if first > last {
+        return None;
+    }
+    Some(
+        (first..=last)
+            .map(|index| format!("{prefix}{index}"))
+            .collect(),
+    )
+}
+
 pub fn parse_vss_from_reader<R>(reader: R) -> Result<BTreeMap<String, DataEntry>, Error>
 where
     R: std::io::Read,
 {
-    let root_entry = serde_json::from_reader::<R, RootEntry>(reader)?;
+    parse_vss_from_reader_with_struct_types(reader, &mut StructTypes::default())
+}
 
-    flatten_vss_tree(root_entry)
+/// Like [`parse_vss_from_reader`], but signals may also use struct types
+/// declared in previously parsed files. Struct types declared in this file
+/// are added to `struct_types`.
+pub fn parse_vss_from_reader_with_struct_types<R>(
+    reader: R,
+    struct_types: &mut StructTypes,
+) -> Result<BTreeMap<String, DataEntry>, Error>
+where
+    R: std::io::Read,
+{
+    let mut tree = VssTree::default();
+    tree.merge_from_reader(reader)?;
+    tree.into_entries_with_struct_types(struct_types)
+}
+
+/// Convert a JSON value (as used in VSS files) to a value of the given DataType
+pub fn parse_value_from_json(
+    value: serde_json::Value,
+    data_type: &types::DataType,
+) -> Result<types::DataValue, Error> {
+    try_from_json_value(Some(value), data_type)
+        .map(|value| value.unwrap_or(types::DataValue::NotAvailable))
 }
 
 pub fn parse_vss_from_str(data: &str) -> Result<BTreeMap<String, DataEntry>, Error> {
-    let root_entry = serde_json::from_str::<RootEntry>(data)?;
+    parse_vss_from_reader(data.as_bytes())
+}
 
-    flatten_vss_tree(root_entry)
+/// A VSS tree in (nested) JSON format built from data entries, i.e. the
+/// reverse of [`VssTree::into_entries`].
+///
+/// Struct types used by the entries are exported into the same tree, so the
+/// result can be parsed again with [`parse_vss_from_str`]. Branches only
+/// exist implicitly in the broker, so they are exported without description.
+#[derive(Debug, Default)]
+pub struct VssExport {
+    root: serde_json::Map<String, serde_json::Value>,
+    struct_types: HashSet<String>,
+}
+
+impl VssExport {
+    /// Add a data entry, optionally with its current value as "x-kuksa-value".
+    ///
+    /// Fails if the entry would end up below another entry or in place of a
+    /// branch, so entries should be added sorted by path.
+    pub fn add_entry(
+        &mut self,
+        path: &str,
+        entry: &DataEntry,
+        value: Option<&types::DataValue>,
+    ) -> Result<(), Error> {
+        let entry_type = match entry.entry_type {
+            types::EntryType::Sensor => "sensor",
+            types::EntryType::Attribute => "attribute",
+            types::EntryType::Actuator => "actuator",
+        };
+        let change_type = match entry.change_type {
+            types::ChangeType::Static => "static",
+            types::ChangeType::OnChange => "onchange",
+            types::ChangeType::Continuous => "continuous",
+        };
+        let mut node = serde_json::Map::new();
+        node.insert("type".to_owned(), entry_type.into());
+        node.insert(
+            "datatype".to_owned(),
+            data_type_name(&entry.data_type).into(),
+        );
+        node.insert("description".to_owned(), entry.description.clone().into());
+        node.insert("x-kuksa-changetype".to_owned(), change_type.into());
+        let optional = [
+            (
+                "comment",
+                entry.comment.clone().map(serde_json::Value::from),
+            ),
+            (
+                "deprecation",
+                entry.deprecation.clone().map(serde_json::Value::from),
+            ),
+            ("unit", entry.unit.clone().map(serde_json::Value::from)),
+            ("min", entry.min.as_ref().map(value_to_json)),
+            ("max", entry.max.as_ref().map(value_to_json)),
+            ("allowed", entry.allowed.as_ref().map(value_to_json)),
+            ("default", entry.default.as_ref().map(value_to_json)),
+            (
+                "x-kuksa-range-policy",
+                (entry.range_policy != types::RangePolicy::default())
+                    .then(|| entry.range_policy.to_string().into()),
+            ),
+            (
+                "x-kuksa-value",
+                value
+                    .filter(|value| **value != types::DataValue::NotAvailable)
+                    .map(value_to_json),
+            ),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                node.insert(key.to_owned(), value);
+            }
+        }
+
+        self.add_struct_type(&entry.data_type)?;
+        insert_node(&mut self.root, path, node)
+    }
+
+    fn add_struct_type(&mut self, data_type: &types::DataType) -> Result<(), Error> {
+        let (types::DataType::Struct(struct_type) | types::DataType::StructArray(struct_type)) =
+            data_type
+        else {
+            return Ok(());
+        };
+        if !self.struct_types.insert(struct_type.name.clone()) {
+            return Ok(());
+        }
+        let mut properties = serde_json::Map::new();
+        for field in &struct_type.fields {
+            properties.insert(
+                field.name.clone(),
+                serde_json::json!({
+                    "type": "property",
+                    "datatype": data_type_name(&field.data_type),
+                    "description": "",
+                }),
+            );
+            self.add_struct_type(&field.data_type)?;
+        }
+        let node = serde_json::Map::from_iter([
+            ("type".to_owned(), "struct".into()),
+            ("description".to_owned(), "".into()),
+            ("children".to_owned(), serde_json::Value::Object(properties)),
+        ]);
+        insert_node(&mut self.root, &struct_type.name, node)
+    }
+
+    pub fn into_json(self) -> serde_json::Value {
+        serde_json::Value::Object(self.root)
+    }
+}
+
+/// Insert a node at the given (dot separated) path, creating the branches
+/// leading to it if needed
+fn insert_node(
+    root: &mut serde_json::Map<String, serde_json::Value>,
+    path: &str,
+    node: serde_json::Map<String, serde_json::Value>,
+) -> Result<(), Error> {
+    let (parent, name) = match path.rsplit_once('.') {
+        Some((parent, name)) => (Some(parent), name),
+        None => (None, path),
+    };
+    let mut children = root;
+    for branch_name in parent.iter().flat_map(|parent| parent.split('.')) {
+        let branch = children.entry(branch_name).or_insert_with(|| {
+            serde_json::json!({
+                "type": "branch",
+                "description": "",
+                "children": {},
+            })
+        });
+        let branch_children = match branch {
+            serde_json::Value::Object(branch)
+                if branch.get("type").and_then(serde_json::Value::as_str) == Some("branch") =>
+            {
+                branch
+                    .get_mut("children")
+                    .and_then(serde_json::Value::as_object_mut)
+            }
+            _ => None,
+        };
+        let Some(branch_children) = branch_children else {
+            return Err(Error::ParseError(format!("{branch_name} is not a branch")).at(path));
+        };
+        children = branch_children;
+    }
+    match children.entry(name) {
+        serde_json::map::Entry::Vacant(vacant) => {
+            vacant.insert(serde_json::Value::Object(node));
+            Ok(())
+        }
+        serde_json::map::Entry::Occupied(_) => {
+            Err(Error::ParseError("already exported".to_owned()).at(path))
+        }
+    }
+}
+
+/// The VSS name of a data type, e.g. "uint8[]" or "Types.Position"
+fn data_type_name(data_type: &types::DataType) -> String {
+    let name = match data_type {
+        types::DataType::String => "string",
+        types::DataType::Bool => "boolean",
+        types::DataType::Int8 => "int8",
+        types::DataType::Int16 => "int16",
+        types::DataType::Int32 => "int32",
+        types::DataType::Int64 => "int64",
+        types::DataType::Uint8 => "uint8",
+        types::DataType::Uint16 => "uint16",
+        types::DataType::Uint32 => "uint32",
+        types::DataType::Uint64 => "uint64",
+        types::DataType::Float => "float",
+        types::DataType::Double => "double",
+        types::DataType::Timestamp => "timestamp",
+        types::DataType::StringArray => "string[]",
+        types::DataType::BoolArray => "boolean[]",
+        types::DataType::Int8Array => "int8[]",
+        types::DataType::Int16Array => "int16[]",
+        types::DataType::Int32Array => "int32[]",
+        types::DataType::Int64Array => "int64[]",
+        types::DataType::Uint8Array => "uint8[]",
+        types::DataType::Uint16Array => "uint16[]",
+        types::DataType::Uint32Array => "uint32[]",
+        types::DataType::Uint64Array => "uint64[]",
+        types::DataType::FloatArray => "float[]",
+        types::DataType::DoubleArray => "double[]",
+        types::DataType::TimestampArray => "timestamp[]",
+        types::DataType::Struct(struct_type) => return struct_type.name.clone(),
+        types::DataType::StructArray(struct_type) => return format!("{}[]", struct_type.name),
+    };
+    name.to_owned()
+}
+
+/// Convert a value to JSON as used in VSS files, i.e. the reverse of
+/// [`parse_value_from_json`]
+pub fn value_to_json(value: &types::DataValue) -> serde_json::Value {
+    match value {
+        types::DataValue::NotAvailable => serde_json::Value::Null,
+        types::DataValue::Bool(value) => serde_json::json!(value),
+        types::DataValue::String(value) => serde_json::json!(value),
+        types::DataValue::Int32(value) => serde_json::json!(value),
+        types::DataValue::Int64(value) => serde_json::json!(value),
+        types::DataValue::Uint32(value) => serde_json::json!(value),
+        types::DataValue::Uint64(value) => serde_json::json!(value),
+        types::DataValue::Float(value) => serde_json::json!(value),
+        types::DataValue::Double(value) => serde_json::json!(value),
+        types::DataValue::BoolArray(values) => serde_json::json!(values),
+        types::DataValue::StringArray(values) => serde_json::json!(values),
+        types::DataValue::Int32Array(values) => serde_json::json!(values),
+        types::DataValue::Int64Array(values) => serde_json::json!(values),
+        types::DataValue::Uint32Array(values) => serde_json::json!(values),
+        types::DataValue::Uint64Array(values) => serde_json::json!(values),
+        types::DataValue::FloatArray(values) => serde_json::json!(values),
+        types::DataValue::Timestamp(value) => serde_json::json!(types::to_rfc3339(value)),
+        types::DataValue::DoubleArray(values) => serde_json::json!(values),
+        types::DataValue::TimestampArray(values) => {
+            serde_json::json!(values.iter().map(types::to_rfc3339).collect::<Vec<_>>())
+        }
+        types::DataValue::Struct(fields) => struct_to_json(fields),
+        types::DataValue::StructArray(values) => {
+            serde_json::Value::Array(values.iter().map(struct_to_json).collect())
+        }
+    }
+}
+
+fn struct_to_json(fields: &types::StructValue) -> serde_json::Value {
+    serde_json::Value::Object(
+        fields
+            .iter()
+            .map(|(name, value)| (name.clone(), value_to_json(value)))
+            .collect(),
+    )
 }
 
 #[test]
@@ -627,7 +1548,7 @@ fn test_parse_vss() {
         Err(err) => panic!("{}", err),
     };
 
-    match flatten_vss_tree(root_entry) {
+    match flatten_vss_tree(root_entry, &mut StructTypes::default()) {
         Ok(entries) => {
             assert_eq!(entries.len(), 5);
             match entries.get("Vehicle.ADAS.ESC.IsEnabled") {
@@ -696,3 +1617,640 @@ fn test_parse_vss() {
         Err(err) => panic!("Expected parsing to work: {err:?}"),
     }
 }
+
+#[test]
+fn test_parse_vss_struct_types() {
+    let types = r#"
+{
+    "Types": {
+        "children": {
+            "Position": {
+                "children": {
+                    "Latitude": {
+                        "datatype": "double",
+                        "description": "Latitude in degrees.",
+                        "type": "property"
+                    },
+                    "Accuracy": {
+                        "datatype": "Accuracy",
+                        "description": "Accuracy of the position.",
+                        "type": "property"
+                    }
+                },
+                "description": "A position.",
+                "type": "struct"
+            },
+            "Accuracy": {
+                "children": {
+                    "Horizontal": {
+                        "datatype": "uint8",
+                        "description": "Horizontal accuracy in meters.",
+                        "type": "property"
+                    }
+                },
+                "description": "Accuracy of a position.",
+                "type": "struct"
+            }
+        },
+        "description": "User defined types.",
+        "type": "branch"
+    }
+}"#;
+    let signals = r#"
+{
+    "Vehicle": {
+        "children": {
+            "Position": {
+                "datatype": "Types.Position",
+                "description": "Current position.",
+                "type": "sensor"
+            },
+            "Waypoints": {
+                "datatype": "Types.Position[]",
+                "default": [{"Latitude": 48.1, "Accuracy": {"Horizontal": 5}}],
+                "description": "Stored waypoints.",
+                "type": "attribute"
+            }
+        },
+        "description": "High-level vehicle data.",
+        "type": "branch"
+    }
+}"#;
+
+    let mut struct_types = StructTypes::default();
+    let entries =
+        parse_vss_from_reader_with_struct_types(types.as_bytes(), &mut struct_types).unwrap();
+    assert!(entries.is_empty());
+
+    let entries =
+        parse_vss_from_reader_with_struct_types(signals.as_bytes(), &mut struct_types).unwrap();
+    let position = match &entries.get("Vehicle.Position").unwrap().data_type {
+        types::DataType::Struct(position) => position.clone(),
+        data_type => panic!("Expected struct type, got {data_type:?}"),
+    };
+    assert_eq!(position.name, "Types.Position");
+    assert_eq!(position.fields.len(), 2);
+    match &position.field("Accuracy").unwrap().data_type {
+        types::DataType::Struct(accuracy) => {
+            assert_eq!(accuracy.name, "Types.Accuracy");
+            assert_eq!(accuracy.fields[0].data_type, types::DataType::Uint8);
+        }
+        data_type => panic!("Expected struct type, got {data_type:?}"),
+    }
+
+    let waypoints = entries.get("Vehicle.Waypoints").unwrap();
+    assert_eq!(waypoints.data_type, types::DataType::StructArray(position));
+    assert_eq!(
+        waypoints.default,
+        Some(types::DataValue::StructArray(vec![
+            types::StructValue::from([
+                ("Latitude".to_owned(), types::DataValue::Double(48.1)),
+                (
+                    "Accuracy".to_owned(),
+                    types::DataValue::Struct(types::StructValue::from([(
+                        "Horizontal".to_owned(),
+                        types::DataValue::Uint32(5)
+                    )]))
+                ),
+            ])
+        ]))
+    );
+
+    // Struct types are not known without the types tree
+    assert!(parse_vss_from_str(signals).is_err());
+}
+
+#[test]
+fn test_parse_vss_timestamp() {
+    let signals = r#"
+{
+    "Vehicle": {
+        "children": {
+            "LastService": {
+                "datatype": "timestamp",
+                "default": "2024-05-01T14:00:00.5+02:00",
+                "description": "Time of the last service.",
+                "type": "attribute"
+            },
+            "ServiceHistory": {
+                "datatype": "timestamp[]",
+                "default": ["1970-01-01T00:00:00Z"],
+                "description": "Times of all services.",
+                "type": "attribute"
+            }
+        },
+        "description": "High-level vehicle data.",
+        "type": "branch"
+    }
+}"#;
+
+    let entries = parse_vss_from_str(signals).unwrap();
+    let last_service = entries.get("Vehicle.LastService").unwrap();
+    assert_eq!(last_service.data_type, types::DataType::Timestamp);
+    assert_eq!(
+        last_service.default,
+        Some(types::DataValue::Timestamp(
+            std::time::UNIX_EPOCH + std::time::Duration::from_millis(1714564800500)
+        ))
+    );
+    let service_history = entries.get("Vehicle.ServiceHistory").unwrap();
+    assert_eq!(service_history.data_type, types::DataType::TimestampArray);
+    assert_eq!(
+        service_history.default,
+        Some(types::DataValue::TimestampArray(vec![
+            std::time::UNIX_EPOCH
+        ]))
+    );
+
+    assert!(
+        parse_vss_from_str(&signals.replace("2024-05-01T14:00:00.5+02:00", "May 1st")).is_err()
+    );
+}
+
+#[test]
+fn test_parse_vss_comment_deprecation() {
+    let signals = r#"
+{
+    "Vehicle": {
+        "children": {
+            "Speed": {
+                "comment": "Negative if reversing.",
+                "datatype": "float",
+                "deprecation": "v5.0 - use Vehicle.Powertrain.Speed",
+                "description": "Vehicle speed.",
+                "type": "sensor",
+                "unit": "km/h"
+            }
+        },
+        "description": "High-level vehicle data.",
+        "type": "branch"
+    }
+}"#;
+
+    let entries = parse_vss_from_str(signals).unwrap();
+    let speed = entries.get("Vehicle.Speed").unwrap();
+    assert_eq!(speed.comment.as_deref(), Some("Negative if reversing."));
+    assert_eq!(
+        speed.deprecation.as_deref(),
+        Some("v5.0 - use Vehicle.Powertrain.Speed")
+    );
+}
+
+#[test]
+fn test_parse_value_from_json() {
+    assert_eq!(
+        parse_value_from_json(
+            serde_json::json!("WVWZZZ1KZAW000001"),
+            &types::DataType::String
+        )
+        .unwrap(),
+        types::DataValue::String("WVWZZZ1KZAW000001".to_owned())
+    );
+    assert_eq!(
+        parse_value_from_json(serde_json::json!(5), &types::DataType::Uint8).unwrap(),
+        types::DataValue::Uint32(5)
+    );
+    assert!(parse_value_from_json(serde_json::json!(500), &types::DataType::Uint8).is_err());
+    assert!(parse_value_from_json(serde_json::json!("five"), &types::DataType::Uint8).is_err());
+}
+
+#[test]
+fn test_vss_tree_overlay() {
+    let base = r#"
+{
+    "Vehicle": {
+        "children": {
+            "Speed": {
+                "datatype": "float",
+                "description": "Vehicle speed.",
+                "max": 250,
+                "type": "sensor"
+            },
+            "Width": {
+                "datatype": "uint16",
+                "description": "Overall vehicle width.",
+                "type": "attribute"
+            }
+        },
+        "description": "High-level vehicle data.",
+        "type": "branch"
+    }
+}"#;
+    let overlay = r#"
+{
+    "Vehicle": {
+        "children": {
+            "Speed": {
+                "description": "Vehicle speed, as shown on the dashboard.",
+                "max": 180
+            },
+            "Width": {
+                "delete": true
+            },
+            "LowVoltageBattery": {
+                "children": {
+                    "CurrentVoltage": {
+                        "datatype": "float",
+                        "description": "Current voltage.",
+                        "type": "sensor"
+                    }
+                },
+                "description": "Battery.",
+                "type": "branch"
+            }
+        }
+    }
+}"#;
+
+    let mut tree = VssTree::default();
+    tree.merge_from_reader(base.as_bytes()).unwrap();
+    tree.merge_from_reader(overlay.as_bytes()).unwrap();
+    let entries = tree.into_entries().unwrap();
+
+    assert_eq!(
+        entries.keys().collect::<Vec<_>>(),
+        vec!["Vehicle.LowVoltageBattery.CurrentVoltage", "Vehicle.Speed"]
+    );
+    let speed = entries.get("Vehicle.Speed").unwrap();
+    assert_eq!(
+        speed.description,
+        "Vehicle speed, as shown on the dashboard."
+    );
+    assert_eq!(speed.data_type, types::DataType::Float);
+    assert_eq!(speed.max, Some(types::DataValue::Float(180.0)));
+
+    // A node must be an object
+    let mut tree = VssTree::default();
+    assert!(tree
+        .merge_from_reader(r#"{"Vehicle": {"children": {"Speed": 5}}}"#.as_bytes())
+        .is_err());
+}
+
+#[test]
+fn test_vss_tree_instances() {
+    let data = r#"
+{
+    "Vehicle": {
+        "children": {
+            "Door": {
+                "children": {
+                    "IsOpen": {
+                        "datatype": "boolean",
+                        "description": "Is door open.",
+                        "type": "actuator"
+                    },
+                    "Count": {
+                        "datatype": "uint8",
+                        "description": "Number of doors.",
+                        "instantiate": false,
+                        "type": "attribute"
+                    },
+                    "Row2": {
+                        "children": {
+                            "PassengerSide": {
+                                "children": {
+                                    "IsOpen": {
+                                        "description": "Is rear right door open."
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                "description": "All doors.",
+                "instances": ["Row[1,2]", ["DriverSide", "PassengerSide"]],
+                "type": "branch"
+            },
+            "Mirror": {
+                "children": {
+                    "Tilt": {
+                        "datatype": "int8",
+                        "description": "Mirror tilt.",
+                        "type": "actuator"
+                    }
+                },
+                "description": "Mirrors.",
+                "instances": ["Left", "Right"],
+                "type": "branch"
+            }
+        },
+        "description": "High-level vehicle data.",
+        "type": "branch"
+    }
+}"#;
+
+    let entries = parse_vss_from_str(data).unwrap();
+    assert_eq!(
+        entries.keys().collect::<Vec<_>>(),
+        vec![
+            "Vehicle.Door.Count",
+            "Vehicle.Door.Row1.DriverSide.IsOpen",
+            "Vehicle.Door.Row1.PassengerSide.IsOpen",
+            "Vehicle.Door.Row2.DriverSide.IsOpen",
+            "Vehicle.Door.Row2.PassengerSide.IsOpen",
+            "Vehicle.Mirror.Left.Tilt",
+            "Vehicle.Mirror.Right.Tilt",
+        ]
+    );
+    assert_eq!(
+        entries
+            .get("Vehicle.Door.Row2.PassengerSide.IsOpen")
+            .unwrap()
+            .description,
+        "Is rear right door open."
+    );
+    assert_eq!(
+        entries
+            .get("Vehicle.Door.Row1.PassengerSide.IsOpen")
+            .unwrap()
+            .description,
+        "Is door open."
+    );
+
+    assert!(parse_vss_from_str(&data.replace("Row[1,2]", "Row[2,1]")).is_err());
+}
+
+#[test]
+fn test_vss_tree_yaml() {
+    let vspec = r#"
+Vehicle:
+  type: branch
+  description: High-level vehicle data.
+
+Vehicle.Speed:
+  datatype: float
+  type: sensor
+  unit: km/h
+  max: 250
+  description: Vehicle speed.
+"#;
+    let mut tree = VssTree::default();
+    tree.merge_from_yaml_reader(vspec.as_bytes()).unwrap();
+    // Nested like in JSON
+    tree.merge_from_yaml_reader(
+        r#"
+Vehicle:
+  children:
+    Speed:
+      max: 180
+"#
+        .as_bytes(),
+    )
+    .unwrap();
+    let entries = tree.into_entries().unwrap();
+    let speed = entries.get("Vehicle.Speed").unwrap();
+    assert_eq!(speed.data_type, types::DataType::Float);
+    assert_eq!(speed.unit.as_deref(), Some("km/h"));
+    assert_eq!(speed.max, Some(types::DataValue::Float(180.0)));
+
+    let mut tree = VssTree::default();
+    let err = tree
+        .merge_from_yaml_reader("Vehicle.Speed:\n  type: sensor\n".as_bytes())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Vehicle.Speed: parent branch is not declared"
+    );
+
+    let mut tree = VssTree::default();
+    tree.merge_from_yaml_reader(vspec.replace("type: sensor", "type: signal").as_bytes())
+        .unwrap();
+    match tree.into_entries() {
+        Err(err) => assert!(err.to_string().starts_with("Vehicle.Speed.type: "), "{err}"),
+        Ok(_) => panic!("Expected unknown type to fail"),
+    }
+}
+
+#[test]
+fn test_vss_tree_vspec_include() {
+    let dir = std::env::temp_dir().join(format!("vspec-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(dir.join("Cabin")).unwrap();
+    std::fs::write(
+        dir.join("Vehicle.vspec"),
+        r#"
+Vehicle:
+  type: branch
+  description: High-level vehicle data.
+
+Vehicle.Cabin:
+  type: branch
+  description: All in-cabin components.
+#include Cabin/Cabin.vspec Vehicle.Cabin
+
+Vehicle.Cabin.DoorCount:
+  default: 2
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("Cabin").join("Cabin.vspec"),
+        r#"
+DoorCount:
+  datatype: uint8
+  type: attribute
+  default: 4
+  description: Number of doors in vehicle.
+"#,
+    )
+    .unwrap();
+
+    let mut tree = VssTree::default();
+    tree.merge_from_file(dir.join("Vehicle.vspec")).unwrap();
+    let entries = tree.into_entries().unwrap();
+    let door_count = entries.get("Vehicle.Cabin.DoorCount").unwrap();
+    assert_eq!(door_count.data_type, types::DataType::Uint8);
+    assert_eq!(door_count.default, Some(types::DataValue::Uint32(2)));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_vss_tree_vspec_include_cycle() {
+    let dir = std::env::temp_dir().join(format!("vspec-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("Vehicle.vspec"),
+        r#"
+Vehicle:
+  type: branch
+  description: High-level vehicle data.
+
+Vehicle.Row1:
+  type: branch
+  description: First row.
+#include Seat.vspec Vehicle.Row1
+
+Vehicle.Row2:
+  type: branch
+  description: Second row.
+#include Seat.vspec Vehicle.Row2
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("Seat.vspec"),
+        r#"
+IsOccupied:
+  datatype: boolean
+  type: sensor
+  description: Is the seat occupied.
+"#,
+    )
+    .unwrap();
+
+    // Including the same file twice is fine
+    let mut tree = VssTree::default();
+    tree.merge_from_file(dir.join("Vehicle.vspec")).unwrap();
+
+    // Including a file from itself is not
+    std::fs::write(
+        dir.join("Seat.vspec"),
+        "#include Vehicle.vspec Vehicle.Row1\n",
+    )
+    .unwrap();
+    let mut tree = VssTree::default();
+    let err = tree.merge_from_file(dir.join("Vehicle.vspec")).unwrap_err();
+    assert!(err.to_string().contains("#include cycle"), "{err}");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_vss_export() {
+    let data = r#"
+{
+    "Types": {
+        "children": {
+            "Position": {
+                "children": {
+                    "Latitude": {
+                        "datatype": "double",
+                        "description": "Latitude in degrees.",
+                        "type": "property"
+                    }
+                },
+                "description": "A position.",
+                "type": "struct"
+            }
+        },
+        "description": "User defined types.",
+        "type": "branch"
+    },
+    "Vehicle": {
+        "children": {
+            "Cabin": {
+                "children": {
+                    "Sunroof": {
+                        "allowed": ["OPEN", "CLOSED"],
+                        "comment": "Position is not reported.",
+                        "datatype": "string",
+                        "description": "Sunroof state.",
+                        "type": "actuator"
+                    }
+                },
+                "description": "All in-cabin components.",
+                "type": "branch"
+            },
+            "Position": {
+                "datatype": "Types.Position",
+                "description": "Current position.",
+                "type": "sensor"
+            },
+            "Speed": {
+                "datatype": "float",
+                "deprecation": "Use Vehicle.Velocity",
+                "description": "Vehicle speed.",
+                "max": 250,
+                "min": 0,
+                "type": "sensor",
+                "unit": "km/h",
+                "x-kuksa-range-policy": "clamp"
+            },
+            "Width": {
+                "datatype": "uint16",
+                "default": 0,
+                "description": "Overall vehicle width.",
+                "type": "attribute"
+            }
+        },
+        "description": "High-level vehicle data.",
+        "type": "branch"
+    }
+}"#;
+    let entries = parse_vss_from_str(data).unwrap();
+
+    let mut export = VssExport::default();
+    for (path, entry) in &entries {
+        let value = match path.as_str() {
+            "Vehicle.Speed" => Some(types::DataValue::Float(42.0)),
+            "Vehicle.Cabin.Sunroof" => Some(types::DataValue::NotAvailable),
+            _ => None,
+        };
+        export.add_entry(path, entry, value.as_ref()).unwrap();
+    }
+    let json = export.into_json();
+
+    let speed = &json["Vehicle"]["children"]["Speed"];
+    assert_eq!(speed["datatype"], "float");
+    assert_eq!(speed["min"], 0.0);
+    assert_eq!(speed["max"], 250.0);
+    assert_eq!(speed["x-kuksa-value"], 42.0);
+    assert_eq!(speed["x-kuksa-changetype"], "continuous");
+    assert_eq!(speed["x-kuksa-range-policy"], "clamp");
+    let sunroof = &json["Vehicle"]["children"]["Cabin"]["children"]["Sunroof"];
+    assert_eq!(sunroof["allowed"], serde_json::json!(["OPEN", "CLOSED"]));
+    assert!(sunroof.get("x-kuksa-value").is_none());
+    assert!(sunroof.get("x-kuksa-range-policy").is_none());
+    assert_eq!(
+        json["Types"]["children"]["Position"]["children"]["Latitude"]["datatype"],
+        "double"
+    );
+
+    // The exported tree parses to the same entries
+    let exported = parse_vss_from_str(&json.to_string()).unwrap();
+    assert_eq!(
+        exported.keys().collect::<Vec<_>>(),
+        entries.keys().collect::<Vec<_>>()
+    );
+    for (path, entry) in &entries {
+        let exported = &exported[path];
+        assert_eq!(exported.data_type, entry.data_type, "{path}");
+        assert_eq!(exported.entry_type, entry.entry_type, "{path}");
+        assert_eq!(exported.change_type, entry.change_type, "{path}");
+        assert_eq!(exported.description, entry.description, "{path}");
+        assert_eq!(exported.comment, entry.comment, "{path}");
+        assert_eq!(exported.deprecation, entry.deprecation, "{path}");
+        assert_eq!(exported.unit, entry.unit, "{path}");
+        assert_eq!(exported.min, entry.min, "{path}");
+        assert_eq!(exported.max, entry.max, "{path}");
+        assert_eq!(exported.allowed, entry.allowed, "{path}");
+        assert_eq!(exported.default, entry.default, "{path}");
+        assert_eq!(exported.range_policy, entry.range_policy, "{path}");
+    }
+}
+
+#[test]
+fn test_vss_export_conflicting_paths() {
+    let entry = || {
+        DataEntry::new(
+            types::DataType::Float,
+            types::ChangeType::Continuous,
+            types::EntryType::Sensor,
+            "",
+        )
+    };
+
+    // An entry below another entry
+    let mut export = VssExport::default();
+    export.add_entry("Vehicle.Speed", &entry(), None).unwrap();
+    assert!(export
+        .add_entry("Vehicle.Speed.Max", &entry(), None)
+        .is_err());
+
+    // An entry in place of a branch
+    let mut export = VssExport::default();
+    export
+        .add_entry("Vehicle.Speed.Max", &entry(), None)
+        .unwrap();
+    assert!(export.add_entry("Vehicle.Speed", &entry(), None).is_err());
+}
@@ -0,0 +1,225 @@
+/********************************************************************************
+ * Copyright (c) 2025 Contributors to the Eclipse Foundation
+ *
+ * See the NOTICE file(s) distributed with this work for additional
+ * information regarding copyright ownership.
+ *
+ * This program and the accompanying materials are made available under the
+ * terms of the Apache License 2.0 which is available at
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ ********************************************************************************/
+
+//! Resuming the streams of `kuksa_val_v2::ResilientClientV2` across a restart
+//! of an in-process databroker.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use databroker::authorization::Authorization;
+use databroker::broker::{ChangeType, DataBroker, DataType, EntryType};
+use databroker::{grpc, permissions, vss};
+use databroker_proto::kuksa::val::v2::{
+    open_provider_stream_request::Action, value::TypedValue, Datapoint, OpenProviderStreamRequest,
+    ProvideSignalRequest, PublishValuesRequest, SampleInterval, SubscribeByIdResponse, Value,
+};
+use kuksa_common::ClientTraitV2;
+use kuksa_val_v2::resilient::ResilientStream;
+use kuksa_val_v2::{ConnectionEvent, KuksaClientV2, ReconnectPolicy, ResilientClientV2};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_stream::StreamExt;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Databroker {
+    addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    server: JoinHandle<()>,
+}
+
+impl Databroker {
+    /// Serve `paths` as sensors on `addr`, registering them in the given
+    /// order, which determines their ids
+    async fn start(addr: SocketAddr, paths: &[&str]) -> Databroker {
+        let broker = DataBroker::new("test", "test");
+        {
+            let database = broker.authorized_access(&permissions::ALLOW_ALL);
+            for path in paths {
+                database
+                    .add_entry(
+                        path.to_string(),
+                        vss::DataEntry::new(
+                            DataType::Float,
+                            ChangeType::OnChange,
+                            EntryType::Sensor,
+                            "N/A",
+                        ),
+                    )
+                    .await
+                    .expect("failed to register entry");
+            }
+        }
+
+        let listener = TcpListener::bind(addr)
+            .await
+            .expect("failed to bind to socket");
+        let addr = listener
+            .local_addr()
+            .expect("failed to determine listener's port");
+        let (shutdown, signal) = oneshot::channel();
+        let server = tokio::spawn(async move {
+            grpc::server::serve_with_incoming_shutdown(
+                TcpListenerStream::new(listener),
+                broker,
+                #[cfg(feature = "tls")]
+                grpc::server::ServerTLS::Disabled,
+                &[grpc::server::Api::KuksaValV2],
+                Authorization::Disabled,
+                async {
+                    let _ = signal.await;
+                },
+            )
+            .await
+            .map_err(|err| err.to_string())
+            .expect("failed to serve");
+        });
+
+        Databroker {
+            addr,
+            shutdown,
+            server,
+        }
+    }
+
+    async fn stop(self) {
+        let _ = self.shutdown.send(());
+        timeout(TIMEOUT, self.server)
+            .await
+            .expect("timed out stopping the databroker")
+            .expect("databroker failed");
+    }
+}
+
+async fn id_of(client: &ResilientClientV2, path: &str) -> i32 {
+    let metadata = client
+        .client()
+        .await
+        .list_metadata((path.to_string(), "*".to_string()))
+        .await
+        .expect("failed to list metadata");
+    metadata[0].id
+}
+
+fn float(value: f32) -> Value {
+    Value {
+        typed_value: Some(TypedValue::Float(value)),
+    }
+}
+
+async fn publish(sender: &mpsc::Sender<OpenProviderStreamRequest>, id: i32, value: f32) {
+    sender
+        .send(OpenProviderStreamRequest {
+            action: Some(Action::PublishValuesRequest(PublishValuesRequest {
+                request_id: 1,
+                data_points: HashMap::from([(
+                    id,
+                    Datapoint {
+                        timestamp: None,
+                        value: Some(float(value)),
+                    },
+                )]),
+            })),
+        })
+        .await
+        .expect("provider stream closed");
+}
+
+/// Wait until `subscription` reports `value` for the signal with `id`
+async fn expect_value(
+    subscription: &mut ResilientStream<SubscribeByIdResponse>,
+    id: i32,
+    value: f32,
+) {
+    timeout(TIMEOUT, async {
+        while let Some(response) = subscription.next().await {
+            let response = response.expect("subscription failed");
+            if response
+                .entries
+                .get(&id)
+                .is_some_and(|datapoint| datapoint.value == Some(float(value)))
+            {
+                return;
+            }
+        }
+        panic!("subscription ended");
+    })
+    .await
+    .expect("timed out waiting for value");
+}
+
+#[tokio::test]
+async fn test_subscription_and_provider_resume_after_restart() {
+    let databroker = Databroker::start(
+        "127.0.0.1:0".parse().unwrap(),
+        &["Vehicle.Speed", "Vehicle.Width"],
+    )
+    .await;
+    let addr = databroker.addr;
+    let uri = kuksa_common::to_uri(format!("http://{addr}")).unwrap();
+    let client = KuksaClientV2::new(uri).into_resilient(ReconnectPolicy {
+        initial_backoff: Duration::from_millis(50),
+        max_backoff: Duration::from_millis(500),
+        multiplier: 2.0,
+        max_attempts: Some(50),
+    });
+    let mut events = client.connection_events();
+
+    let speed = id_of(&client, "Vehicle.Speed").await;
+    let mut subscription = client.subscribe_by_id(vec![speed], None).await.unwrap();
+    let provider = client.open_provider_stream(None).await.unwrap();
+    provider
+        .sender
+        .send(OpenProviderStreamRequest {
+            action: Some(Action::ProvideSignalRequest(ProvideSignalRequest {
+                signals_sample_intervals: HashMap::from([(
+                    speed,
+                    SampleInterval { interval_ms: 0 },
+                )]),
+            })),
+        })
+        .await
+        .unwrap();
+    publish(&provider.sender, speed, 1.0).await;
+    expect_value(&mut subscription, speed, 1.0).await;
+
+    // Registering the signals in another order assigns other ids
+    databroker.stop().await;
+    let databroker = Databroker::start(addr, &["Vehicle.Width", "Vehicle.Speed"]).await;
+
+    timeout(TIMEOUT, async {
+        let mut resumed = 0;
+        while resumed < 2 {
+            match events.next().await {
+                Some(Ok(ConnectionEvent::Resumed)) => resumed += 1,
+                Some(Ok(ConnectionEvent::GaveUp { reason })) => panic!("gave up: {reason}"),
+                Some(_) => {}
+                None => panic!("connection events ended"),
+            }
+        }
+    })
+    .await
+    .expect("timed out waiting for the streams to resume");
+    assert_ne!(id_of(&client, "Vehicle.Speed").await, speed);
+
+    // Both streams keep using the id known before restarting
+    publish(&provider.sender, speed, 2.0).await;
+    expect_value(&mut subscription, speed, 2.0).await;
+
+    databroker.stop().await;
+}
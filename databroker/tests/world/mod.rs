@@ -233,14 +233,12 @@ impl DataBrokerWorld {
                 if let Err(_error) = database
                     .add_entry(
                         name,
-                        data_type,
-                        change_type,
-                        entry_type,
-                        "N/A".to_string(),
-                        None, // min
-                        None, // max
-                        None,
-                        None,
+                        databroker::vss::DataEntry::new(
+                            data_type,
+                            change_type,
+                            entry_type,
+                            "N/A".to_string(),
+                        ),
                     )
                     .await
                 {
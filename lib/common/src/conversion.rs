@@ -108,6 +108,13 @@ impl ConvertToV1<protoV1::Metadata> for protoV2::Metadata {
                                 protoV2::value::TypedValue::Bool(_) => {
                                     panic!("Boolean values are not supported in ValueRestriction")
                                 }
+                                protoV2::value::TypedValue::StructValue(_)
+                                | protoV2::value::TypedValue::StructArray(_)
+                                | protoV2::value::TypedValue::Timestamp(_)
+                                | protoV2::value::TypedValue::TimestampArray(_) => {
+                                    // Not expressible as a v1 ValueRestriction
+                                    None
+                                }
                                 protoV2::value::TypedValue::Int32(val) => {
                                     Some(protoV1::ValueRestriction {
                                         r#type: Some(protoV1::value_restriction::Type::Signed(
@@ -400,6 +407,20 @@ impl ConvertToV1<Option<protoV1::datapoint::Value>> for Option<protoV2::Value> {
                         protoV1::DoubleArray { values: arr.values },
                     ))
                 }
+                Some(protoV2::value::TypedValue::Timestamp(val)) => {
+                    Some(protoV1::datapoint::Value::TimestampValue(val))
+                }
+                Some(protoV2::value::TypedValue::TimestampArray(arr)) => {
+                    Some(protoV1::datapoint::Value::TimestampArray(
+                        protoV1::TimestampArray { values: arr.values },
+                    ))
+                }
+                // kuksa.val.v1 has no representation of struct values
+                Some(protoV2::value::TypedValue::StructValue(_))
+                | Some(protoV2::value::TypedValue::StructArray(_)) => {
+                    warn!("Struct values can not be converted to kuksa.val.v1");
+                    None
+                }
                 None => None,
             },
             None => None,
@@ -519,6 +540,16 @@ impl ConvertToV2<SensorUpdateTypeV2> for protoV1::Datapoint {
                         },
                     )),
                 },
+                protoV1::datapoint::Value::TimestampValue(val) => protoV2::Value {
+                    typed_value: Some(protoV2::value::TypedValue::Timestamp(val)),
+                },
+                protoV1::datapoint::Value::TimestampArray(timestamp_array) => protoV2::Value {
+                    typed_value: Some(protoV2::value::TypedValue::TimestampArray(
+                        protoV2::TimestampArray {
+                            values: timestamp_array.values,
+                        },
+                    )),
+                },
             },
             None => protoV2::Value { typed_value: None },
         }
@@ -608,6 +639,8 @@ mod tests {
             unit: "".to_string(),
             allowed_values: None,
             min_sample_interval: None,
+            struct_type: "".to_string(),
+            default_value: None,
         }];
         let expected_metadata_response = vec![protoV1::DataEntry {
             metadata: Some(protoV1::Metadata {
@@ -648,6 +681,8 @@ mod tests {
                     unit: "".to_string(),
                     allowed_values: None,
                     min_sample_interval: None,
+                    struct_type: "".to_string(),
+                    default_value: None,
                 },
                 protoV1::Metadata {
                     data_type: protoV1::DataType::Int32.into(),
@@ -684,6 +719,8 @@ mod tests {
                         )),
                     }),
                     min_sample_interval: None,
+                    struct_type: "".to_string(),
+                    default_value: None,
                 },
                 protoV1::Metadata {
                     data_type: protoV1::DataType::Float.into(),
@@ -728,6 +765,8 @@ mod tests {
                         )),
                     }),
                     min_sample_interval: None,
+                    struct_type: "".to_string(),
+                    default_value: None,
                 },
                 protoV1::Metadata {
                     data_type: protoV1::DataType::Uint32.into(),
@@ -770,6 +809,8 @@ mod tests {
                         )),
                     }),
                     min_sample_interval: None,
+                    struct_type: "".to_string(),
+                    default_value: None,
                 },
                 protoV1::Metadata {
                     data_type: protoV1::DataType::Uint64.into(),
@@ -814,6 +855,8 @@ mod tests {
                         )),
                     }),
                     min_sample_interval: None,
+                    struct_type: "".to_string(),
+                    default_value: None,
                 },
                 protoV1::Metadata {
                     data_type: protoV1::DataType::Double.into(),
@@ -858,6 +901,8 @@ mod tests {
                         )),
                     }),
                     min_sample_interval: None,
+                    struct_type: "".to_string(),
+                    default_value: None,
                 },
                 protoV1::Metadata {
                     data_type: protoV1::DataType::String.into(),
@@ -888,6 +933,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_convert_to_v1_metadata_v2_unsupported_value_restriction() {
+        let allowed_values = vec![
+            protoV2::value::TypedValue::StructValue(protoV2::StructValue::default()),
+            protoV2::value::TypedValue::Timestamp(prost_types::Timestamp::default()),
+        ];
+
+        for typed_value in allowed_values {
+            let metadata_v2 = protoV2::Metadata {
+                data_type: protoV2::DataType::Struct.into(),
+                entry_type: protoV2::EntryType::Sensor.into(),
+                path: "Vehicle.Test".to_string(),
+                allowed_values: Some(protoV2::Value {
+                    typed_value: Some(typed_value),
+                }),
+                ..Default::default()
+            };
+            let output_v1: protoV1::Metadata = metadata_v2.convert_to_v1();
+            assert_eq!(output_v1.value_restriction, None);
+        }
+    }
+
     // impl ConvertToV1<Option<protoV1::datapoint::Value>> for Option<protoV2::Value> {}
     #[test]
     fn test_convert_to_v1_value_v2() {
@@ -12,22 +12,26 @@
 ********************************************************************************/
 
 pub mod conversion;
+pub mod token;
 pub mod types;
 
 use databroker_proto::kuksa::val::v1::Error;
 use http::Uri;
 use log::info;
 use std::convert::TryFrom;
-use std::sync::Once;
+use std::sync::{Arc, Once};
+use token::{CachedToken, FileTokenProvider, TokenProvider};
 use tokio_stream::wrappers::BroadcastStream;
 use tonic::{async_trait, transport::Channel};
 
 static INIT: Once = Once::new();
 
-#[derive(Debug)]
+/// Connection to the databroker. Clones share the channel once connected.
+#[derive(Debug, Clone)]
 pub struct Client {
     uri: Uri,
     token: Option<tonic::metadata::AsciiMetadataValue>,
+    token_provider: Option<CachedToken>,
     #[cfg(feature = "tls")]
     tls_config: Option<tonic::transport::ClientTlsConfig>,
     channel: Option<tonic::transport::Channel>,
@@ -272,6 +276,7 @@ impl std::fmt::Display for ClientError {
 #[derive(Debug)]
 pub enum TokenError {
     MalformedTokenError(String),
+    UnavailableTokenError(String),
 }
 
 impl std::error::Error for TokenError {}
@@ -279,11 +284,22 @@ impl std::fmt::Display for TokenError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TokenError::MalformedTokenError(msg) => f.pad(msg),
+            TokenError::UnavailableTokenError(msg) => f.pad(msg),
         }
     }
 }
 
+/// Authority used to carry Unix domain socket paths in a `Uri`, as
+/// `unix:///path` has no authority and can't be represented by `http::Uri`.
+const UNIX_SOCKET_AUTHORITY: &str = "localhost";
+
+/// Parse a server URI. Besides `host:port` and `http(s)://host:port`, Unix
+/// domain sockets can be given as `unix:///path/to/socket` or `unix:path`.
 pub fn to_uri(uri: impl AsRef<str>) -> Result<Uri, String> {
+    if let Some(path) = uri.as_ref().strip_prefix("unix:") {
+        return unix_socket_uri(path.strip_prefix("//").unwrap_or(path));
+    }
+
     let uri = uri
         .as_ref()
         .parse::<tonic::transport::Uri>()
@@ -314,6 +330,38 @@ pub fn to_uri(uri: impl AsRef<str>) -> Result<Uri, String> {
     tonic::transport::Uri::from_parts(parts).map_err(|err| format!("{err}"))
 }
 
+fn unix_socket_uri(path: &str) -> Result<Uri, String> {
+    if path.is_empty() {
+        return Err("No unix socket path specified".to_owned());
+    }
+    let path = if path.starts_with('/') {
+        path.to_owned()
+    } else {
+        let cwd = std::env::current_dir().map_err(|err| format!("{err}"))?;
+        cwd.join(path).to_string_lossy().into_owned()
+    };
+    Uri::builder()
+        .scheme("unix")
+        .authority(UNIX_SOCKET_AUTHORITY)
+        .path_and_query(path)
+        .build()
+        .map_err(|err| format!("{err}"))
+}
+
+/// Path of the Unix domain socket `uri` points to, if any
+fn unix_socket_path(uri: &Uri) -> Option<&str> {
+    match uri.scheme_str() {
+        Some("unix") => Some(uri.path()),
+        _ => None,
+    }
+}
+
+/// Value of the authorization header for `token`
+fn bearer(token: &str) -> Result<tonic::metadata::AsciiMetadataValue, TokenError> {
+    tonic::metadata::AsciiMetadataValue::try_from(&format!("Bearer {token}"))
+        .map_err(|err| TokenError::MalformedTokenError(format!("{err}")))
+}
+
 fn init_logger() {
     INIT.call_once(|| {
         env_logger::init();
@@ -327,6 +375,7 @@ impl Client {
         Client {
             uri,
             token: None,
+            token_provider: None,
             #[cfg(feature = "tls")]
             tls_config: None,
             channel: None,
@@ -335,7 +384,10 @@ impl Client {
     }
 
     pub fn get_uri(&self) -> String {
-        self.uri.to_string()
+        match unix_socket_path(&self.uri) {
+            Some(path) => format!("unix://{path}"),
+            None => self.uri.to_string(),
+        }
     }
 
     #[cfg(feature = "tls")]
@@ -344,13 +396,28 @@ impl Client {
     }
 
     pub fn set_access_token(&mut self, token: impl AsRef<str>) -> Result<(), TokenError> {
-        match tonic::metadata::AsciiMetadataValue::try_from(&format!("Bearer {}", token.as_ref())) {
-            Ok(token) => {
-                self.token = Some(token);
-                Ok(())
-            }
-            Err(err) => Err(TokenError::MalformedTokenError(format!("{err}"))),
-        }
+        self.token = Some(bearer(token.as_ref())?);
+        self.token_provider = None;
+        Ok(())
+    }
+
+    /// Query `provider` for the access token before each call, replacing
+    /// any token set with [`Client::set_access_token`]
+    pub fn set_token_provider(&mut self, provider: impl TokenProvider + 'static) {
+        self.token_provider = Some(CachedToken::new(Arc::new(provider)));
+        self.token = None;
+    }
+
+    /// Use the access token in the file at `path`, reading it again whenever
+    /// the file changes. Fails if the token can't be read now.
+    pub fn set_token_file(
+        &mut self,
+        path: impl Into<std::path::PathBuf>,
+    ) -> Result<(), TokenError> {
+        let provider = FileTokenProvider::new(path);
+        bearer(&provider.token()?.token)?;
+        self.set_token_provider(provider);
+        Ok(())
     }
 
     pub fn is_connected(&self) -> bool {
@@ -369,10 +436,16 @@ impl Client {
     }
 
     async fn try_create_channel(&mut self) -> Result<&Channel, ClientError> {
+        let endpoint = match unix_socket_path(&self.uri) {
+            // Let tonic connect through its Unix domain socket connector
+            Some(path) => tonic::transport::Endpoint::from_shared(format!("unix://{path}"))
+                .map_err(|err| ClientError::Connection(format!("Invalid socket path: {err}")))?,
+            None => tonic::transport::Channel::builder(self.uri.clone()),
+        };
         #[cfg(feature = "tls")]
-        let mut builder = tonic::transport::Channel::builder(self.uri.clone());
+        let mut builder = endpoint;
         #[cfg(not(feature = "tls"))]
-        let builder = tonic::transport::Channel::builder(self.uri.clone());
+        let builder = endpoint;
 
         #[cfg(feature = "tls")]
         if let Some(tls_config) = &self.tls_config {
@@ -406,7 +479,8 @@ impl Client {
                 }
                 Err(ClientError::Connection(format!(
                     "Failed to connect to {}: {}",
-                    self.uri, err
+                    self.get_uri(),
+                    err
                 )))
             }
         }
@@ -438,7 +512,12 @@ impl Client {
         &mut self,
     ) -> impl FnMut(tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> + '_ {
         move |mut req: tonic::Request<()>| {
-            if let Some(token) = &self.token {
+            if let Some(token_provider) = &mut self.token_provider {
+                let token = token_provider.authorization().map_err(|err| {
+                    tonic::Status::unauthenticated(format!("No access token: {err}"))
+                })?;
+                req.metadata_mut().insert("authorization", token);
+            } else if let Some(token) = &self.token {
                 // debug!("Inserting auth token: {:?}", token);
                 req.metadata_mut().insert("authorization", token.clone());
             }
@@ -446,3 +525,40 @@ impl Client {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_uri_tcp() {
+        let uri = to_uri("127.0.0.1:55555").unwrap();
+        assert_eq!(uri.to_string(), "http://127.0.0.1:55555/");
+        assert_eq!(unix_socket_path(&uri), None);
+
+        let uri = to_uri("https://databroker:55555").unwrap();
+        assert_eq!(uri.to_string(), "https://databroker:55555/");
+    }
+
+    #[test]
+    fn test_to_uri_unix_socket() {
+        let uri = to_uri("unix:///run/kuksa/databroker.sock").unwrap();
+        assert_eq!(unix_socket_path(&uri), Some("/run/kuksa/databroker.sock"));
+
+        let uri = to_uri("unix:/tmp/databroker.sock").unwrap();
+        assert_eq!(unix_socket_path(&uri), Some("/tmp/databroker.sock"));
+
+        let uri = to_uri("unix:databroker.sock").unwrap();
+        let path = unix_socket_path(&uri).unwrap();
+        assert!(path.starts_with('/'));
+        assert!(path.ends_with("/databroker.sock"));
+
+        assert!(to_uri("unix://").is_err());
+    }
+
+    #[test]
+    fn test_client_unix_socket_uri() {
+        let client = Client::new(to_uri("unix:///run/kuksa/databroker.sock").unwrap());
+        assert_eq!(client.get_uri(), "unix:///run/kuksa/databroker.sock");
+    }
+}
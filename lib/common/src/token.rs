@@ -0,0 +1,256 @@
+/********************************************************************************
+* Copyright (c) 2025 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+//! Access tokens which change while a client is running, e.g. because they
+//! expire and are rotated.
+
+use base64::Engine;
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::TokenError;
+
+/// Tokens expiring within this margin are fetched again
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessToken {
+    /// Access token (without "Bearer " prefix)
+    pub token: String,
+    /// When the token expires, if known
+    pub expires_at: Option<SystemTime>,
+}
+
+impl AccessToken {
+    /// Access token taking the expiry time from the `exp` claim of `token`,
+    /// if it is a JWT. The signature is not verified.
+    pub fn from_jwt(token: impl Into<String>) -> Self {
+        let token = token.into();
+        let expires_at = jwt_expiry(&token);
+        AccessToken { token, expires_at }
+    }
+}
+
+fn jwt_expiry(token: &str) -> Option<SystemTime> {
+    let payload = token.split('.').nth(1)?;
+    let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&payload).ok()?;
+    let exp = claims.get("exp")?.as_u64()?;
+    UNIX_EPOCH.checked_add(Duration::from_secs(exp))
+}
+
+/// Source of access tokens queried by [`crate::Client`] before each call.
+///
+/// The client caches tokens with an expiry time until shortly before they
+/// expire. Tokens without expiry time are fetched on every call, so
+/// providers should cache them themselves.
+pub trait TokenProvider: Send + Sync + Debug {
+    fn token(&self) -> Result<AccessToken, TokenError>;
+}
+
+/// Reads the access token from a file, reading it again whenever the file
+/// is modified, e.g. when a token is rotated.
+#[derive(Debug)]
+pub struct FileTokenProvider {
+    path: PathBuf,
+    // Token read with the modification time of the file at that point
+    cached: Mutex<Option<(SystemTime, AccessToken)>>,
+}
+
+impl FileTokenProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileTokenProvider {
+            path: path.into(),
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+impl TokenProvider for FileTokenProvider {
+    fn token(&self) -> Result<AccessToken, TokenError> {
+        let read_error = |err: std::io::Error| {
+            TokenError::UnavailableTokenError(format!(
+                "Failed to read token file \"{}\": {err}",
+                self.path.display()
+            ))
+        };
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(read_error)?;
+
+        let mut cached = self.cached.lock().unwrap();
+        if let Some((cached_modified, token)) = &*cached {
+            if *cached_modified == modified {
+                return Ok(token.clone());
+            }
+        }
+        let content = std::fs::read_to_string(&self.path).map_err(read_error)?;
+        let token = AccessToken::from_jwt(content.trim());
+        *cached = Some((modified, token.clone()));
+        Ok(token)
+    }
+}
+
+/// Token of a [`TokenProvider`] cached until shortly before it expires
+#[derive(Debug, Clone)]
+pub(crate) struct CachedToken {
+    provider: Arc<dyn TokenProvider>,
+    cached: Option<(tonic::metadata::AsciiMetadataValue, SystemTime)>,
+}
+
+impl CachedToken {
+    pub(crate) fn new(provider: Arc<dyn TokenProvider>) -> Self {
+        CachedToken {
+            provider,
+            cached: None,
+        }
+    }
+
+    /// Value of the authorization header
+    pub(crate) fn authorization(
+        &mut self,
+    ) -> Result<tonic::metadata::AsciiMetadataValue, TokenError> {
+        if let Some((value, expires_at)) = &self.cached {
+            if SystemTime::now() + REFRESH_MARGIN < *expires_at {
+                return Ok(value.clone());
+            }
+        }
+        let token = self.provider.token()?;
+        let value = crate::bearer(&token.token)?;
+        self.cached = token
+            .expires_at
+            .map(|expires_at| (value.clone(), expires_at));
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn jwt(exp: u64) -> String {
+        let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(format!("{{\"sub\":\"test\",\"exp\":{exp}}}"));
+        format!("eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCJ9.{payload}.c2lnbmF0dXJl")
+    }
+
+    fn unix_time(time: SystemTime) -> u64 {
+        time.duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    #[derive(Debug)]
+    struct CountingProvider {
+        expires_in: Option<Duration>,
+        calls: AtomicU32,
+    }
+
+    impl TokenProvider for CountingProvider {
+        fn token(&self) -> Result<AccessToken, TokenError> {
+            let calls = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(AccessToken {
+                token: format!("token{calls}"),
+                expires_at: self
+                    .expires_in
+                    .map(|expires_in| SystemTime::now() + expires_in),
+            })
+        }
+    }
+
+    #[test]
+    fn test_access_token_from_jwt() {
+        let token = AccessToken::from_jwt(jwt(2000000000));
+        assert_eq!(
+            token.expires_at,
+            Some(UNIX_EPOCH + Duration::from_secs(2000000000))
+        );
+        assert_eq!(AccessToken::from_jwt("not a jwt").expires_at, None);
+    }
+
+    #[test]
+    fn test_cached_token_is_reused_until_near_expiry() {
+        let provider = Arc::new(CountingProvider {
+            expires_in: Some(Duration::from_secs(15 * 60)),
+            calls: AtomicU32::new(0),
+        });
+        let mut cached = CachedToken::new(provider.clone());
+        assert_eq!(
+            cached.authorization().unwrap().to_str().unwrap(),
+            "Bearer token1"
+        );
+        assert_eq!(
+            cached.authorization().unwrap().to_str().unwrap(),
+            "Bearer token1"
+        );
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+
+        let provider = Arc::new(CountingProvider {
+            expires_in: Some(Duration::from_secs(30)),
+            calls: AtomicU32::new(0),
+        });
+        let mut cached = CachedToken::new(provider.clone());
+        assert_eq!(
+            cached.authorization().unwrap().to_str().unwrap(),
+            "Bearer token1"
+        );
+        assert_eq!(
+            cached.authorization().unwrap().to_str().unwrap(),
+            "Bearer token2"
+        );
+    }
+
+    #[test]
+    fn test_cached_token_without_expiry_is_fetched_every_time() {
+        let provider = Arc::new(CountingProvider {
+            expires_in: None,
+            calls: AtomicU32::new(0),
+        });
+        let mut cached = CachedToken::new(provider.clone());
+        assert_eq!(
+            cached.authorization().unwrap().to_str().unwrap(),
+            "Bearer token1"
+        );
+        assert_eq!(
+            cached.authorization().unwrap().to_str().unwrap(),
+            "Bearer token2"
+        );
+    }
+
+    #[test]
+    fn test_file_token_provider_reads_rotated_token() {
+        let path = std::env::temp_dir().join(format!("kuksa-token-{}", std::process::id()));
+        let provider = FileTokenProvider::new(&path);
+        assert!(matches!(
+            provider.token(),
+            Err(TokenError::UnavailableTokenError(_))
+        ));
+
+        let first = jwt(unix_time(SystemTime::now()) + 900);
+        std::fs::write(&path, format!("{first}\n")).unwrap();
+        assert_eq!(provider.token().unwrap().token, first);
+
+        let second = jwt(unix_time(SystemTime::now()) + 1800);
+        std::fs::write(&path, &second).unwrap();
+        // Make sure the modification time differs on coarse grained file systems
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(provider.token().unwrap().token, second);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
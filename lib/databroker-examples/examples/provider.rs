@@ -0,0 +1,58 @@
+/*
+ * *******************************************************************************
+ *  Copyright (c) 2025 Contributors to the Eclipse Foundation
+ *
+ *  See the NOTICE file(s) distributed with this work for additional
+ *  information regarding copyright ownership.
+ *
+ *  This program and the accompanying materials are made available under the
+ *  terms of the Apache License 2.0 which is available at
+ *  http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  SPDX-License-Identifier: Apache-2.0
+ * ******************************************************************************
+ */
+
+use databroker_proto::kuksa::val::v2::value::TypedValue;
+use databroker_proto::kuksa::val::v2::Value;
+use kuksa_val_v2::provider::ProviderBuilder;
+use kuksa_val_v2::KuksaClientV2;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() {
+    let host = if cfg!(target_os = "macos") {
+        "http://localhost:55556"
+    } else {
+        "http://localhost:55555"
+    };
+    let client = KuksaClientV2::from_host(host);
+
+    let provider = ProviderBuilder::new()
+        .actuator("Vehicle.Body.Trunk.Rear.IsOpen", |value| {
+            println!("Trunk actuation requested: {:?}", value.typed_value);
+            Ok(())
+        })
+        .signal("Vehicle.Speed")
+        .on_filter_update(|path, interval| match interval {
+            Some(interval) => println!("{path} requested every {interval:?}"),
+            None => println!("{path} requested on every change"),
+        })
+        .on_publish_error(|path, error| println!("Publishing {path} failed: {}", error.message))
+        .start(client)
+        .await
+        .expect("Failed to start provider");
+
+    let mut speed = 0.0;
+    while !provider.is_closed() {
+        let value = Value {
+            typed_value: Some(TypedValue::Float(speed)),
+        };
+        if let Err(err) = provider.publish("Vehicle.Speed", value).await {
+            println!("Error: Could not publish Vehicle.Speed: {err}");
+            break;
+        }
+        speed = (speed + 1.0) % 250.0;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
@@ -30,6 +30,13 @@ impl KuksaClient {
         }
     }
 
+    /// Create a client connecting to the databroker over the Unix domain
+    /// socket at `path`, e.g. `/run/kuksa/databroker.sock`
+    pub fn from_unix_socket(path: impl AsRef<str>) -> Result<Self, String> {
+        let uri = kuksa_common::to_uri(format!("unix://{}", path.as_ref()))?;
+        Ok(Self::new(uri))
+    }
+
     async fn set(&mut self, entry: DataEntry, _fields: Vec<i32>) -> Result<(), ClientError> {
         let mut client = proto::v1::val_client::ValClient::with_interceptor(
             self.basic_client.get_channel().await?.clone(),
@@ -27,7 +27,13 @@ use tonic::async_trait;
 use kuksa_common::conversion::{ConvertToV1, ConvertToV2};
 use kuksa_common::types::{OpenProviderStream, ServerInfo};
 
-#[derive(Debug)]
+pub mod provider;
+pub mod resilient;
+pub mod signal;
+pub use resilient::{ConnectionEvent, ReconnectPolicy, ResilientClientV2};
+pub use signal::{Signal, SignalType};
+
+#[derive(Debug, Clone)]
 pub struct KuksaClientV2 {
     pub basic_client: Client,
 }
@@ -44,6 +50,13 @@ impl KuksaClientV2 {
         Self::new(uri)
     }
 
+    /// Create a client connecting to the databroker over the Unix domain
+    /// socket at `path`, e.g. `/run/kuksa/databroker.sock`
+    pub fn from_unix_socket(path: impl AsRef<str>) -> Result<Self, String> {
+        let uri = kuksa_common::to_uri(format!("unix://{}", path.as_ref()))?;
+        Ok(Self::new(uri))
+    }
+
     /// Resolves the databroker ids for the specified list of paths and returns them in a HashMap<String, i32>
     ///
     /// Returns (GRPC error code):
@@ -0,0 +1,767 @@
+/********************************************************************************
+* Copyright (c) 2025 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+//! Typed provider API on top of [`ClientTraitV2::open_provider_stream`].
+//!
+//! ```no_run
+//! # use kuksa_val_v2::{KuksaClientV2, provider::ProviderBuilder};
+//! # use databroker_proto::kuksa::val::v2::{value::TypedValue, Value};
+//! # async fn run() -> Result<(), kuksa_val_v2::ClientError> {
+//! let client = KuksaClientV2::from_host("http://localhost:55555");
+//! let provider = ProviderBuilder::new()
+//!     .actuator("Vehicle.Body.Trunk.Rear.IsOpen", |value| {
+//!         println!("Trunk: {value:?}");
+//!         Ok(())
+//!     })
+//!     .signal("Vehicle.Speed")
+//!     .on_filter_update(|path, interval| println!("{path}: {interval:?}"))
+//!     .start(client)
+//!     .await?;
+//!
+//! let speed = Value {
+//!     typed_value: Some(TypedValue::Float(100.0)),
+//! };
+//! provider.publish("Vehicle.Speed", speed).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use databroker_proto::kuksa::val::v2::{
+    open_provider_stream_request::Action as RequestAction,
+    open_provider_stream_response::Action as ResponseAction, signal_id::Signal,
+    BatchActuateStreamResponse, Datapoint, Error, ErrorCode, GetProviderValueResponse,
+    OpenProviderStreamRequest, OpenProviderStreamResponse, ProvideActuationRequest,
+    ProvideSignalRequest, PublishValuesRequest, SampleInterval, SignalId, Value,
+};
+use kuksa_common::{ClientError, ClientTraitV2};
+use prost_types::Timestamp;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tonic::Streaming;
+
+use crate::KuksaClientV2;
+
+/// Called with the requested value when an actuator is actuated. Returning
+/// an error rejects the actuation.
+pub type ActuationHandler = Box<dyn FnMut(&Value) -> Result<(), Error> + Send>;
+
+/// Called with the path of a provided signal and the minimum sample interval
+/// requested by its subscribers, `None` if no interval is requested.
+pub type FilterHandler = Box<dyn FnMut(&str, Option<Duration>) + Send>;
+
+/// Called with the path of a signal whose published value was rejected
+pub type PublishErrorHandler = Box<dyn FnMut(&str, &Error) + Send>;
+
+/// Called when the databroker closed the stream, with the error status if it failed
+pub type CloseHandler = Box<dyn FnOnce(Option<tonic::Status>) + Send>;
+
+#[derive(Default)]
+pub struct ProviderBuilder {
+    actuators: Vec<(String, ActuationHandler)>,
+    signals: Vec<String>,
+    filter_handler: Option<FilterHandler>,
+    publish_error_handler: Option<PublishErrorHandler>,
+    close_handler: Option<CloseHandler>,
+    auto_ack: bool,
+    buffer_size: Option<usize>,
+}
+
+impl ProviderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Provide actuation of the actuator at `path`, handling actuation
+    /// requests with `handler`
+    pub fn actuator(
+        mut self,
+        path: impl Into<String>,
+        handler: impl FnMut(&Value) -> Result<(), Error> + Send + 'static,
+    ) -> Self {
+        self.actuators.push((path.into(), Box::new(handler)));
+        self
+    }
+
+    /// Provide the signal at `path`. Signals not registered here are claimed
+    /// when publishing them for the first time.
+    pub fn signal(mut self, path: impl Into<String>) -> Self {
+        self.signals.push(path.into());
+        self
+    }
+
+    /// Handle changes of the sample intervals requested for provided signals
+    pub fn on_filter_update(
+        mut self,
+        handler: impl FnMut(&str, Option<Duration>) + Send + 'static,
+    ) -> Self {
+        self.filter_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Handle values rejected by the databroker after publishing them
+    pub fn on_publish_error(mut self, handler: impl FnMut(&str, &Error) + Send + 'static) -> Self {
+        self.publish_error_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Handle the databroker closing the stream. Not called when the stream is
+    /// closed by dropping the [`Provider`].
+    pub fn on_close(
+        mut self,
+        handler: impl FnOnce(Option<tonic::Status>) + Send + 'static,
+    ) -> Self {
+        self.close_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Acknowledge accepted actuations by publishing the actuated value as
+    /// the current value of the actuator, see [`Provider::set_auto_ack`]
+    pub fn auto_ack(mut self, auto_ack: bool) -> Self {
+        self.auto_ack = auto_ack;
+        self
+    }
+
+    /// Buffer size of the provider stream, see [`ClientTraitV2::open_provider_stream`]
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    /// Open the provider stream and claim the registered actuators and signals.
+    ///
+    /// Returns (GRPC error code):
+    ///   NOT_FOUND if any of the paths doesn't exist
+    ///   and the errors of [`ClientTraitV2::open_provider_stream`]
+    pub async fn start(self, mut client: KuksaClientV2) -> Result<Provider, ClientError> {
+        let mut state = ProviderState {
+            auto_ack: self.auto_ack,
+            ..Default::default()
+        };
+        let mut actuation_handlers = HashMap::new();
+        for (path, handler) in self.actuators {
+            let id = resolve_id(&mut client, &path).await?;
+            state.insert(path, id);
+            actuation_handlers.insert(id, handler);
+        }
+        let mut signal_ids = Vec::with_capacity(self.signals.len());
+        for path in self.signals {
+            let id = resolve_id(&mut client, &path).await?;
+            state.insert(path, id);
+            signal_ids.push(id);
+        }
+
+        let stream = client.open_provider_stream(self.buffer_size).await?;
+        let (new_actuators, new_actuators_receiver) = mpsc::unbounded_channel();
+        let provider = Provider {
+            client: tokio::sync::Mutex::new(client),
+            sender: stream.sender,
+            new_actuators,
+            state: Arc::new(Mutex::new(state)),
+        };
+
+        if !actuation_handlers.is_empty() {
+            let mut actuator_ids: Vec<_> = actuation_handlers.keys().copied().collect();
+            actuator_ids.sort_unstable();
+            provider
+                .send(provide_actuation_request(actuator_ids.clone()))
+                .await?;
+            if self.auto_ack {
+                signal_ids.extend(actuator_ids);
+            }
+        }
+        if !signal_ids.is_empty() {
+            provider.send(provide_signal_request(signal_ids)).await?;
+        }
+
+        tokio::spawn(handle_responses(
+            provider.sender.downgrade(),
+            stream.receiver_stream,
+            provider.state.clone(),
+            Handlers {
+                actuation: actuation_handlers,
+                filter: self.filter_handler,
+                publish_error: self.publish_error_handler,
+                close: self.close_handler,
+            },
+            new_actuators_receiver,
+        ));
+
+        Ok(provider)
+    }
+}
+
+#[derive(Default)]
+struct ProviderState {
+    auto_ack: bool,
+    // Cached ids of the provided signals and actuators
+    ids: HashMap<String, i32>,
+    paths: HashMap<i32, String>,
+    // Last published value per signal, used to answer GetProviderValueRequests
+    values: HashMap<i32, Datapoint>,
+    next_request_id: u32,
+}
+
+impl ProviderState {
+    fn insert(&mut self, path: String, id: i32) {
+        self.paths.insert(id, path.clone());
+        self.ids.insert(path, id);
+    }
+
+    fn path(&self, id: i32) -> String {
+        self.paths
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| format!("#{id}"))
+    }
+
+    fn next_request_id(&mut self) -> u32 {
+        self.next_request_id = self.next_request_id.wrapping_add(1);
+        self.next_request_id
+    }
+}
+
+struct Handlers {
+    actuation: HashMap<i32, ActuationHandler>,
+    filter: Option<FilterHandler>,
+    publish_error: Option<PublishErrorHandler>,
+    close: Option<CloseHandler>,
+}
+
+/// Running provider, created with [`ProviderBuilder::start`]. Actuation and
+/// filter update requests are handled in the background until the provider
+/// is dropped or the stream is closed by the databroker.
+pub struct Provider {
+    client: tokio::sync::Mutex<KuksaClientV2>,
+    sender: mpsc::Sender<OpenProviderStreamRequest>,
+    // Handlers of actuators provided after starting, for the background task
+    new_actuators: mpsc::UnboundedSender<(i32, ActuationHandler)>,
+    state: Arc<Mutex<ProviderState>>,
+}
+
+impl Provider {
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    /// Enable or disable acknowledging accepted actuations by publishing the
+    /// actuated value as the current value of the actuator. Actuators
+    /// provided while enabled are claimed as signals too, so their values
+    /// can be published.
+    pub fn set_auto_ack(&self, auto_ack: bool) {
+        self.state.lock().unwrap().auto_ack = auto_ack;
+    }
+
+    /// Provide actuation of the actuator at `path`, like [`ProviderBuilder::actuator`]
+    pub async fn provide_actuator(
+        &self,
+        path: impl Into<String>,
+        handler: impl FnMut(&Value) -> Result<(), Error> + Send + 'static,
+    ) -> Result<(), ClientError> {
+        let path = path.into();
+        let id = resolve_id(&mut *self.client.lock().await, &path).await?;
+        let auto_ack = {
+            let mut state = self.state.lock().unwrap();
+            state.insert(path, id);
+            state.auto_ack
+        };
+        // Before claiming the actuator, so no actuation request misses the handler
+        self.new_actuators
+            .send((id, Box::new(handler)))
+            .map_err(|_| ClientError::Connection("Provider stream closed".to_string()))?;
+        self.send(provide_actuation_request(vec![id])).await?;
+        if auto_ack {
+            self.send(provide_signal_request(vec![id])).await?;
+        }
+        Ok(())
+    }
+
+    /// Publish the value of the signal at `path`
+    pub async fn publish(&self, path: &str, value: Value) -> Result<(), ClientError> {
+        self.publish_values(HashMap::from([(path.to_string(), value)]))
+            .await
+    }
+
+    /// Publish the values of several signals in one request. Signals not
+    /// provided yet are resolved and claimed first.
+    ///
+    /// Values rejected by the databroker are reported to the handler set with
+    /// [`ProviderBuilder::on_publish_error`].
+    pub async fn publish_values(&self, values: HashMap<String, Value>) -> Result<(), ClientError> {
+        let unknown: Vec<String> = {
+            let state = self.state.lock().unwrap();
+            values
+                .keys()
+                .filter(|path| !state.ids.contains_key(*path))
+                .cloned()
+                .collect()
+        };
+        if !unknown.is_empty() {
+            self.provide_signals(unknown).await?;
+        }
+
+        let action = {
+            let mut state = self.state.lock().unwrap();
+            let values = values
+                .into_iter()
+                .filter_map(|(path, value)| state.ids.get(&path).map(|id| (*id, value)))
+                .collect();
+            publish_request(&mut state, values)
+        };
+        self.send(action).await
+    }
+
+    /// Provide the signals at `paths`, like [`ProviderBuilder::signal`]
+    pub async fn provide_signals(&self, paths: Vec<String>) -> Result<(), ClientError> {
+        let mut ids = Vec::with_capacity(paths.len());
+        {
+            let mut client = self.client.lock().await;
+            for path in paths {
+                let id = resolve_id(&mut client, &path).await?;
+                self.state.lock().unwrap().insert(path, id);
+                ids.push(id);
+            }
+        }
+        self.send(provide_signal_request(ids)).await
+    }
+
+    async fn send(&self, action: RequestAction) -> Result<(), ClientError> {
+        self.sender
+            .send(OpenProviderStreamRequest {
+                action: Some(action),
+            })
+            .await
+            .map_err(|_| ClientError::Connection("Provider stream closed".to_string()))
+    }
+}
+
+/// Resolve the id of the signal at `path`
+async fn resolve_id(client: &mut KuksaClientV2, path: &str) -> Result<i32, ClientError> {
+    let metadata = client
+        .list_metadata((path.to_string(), "*".to_string()))
+        .await?;
+    metadata
+        .iter()
+        .find(|metadata| metadata.path == path)
+        .map(|metadata| metadata.id)
+        .ok_or_else(|| ClientError::Status(tonic::Status::not_found(format!("{path} not found"))))
+}
+
+fn provide_actuation_request(ids: Vec<i32>) -> RequestAction {
+    RequestAction::ProvideActuationRequest(ProvideActuationRequest {
+        actuator_identifiers: ids
+            .into_iter()
+            .map(|id| SignalId {
+                signal: Some(Signal::Id(id)),
+            })
+            .collect(),
+    })
+}
+
+fn provide_signal_request(ids: Vec<i32>) -> RequestAction {
+    RequestAction::ProvideSignalRequest(ProvideSignalRequest {
+        signals_sample_intervals: ids
+            .into_iter()
+            .map(|id| (id, SampleInterval { interval_ms: 0 }))
+            .collect(),
+    })
+}
+
+/// Record `values` as the latest published values and build the request publishing them
+fn publish_request(state: &mut ProviderState, values: HashMap<i32, Value>) -> RequestAction {
+    let timestamp = Timestamp::from(SystemTime::now());
+    let data_points: HashMap<i32, Datapoint> = values
+        .into_iter()
+        .map(|(id, value)| {
+            (
+                id,
+                Datapoint {
+                    timestamp: Some(timestamp),
+                    value: Some(value),
+                },
+            )
+        })
+        .collect();
+    state
+        .values
+        .extend(data_points.iter().map(|(id, dp)| (*id, dp.clone())));
+    RequestAction::PublishValuesRequest(PublishValuesRequest {
+        request_id: state.next_request_id(),
+        data_points,
+    })
+}
+
+/// Dispatch `action` to the handlers and compute the requests answering it.
+/// `state` is not locked while calling the handlers.
+fn handle_response(
+    action: ResponseAction,
+    state: &Mutex<ProviderState>,
+    handlers: &mut Handlers,
+) -> Vec<RequestAction> {
+    let mut requests = Vec::new();
+    match action {
+        ResponseAction::BatchActuateStreamRequest(request) => {
+            let mut acknowledged = HashMap::new();
+            for actuate_request in request.actuate_requests {
+                let id = match actuate_request
+                    .signal_id
+                    .as_ref()
+                    .and_then(|s| s.signal.as_ref())
+                {
+                    Some(Signal::Id(id)) => Some(*id),
+                    Some(Signal::Path(path)) => state.lock().unwrap().ids.get(path).copied(),
+                    None => None,
+                };
+                let handler = match id {
+                    Some(id) => handlers.actuation.get_mut(&id),
+                    None => None,
+                };
+                let result = match (handler, &actuate_request.value) {
+                    (Some(handler), Some(value)) => handler(value),
+                    (Some(_), None) => Err(Error {
+                        code: ErrorCode::InvalidArgument.into(),
+                        message: "No value to actuate".to_string(),
+                    }),
+                    (None, _) => Err(Error {
+                        code: ErrorCode::NotFound.into(),
+                        message: "Actuator not provided".to_string(),
+                    }),
+                };
+                if let (Some(id), Some(value), Ok(())) = (id, &actuate_request.value, &result) {
+                    acknowledged.insert(id, value.clone());
+                }
+                requests.push(RequestAction::BatchActuateStreamResponse(
+                    BatchActuateStreamResponse {
+                        signal_id: actuate_request.signal_id,
+                        error: Some(result.err().unwrap_or(Error {
+                            code: ErrorCode::Ok.into(),
+                            message: String::new(),
+                        })),
+                    },
+                ));
+            }
+            let mut state = state.lock().unwrap();
+            if state.auto_ack && !acknowledged.is_empty() {
+                requests.push(publish_request(&mut state, acknowledged));
+            }
+        }
+        ResponseAction::UpdateFilterRequest(request) => {
+            if let Some(handler) = &mut handlers.filter {
+                for (id, filter) in request.filters_update {
+                    let interval = filter
+                        .min_sample_interval
+                        .filter(|interval| interval.interval_ms > 0)
+                        .map(|interval| Duration::from_millis(interval.interval_ms.into()));
+                    let path = state.lock().unwrap().path(id);
+                    handler(&path, interval);
+                }
+            }
+        }
+        ResponseAction::PublishValuesResponse(response) => {
+            if let Some(handler) = &mut handlers.publish_error {
+                for (id, error) in response.status {
+                    let path = state.lock().unwrap().path(id);
+                    handler(&path, &error);
+                }
+            }
+        }
+        ResponseAction::GetProviderValueRequest(request) => {
+            let state = state.lock().unwrap();
+            let entries = request
+                .signal_ids
+                .iter()
+                .filter_map(|id| state.values.get(id).map(|dp| (*id, dp.clone())))
+                .collect();
+            requests.push(RequestAction::GetProviderValueResponse(
+                GetProviderValueResponse {
+                    request_id: request.request_id,
+                    entries,
+                },
+            ));
+        }
+        ResponseAction::ProvideActuationResponse(_)
+        | ResponseAction::ProvideSignalResponse(_)
+        | ResponseAction::RefreshTokenResponse(_) => {}
+    }
+    requests
+}
+
+async fn handle_responses(
+    // Weak, so dropping the Provider closes the stream
+    sender: mpsc::WeakSender<OpenProviderStreamRequest>,
+    mut receiver: Streaming<OpenProviderStreamResponse>,
+    state: Arc<Mutex<ProviderState>>,
+    mut handlers: Handlers,
+    mut new_actuators: mpsc::UnboundedReceiver<(i32, ActuationHandler)>,
+) {
+    let status = loop {
+        let response = match receiver.message().await {
+            Ok(Some(response)) => response,
+            Ok(None) => break None,
+            Err(status) => break Some(status),
+        };
+        while let Ok((id, handler)) = new_actuators.try_recv() {
+            handlers.actuation.insert(id, handler);
+        }
+        let Some(action) = response.action else {
+            continue;
+        };
+        let requests = handle_response(action, &state, &mut handlers);
+        for request in requests {
+            let request = OpenProviderStreamRequest {
+                action: Some(request),
+            };
+            let Some(sender) = sender.upgrade() else {
+                return;
+            };
+            if sender.send(request).await.is_err() {
+                return;
+            }
+        }
+    };
+    // Closed by the databroker rather than by dropping the Provider
+    if sender.upgrade().is_some() {
+        if let Some(handler) = handlers.close.take() {
+            handler(status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use databroker_proto::kuksa::val::v2::{
+        value::TypedValue, ActuateRequest, BatchActuateStreamRequest, Filter,
+        GetProviderValueRequest, UpdateFilterRequest,
+    };
+    use std::sync::mpsc as std_mpsc;
+
+    fn bool_value(value: bool) -> Value {
+        Value {
+            typed_value: Some(TypedValue::Bool(value)),
+        }
+    }
+
+    fn handlers() -> Handlers {
+        Handlers {
+            actuation: HashMap::new(),
+            filter: None,
+            publish_error: None,
+            close: None,
+        }
+    }
+
+    fn state() -> ProviderState {
+        let mut state = ProviderState::default();
+        state.insert("Vehicle.Body.Trunk.Rear.IsOpen".to_string(), 1);
+        state.insert("Vehicle.Speed".to_string(), 2);
+        state
+    }
+
+    fn actuate_request(id: i32, value: bool) -> ActuateRequest {
+        ActuateRequest {
+            signal_id: Some(SignalId {
+                signal: Some(Signal::Id(id)),
+            }),
+            value: Some(bool_value(value)),
+        }
+    }
+
+    #[test]
+    fn test_actuation_is_dispatched_to_handler() {
+        let state = Mutex::new(state());
+        let mut handlers = handlers();
+        let (tx, rx) = std_mpsc::channel();
+        handlers.actuation.insert(
+            1,
+            Box::new(move |value: &Value| -> Result<(), Error> {
+                tx.send(value.clone()).unwrap();
+                Ok(())
+            }),
+        );
+
+        let requests = handle_response(
+            ResponseAction::BatchActuateStreamRequest(BatchActuateStreamRequest {
+                actuate_requests: vec![actuate_request(1, true), actuate_request(3, true)],
+            }),
+            &state,
+            &mut handlers,
+        );
+
+        assert_eq!(rx.try_recv().unwrap(), bool_value(true));
+        let codes: Vec<_> = requests
+            .iter()
+            .map(|request| match request {
+                RequestAction::BatchActuateStreamResponse(response) => {
+                    response.error.as_ref().unwrap().code()
+                }
+                _ => panic!("Expected a BatchActuateStreamResponse"),
+            })
+            .collect();
+        assert_eq!(codes, vec![ErrorCode::Ok, ErrorCode::NotFound]);
+    }
+
+    #[test]
+    fn test_actuation_rejected_by_handler() {
+        let state = Mutex::new(state());
+        let mut handlers = handlers();
+        handlers.actuation.insert(
+            1,
+            Box::new(|_: &Value| -> Result<(), Error> {
+                Err(Error {
+                    code: ErrorCode::InvalidArgument.into(),
+                    message: "Trunk is locked".to_string(),
+                })
+            }),
+        );
+
+        let requests = handle_response(
+            ResponseAction::BatchActuateStreamRequest(BatchActuateStreamRequest {
+                actuate_requests: vec![ActuateRequest {
+                    signal_id: Some(SignalId {
+                        signal: Some(Signal::Path("Vehicle.Body.Trunk.Rear.IsOpen".to_string())),
+                    }),
+                    value: Some(bool_value(true)),
+                }],
+            }),
+            &state,
+            &mut handlers,
+        );
+
+        match &requests[..] {
+            [RequestAction::BatchActuateStreamResponse(response)] => {
+                let error = response.error.as_ref().unwrap();
+                assert_eq!(error.code(), ErrorCode::InvalidArgument);
+                assert_eq!(error.message, "Trunk is locked");
+            }
+            _ => panic!("Expected a BatchActuateStreamResponse"),
+        }
+    }
+
+    #[test]
+    fn test_filter_update_is_dispatched_to_handler() {
+        let state = Mutex::new(state());
+        let mut handlers = handlers();
+        let (tx, rx) = std_mpsc::channel();
+        handlers.filter = Some(Box::new(move |path: &str, interval: Option<Duration>| {
+            tx.send((path.to_string(), interval)).unwrap();
+        }));
+
+        let requests = handle_response(
+            ResponseAction::UpdateFilterRequest(UpdateFilterRequest {
+                request_id: 1,
+                filters_update: HashMap::from([(
+                    2,
+                    Filter {
+                        duration_ms: 0,
+                        min_sample_interval: Some(SampleInterval { interval_ms: 100 }),
+                    },
+                )]),
+            }),
+            &state,
+            &mut handlers,
+        );
+
+        assert!(requests.is_empty());
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            (
+                "Vehicle.Speed".to_string(),
+                Some(Duration::from_millis(100))
+            )
+        );
+    }
+
+    #[test]
+    fn test_accepted_actuation_auto_ack() {
+        let mut state = state();
+        state.auto_ack = true;
+        let state = Mutex::new(state);
+        let mut handlers = handlers();
+        handlers
+            .actuation
+            .insert(1, Box::new(|_: &Value| -> Result<(), Error> { Ok(()) }));
+
+        let requests = handle_response(
+            ResponseAction::BatchActuateStreamRequest(BatchActuateStreamRequest {
+                actuate_requests: vec![actuate_request(1, true), actuate_request(3, true)],
+            }),
+            &state,
+            &mut handlers,
+        );
+        assert_eq!(requests.len(), 3);
+        match &requests[2] {
+            RequestAction::PublishValuesRequest(request) => {
+                // Only the accepted actuation is acknowledged
+                assert_eq!(request.data_points.len(), 1);
+                assert_eq!(request.data_points[&1].value, Some(bool_value(true)));
+            }
+            _ => panic!("Expected a PublishValuesRequest"),
+        }
+    }
+
+    #[test]
+    fn test_handlers_called_without_state_locked() {
+        let state = Arc::new(Mutex::new(state()));
+        let mut handlers = handlers();
+        let handler_state = state.clone();
+        handlers.actuation.insert(
+            1,
+            Box::new(move |_: &Value| -> Result<(), Error> {
+                assert!(handler_state.try_lock().is_ok());
+                Ok(())
+            }),
+        );
+
+        handle_response(
+            ResponseAction::BatchActuateStreamRequest(BatchActuateStreamRequest {
+                actuate_requests: vec![ActuateRequest {
+                    signal_id: Some(SignalId {
+                        signal: Some(Signal::Path("Vehicle.Body.Trunk.Rear.IsOpen".to_string())),
+                    }),
+                    value: Some(bool_value(true)),
+                }],
+            }),
+            &state,
+            &mut handlers,
+        );
+    }
+
+    #[test]
+    fn test_published_values_are_returned_to_server() {
+        let mut state = state();
+        let mut handlers = handlers();
+
+        let action = publish_request(&mut state, HashMap::from([(2, bool_value(false))]));
+        assert!(matches!(action, RequestAction::PublishValuesRequest(_)));
+        let state = Mutex::new(state);
+
+        let requests = handle_response(
+            ResponseAction::GetProviderValueRequest(GetProviderValueRequest {
+                request_id: 5,
+                signal_ids: vec![1, 2],
+            }),
+            &state,
+            &mut handlers,
+        );
+        match &requests[..] {
+            [RequestAction::GetProviderValueResponse(response)] => {
+                assert_eq!(response.request_id, 5);
+                assert_eq!(response.entries.len(), 1);
+                assert_eq!(response.entries[&2].value, Some(bool_value(false)));
+            }
+            _ => panic!("Expected a GetProviderValueResponse"),
+        }
+    }
+}
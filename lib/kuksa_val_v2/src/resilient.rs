@@ -0,0 +1,800 @@
+/********************************************************************************
+* Copyright (c) 2025 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+//! Opt-in resilient mode of [`KuksaClientV2`].
+//!
+//! Streams opened through [`ResilientClientV2`] survive databroker restarts:
+//! when a stream ends, the client reconnects with exponential backoff and
+//! resumes it, i.e. subscribes again to the same signals, or opens a new
+//! provider stream and claims the same signals and actuators again.
+
+use databroker_proto::kuksa::val::v2::{
+    open_provider_stream_request::Action, open_provider_stream_response::Action as ResponseAction,
+    signal_id::Signal, GetProviderValueResponse, Metadata, OpenProviderStreamRequest,
+    OpenProviderStreamResponse, ProvideSignalRequest, PublishValuesRequest, PublishValuesResponse,
+    SignalId, SubscribeByIdResponse, SubscribeResponse, UpdateFilterRequest,
+};
+use kuksa_common::types::OpenProviderStream;
+use kuksa_common::{ClientError, ClientTraitV2};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, Mutex, MutexGuard};
+use tokio_stream::wrappers::{BroadcastStream, ReceiverStream};
+use tonic::{async_trait, Code, Status, Streaming};
+
+use crate::KuksaClientV2;
+
+/// Stream of messages of a resumed stream. Ends after an `Err` if the stream
+/// could not be resumed.
+pub type ResilientStream<T> = ReceiverStream<Result<T, ClientError>>;
+
+/// How to retry connecting to the databroker after a stream ended
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Delay before the first attempt
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between attempts
+    pub max_backoff: Duration,
+    /// Factor the delay grows by after each failed attempt
+    pub multiplier: f64,
+    /// Give up after this many attempts, retry forever if `None`
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before reconnection attempt `attempt`, starting at 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        if delay.is_finite() && delay < self.max_backoff.as_secs_f64() {
+            Duration::from_secs_f64(delay)
+        } else {
+            self.max_backoff
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    /// A stream ended, e.g. because the databroker restarted
+    Disconnected { reason: String },
+    /// Waiting `delay` before reconnection attempt `attempt`
+    Reconnecting { attempt: u32, delay: Duration },
+    /// Reconnected and resumed a stream
+    Resumed,
+    /// A stream could not be resumed and was closed
+    GaveUp { reason: String },
+}
+
+/// Whether an operation failing with `err` may succeed when retried later
+fn is_transient(err: &ClientError) -> bool {
+    match err {
+        ClientError::Connection(_) => true,
+        ClientError::Status(status) => matches!(
+            status.code(),
+            Code::Unavailable
+                | Code::Unknown
+                | Code::Internal
+                | Code::Cancelled
+                | Code::Aborted
+                | Code::DeadlineExceeded
+                | Code::ResourceExhausted
+        ),
+        ClientError::Function(_) => false,
+    }
+}
+
+/// A stream that can be opened again after reconnecting
+#[async_trait]
+trait Resumable: Send + Sync + 'static {
+    type Stream: Send;
+
+    async fn open(&mut self, client: &mut KuksaClientV2) -> Result<Self::Stream, ClientError>;
+}
+
+/// Maps the signal ids in messages of a resumed stream to the ids known to the caller
+trait RestoreIds<T> {
+    fn restore_ids(&self, message: T) -> T;
+}
+
+struct Subscription {
+    signal_paths: Vec<String>,
+    buffer_size: Option<u32>,
+}
+
+#[async_trait]
+impl Resumable for Subscription {
+    type Stream = Streaming<SubscribeResponse>;
+
+    async fn open(&mut self, client: &mut KuksaClientV2) -> Result<Self::Stream, ClientError> {
+        client
+            .subscribe(self.signal_paths.clone(), self.buffer_size)
+            .await
+    }
+}
+
+impl RestoreIds<SubscribeResponse> for Subscription {
+    // Keyed by path
+    fn restore_ids(&self, response: SubscribeResponse) -> SubscribeResponse {
+        response
+    }
+}
+
+struct SubscriptionById {
+    // Ids as known to the caller
+    signal_ids: Vec<i32>,
+    buffer_size: Option<u32>,
+    ids: SignalIds,
+}
+
+#[async_trait]
+impl Resumable for SubscriptionById {
+    type Stream = Streaming<SubscribeByIdResponse>;
+
+    async fn open(&mut self, client: &mut KuksaClientV2) -> Result<Self::Stream, ClientError> {
+        let metadata = list_all_metadata(client).await?;
+        self.ids.update(&metadata, &self.signal_ids)?;
+        let signal_ids = self
+            .signal_ids
+            .iter()
+            .map(|id| self.ids.to_current(*id))
+            .collect();
+        client.subscribe_by_id(signal_ids, self.buffer_size).await
+    }
+}
+
+impl RestoreIds<SubscribeByIdResponse> for SubscriptionById {
+    fn restore_ids(&self, response: SubscribeByIdResponse) -> SubscribeByIdResponse {
+        SubscribeByIdResponse {
+            entries: self.ids.map_keys(response.entries, SignalIds::to_known),
+        }
+    }
+}
+
+/// Signal ids handed out to the caller. The databroker may assign other ids
+/// to the same signals after restarting, so the paths behind the ids are
+/// recorded and resolved again after reconnecting.
+#[derive(Default)]
+struct SignalIds {
+    // Path per id known to the caller
+    paths: HashMap<i32, String>,
+    // Id assigned by the databroker connected to per id known to the caller,
+    // and the other way around. Ids missing here are unchanged.
+    current: HashMap<i32, i32>,
+    known: HashMap<i32, i32>,
+}
+
+impl SignalIds {
+    fn to_current(&self, id: i32) -> i32 {
+        self.current.get(&id).copied().unwrap_or(id)
+    }
+
+    fn to_known(&self, id: i32) -> i32 {
+        self.known.get(&id).copied().unwrap_or(id)
+    }
+
+    /// Record the paths of `new_ids` and resolve all recorded paths, with
+    /// `metadata` of all signals of the databroker connected to.
+    ///
+    /// Returns NOT_FOUND if a recorded path doesn't exist anymore
+    fn update(&mut self, metadata: &[Metadata], new_ids: &[i32]) -> Result<(), ClientError> {
+        for id in new_ids {
+            if self.paths.contains_key(id) {
+                continue;
+            }
+            let current_id = self.to_current(*id);
+            // Unknown ids are rejected by the databroker
+            if let Some(metadata) = metadata.iter().find(|metadata| metadata.id == current_id) {
+                self.paths.insert(*id, metadata.path.clone());
+            }
+        }
+
+        let ids: HashMap<&str, i32> = metadata
+            .iter()
+            .map(|metadata| (metadata.path.as_str(), metadata.id))
+            .collect();
+        let mut current = HashMap::with_capacity(self.paths.len());
+        for (id, path) in &self.paths {
+            match ids.get(path.as_str()) {
+                Some(current_id) => current.insert(*id, *current_id),
+                None => {
+                    return Err(ClientError::Status(Status::not_found(format!(
+                        "{path} not found"
+                    ))))
+                }
+            };
+        }
+        self.known = current
+            .iter()
+            .map(|(known_id, current_id)| (*current_id, *known_id))
+            .collect();
+        self.current = current;
+        Ok(())
+    }
+
+    fn map_keys<V>(&self, map: HashMap<i32, V>, f: fn(&Self, i32) -> i32) -> HashMap<i32, V> {
+        map.into_iter()
+            .map(|(id, value)| (f(self, id), value))
+            .collect()
+    }
+
+    fn map_signal_id(&self, signal_id: &mut Option<SignalId>, f: fn(&Self, i32) -> i32) {
+        if let Some(SignalId {
+            signal: Some(Signal::Id(id)),
+        }) = signal_id
+        {
+            *id = f(self, *id);
+        }
+    }
+
+    /// Map the ids in `request` of the caller to the ids of the databroker connected to
+    fn to_current_request(
+        &self,
+        mut request: OpenProviderStreamRequest,
+    ) -> OpenProviderStreamRequest {
+        let f = Self::to_current;
+        request.action = request.action.map(|action| match action {
+            Action::ProvideActuationRequest(mut request) => {
+                for signal_id in &mut request.actuator_identifiers {
+                    if let Some(Signal::Id(id)) = &mut signal_id.signal {
+                        *id = f(self, *id);
+                    }
+                }
+                Action::ProvideActuationRequest(request)
+            }
+            Action::ProvideSignalRequest(request) => {
+                Action::ProvideSignalRequest(ProvideSignalRequest {
+                    signals_sample_intervals: self.map_keys(request.signals_sample_intervals, f),
+                })
+            }
+            Action::PublishValuesRequest(request) => {
+                Action::PublishValuesRequest(PublishValuesRequest {
+                    request_id: request.request_id,
+                    data_points: self.map_keys(request.data_points, f),
+                })
+            }
+            Action::BatchActuateStreamResponse(mut response) => {
+                self.map_signal_id(&mut response.signal_id, f);
+                Action::BatchActuateStreamResponse(response)
+            }
+            Action::GetProviderValueResponse(response) => {
+                Action::GetProviderValueResponse(GetProviderValueResponse {
+                    request_id: response.request_id,
+                    entries: self.map_keys(response.entries, f),
+                })
+            }
+            action @ (Action::UpdateFilterResponse(_)
+            | Action::ProviderErrorIndication(_)
+            | Action::RefreshTokenRequest(_)) => action,
+        });
+        request
+    }
+
+    /// Map the ids in `response` of the databroker connected to to the ids of the caller
+    fn to_known_response(
+        &self,
+        mut response: OpenProviderStreamResponse,
+    ) -> OpenProviderStreamResponse {
+        let f = Self::to_known;
+        response.action = response.action.map(|action| match action {
+            ResponseAction::PublishValuesResponse(response) => {
+                ResponseAction::PublishValuesResponse(PublishValuesResponse {
+                    request_id: response.request_id,
+                    status: self.map_keys(response.status, f),
+                })
+            }
+            ResponseAction::BatchActuateStreamRequest(mut request) => {
+                for actuate_request in &mut request.actuate_requests {
+                    self.map_signal_id(&mut actuate_request.signal_id, f);
+                }
+                ResponseAction::BatchActuateStreamRequest(request)
+            }
+            ResponseAction::UpdateFilterRequest(request) => {
+                ResponseAction::UpdateFilterRequest(UpdateFilterRequest {
+                    request_id: request.request_id,
+                    filters_update: self.map_keys(request.filters_update, f),
+                })
+            }
+            ResponseAction::GetProviderValueRequest(mut request) => {
+                for id in &mut request.signal_ids {
+                    *id = f(self, *id);
+                }
+                ResponseAction::GetProviderValueRequest(request)
+            }
+            action @ (ResponseAction::ProvideActuationResponse(_)
+            | ResponseAction::ProvideSignalResponse(_)
+            | ResponseAction::RefreshTokenResponse(_)) => action,
+        });
+        response
+    }
+}
+
+/// Metadata of all signals, to resolve paths and ids with
+async fn list_all_metadata(client: &mut KuksaClientV2) -> Result<Vec<Metadata>, ClientError> {
+    client
+        .list_metadata(("**".to_string(), "*".to_string()))
+        .await
+}
+
+#[derive(Default)]
+struct ProviderRegistrations {
+    buffer_size: Option<usize>,
+    // ProvideActuationRequests and ProvideSignalRequests sent so far, with
+    // the ids known to the caller
+    requests: Vec<OpenProviderStreamRequest>,
+    ids: SignalIds,
+}
+
+impl ProviderRegistrations {
+    /// Record `request` if it claims signals. Returns the ids of the claimed
+    /// signals whose paths are not recorded yet.
+    fn record(&mut self, request: &OpenProviderStreamRequest) -> Vec<i32> {
+        let ids: Vec<i32> = match &request.action {
+            Some(Action::ProvideActuationRequest(request)) => request
+                .actuator_identifiers
+                .iter()
+                .filter_map(|signal_id| match signal_id.signal {
+                    Some(Signal::Id(id)) => Some(id),
+                    _ => None,
+                })
+                .collect(),
+            Some(Action::ProvideSignalRequest(request)) => {
+                request.signals_sample_intervals.keys().copied().collect()
+            }
+            _ => return Vec::new(),
+        };
+        self.requests.push(request.clone());
+        ids.into_iter()
+            .filter(|id| !self.ids.paths.contains_key(id))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Resumable for ProviderRegistrations {
+    type Stream = OpenProviderStream;
+
+    async fn open(&mut self, client: &mut KuksaClientV2) -> Result<Self::Stream, ClientError> {
+        if !self.ids.paths.is_empty() {
+            let metadata = list_all_metadata(client).await?;
+            self.ids.update(&metadata, &[])?;
+        }
+        let stream = client.open_provider_stream(self.buffer_size).await?;
+        for request in &self.requests {
+            stream
+                .sender
+                .send(self.ids.to_current_request(request.clone()))
+                .await
+                .map_err(|_| ClientError::Connection("Provider stream closed".to_string()))?;
+        }
+        Ok(stream)
+    }
+}
+
+/// Provider stream resumed after reconnecting, see [`ResilientClientV2::open_provider_stream`]
+pub struct ResilientProviderStream {
+    pub sender: mpsc::Sender<OpenProviderStreamRequest>,
+    pub receiver_stream: ResilientStream<OpenProviderStreamResponse>,
+}
+
+struct Shared {
+    client: Mutex<KuksaClientV2>,
+    policy: ReconnectPolicy,
+    events: broadcast::Sender<ConnectionEvent>,
+}
+
+impl Shared {
+    fn notify(&self, event: ConnectionEvent) {
+        // Nobody listening is fine
+        let _ = self.events.send(event);
+    }
+
+    async fn open<R: Resumable>(&self, resumable: &mut R) -> Result<R::Stream, ClientError> {
+        resumable.open(&mut *self.client.lock().await).await
+    }
+
+    /// Record the paths of the signals with `ids`, see [`SignalIds::update`]
+    async fn record_paths(
+        &self,
+        signal_ids: &mut SignalIds,
+        ids: &[i32],
+    ) -> Result<(), ClientError> {
+        let metadata = list_all_metadata(&mut *self.client.lock().await).await?;
+        signal_ids.update(&metadata, ids)
+    }
+
+    /// Reconnect after a stream ended with `error` and open `resumable` again
+    async fn resume<R: Resumable>(
+        &self,
+        resumable: &mut R,
+        error: ClientError,
+    ) -> Result<R::Stream, ClientError> {
+        self.notify(ConnectionEvent::Disconnected {
+            reason: error.to_string(),
+        });
+        let mut error = error;
+        let mut attempt = 0;
+        while is_transient(&error) {
+            attempt += 1;
+            if self
+                .policy
+                .max_attempts
+                .is_some_and(|max_attempts| attempt > max_attempts)
+            {
+                break;
+            }
+            let delay = self.policy.delay(attempt);
+            self.notify(ConnectionEvent::Reconnecting { attempt, delay });
+            tokio::time::sleep(delay).await;
+
+            let mut client = self.client.lock().await;
+            let result = match client.basic_client.try_connect().await {
+                Ok(()) => resumable.open(&mut client).await,
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(stream) => {
+                    self.notify(ConnectionEvent::Resumed);
+                    return Ok(stream);
+                }
+                Err(err) => error = err,
+            }
+        }
+        self.notify(ConnectionEvent::GaveUp {
+            reason: error.to_string(),
+        });
+        Err(error)
+    }
+}
+
+/// [`KuksaClientV2`] resuming subscriptions and provider streams after the
+/// connection to the databroker is lost. Create it with
+/// [`KuksaClientV2::into_resilient`].
+#[derive(Clone)]
+pub struct ResilientClientV2 {
+    shared: Arc<Shared>,
+}
+
+impl ResilientClientV2 {
+    pub fn new(client: KuksaClientV2, policy: ReconnectPolicy) -> Self {
+        let (events, _) = broadcast::channel(16);
+        ResilientClientV2 {
+            shared: Arc::new(Shared {
+                client: Mutex::new(client),
+                policy,
+                events,
+            }),
+        }
+    }
+
+    /// The wrapped client, for calls which don't open a stream
+    pub async fn client(&self) -> MutexGuard<'_, KuksaClientV2> {
+        self.shared.client.lock().await
+    }
+
+    /// Events about streams being disconnected and resumed
+    pub fn connection_events(&self) -> BroadcastStream<ConnectionEvent> {
+        BroadcastStream::new(self.shared.events.subscribe())
+    }
+
+    /// Like [`KuksaClientV2::subscribe`], subscribing again with the same
+    /// paths and buffer size after reconnecting.
+    pub async fn subscribe(
+        &self,
+        signal_paths: Vec<String>,
+        buffer_size: Option<u32>,
+    ) -> Result<ResilientStream<SubscribeResponse>, ClientError> {
+        self.resume_streaming(Subscription {
+            signal_paths,
+            buffer_size,
+        })
+        .await
+    }
+
+    /// Like [`KuksaClientV2::subscribe_by_id`], subscribing again to the same
+    /// signals with the same buffer size after reconnecting. The ids are
+    /// resolved again by path after reconnecting, while the responses keep
+    /// using the ids passed here.
+    pub async fn subscribe_by_id(
+        &self,
+        signal_ids: Vec<i32>,
+        buffer_size: Option<u32>,
+    ) -> Result<ResilientStream<SubscribeByIdResponse>, ClientError> {
+        self.resume_streaming(SubscriptionById {
+            signal_ids,
+            buffer_size,
+            ids: SignalIds::default(),
+        })
+        .await
+    }
+
+    /// Like [`KuksaClientV2::open_provider_stream`]. After reconnecting, a new
+    /// provider stream is opened and all `ProvideActuationRequest`s and
+    /// `ProvideSignalRequest`s sent so far are sent again. Signal ids are
+    /// resolved again by path after reconnecting and translated in both
+    /// directions, so the stream keeps using the ids known before.
+    ///
+    /// Requests sent while reconnecting are delivered once the new stream is
+    /// open.
+    pub async fn open_provider_stream(
+        &self,
+        buffer_size: Option<usize>,
+    ) -> Result<ResilientProviderStream, ClientError> {
+        let mut registrations = ProviderRegistrations {
+            buffer_size,
+            ..Default::default()
+        };
+        let stream = self.shared.open(&mut registrations).await?;
+
+        let buffer_size = buffer_size.unwrap_or(1);
+        let (sender, requests) = mpsc::channel(buffer_size);
+        let (responses, receiver) = mpsc::channel(buffer_size);
+        tokio::spawn(handle_provider_stream(
+            self.shared.clone(),
+            registrations,
+            stream,
+            requests,
+            responses,
+        ));
+
+        Ok(ResilientProviderStream {
+            sender,
+            receiver_stream: ReceiverStream::new(receiver),
+        })
+    }
+
+    async fn resume_streaming<R, T>(
+        &self,
+        mut resumable: R,
+    ) -> Result<ResilientStream<T>, ClientError>
+    where
+        R: Resumable<Stream = Streaming<T>> + RestoreIds<T>,
+        T: Send + 'static,
+    {
+        let stream = self.shared.open(&mut resumable).await?;
+        let (sender, receiver) = mpsc::channel(1);
+        tokio::spawn(forward_streaming(
+            self.shared.clone(),
+            resumable,
+            stream,
+            sender,
+        ));
+        Ok(ReceiverStream::new(receiver))
+    }
+}
+
+impl KuksaClientV2 {
+    /// Turn into a client resuming its streams after the connection to the
+    /// databroker is lost, see [`ResilientClientV2`]
+    pub fn into_resilient(self, policy: ReconnectPolicy) -> ResilientClientV2 {
+        ResilientClientV2::new(self, policy)
+    }
+}
+
+async fn forward_streaming<R, T>(
+    shared: Arc<Shared>,
+    mut resumable: R,
+    mut stream: Streaming<T>,
+    sender: mpsc::Sender<Result<T, ClientError>>,
+) where
+    R: Resumable<Stream = Streaming<T>> + RestoreIds<T>,
+    T: Send + 'static,
+{
+    loop {
+        let error = loop {
+            match stream.message().await {
+                Ok(Some(message)) => {
+                    if sender
+                        .send(Ok(resumable.restore_ids(message)))
+                        .await
+                        .is_err()
+                    {
+                        // Receiver dropped, stop resuming
+                        return;
+                    }
+                }
+                Ok(None) => break ClientError::Connection("Stream closed by server".to_string()),
+                Err(status) => break ClientError::Status(status),
+            }
+        };
+        match shared.resume(&mut resumable, error).await {
+            Ok(resumed) => stream = resumed,
+            Err(err) => {
+                let _ = sender.send(Err(err)).await;
+                return;
+            }
+        }
+    }
+}
+
+async fn handle_provider_stream(
+    shared: Arc<Shared>,
+    mut registrations: ProviderRegistrations,
+    mut stream: OpenProviderStream,
+    mut requests: mpsc::Receiver<OpenProviderStreamRequest>,
+    responses: mpsc::Sender<Result<OpenProviderStreamResponse, ClientError>>,
+) {
+    loop {
+        let error = loop {
+            tokio::select! {
+                request = requests.recv() => match request {
+                    Some(request) => {
+                        let unrecorded = registrations.record(&request);
+                        if !unrecorded.is_empty() {
+                            // Without a path, the id is sent unchanged after
+                            // reconnecting
+                            let _ = shared.record_paths(&mut registrations.ids, &unrecorded).await;
+                        }
+                        let request = registrations.ids.to_current_request(request);
+                        if stream.sender.send(request).await.is_err() {
+                            break ClientError::Connection("Provider stream closed".to_string());
+                        }
+                    }
+                    // Sender dropped, stop providing
+                    None => return,
+                },
+                response = stream.receiver_stream.message() => match response {
+                    Ok(Some(response)) => {
+                        let response = registrations.ids.to_known_response(response);
+                        if responses.send(Ok(response)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Ok(None) => {
+                        break ClientError::Connection("Stream closed by server".to_string())
+                    }
+                    Err(status) => break ClientError::Status(status),
+                },
+            }
+        };
+        match shared.resume(&mut registrations, error).await {
+            Ok(resumed) => stream = resumed,
+            Err(err) => {
+                let _ = responses.send(Err(err)).await;
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use databroker_proto::kuksa::val::v2::{GetProviderValueRequest, ProvideActuationRequest};
+
+    #[test]
+    fn test_reconnect_policy_delay() {
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+            max_attempts: None,
+        };
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(4), Duration::from_millis(800));
+        assert_eq!(policy.delay(5), Duration::from_secs(1));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&ClientError::Connection(
+            "connection refused".to_string()
+        )));
+        assert!(is_transient(&ClientError::Status(tonic::Status::new(
+            Code::Unavailable,
+            "databroker restarting"
+        ))));
+        assert!(!is_transient(&ClientError::Status(tonic::Status::new(
+            Code::PermissionDenied,
+            "no access"
+        ))));
+        assert!(!is_transient(&ClientError::Status(tonic::Status::new(
+            Code::AlreadyExists,
+            "provider already claimed actuator"
+        ))));
+    }
+
+    #[test]
+    fn test_provider_registrations_record() {
+        let mut registrations = ProviderRegistrations::default();
+        let unrecorded = registrations.record(&OpenProviderStreamRequest {
+            action: Some(Action::ProvideActuationRequest(ProvideActuationRequest {
+                actuator_identifiers: vec![SignalId {
+                    signal: Some(Signal::Id(3)),
+                }],
+            })),
+        });
+        assert_eq!(unrecorded, vec![3]);
+        registrations.record(&OpenProviderStreamRequest {
+            action: Some(Action::PublishValuesRequest(PublishValuesRequest {
+                request_id: 1,
+                data_points: Default::default(),
+            })),
+        });
+        registrations.record(&OpenProviderStreamRequest {
+            action: Some(Action::ProvideSignalRequest(ProvideSignalRequest {
+                signals_sample_intervals: Default::default(),
+            })),
+        });
+        assert_eq!(registrations.requests.len(), 2);
+    }
+
+    fn metadata(path: &str, id: i32) -> Metadata {
+        Metadata {
+            path: path.to_string(),
+            id,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_signal_ids_resolved_again_after_restart() {
+        let mut ids = SignalIds::default();
+        let before = [metadata("Vehicle.Speed", 1), metadata("Vehicle.Width", 2)];
+        ids.update(&before, &[2]).unwrap();
+        assert_eq!(ids.to_current(2), 2);
+
+        // Ids assigned differently after restarting
+        let after = [metadata("Vehicle.Width", 7), metadata("Vehicle.Speed", 8)];
+        ids.update(&after, &[]).unwrap();
+        assert_eq!(ids.to_current(2), 7);
+        assert_eq!(ids.to_known(7), 2);
+
+        let request = ids.to_current_request(OpenProviderStreamRequest {
+            action: Some(Action::ProvideSignalRequest(ProvideSignalRequest {
+                signals_sample_intervals: HashMap::from([(2, Default::default())]),
+            })),
+        });
+        match request.action {
+            Some(Action::ProvideSignalRequest(request)) => {
+                assert!(request.signals_sample_intervals.contains_key(&7));
+            }
+            _ => panic!("Expected a ProvideSignalRequest"),
+        }
+
+        let response = ids.to_known_response(OpenProviderStreamResponse {
+            action: Some(ResponseAction::GetProviderValueRequest(
+                GetProviderValueRequest {
+                    request_id: 1,
+                    signal_ids: vec![7],
+                },
+            )),
+        });
+        match response.action {
+            Some(ResponseAction::GetProviderValueRequest(request)) => {
+                assert_eq!(request.signal_ids, vec![2]);
+            }
+            _ => panic!("Expected a GetProviderValueRequest"),
+        }
+
+        // Signal gone after restarting
+        let err = ids
+            .update(&[metadata("Vehicle.Speed", 1)], &[])
+            .unwrap_err();
+        assert!(!is_transient(&err));
+    }
+}
@@ -0,0 +1,280 @@
+/********************************************************************************
+* Copyright (c) 2025 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+//! Typed signal access.
+//!
+//! A [`Signal`] couples the path of a signal with the Rust type of its value,
+//! so values are converted from and to [`Value`] without matching its oneof.
+//! Signal definitions are usually generated from a VSS JSON file with
+//! `kuksa-vss-codegen` in a build script:
+//!
+//! ```ignore
+//! mod vss {
+//!     include!(concat!(env!("OUT_DIR"), "/vss.rs"));
+//! }
+//!
+//! let speed: Option<f32> = client.get_signal(vss::Vehicle::Speed).await?;
+//! ```
+
+use databroker_proto::kuksa::val::v2::{
+    value::TypedValue, BoolArray, DoubleArray, FloatArray, Int32Array, Int64Array, StringArray,
+    SubscribeResponse, TimestampArray, Uint32Array, Uint64Array, Value,
+};
+use kuksa_common::{ClientError, ClientTraitV2};
+use std::marker::PhantomData;
+use tonic::Streaming;
+
+use crate::KuksaClientV2;
+
+pub use prost_types::Timestamp;
+
+/// Rust types which can be converted from and to [`Value`]
+pub trait SignalType: Sized {
+    fn into_value(self) -> Value;
+
+    /// `None` if `value` holds another data type or is out of range of `Self`
+    fn from_value(value: Value) -> Option<Self>;
+}
+
+macro_rules! impl_signal_type {
+    ($type:ty, $variant:ident) => {
+        impl SignalType for $type {
+            fn into_value(self) -> Value {
+                Value {
+                    typed_value: Some(TypedValue::$variant(self.into())),
+                }
+            }
+
+            fn from_value(value: Value) -> Option<Self> {
+                match value.typed_value {
+                    Some(TypedValue::$variant(value)) => value.try_into().ok(),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_signal_array_type {
+    ($type:ty, $variant:ident, $array:ident) => {
+        impl SignalType for Vec<$type> {
+            fn into_value(self) -> Value {
+                Value {
+                    typed_value: Some(TypedValue::$variant($array {
+                        values: self.into_iter().map(Into::into).collect(),
+                    })),
+                }
+            }
+
+            fn from_value(value: Value) -> Option<Self> {
+                match value.typed_value {
+                    Some(TypedValue::$variant(array)) => array
+                        .values
+                        .into_iter()
+                        .map(|value| value.try_into().ok())
+                        .collect(),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_signal_type!(String, String);
+impl_signal_type!(bool, Bool);
+impl_signal_type!(i8, Int32);
+impl_signal_type!(i16, Int32);
+impl_signal_type!(i32, Int32);
+impl_signal_type!(i64, Int64);
+impl_signal_type!(u8, Uint32);
+impl_signal_type!(u16, Uint32);
+impl_signal_type!(u32, Uint32);
+impl_signal_type!(u64, Uint64);
+impl_signal_type!(f32, Float);
+impl_signal_type!(f64, Double);
+impl_signal_type!(Timestamp, Timestamp);
+
+impl_signal_array_type!(String, StringArray, StringArray);
+impl_signal_array_type!(bool, BoolArray, BoolArray);
+impl_signal_array_type!(i8, Int32Array, Int32Array);
+impl_signal_array_type!(i16, Int32Array, Int32Array);
+impl_signal_array_type!(i32, Int32Array, Int32Array);
+impl_signal_array_type!(i64, Int64Array, Int64Array);
+impl_signal_array_type!(u8, Uint32Array, Uint32Array);
+impl_signal_array_type!(u16, Uint32Array, Uint32Array);
+impl_signal_array_type!(u32, Uint32Array, Uint32Array);
+impl_signal_array_type!(u64, Uint64Array, Uint64Array);
+impl_signal_array_type!(f32, FloatArray, FloatArray);
+impl_signal_array_type!(f64, DoubleArray, DoubleArray);
+impl_signal_array_type!(Timestamp, TimestampArray, TimestampArray);
+
+/// Path of a signal whose values are of type `T`
+pub struct Signal<T> {
+    path: &'static str,
+    value_type: PhantomData<fn() -> T>,
+}
+
+impl<T> Signal<T> {
+    pub const fn new(path: &'static str) -> Self {
+        Signal {
+            path,
+            value_type: PhantomData,
+        }
+    }
+
+    pub const fn path(&self) -> &'static str {
+        self.path
+    }
+}
+
+// Not derived, as that would require T: Clone
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Signal<T> {}
+
+impl<T> std::fmt::Debug for Signal<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Signal").field(&self.path).finish()
+    }
+}
+
+fn unexpected_type(path: &str) -> ClientError {
+    ClientError::Status(tonic::Status::invalid_argument(format!(
+        "Unexpected data type of {path}"
+    )))
+}
+
+/// Convert a value of the signal at `path`, `None` if it is not set
+fn typed_value<T: SignalType>(path: &str, value: Option<Value>) -> Result<Option<T>, ClientError> {
+    match value {
+        Some(value) if value.typed_value.is_some() => T::from_value(value)
+            .map(Some)
+            .ok_or_else(|| unexpected_type(path)),
+        _ => Ok(None),
+    }
+}
+
+/// Subscription to a single signal, see [`KuksaClientV2::subscribe_signal`]
+pub struct SignalStream<T> {
+    signal: Signal<T>,
+    stream: Streaming<SubscribeResponse>,
+}
+
+impl<T: SignalType> SignalStream<T> {
+    /// Wait for the next update. Returns `Ok(None)` when the stream ended and
+    /// `Ok(Some(None))` if the signal has no value.
+    pub async fn message(&mut self) -> Result<Option<Option<T>>, ClientError> {
+        loop {
+            match self.stream.message().await {
+                Ok(Some(mut response)) => {
+                    if let Some(datapoint) = response.entries.remove(self.signal.path) {
+                        return typed_value(self.signal.path, datapoint.value).map(Some);
+                    }
+                }
+                Ok(None) => return Ok(None),
+                Err(status) => return Err(ClientError::Status(status)),
+            }
+        }
+    }
+}
+
+impl KuksaClientV2 {
+    /// Get the current value of `signal`, see [`ClientTraitV2::get_value`].
+    /// Fails with INVALID_ARGUMENT if the value has another data type.
+    pub async fn get_signal<T: SignalType>(
+        &mut self,
+        signal: Signal<T>,
+    ) -> Result<Option<T>, ClientError> {
+        let datapoint = self.get_value(signal.path.to_string()).await?;
+        typed_value(signal.path, datapoint.and_then(|datapoint| datapoint.value))
+    }
+
+    /// Publish the current value of `signal`, see [`ClientTraitV2::publish_value`]
+    pub async fn publish_signal<T: SignalType>(
+        &mut self,
+        signal: Signal<T>,
+        value: T,
+    ) -> Result<(), ClientError> {
+        self.publish_value(signal.path.to_string(), value.into_value())
+            .await
+    }
+
+    /// Request actuation of `signal`, see [`ClientTraitV2::actuate`]
+    pub async fn actuate_signal<T: SignalType>(
+        &mut self,
+        signal: Signal<T>,
+        value: T,
+    ) -> Result<(), ClientError> {
+        self.actuate(signal.path.to_string(), value.into_value())
+            .await
+    }
+
+    /// Subscribe to `signal`, see [`ClientTraitV2::subscribe`]
+    pub async fn subscribe_signal<T: SignalType>(
+        &mut self,
+        signal: Signal<T>,
+        buffer_size: Option<u32>,
+    ) -> Result<SignalStream<T>, ClientError> {
+        let stream = self
+            .subscribe(vec![signal.path.to_string()], buffer_size)
+            .await?;
+        Ok(SignalStream { signal, stream })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(non_upper_case_globals)]
+    const Speed: Signal<f32> = Signal::new("Vehicle.Speed");
+
+    #[test]
+    fn test_signal_type_round_trip() {
+        assert_eq!(f32::from_value(1.5f32.into_value()), Some(1.5));
+        assert_eq!(u8::from_value(200u8.into_value()), Some(200));
+        assert_eq!(
+            String::from_value("Tesla".to_string().into_value()),
+            Some("Tesla".to_string())
+        );
+        assert_eq!(
+            Vec::<i16>::from_value(vec![-1i16, 2].into_value()),
+            Some(vec![-1, 2])
+        );
+    }
+
+    #[test]
+    fn test_signal_type_mismatch() {
+        assert_eq!(u8::from_value(300u32.into_value()), None);
+        assert_eq!(u8::from_value(true.into_value()), None);
+        assert_eq!(Vec::<u8>::from_value(vec![1u32, 256].into_value()), None);
+    }
+
+    #[test]
+    fn test_typed_value() {
+        assert_eq!(
+            typed_value::<f32>(Speed.path(), Some(50.0f32.into_value())).unwrap(),
+            Some(50.0)
+        );
+        assert_eq!(typed_value::<f32>(Speed.path(), None).unwrap(), None);
+        assert_eq!(
+            typed_value::<f32>(Speed.path(), Some(Value { typed_value: None })).unwrap(),
+            None
+        );
+        assert!(typed_value::<f32>(Speed.path(), Some(true.into_value())).is_err());
+    }
+}
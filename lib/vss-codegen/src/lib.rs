@@ -0,0 +1,364 @@
+/********************************************************************************
+* Copyright (c) 2025 Contributors to the Eclipse Foundation
+*
+* See the NOTICE file(s) distributed with this work for additional
+* information regarding copyright ownership.
+*
+* This program and the accompanying materials are made available under the
+* terms of the Apache License 2.0 which is available at
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* SPDX-License-Identifier: Apache-2.0
+********************************************************************************/
+
+//! Generate typed signal definitions for `kuksa_val_v2` from a VSS JSON file,
+//! e.g. one of `data/vss-core`.
+//!
+//! Branches become modules and signals become `Signal<T>` constants named
+//! like in VSS, so `Vehicle.Speed` is available as `Vehicle::Speed`. Use it
+//! from a build script:
+//!
+//! ```no_run
+//! // build.rs
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! kuksa_vss_codegen::generate("vss.json", format!("{out_dir}/vss.rs")).unwrap();
+//! ```
+//!
+//! and include the generated file:
+//!
+//! ```ignore
+//! mod vss {
+//!     include!(concat!(env!("OUT_DIR"), "/vss.rs"));
+//! }
+//!
+//! let speed: Option<f32> = client.get_signal(vss::Vehicle::Speed).await?;
+//! ```
+
+use serde_json::{Map, Value};
+use std::fmt::Write;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    InvalidVss(String),
+}
+
+impl std::error::Error for Error {}
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{err}"),
+            Error::Json(err) => write!(f, "Invalid JSON: {err}"),
+            Error::InvalidVss(msg) => write!(f, "Invalid VSS: {msg}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+/// Generate signal definitions for the VSS JSON file `input` into `output`
+/// with the default [`Generator`]
+pub fn generate(input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<(), Error> {
+    Generator::new().generate_file(input, output)
+}
+
+pub struct Generator {
+    crate_path: String,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Generator {
+            crate_path: "::kuksa_val_v2".to_string(),
+        }
+    }
+}
+
+impl Generator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Path of the `kuksa_val_v2` crate in the generated code, in case it is
+    /// renamed or re-exported
+    pub fn crate_path(mut self, crate_path: impl Into<String>) -> Self {
+        self.crate_path = crate_path.into();
+        self
+    }
+
+    /// Generate signal definitions for the VSS JSON file `input` into
+    /// `output`. Asks cargo to run the build script again if `input` changes.
+    pub fn generate_file(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        let input = input.as_ref();
+        println!("cargo:rerun-if-changed={}", input.display());
+        let code = self.generate(&std::fs::read_to_string(input)?)?;
+        // Keep the file untouched if nothing changed, to avoid recompiling
+        if std::fs::read_to_string(output.as_ref()).ok().as_ref() != Some(&code) {
+            std::fs::write(output, code)?;
+        }
+        Ok(())
+    }
+
+    /// Generate signal definitions for the VSS tree `vss_json`
+    pub fn generate(&self, vss_json: &str) -> Result<String, Error> {
+        let root: Value = serde_json::from_str(vss_json)?;
+        let root = root
+            .as_object()
+            .ok_or_else(|| Error::InvalidVss("Expected an object at the root".to_string()))?;
+
+        let mut code = String::from("// Generated by kuksa-vss-codegen, do not edit.\n");
+        for (name, node) in root {
+            code.push('\n');
+            code.push_str("#[allow(non_snake_case, non_upper_case_globals, dead_code)]\n");
+            self.write_node(&mut code, name, name, node, 0)?;
+        }
+        Ok(code)
+    }
+
+    fn write_node(
+        &self,
+        code: &mut String,
+        name: &str,
+        path: &str,
+        node: &Value,
+        depth: usize,
+    ) -> Result<(), Error> {
+        let node = node
+            .as_object()
+            .ok_or_else(|| Error::InvalidVss(format!("{path} is not an object")))?;
+        let indent = "    ".repeat(depth);
+        let node_type = node.get("type").and_then(Value::as_str).unwrap_or_default();
+
+        match node_type {
+            "branch" => {
+                write_doc(code, &indent, node, None);
+                writeln!(code, "{indent}pub mod {} {{", identifier(name)).unwrap();
+                if let Some(children) = node.get("children").and_then(Value::as_object) {
+                    let mut first = true;
+                    for (child_name, child) in children {
+                        if !first {
+                            code.push('\n');
+                        }
+                        first = false;
+                        let child_path = format!("{path}.{child_name}");
+                        self.write_node(code, child_name, &child_path, child, depth + 1)?;
+                    }
+                }
+                writeln!(code, "{indent}}}").unwrap();
+            }
+            "sensor" | "actuator" | "attribute" => {
+                let datatype = node.get("datatype").and_then(Value::as_str);
+                let Some(rust_type) = datatype.and_then(|datatype| self.rust_type(datatype)) else {
+                    writeln!(
+                        code,
+                        "{indent}// {path}: unsupported data type {}",
+                        datatype.unwrap_or("(none)")
+                    )
+                    .unwrap();
+                    return Ok(());
+                };
+                write_doc(code, &indent, node, Some((path, node_type)));
+                if let Some(deprecation) = node.get("deprecation").and_then(Value::as_str) {
+                    writeln!(code, "{indent}#[deprecated(note = {deprecation:?})]").unwrap();
+                }
+                writeln!(
+                    code,
+                    "{indent}pub const {}: {crate_path}::Signal<{rust_type}> = {crate_path}::Signal::new({path:?});",
+                    identifier(name),
+                    crate_path = self.crate_path,
+                )
+                .unwrap();
+            }
+            // Struct type definitions and properties have no values of their own
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn rust_type(&self, datatype: &str) -> Option<String> {
+        if let Some(element) = datatype.strip_suffix("[]") {
+            return self
+                .rust_type(element)
+                .map(|element| format!("Vec<{element}>"));
+        }
+        let rust_type = match datatype {
+            "boolean" => "bool",
+            "string" => "String",
+            "int8" => "i8",
+            "int16" => "i16",
+            "int32" => "i32",
+            "int64" => "i64",
+            "uint8" => "u8",
+            "uint16" => "u16",
+            "uint32" => "u32",
+            "uint64" => "u64",
+            "float" => "f32",
+            "double" => "f64",
+            "timestamp" => return Some(format!("{}::signal::Timestamp", self.crate_path)),
+            _ => return None,
+        };
+        Some(rust_type.to_string())
+    }
+}
+
+/// Write the description of `node` as doc comment, followed by the path,
+/// type and unit of signals
+fn write_doc(
+    code: &mut String,
+    indent: &str,
+    node: &Map<String, Value>,
+    signal: Option<(&str, &str)>,
+) {
+    let description = node
+        .get("description")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    for line in description.lines() {
+        writeln!(code, "{indent}/// {}", line.trim_end()).unwrap();
+    }
+    if let Some((path, node_type)) = signal {
+        if !description.is_empty() {
+            writeln!(code, "{indent}///").unwrap();
+        }
+        match node.get("unit").and_then(Value::as_str) {
+            Some(unit) => writeln!(code, "{indent}/// `{path}`, {node_type} in {unit}").unwrap(),
+            None => writeln!(code, "{indent}/// `{path}`, {node_type}").unwrap(),
+        }
+    }
+}
+
+/// Turn a VSS node name into a valid Rust identifier
+fn identifier(name: &str) -> String {
+    let mut identifier: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    match identifier.as_str() {
+        "self" | "Self" | "super" | "crate" => identifier.push('_'),
+        "as" | "async" | "await" | "break" | "const" | "continue" | "dyn" | "else" | "enum"
+        | "extern" | "false" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop" | "match"
+        | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "static" | "struct" | "trait"
+        | "true" | "type" | "unsafe" | "use" | "where" | "while" => identifier.insert_str(0, "r#"),
+        _ => {}
+    }
+    identifier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VSS: &str = r#"{
+        "Vehicle": {
+            "type": "branch",
+            "description": "High-level vehicle data.",
+            "children": {
+                "Speed": {
+                    "type": "sensor",
+                    "datatype": "float",
+                    "unit": "km/h",
+                    "description": "Vehicle speed."
+                },
+                "Body": {
+                    "type": "branch",
+                    "description": "All body components.",
+                    "children": {
+                        "Trunk": {
+                            "type": "actuator",
+                            "datatype": "boolean",
+                            "description": "Is trunk open.",
+                            "deprecation": "Use Vehicle.Body.Trunk.Rear.IsOpen"
+                        },
+                        "Lights": {
+                            "type": "attribute",
+                            "datatype": "uint8[]",
+                            "description": "Light ids."
+                        },
+                        "Sticker": {
+                            "type": "attribute",
+                            "datatype": "Types.Sticker",
+                            "description": "Struct typed."
+                        }
+                    }
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_generate() {
+        let code = Generator::new().generate(VSS).unwrap();
+        assert!(code.contains("pub mod Vehicle {\n"));
+        assert!(code.contains(
+            "    /// Vehicle speed.\n    ///\n    /// `Vehicle.Speed`, sensor in km/h\n    pub const Speed: ::kuksa_val_v2::Signal<f32> = ::kuksa_val_v2::Signal::new(\"Vehicle.Speed\");\n"
+        ));
+        assert!(code.contains("    /// All body components.\n    pub mod Body {\n"));
+        assert!(code.contains(
+            "        #[deprecated(note = \"Use Vehicle.Body.Trunk.Rear.IsOpen\")]\n        pub const Trunk: ::kuksa_val_v2::Signal<bool>"
+        ));
+        assert!(code.contains("pub const Lights: ::kuksa_val_v2::Signal<Vec<u8>>"));
+        assert!(code.contains("// Vehicle.Body.Sticker: unsupported data type Types.Sticker"));
+    }
+
+    #[test]
+    fn test_generate_with_crate_path() {
+        let code = Generator::new()
+            .crate_path("crate::vss")
+            .generate(VSS)
+            .unwrap();
+        assert!(code.contains(
+            "pub const Speed: crate::vss::Signal<f32> = crate::vss::Signal::new(\"Vehicle.Speed\");"
+        ));
+    }
+
+    #[test]
+    fn test_generate_invalid_vss() {
+        assert!(matches!(
+            Generator::new().generate("[]"),
+            Err(Error::InvalidVss(_))
+        ));
+        assert!(matches!(
+            Generator::new().generate("{"),
+            Err(Error::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_generate_vss_core() {
+        let vss = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../data/vss-core/vss_release_4.0.json"
+        ))
+        .unwrap();
+        let code = Generator::new().generate(&vss).unwrap();
+        assert!(code.contains("Signal::new(\"Vehicle.Cabin.Door.Row1.DriverSide.IsOpen\");"));
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier("Speed"), "Speed");
+        assert_eq!(identifier("2ndRow"), "_2ndRow");
+        assert_eq!(identifier("type"), "r#type");
+        assert_eq!(identifier("self"), "self_");
+        assert_eq!(identifier("Is-Open"), "Is_Open");
+    }
+}